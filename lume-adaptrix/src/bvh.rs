@@ -0,0 +1,227 @@
+//! A BVH over each mesh's cluster groups, so culling only visits the parts of the DAG that
+//! are inside the frustum and near the LOD cut. `build_bvh` runs offline in the processor;
+//! `traverse` is the CPU reference of `bvh_cull.wgsl`, for tests and tools without a GPU.
+
+use crate::lod::{max_scale, transform_sphere, LodView};
+use crate::{is_sphere_in_frustum, merge_spheres, BvhNode, Cluster, MeshInstance};
+use glam::Vec4;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Most children of an inner node.
+pub const BVH_FANOUT: usize = 4;
+
+struct BuildNode {
+    node: BvhNode,
+    children: Vec<BuildNode>,
+}
+
+/// Builds the BVH of one mesh's `clusters`, `levels` holding each cluster's level in the DAG.
+/// Returns the nodes, root first, and the order to store the clusters in: `order[i]` goes to
+/// position `i`, keeping every group contiguous for its leaf.
+pub fn build_bvh(clusters: &[Cluster], levels: &[u32]) -> (Vec<BvhNode>, Vec<usize>) {
+    // Siblings simplified from the same group share their parent's sphere and error.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of = HashMap::new();
+    for (i, cluster) in clusters.iter().enumerate() {
+        let key = (levels[i], cluster.parent_sphere.to_array().map(f32::to_bits), cluster.parent_error.to_bits());
+        let group = *group_of.entry(key).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(i);
+    }
+
+    let mut order = Vec::with_capacity(clusters.len());
+    let mut by_level: BTreeMap<u32, Vec<BuildNode>> = BTreeMap::new();
+    for group in groups {
+        let node = BvhNode {
+            bounding_sphere: group.iter().map(|&c| clusters[c].bounding_sphere).reduce(merge_spheres).unwrap(),
+            lod_sphere: clusters[group[0]].parent_sphere,
+            max_parent_error: clusters[group[0]].parent_error,
+            first: order.len() as u32,
+            count: group.len() as u32,
+            leaf: 1,
+        };
+        by_level.entry(levels[group[0]]).or_default().push(BuildNode { node, children: Vec::new() });
+        order.extend(group);
+    }
+
+    // One subtree per level, so views far from a level's LOD reject all of it at the top.
+    let levels: Vec<BuildNode> = by_level.into_values().map(build_subtree).collect();
+    if levels.is_empty() {
+        return (Vec::new(), order);
+    }
+    let root = build_subtree(levels);
+
+    // Breadth first, so the children of every node are contiguous.
+    let mut nodes = vec![root.node];
+    let mut unvisited = VecDeque::from([(0, root.children)]);
+    while let Some((index, children)) = unvisited.pop_front() {
+        if children.is_empty() {
+            continue;
+        }
+        nodes[index].first = nodes.len() as u32;
+        nodes[index].count = children.len() as u32;
+        for child in children {
+            unvisited.push_back((nodes.len(), child.children));
+            nodes.push(child.node);
+        }
+    }
+    (nodes, order)
+}
+
+/// Splits `items` along the longest axis of their centres until every node has at most
+/// `BVH_FANOUT` children.
+fn build_subtree(mut items: Vec<BuildNode>) -> BuildNode {
+    if items.len() == 1 {
+        return items.pop().unwrap();
+    }
+    if items.len() > BVH_FANOUT {
+        let center = |item: &BuildNode| item.node.bounding_sphere.truncate();
+        let (min, max) = items.iter().fold((center(&items[0]), center(&items[0])), |(min, max), item| (min.min(center(item)), max.max(center(item))));
+        let extent = max - min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+        items.sort_by(|a, b| center(a)[axis].total_cmp(&center(b)[axis]));
+
+        let run = items.len().div_ceil(BVH_FANOUT);
+        let mut children = Vec::with_capacity(BVH_FANOUT);
+        while !items.is_empty() {
+            let rest = items.split_off(run.min(items.len()));
+            children.push(build_subtree(items));
+            items = rest;
+        }
+        items = children;
+    }
+
+    let node = BvhNode {
+        bounding_sphere: items.iter().map(|c| c.node.bounding_sphere).reduce(merge_spheres).unwrap(),
+        lod_sphere: items.iter().map(|c| c.node.lod_sphere).reduce(merge_spheres).unwrap(),
+        max_parent_error: items.iter().map(|c| c.node.max_parent_error).fold(0.0, f32::max),
+        first: 0,
+        count: 0,
+        leaf: 0,
+    };
+    BuildNode { node, children: items }
+}
+
+/// Whether `bvh_cull.wgsl` descends into `node`: it is inside the frustum and may hold
+/// clusters of the LOD cut.
+fn is_node_visible(node: &BvhNode, instance: &MeshInstance, frustum: &[Vec4; 6], view: &LodView) -> bool {
+    let m = instance.world_from_local;
+    let scale = max_scale(m);
+    is_sphere_in_frustum(frustum, transform_sphere(m, scale, node.bounding_sphere))
+        && view.is_error_visible(view.pixels_per_unit * scale, transform_sphere(m, scale, node.lod_sphere), node.max_parent_error)
+}
+
+/// The `(instance, cluster)` pairs `bvh_cull.wgsl` queues for `cull.wgsl`: every cluster of
+/// the visible leaves of every instance's BVH in `nodes`, in no particular order.
+pub fn traverse(nodes: &[BvhNode], instances: &[MeshInstance], frustum: &[Vec4; 6], view: &LodView) -> Vec<[u32; 2]> {
+    let mut queued = Vec::new();
+    for (i, instance) in instances.iter().enumerate() {
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &nodes[(instance.bvh_root + index) as usize];
+            if !is_node_visible(node, instance, frustum, view) {
+                continue;
+            }
+            let children = node.first..node.first + node.count;
+            if node.leaf != 0 {
+                queued.extend(children.map(|c| [i as u32, instance.cluster_base + c]));
+            } else {
+                stack.extend(children);
+            }
+        }
+    }
+    queued
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lod::select_clusters;
+    use bytemuck::Zeroable;
+    use glam::{Mat4, Vec2, Vec3};
+
+    /// Eight leaves in a row, simplified pairwise into four clusters in two groups, then
+    /// into a root; listed out of group order.
+    fn dag() -> (Vec<Cluster>, Vec<u32>) {
+        let cluster = |sphere: Vec4, error: f32, parent_sphere: Vec4, parent_error: f32| Cluster {
+            bounding_sphere: sphere,
+            error_metric: error,
+            parent_sphere,
+            parent_error,
+            ..Cluster::zeroed()
+        };
+        let mid = [Vec4::new(-4.0, 0.0, 0.0, 4.0), Vec4::new(4.0, 0.0, 0.0, 4.0)];
+        let root = Vec4::new(0.0, 0.0, 0.0, 8.0);
+        let mut clusters: Vec<Cluster> = [0, 4, 1, 5, 2, 6, 3, 7].iter()
+            .map(|&i| cluster(Vec4::new(i as f32 * 2.0 - 7.0, 0.0, 0.0, 1.0), 0.0, mid[i / 4], 0.01))
+            .collect();
+        clusters.extend([0, 0, 1, 1].map(|i| cluster(mid[i], 0.01, root, 0.05)));
+        clusters.push(cluster(root, 0.05, root, f32::MAX));
+        (clusters, vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2])
+    }
+
+    #[test]
+    fn builds_contiguous_groups_under_bounding_nodes() {
+        let (clusters, levels) = dag();
+        let (nodes, order) = build_bvh(&clusters, &levels);
+
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..clusters.len()).collect::<Vec<_>>());
+        let clusters: Vec<Cluster> = order.iter().map(|&i| clusters[i]).collect();
+
+        let contains = |outer: Vec4, inner: Vec4| outer.truncate().distance(inner.truncate()) + inner.w <= outer.w * 1.0001;
+        let mut leaf_clusters = Vec::new();
+        let mut stack = vec![(0, None::<BvhNode>)];
+        while let Some((index, parent)) = stack.pop() {
+            let node = nodes[index as usize];
+            if let Some(parent) = parent {
+                assert!(contains(parent.bounding_sphere, node.bounding_sphere) && contains(parent.lod_sphere, node.lod_sphere));
+                assert!(node.max_parent_error <= parent.max_parent_error);
+            }
+            let children = node.first..node.first + node.count;
+            if node.leaf == 0 {
+                assert!((2..=BVH_FANOUT as u32).contains(&node.count), "{:?}", node);
+                stack.extend(children.map(|c| (c, Some(node))));
+                continue;
+            }
+            // A leaf is one group: same level and parent, bounding all its clusters.
+            let group = &clusters[children.start as usize..children.end as usize];
+            assert!(group.iter().all(|c| c.parent_sphere == node.lod_sphere && c.parent_error == node.max_parent_error), "{:?}", node);
+            assert!(group.iter().all(|c| contains(node.bounding_sphere, c.bounding_sphere)));
+            leaf_clusters.extend(children);
+        }
+        leaf_clusters.sort();
+        assert_eq!(leaf_clusters, (0..clusters.len() as u32).collect::<Vec<_>>());
+        // Four leaf groups, the two of level 0 under their own node.
+        assert_eq!(nodes.iter().filter(|n| n.leaf != 0).count(), 4);
+        assert_eq!(nodes[0].count, 3);
+    }
+
+    #[test]
+    fn traversal_keeps_every_cluster_of_the_cut() {
+        let (clusters, levels) = dag();
+        let (nodes, order) = build_bvh(&clusters, &levels);
+        let clusters: Vec<Cluster> = order.iter().map(|&i| clusters[i]).collect();
+        let instance = MeshInstance { world_from_local: Mat4::IDENTITY, cluster_base: 0, cluster_count: 13, bvh_root: 0, _padding: 0, bounding_sphere: nodes[0].bounding_sphere };
+        let instances = [instance, MeshInstance { world_from_local: Mat4::from_translation(Vec3::new(0.0, 0.0, 500.0)), ..instance }];
+
+        for z in [2.0, 10.0, 30.0, 200.0] {
+            let view_proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 1000.0) * Mat4::look_at_rh(Vec3::new(0.0, 0.0, z), Vec3::ZERO, Vec3::Y);
+            let frustum = crate::frustum_planes(view_proj);
+            let view = LodView::new(view_proj, Vec2::new(1000.0, 1000.0), 1.0);
+            let queued = traverse(&nodes, &instances, &frustum, &view);
+
+            // The second instance is behind the camera.
+            assert!(queued.iter().all(|&[i, _]| i == 0), "camera at z = {}: {:?}", z, queued);
+            if z > 20.0 {
+                assert!(queued.len() <= 5, "camera at z = {}: the leaves are queued in {:?}", z, queued);
+            }
+            for c in select_clusters(&clusters, &instances[0], &view) {
+                assert!(queued.contains(&[0, c]), "camera at z = {}: {} not in {:?}", z, c, queued);
+            }
+        }
+    }
+}
@@ -1,3 +1,7 @@
+pub mod bvh;
+pub mod lod;
+pub mod shaders;
+
 use bytemuck::{Pod, Zeroable};
 use glam::{Vec4, Mat4};
 
@@ -8,10 +12,16 @@ pub struct Cluster {
     pub triangle_offset: u32,
     pub vertex_count: u32,
     pub triangle_count: u32,
+    /// Encloses the cluster. Clusters simplified from the same group share it, so they
+    /// always make the same LOD decision.
     pub bounding_sphere: Vec4, // 16字节
+    /// World-space error of this cluster relative to the original mesh, measured over `bounding_sphere`.
     pub error_metric: f32,     // 4字节
+    /// `error_metric` of the clusters this one was simplified into; `f32::MAX` for roots.
     pub parent_error: f32,     // 4字节
-    pub _padding: [f32; 2],    // 8字节，使总大小对齐到 16 的倍数 (48字节)
+    pub _padding: [f32; 2],    // 8字节，使 parent_sphere 对齐到 16 字节
+    /// `bounding_sphere` of the clusters this one was simplified into.
+    pub parent_sphere: Vec4,   // 16字节，总计 64 字节
 }
 
 #[repr(C)]
@@ -28,11 +38,138 @@ pub struct MeshInstance {
     pub world_from_local: Mat4,
     pub cluster_base: u32,
     pub cluster_count: u32,
-    pub _padding: [u32; 2],
+    /// Root of the mesh's nodes in the BVH buffer; see `BvhNode`.
+    pub bvh_root: u32,
+    pub _padding: u32,
+    /// Encloses every cluster of the mesh in its local space; see `mesh_bounds`.
+    pub bounding_sphere: Vec4, // 总计 96 字节
+}
+
+/// Most triangles in a cluster; the VisBuffer pass draws this many per visible cluster.
+pub const MAX_CLUSTER_TRIANGLES: u32 = 256;
+
+/// Header of a GPU queue of `(instance, cluster)` pairs, `WorkQueue` in `common.wgsl`.
+/// `dispatch` is the indirect dispatch of the pass consuming the queue.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Pod, Zeroable)]
+pub struct WorkQueue {
+    pub dispatch: [u32; 3],
+    pub count: u32,
+}
+
+impl WorkQueue {
+    /// What the host resets a queue to before the pass filling it.
+    pub const EMPTY: Self = Self { dispatch: [0, 1, 1], count: 0 };
+}
+
+/// A node of a mesh's cluster BVH, `BvhNode` in `common.wgsl`; see `bvh::build_bvh`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct BvhNode {
+    /// Encloses the `bounding_sphere` of every cluster below.
+    pub bounding_sphere: Vec4,
+    /// Encloses the `parent_sphere` of every cluster below.
+    pub lod_sphere: Vec4,
+    /// The largest `parent_error` below. Where it is invisible over `lod_sphere`, no cluster
+    /// below is in the LOD cut.
+    pub max_parent_error: f32,
+    /// Leaves: the clusters of one group, relative to `MeshInstance::cluster_base`. Inner
+    /// nodes: the children, relative to `MeshInstance::bvh_root`.
+    pub first: u32,
+    pub count: u32,
+    /// 1 for leaves, 0 for inner nodes.
+    pub leaf: u32, // 总计 48 字节
+}
+
+/// Header of the node queue of `bvh_cull.wgsl`. Entries below the instance count are the
+/// instances' roots and not stored; `count` entries are stored after them.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Pod, Zeroable)]
+pub struct NodeQueue {
+    /// Entries taken by the shader, possibly past the last one pushed.
+    pub next: u32,
+    pub count: u32,
+    /// Entries pushed but not yet processed; the traversal ends once it is 0.
+    pub pending: u32,
+    pub _padding: u32,
+}
+
+impl NodeQueue {
+    /// What the host resets the queue to before traversing the BVHs of `instance_count` instances.
+    pub fn new(instance_count: u32) -> Self {
+        Self { next: 0, count: 0, pending: instance_count, _padding: 0 }
+    }
 }
 
+/// A free word of the node queue's entries. The host fills a new queue with it; the shader
+/// frees every entry it takes, so the queue is ready for the next frame.
+pub const EMPTY_QUEUE_SLOT: u32 = u32::MAX;
+
 pub struct AdaptrixMesh {
     pub clusters: Vec<Cluster>,
     pub vertices: Vec<AdaptrixVertex>,
     pub indices: Vec<u32>,
-}
\ No newline at end of file
+    pub bvh: Vec<BvhNode>,
+}
+
+/// The smallest sphere enclosing both spheres.
+pub fn merge_spheres(a: Vec4, b: Vec4) -> Vec4 {
+    let distance = a.truncate().distance(b.truncate());
+    if distance + b.w <= a.w {
+        return a;
+    }
+    if distance + a.w <= b.w {
+        return b;
+    }
+    let radius = (distance + a.w + b.w) * 0.5;
+    let center = a.truncate() + (b.truncate() - a.truncate()) * ((radius - a.w) / distance);
+    center.extend(radius)
+}
+
+/// A sphere enclosing all of a mesh's `clusters`, for `MeshInstance::bounding_sphere`.
+pub fn mesh_bounds(clusters: &[Cluster]) -> Vec4 {
+    clusters.iter().map(|c| c.bounding_sphere).reduce(merge_spheres).unwrap_or(Vec4::ZERO)
+}
+
+/// The planes bounding the clip volume of `view_proj`, depth 0 to 1, for `View::frustum`:
+/// left, right, bottom, top, near, far. Points inside have `dot(xyz, p) + w >= 0`.
+pub fn frustum_planes(view_proj: Mat4) -> [Vec4; 6] {
+    let (x, y, z, w) = (view_proj.row(0), view_proj.row(1), view_proj.row(2), view_proj.row(3));
+    [w + x, w - x, w + y, w - y, z, w - z].map(|plane| plane / plane.truncate().length())
+}
+
+/// CPU version of `sphere_in_frustum` in `culling.wgsl`.
+pub fn is_sphere_in_frustum(frustum: &[Vec4; 6], sphere: Vec4) -> bool {
+    frustum.iter().all(|plane| plane.truncate().dot(sphere.truncate()) + plane.w >= -sphere.w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_spheres() {
+        let inner = Vec4::new(0.5, 0.0, 0.0, 0.5);
+        let outer = Vec4::new(0.0, 0.0, 0.0, 2.0);
+        assert_eq!(merge_spheres(inner, outer), outer);
+        assert_eq!(merge_spheres(outer, inner), outer);
+        assert_eq!(merge_spheres(Vec4::new(-1.0, 0.0, 0.0, 1.0), Vec4::new(3.0, 0.0, 0.0, 1.0)), Vec4::new(1.0, 0.0, 0.0, 3.0));
+    }
+
+    #[test]
+    fn frustum_planes_bound_the_clip_volume() {
+        let view_proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 100.0)
+            * Mat4::look_at_rh(glam::Vec3::ZERO, glam::Vec3::NEG_Z, glam::Vec3::Y);
+        let frustum = frustum_planes(view_proj);
+        // Planes through the near corners, the far plane at distance 100.
+        assert!((frustum[0] - Vec4::new(1.0, 0.0, -1.0, 0.0) / 2f32.sqrt()).length() < 1e-5, "{}", frustum[0]);
+        assert!((frustum[4] - Vec4::new(0.0, 0.0, -1.0, -1.0)).length() < 1e-5, "{}", frustum[4]);
+        assert!((frustum[5] - Vec4::new(0.0, 0.0, 1.0, 100.0)).length() < 1e-3, "{}", frustum[5]);
+
+        assert!(is_sphere_in_frustum(&frustum, Vec4::new(0.0, 0.0, -50.0, 1.0)));
+        assert!(is_sphere_in_frustum(&frustum, Vec4::new(0.0, 0.0, -0.5, 1.0)));
+        assert!(!is_sphere_in_frustum(&frustum, Vec4::new(0.0, 0.0, 0.5, 1.0)));
+        assert!(!is_sphere_in_frustum(&frustum, Vec4::new(12.0, 0.0, -10.0, 1.0)));
+        assert!(!is_sphere_in_frustum(&frustum, Vec4::new(0.0, 0.0, -102.0, 1.0)));
+    }
+}
@@ -0,0 +1,95 @@
+//! CPU reference of the LOD cut made by `cull.wgsl`, for tests and tools without a GPU.
+
+use crate::{Cluster, MeshInstance};
+use glam::{Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+
+/// What the cut depends on from the view uniform.
+#[derive(Copy, Clone, Debug)]
+pub struct LodView {
+    pub camera: Vec3,
+    /// Pixels per world unit at distance 1.
+    pub pixels_per_unit: f32,
+    pub error_threshold: f32,
+}
+
+impl LodView {
+    /// Derives the camera and projection scale from a perspective `view_proj`, as the shader does.
+    pub fn new(view_proj: Mat4, viewport_size: Vec2, error_threshold: f32) -> Self {
+        let camera = view_proj.inverse() * Vec4::new(0.0, 0.0, 1.0, 0.0);
+        Self {
+            camera: camera.xyz() / camera.w,
+            pixels_per_unit: view_proj.row(1).xyz().length() * viewport_size.y * 0.5,
+            error_threshold,
+        }
+    }
+
+    pub(crate) fn is_error_visible(&self, pixels_per_unit: f32, sphere: Vec4, error: f32) -> bool {
+        let distance = ((sphere.xyz() - self.camera).length() - sphere.w).max(0.0);
+        error > self.error_threshold * distance / pixels_per_unit
+    }
+}
+
+/// Whether the cut through the DAG for `view` draws `cluster` of an instance placed by `world_from_local`.
+pub fn is_cluster_selected(cluster: &Cluster, world_from_local: Mat4, view: &LodView) -> bool {
+    let scale = max_scale(world_from_local);
+    let transform = |sphere: Vec4| transform_sphere(world_from_local, scale, sphere);
+    let pixels_per_unit = view.pixels_per_unit * scale;
+    !view.is_error_visible(pixels_per_unit, transform(cluster.bounding_sphere), cluster.error_metric)
+        && view.is_error_visible(pixels_per_unit, transform(cluster.parent_sphere), cluster.parent_error)
+}
+
+/// The largest factor `world_from_local` scales lengths by.
+pub(crate) fn max_scale(world_from_local: Mat4) -> f32 {
+    world_from_local.x_axis.xyz().length()
+        .max(world_from_local.y_axis.xyz().length())
+        .max(world_from_local.z_axis.xyz().length())
+}
+
+pub(crate) fn transform_sphere(world_from_local: Mat4, scale: f32, sphere: Vec4) -> Vec4 {
+    world_from_local.transform_point3(sphere.xyz()).extend(sphere.w * scale)
+}
+
+/// Indices into `clusters` of the instance's clusters in the cut, in order.
+pub fn select_clusters(clusters: &[Cluster], instance: &MeshInstance, view: &LodView) -> Vec<u32> {
+    let range = instance.cluster_base..instance.cluster_base + instance.cluster_count;
+    range.filter(|&i| is_cluster_selected(&clusters[i as usize], instance.world_from_local, view)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    fn cluster(sphere: Vec4, error: f32, parent_sphere: Vec4, parent_error: f32) -> Cluster {
+        Cluster { bounding_sphere: sphere, error_metric: error, parent_sphere, parent_error, ..Cluster::zeroed() }
+    }
+
+    #[test]
+    fn selects_one_level_per_distance() {
+        // Two leaves simplified into one root with an error of 0.01 units.
+        let root = Vec4::new(0.0, 0.0, 0.0, 2.0);
+        let clusters = [
+            cluster(Vec4::new(-1.0, 0.0, 0.0, 1.0), 0.0, root, 0.01),
+            cluster(Vec4::new(1.0, 0.0, 0.0, 1.0), 0.0, root, 0.01),
+            cluster(root, 0.01, root, f32::MAX),
+        ];
+        let instance = MeshInstance { world_from_local: Mat4::IDENTITY, cluster_base: 0, cluster_count: 3, bvh_root: 0, _padding: 0, bounding_sphere: root };
+        let view_at = |z: f32| {
+            let view_proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 1000.0) * Mat4::look_at_rh(Vec3::new(0.0, 0.0, z), Vec3::ZERO, Vec3::Y);
+            LodView::new(view_proj, Vec2::new(1000.0, 1000.0), 1.0)
+        };
+        let view = view_at(10.0);
+        assert!((view.camera - Vec3::new(0.0, 0.0, 10.0)).length() < 1e-3, "{}", view.camera);
+        assert!((view.pixels_per_unit - 500.0).abs() < 1e-2, "{}", view.pixels_per_unit);
+
+        // 0.01 units are 1 pixel 5 units away from the root's sphere.
+        assert_eq!(select_clusters(&clusters, &instance, &view_at(6.0)), [0, 1]);
+        assert_eq!(select_clusters(&clusters, &instance, &view_at(8.0)), [2]);
+        assert_eq!(select_clusters(&clusters, &instance, &view_at(1.0)), [0, 1]);
+
+        // Scaling the instance up scales its errors and spheres, so the root is used farther away.
+        let scaled = MeshInstance { world_from_local: Mat4::from_scale(Vec3::splat(2.0)), ..instance };
+        assert_eq!(select_clusters(&clusters, &scaled, &view_at(12.0)), [0, 1]);
+        assert_eq!(select_clusters(&clusters, &scaled, &view_at(16.0)), [2]);
+    }
+}
@@ -0,0 +1,56 @@
+//! WGSL sources of the Adaptrix passes, keyed by file name so `#include`s can be
+//! resolved without touching the disk (e.g. with `lume_core::shader::MemoryResolver`).
+
+pub const SOURCES: &[(&str, &str)] = &[
+    ("common.wgsl", include_str!("shaders/common.wgsl")),
+    ("culling.wgsl", include_str!("shaders/culling.wgsl")),
+    ("occlusion.wgsl", include_str!("shaders/occlusion.wgsl")),
+    ("instance_cull.wgsl", include_str!("shaders/instance_cull.wgsl")),
+    ("bvh_cull.wgsl", include_str!("shaders/bvh_cull.wgsl")),
+    ("cull.wgsl", include_str!("shaders/cull.wgsl")),
+    ("hzb.wgsl", include_str!("shaders/hzb.wgsl")),
+    ("visbuffer.vert.wgsl", include_str!("shaders/visbuffer.vert.wgsl")),
+    ("visbuffer.frag.wgsl", include_str!("shaders/visbuffer.frag.wgsl")),
+    ("resolve.vert.wgsl", include_str!("shaders/resolve.vert.wgsl")),
+    ("resolve.frag.wgsl", include_str!("shaders/resolve.frag.wgsl")),
+];
+
+#[cfg(test)]
+mod tests {
+    use lume_core::shader::{compile_shader_to, MemoryResolver, ShaderSource, ShaderTarget};
+    use std::path::Path;
+
+    /// Declarations included by the passes, without entry points of their own.
+    const INCLUDES: [&str; 3] = ["common.wgsl", "culling.wgsl", "occlusion.wgsl"];
+
+    /// Compares each pass against `shaders/golden/<file>.<ext>`. Set `LUME_BLESS=1` to
+    /// rewrite the golden files after an intended change.
+    #[test]
+    fn cross_compiles_to_golden_outputs() {
+        let resolver = MemoryResolver::new(super::SOURCES);
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/shaders/golden");
+        let bless = std::env::var_os("LUME_BLESS").is_some();
+        let targets = [
+            ("metal", ShaderTarget::Msl { version: (2, 1) }),
+            ("es.glsl", ShaderTarget::GlslEs { version: 310, entry_point: "main".to_string() }),
+            ("hlsl", ShaderTarget::Hlsl { shader_model: (5, 1) }),
+        ];
+
+        let mut mismatches = Vec::new();
+        for (file, _) in super::SOURCES.iter().filter(|(file, _)| !INCLUDES.contains(file)) {
+            for (extension, target) in &targets {
+                let source = ShaderSource::WgslFile { path: file, resolver: &resolver, defines: Default::default() };
+                let output = compile_shader_to(source, target).unwrap_or_else(|e| panic!("{} to {:?}: {}", file, target, e));
+                let path = golden.join(format!("{}.{}", file.trim_end_matches(".wgsl"), extension));
+                let output = output.source().unwrap();
+                if bless {
+                    std::fs::create_dir_all(&golden).unwrap();
+                    std::fs::write(&path, output).unwrap();
+                } else if std::fs::read_to_string(&path).ok().as_deref() != Some(output) {
+                    mismatches.push(path.display().to_string());
+                }
+            }
+        }
+        assert!(mismatches.is_empty(), "Outputs differ from {:?}; rerun with LUME_BLESS=1 if intended", mismatches);
+    }
+}
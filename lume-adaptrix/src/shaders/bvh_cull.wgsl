@@ -0,0 +1,96 @@
+// First culling stage for large meshes: walks every instance's cluster BVH, descending into
+// the nodes inside the frustum that may hold clusters of the LOD cut, and queues the clusters
+// of the visible leaves for `cull.wgsl`, like `instance_cull.wgsl` does for whole meshes.
+//
+// Persistent threads: a fixed number of workgroups takes (instance, node) entries from one
+// global queue until it is drained. Entries below the instance count are the instances'
+// roots, implied rather than stored; children are pushed after them. Threads only spin in
+// the outer loop, so lanes of one subgroup waiting for an entry never block its producer.
+
+#include "culling.wgsl"
+
+@group(0) @binding(0) var<storage, read> nodes: array<BvhNode>;
+@group(0) @binding(1) var<storage, read> instances: array<MeshInstance>;
+// `NodeQueue` in lume-adaptrix/src/lib.rs. `pending` counts entries pushed but not yet
+// processed: once it is 0, no entry will ever be pushed again.
+@group(0) @binding(2) var<storage, read_write> node_queue: array<atomic<u32>, 4>;
+// Two words per entry, EMPTY_SLOT until pushed, and again once taken.
+@group(0) @binding(3) var<storage, read_write> node_entries: array<atomic<u32>>;
+@group(0) @binding(4) var<storage, read_write> work_queue: WorkQueue;
+@group(0) @binding(5) var<storage, read_write> queue: array<vec2<u32>>;
+
+const NODE_QUEUE_NEXT: u32 = 0u;
+const NODE_QUEUE_COUNT: u32 = 1u;
+const NODE_QUEUE_PENDING: u32 = 2u;
+const EMPTY_SLOT: u32 = 0xffffffffu;
+const NONE: u32 = 0xffffffffu;
+
+fn push_node(entry: vec2<u32>) {
+    let slot = atomicAdd(&node_queue[NODE_QUEUE_COUNT], 1u);
+    if (slot < arrayLength(&node_entries) / 2u) {
+        atomicAdd(&node_queue[NODE_QUEUE_PENDING], 1u);
+        atomicStore(&node_entries[slot * 2u], entry.x);
+        atomicStore(&node_entries[slot * 2u + 1u], entry.y);
+    }
+}
+
+fn push_clusters(instance: u32, first: u32, count: u32) {
+    let start = atomicAdd(&work_queue[QUEUE_COUNT], count);
+    atomicMax(&work_queue[QUEUE_DISPATCH_X], (start + count + 63u) / 64u);
+    for (var i = 0u; i < count && start + i < arrayLength(&queue); i = i + 1u) {
+        queue[start + i] = vec2<u32>(instance, first + i);
+    }
+}
+
+fn process(entry: vec2<u32>) {
+    let instance = instances[entry.x];
+    let node = nodes[instance.bvh_root + entry.y];
+    let m = instance.world_from_local;
+    let scale = max_scale(m);
+    if (!sphere_in_frustum(transform_sphere(m, scale, node.bounding_sphere))) {
+        return;
+    }
+    // Where even the largest parent error below is invisible, every cluster below is finer than the cut.
+    if (!is_error_visible(view_camera(), view_pixels_per_unit(scale), transform_sphere(m, scale, node.lod_sphere), node.max_parent_error)) {
+        return;
+    }
+    if (node.leaf != 0u) {
+        push_clusters(entry.x, instance.cluster_base + node.first, node.count);
+        return;
+    }
+    for (var i = 0u; i < node.count; i = i + 1u) {
+        push_node(vec2<u32>(entry.x, node.first + i));
+    }
+}
+
+@compute @workgroup_size(64)
+fn main() {
+    let roots = arrayLength(&instances);
+    var taken = NONE;
+    loop {
+        if (taken == NONE) {
+            taken = atomicAdd(&node_queue[NODE_QUEUE_NEXT], 1u);
+        }
+        var entry = vec2<u32>(taken, 0u);
+        if (taken >= roots) {
+            // The entry may not be written yet; come back for it, unless nothing can push it any more.
+            let slot = (taken - roots) * 2u;
+            if (slot < arrayLength(&node_entries)) {
+                entry = vec2<u32>(atomicLoad(&node_entries[slot]), atomicLoad(&node_entries[slot + 1u]));
+            } else {
+                entry = vec2<u32>(EMPTY_SLOT);
+            }
+            if (entry.x == EMPTY_SLOT || entry.y == EMPTY_SLOT) {
+                if (atomicLoad(&node_queue[NODE_QUEUE_PENDING]) == 0u) {
+                    break;
+                }
+                continue;
+            }
+            atomicStore(&node_entries[slot], EMPTY_SLOT);
+            atomicStore(&node_entries[slot + 1u], EMPTY_SLOT);
+        }
+        taken = NONE;
+        process(entry);
+        atomicSub(&node_queue[NODE_QUEUE_PENDING], 1u);
+    }
+}
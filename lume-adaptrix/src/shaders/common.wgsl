@@ -0,0 +1,66 @@
+// Declarations shared by the Adaptrix passes. Layouts match the host-side structs
+// in lume-adaptrix/src/lib.rs and the view uniform written by the renderer.
+
+struct Cluster {
+    vertex_offset: u32,
+    triangle_offset: u32,
+    vertex_count: u32,
+    triangle_count: u32,
+    bounding_sphere: vec4<f32>,
+    error_metric: f32,
+    parent_error: f32,
+    pad0: f32,
+    pad1: f32,
+    parent_sphere: vec4<f32>,
+};
+
+struct AdaptrixVertex {
+    px: f32, py: f32, pz: f32,
+    nx: f32, ny: f32, nz: f32,
+    u: f32, v: f32,
+};
+
+struct MeshInstance {
+    world_from_local: mat4x4<f32>,
+    cluster_base: u32,
+    cluster_count: u32,
+    bvh_root: u32,
+    pad0: u32,
+    bounding_sphere: vec4<f32>,
+};
+
+// `first` and `count` are the clusters of one group in leaves, relative to the instance's
+// `cluster_base`, and the children in inner nodes, relative to its `bvh_root`.
+struct BvhNode {
+    bounding_sphere: vec4<f32>,
+    lod_sphere: vec4<f32>,
+    max_parent_error: f32,
+    first: u32,
+    count: u32,
+    leaf: u32,
+};
+
+// Header of a queue of (instance, cluster) pairs. The first three words are the indirect
+// dispatch of the pass consuming the queue, one 64-wide workgroup per 64 entries, the last
+// the entry count; the host resets it to (0, 1, 1, 0) before the pass filling it. Arrays
+// rather than structs, so SPIR-V consumers can turn them back into atomics.
+alias WorkQueue = array<atomic<u32>, 4>;
+const QUEUE_DISPATCH_X: u32 = 0u;
+const QUEUE_COUNT: u32 = 3u;
+
+// Indirect draw of the visible clusters, one instance per cluster. The host sets the vertex
+// count to three per triangle of the largest cluster and the rest to 0.
+alias DrawArgs = array<atomic<u32>, 4>;
+const DRAW_INSTANCE_COUNT: u32 = 1u;
+
+// A VisBuffer texel holds the InstanceID in x and (ClusterID + 1) << PRIMITIVE_ID_BITS |
+// PrimitiveID in y, so the y = 0 the clear leaves marks the background even for cluster 0.
+const PRIMITIVE_ID_BITS: u32 = 10u;
+
+struct View {
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    frustum: array<vec4<f32>, 6>,
+    viewport_size: vec2<f32>,
+    error_threshold: f32,
+};
@@ -1,66 +1,80 @@
-struct Cluster {
-    vertex_offset: u32,
-    triangle_offset: u32,
-    vertex_count: u32,
-    triangle_count: u32,
-    bounding_sphere: vec4<f32>,
-    error_metric: f32,
-    parent_error: f32,
-};
+// Second culling stage: frustum, LOD and occlusion tests of the (instance, cluster) pairs
+// queued by `instance_cull.wgsl` or `bvh_cull.wgsl`, appending the visible ones to an indirect draw.
 
-struct MeshInstance {
-    world_from_local: mat4x4<f32>,
-    cluster_base: u32,
-    cluster_count: u32,
-};
+#include "culling.wgsl"
 
-struct View {
-    view_proj: mat4x4<f32>,
-    frustum: array<vec4<f32>, 6>,
-    viewport_size: vec2<f32>,
-    error_threshold: f32,
-};
+#ifdef OCCLUSION_FIRST_PASS
+#include "occlusion.wgsl"
+#define OCCLUSION_CULLING
+#endif
+#ifdef OCCLUSION_SECOND_PASS
+#include "occlusion.wgsl"
+#define OCCLUSION_CULLING
+#endif
 
 @group(0) @binding(0) var<storage, read> clusters: array<Cluster>;
 @group(0) @binding(1) var<storage, read> instances: array<MeshInstance>;
-@group(0) @binding(2) var<storage, read_write> visible_clusters: array<u32>;
-@group(0) @binding(3) var<storage, read_write> visible_count: atomic<u32>;
-
-@group(1) @binding(0) var<uniform> view: View;
-
-fn sphere_in_frustum(sphere: vec4<f32>) -> bool {
-    for (var i = 0; i < 6; i = i + 1) {
-        if (dot(view.frustum[i].xyz, sphere.xyz) + view.frustum[i].w < -sphere.w) {
-            return false;
-        }
-    }
-    return true;
-}
+@group(0) @binding(2) var<storage, read_write> visible_clusters: array<vec2<u32>>;
+@group(0) @binding(3) var<storage, read_write> draw_args: DrawArgs;
+@group(0) @binding(4) var<storage, read_write> work_queue: WorkQueue;
+@group(0) @binding(5) var<storage, read> queue: array<vec2<u32>>;
 
+// One invocation per queued pair. The second pass takes its pairs from the first's
+// occlusion queue instead, which may hold whole instances, so it repeats every test.
 @compute @workgroup_size(64)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let cluster_idx = global_id.x;
-    // For simplicity, assume one instance for now or a global cluster list
-    // In a real system, we'd have a nested culling or a flat list of potential clusters
-    
-    if (cluster_idx >= arrayLength(&clusters)) {
+#ifdef OCCLUSION_SECOND_PASS
+    if (global_id.x >= min(atomicLoad(&occluded_queue[QUEUE_COUNT]), arrayLength(&occluded))) {
         return;
     }
+    let entry = occluded[global_id.x];
+#else
+    if (global_id.x >= min(atomicLoad(&work_queue[QUEUE_COUNT]), arrayLength(&queue))) {
+        return;
+    }
+    let entry = queue[global_id.x];
+#endif
+    if (entry.x >= arrayLength(&instances) || entry.y >= arrayLength(&clusters)) {
+        return;
+    }
+
+    let instance = instances[entry.x];
+    let cluster = clusters[entry.y];
+    let m = instance.world_from_local;
+    let scale = max_scale(m);
+    let bounding_sphere = transform_sphere(m, scale, cluster.bounding_sphere);
 
-    let cluster = clusters[cluster_idx];
-    
     // Frustum culling
-    if (!sphere_in_frustum(cluster.bounding_sphere)) {
+    if (!sphere_in_frustum(bounding_sphere)) {
         return;
     }
 
-    // LOD selection (simplified)
-    // In Nanite/Adaptrix, we select clusters based on screen-space error
-    // For this prototype, we'll just accept everything if error_metric == 0
-    // and if error_metric > 0, we check if it's small enough.
-    
-    // TODO: Implement more complex LOD selection
-    
-    let idx = atomicAdd(&visible_count, 1u);
-    visible_clusters[idx] = cluster_idx;
+    // LOD selection: draw the cluster if its own error is invisible but its parent's is not.
+    // Errors and spheres grow monotonically up the DAG, so exactly one cluster is drawn along
+    // every path from a leaf to a root.
+    let camera = view_camera();
+    let pixels_per_unit = view_pixels_per_unit(scale);
+    if (is_error_visible(camera, pixels_per_unit, bounding_sphere, cluster.error_metric)) {
+        return;
+    }
+    if (!is_error_visible(camera, pixels_per_unit, transform_sphere(m, scale, cluster.parent_sphere), cluster.parent_error)) {
+        return;
+    }
+
+#ifdef OCCLUSION_CULLING
+    if (is_occluded(bounding_sphere)) {
+#ifdef OCCLUSION_FIRST_PASS
+        let slot = reserve_occluded(1u);
+        if (slot < arrayLength(&occluded)) {
+            occluded[slot] = entry;
+        }
+#endif
+        return;
+    }
+#endif
+
+    let idx = atomicAdd(&draw_args[DRAW_INSTANCE_COUNT], 1u);
+    if (idx < arrayLength(&visible_clusters)) {
+        visible_clusters[idx] = entry;
+    }
 }
@@ -0,0 +1,44 @@
+// Tests shared by the instance and cluster culling passes.
+
+#include "common.wgsl"
+
+@group(1) @binding(0) var<uniform> view: View;
+
+fn sphere_in_frustum(sphere: vec4<f32>) -> bool {
+    for (var i = 0; i < 6; i = i + 1) {
+        if (dot(view.frustum[i].xyz, sphere.xyz) + view.frustum[i].w < -sphere.w) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// The largest factor `world_from_local` scales lengths by.
+fn max_scale(world_from_local: mat4x4<f32>) -> f32 {
+    let m = world_from_local;
+    return max(length(m[0].xyz), max(length(m[1].xyz), length(m[2].xyz)));
+}
+
+fn transform_sphere(world_from_local: mat4x4<f32>, scale: f32, sphere: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>((world_from_local * vec4<f32>(sphere.xyz, 1.0)).xyz, sphere.w * scale);
+}
+
+fn view_camera() -> vec3<f32> {
+    let camera = view.inv_view_proj * vec4<f32>(0.0, 0.0, 1.0, 0.0);
+    return camera.xyz / camera.w;
+}
+
+// Converts local units of an instance scaled by `scale` to pixels at distance 1. Row 1 of
+// view_proj is the projection's y scale times the camera's unit up vector.
+fn view_pixels_per_unit(scale: f32) -> f32 {
+    let vp = view.view_proj;
+    return length(vec3<f32>(vp[0].y, vp[1].y, vp[2].y)) * view.viewport_size.y * 0.5 * scale;
+}
+
+// Whether `error` projects to more than `view.error_threshold` pixels at the point of the
+// world-space `sphere` closest to the camera. Dividing by `pixels_per_unit` keeps the roots'
+// parent error of f32::MAX finite.
+fn is_error_visible(camera: vec3<f32>, pixels_per_unit: f32, sphere: vec4<f32>, error: f32) -> bool {
+    let distance = max(length(sphere.xyz - camera) - sphere.w, 0.0);
+    return error > view.error_threshold * distance / pixels_per_unit;
+}
@@ -0,0 +1,253 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    vec4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    vec4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    mat4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    vec4 bounding_sphere;
+};
+struct BvhNode {
+    vec4 bounding_sphere;
+    vec4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct View {
+    mat4x4 view_proj;
+    mat4x4 inv_view_proj;
+    vec4 frustum[6];
+    vec2 viewport_size;
+    float error_threshold;
+};
+const uint QUEUE_DISPATCH_X = 0u;
+const uint QUEUE_COUNT = 3u;
+const uint DRAW_INSTANCE_COUNT = 1u;
+const uint PRIMITIVE_ID_BITS = 10u;
+const uint NODE_QUEUE_NEXT = 0u;
+const uint NODE_QUEUE_COUNT = 1u;
+const uint NODE_QUEUE_PENDING = 2u;
+const uint EMPTY_SLOT = 4294967295u;
+const uint NONE = 4294967295u;
+
+layout(std140, binding = 6) uniform View_block_0Compute { View _group_1_binding_0_cs; };
+
+layout(std430, binding = 0) readonly buffer type_10_block_1Compute { BvhNode _group_0_binding_0_cs[]; };
+
+layout(std430, binding = 1) readonly buffer type_11_block_2Compute { MeshInstance _group_0_binding_1_cs[]; };
+
+layout(std430, binding = 2) buffer type_12_block_3Compute { uint _group_0_binding_2_cs[4]; };
+
+layout(std430, binding = 3) buffer type_13_block_4Compute { uint _group_0_binding_3_cs[]; };
+
+layout(std430, binding = 4) buffer WorkQueue_block_5Compute { uint _group_0_binding_4_cs[4]; };
+
+layout(std430, binding = 5) buffer type_15_block_6Compute { uvec2 _group_0_binding_5_cs[]; };
+
+
+bool sphere_in_frustum(vec4 sphere) {
+    int i = 0;
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            int _e25 = i;
+            i = (_e25 + 1);
+        }
+        loop_init = false;
+        int _e3 = i;
+        if ((_e3 < 6)) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i;
+            vec4 _e10 = _group_1_binding_0_cs.frustum[_e8];
+            int _e16 = i;
+            float _e19 = _group_1_binding_0_cs.frustum[_e16].w;
+            if (((dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w))) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(mat4x4 world_from_local) {
+    return max(length(world_from_local[0].xyz), max(length(world_from_local[1].xyz), length(world_from_local[2].xyz)));
+}
+
+vec4 transform_sphere(mat4x4 world_from_local_1, float scale, vec4 sphere_1) {
+    return vec4((world_from_local_1 * vec4(sphere_1.xyz, 1.0)).xyz, (sphere_1.w * scale));
+}
+
+vec3 view_camera() {
+    mat4x4 _e2 = _group_1_binding_0_cs.inv_view_proj;
+    vec4 camera_1 = (_e2 * vec4(0.0, 0.0, 1.0, 0.0));
+    return (camera_1.xyz / vec3(camera_1.w));
+}
+
+float view_pixels_per_unit(float scale_1) {
+    mat4x4 vp = _group_1_binding_0_cs.view_proj;
+    float _e15 = _group_1_binding_0_cs.viewport_size.y;
+    return (((length(vec3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1);
+}
+
+bool is_error_visible(vec3 camera, float pixels_per_unit, vec4 sphere_2, float error) {
+    float distance_ = max((length((sphere_2.xyz - camera)) - sphere_2.w), 0.0);
+    float _e13 = _group_1_binding_0_cs.error_threshold;
+    return (error > ((_e13 * distance_) / pixels_per_unit));
+}
+
+void push_node(uvec2 entry_1) {
+    uint _e4 = atomicAdd(_group_0_binding_2_cs[1], 1u);
+    if ((_e4 < (uint(_group_0_binding_3_cs.length()) / 2u))) {
+        uint _e13 = atomicAdd(_group_0_binding_2_cs[2], 1u);
+        _group_0_binding_3_cs[(_e4 * 2u)] = entry_1.x;
+        _group_0_binding_3_cs[((_e4 * 2u) + 1u)] = entry_1.y;
+        return;
+    } else {
+        return;
+    }
+}
+
+void push_clusters(uint instance, uint first, uint count) {
+    uint i_1 = 0u;
+    uint _e5 = atomicAdd(_group_0_binding_4_cs[3], count);
+    uint _e13 = atomicMax(_group_0_binding_4_cs[0], (((_e5 + count) + 63u) / 64u));
+    bool loop_init_1 = true;
+    while(true) {
+        if (!loop_init_1) {
+            uint _e31 = i_1;
+            i_1 = (_e31 + 1u);
+        }
+        loop_init_1 = false;
+        uint _e16 = i_1;
+        uint _e18 = i_1;
+        if (((_e16 < count) && ((_e5 + _e18) < uint(_group_0_binding_5_cs.length())))) {
+        } else {
+            break;
+        }
+        {
+            uint _e25 = i_1;
+            uint _e28 = i_1;
+            _group_0_binding_5_cs[(_e5 + _e25)] = uvec2(instance, (first + _e28));
+        }
+    }
+    return;
+}
+
+void process(uvec2 entry_2) {
+    uint i_2 = 0u;
+    MeshInstance instance_1 = _group_0_binding_1_cs[entry_2.x];
+    BvhNode node = _group_0_binding_0_cs[(instance_1.bvh_root + entry_2.y)];
+    mat4x4 m = instance_1.world_from_local;
+    float _e12 = max_scale(m);
+    vec4 _e14 = transform_sphere(m, _e12, node.bounding_sphere);
+    bool _e15 = sphere_in_frustum(_e14);
+    if (!(_e15)) {
+        return;
+    }
+    vec3 _e17 = view_camera();
+    float _e18 = view_pixels_per_unit(_e12);
+    vec4 _e20 = transform_sphere(m, _e12, node.lod_sphere);
+    bool _e22 = is_error_visible(_e17, _e18, _e20, node.max_parent_error);
+    if (!(_e22)) {
+        return;
+    }
+    if ((node.leaf != 0u)) {
+        push_clusters(entry_2.x, (instance_1.cluster_base + node.first), node.count);
+        return;
+    }
+    bool loop_init_2 = true;
+    while(true) {
+        if (!loop_init_2) {
+            uint _e42 = i_2;
+            i_2 = (_e42 + 1u);
+        }
+        loop_init_2 = false;
+        uint _e34 = i_2;
+        if ((_e34 < node.count)) {
+        } else {
+            break;
+        }
+        {
+            uint _e39 = i_2;
+            push_node(uvec2(entry_2.x, (node.first + _e39)));
+        }
+    }
+    return;
+}
+
+void main() {
+    uint taken = NONE;
+    uvec2 entry = uvec2(0u);
+    uint roots = uint(_group_0_binding_1_cs.length());
+    while(true) {
+        uint _e4 = taken;
+        if ((_e4 == NONE)) {
+            uint _e10 = atomicAdd(_group_0_binding_2_cs[0], 1u);
+            taken = _e10;
+        }
+        uint _e11 = taken;
+        entry = uvec2(_e11, 0u);
+        uint _e15 = taken;
+        if ((_e15 >= roots)) {
+            uint _e17 = taken;
+            uint slot = ((_e17 - roots) * 2u);
+            if ((slot < uint(_group_0_binding_3_cs.length()))) {
+                uint _e26 = _group_0_binding_3_cs[slot];
+                uint _e31 = _group_0_binding_3_cs[(slot + 1u)];
+                entry = uvec2(_e26, _e31);
+            } else {
+                entry = uvec2(4294967295u);
+            }
+            uint _e36 = entry.x;
+            uint _e40 = entry.y;
+            if (((_e36 == EMPTY_SLOT) || (_e40 == EMPTY_SLOT))) {
+                uint _e46 = _group_0_binding_2_cs[2];
+                if ((_e46 == 0u)) {
+                    break;
+                }
+                continue;
+            }
+            _group_0_binding_3_cs[slot] = EMPTY_SLOT;
+            _group_0_binding_3_cs[(slot + 1u)] = EMPTY_SLOT;
+        }
+        taken = NONE;
+        uvec2 _e58 = entry;
+        process(_e58);
+        uint _e62 = atomicAdd(_group_0_binding_2_cs[2], -1u);
+    }
+    return;
+}
+
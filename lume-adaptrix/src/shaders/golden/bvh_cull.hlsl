@@ -0,0 +1,295 @@
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    float4 parent_sphere;
+};
+
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+
+struct MeshInstance {
+    row_major float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    float4 bounding_sphere;
+};
+
+struct BvhNode {
+    float4 bounding_sphere;
+    float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+
+struct View {
+    row_major float4x4 view_proj;
+    row_major float4x4 inv_view_proj;
+    float4 frustum[6];
+    float2 viewport_size;
+    float error_threshold;
+    int _end_pad_0;
+};
+
+static const uint QUEUE_DISPATCH_X = 0u;
+static const uint QUEUE_COUNT = 3u;
+static const uint DRAW_INSTANCE_COUNT = 1u;
+static const uint PRIMITIVE_ID_BITS = 10u;
+static const uint NODE_QUEUE_NEXT = 0u;
+static const uint NODE_QUEUE_COUNT = 1u;
+static const uint NODE_QUEUE_PENDING = 2u;
+static const uint EMPTY_SLOT = 4294967295u;
+static const uint NONE = 4294967295u;
+
+cbuffer view : register(b0, space1) { View view; }
+ByteAddressBuffer nodes : register(t0);
+ByteAddressBuffer instances : register(t1);
+RWByteAddressBuffer node_queue : register(u2);
+RWByteAddressBuffer node_entries : register(u3);
+RWByteAddressBuffer work_queue : register(u4);
+RWByteAddressBuffer queue : register(u5);
+
+bool sphere_in_frustum(float4 sphere)
+{
+    int i = 0;
+
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            int _e25 = i;
+            i = (_e25 + 1);
+        }
+        loop_init = false;
+        int _e3 = i;
+        if ((_e3 < 6)) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i;
+            float4 _e10 = view.frustum[_e8];
+            int _e16 = i;
+            float _e19 = view.frustum[_e16].w;
+            if (((dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w))) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(float4x4 world_from_local)
+{
+    return max(length(world_from_local[0].xyz), max(length(world_from_local[1].xyz), length(world_from_local[2].xyz)));
+}
+
+float4 transform_sphere(float4x4 world_from_local_1, float scale, float4 sphere_1)
+{
+    return float4(mul(float4(sphere_1.xyz, 1.0), world_from_local_1).xyz, (sphere_1.w * scale));
+}
+
+float3 view_camera()
+{
+    float4x4 _e2 = view.inv_view_proj;
+    float4 camera_1 = mul(float4(0.0, 0.0, 1.0, 0.0), _e2);
+    return (camera_1.xyz / (camera_1.w).xxx);
+}
+
+float view_pixels_per_unit(float scale_1)
+{
+    float4x4 vp = view.view_proj;
+    float _e15 = view.viewport_size.y;
+    return (((length(float3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1);
+}
+
+bool is_error_visible(float3 camera, float pixels_per_unit, float4 sphere_2, float error)
+{
+    float distance_ = max((length((sphere_2.xyz - camera)) - sphere_2.w), 0.0);
+    float _e13 = view.error_threshold;
+    return (error > ((_e13 * distance_) / pixels_per_unit));
+}
+
+uint NagaBufferLengthRW(RWByteAddressBuffer buffer)
+{
+    uint ret;
+    buffer.GetDimensions(ret);
+    return ret;
+}
+
+void push_node(uint2 entry_1)
+{
+    uint _e4; node_queue.InterlockedAdd(4, 1u, _e4);
+    if ((_e4 < (((NagaBufferLengthRW(node_entries) - 0) / 4) / 2u))) {
+        uint _e13; node_queue.InterlockedAdd(8, 1u, _e13);
+        node_entries.Store((_e4 * 2u)*4, asuint(entry_1.x));
+        node_entries.Store(((_e4 * 2u) + 1u)*4, asuint(entry_1.y));
+        return;
+    } else {
+        return;
+    }
+}
+
+void push_clusters(uint instance, uint first, uint count)
+{
+    uint i_1 = 0u;
+
+    uint _e5; work_queue.InterlockedAdd(12, count, _e5);
+    uint _e13; work_queue.InterlockedMax(0, (((_e5 + count) + 63u) / 64u), _e13);
+    bool loop_init_1 = true;
+    while(true) {
+        if (!loop_init_1) {
+            uint _e31 = i_1;
+            i_1 = (_e31 + 1u);
+        }
+        loop_init_1 = false;
+        uint _e16 = i_1;
+        uint _e18 = i_1;
+        if (((_e16 < count) && ((_e5 + _e18) < ((NagaBufferLengthRW(queue) - 0) / 8)))) {
+        } else {
+            break;
+        }
+        {
+            uint _e25 = i_1;
+            uint _e28 = i_1;
+            queue.Store2((_e5 + _e25)*8, asuint(uint2(instance, (first + _e28))));
+        }
+    }
+    return;
+}
+
+MeshInstance ConstructMeshInstance(float4x4 arg0, uint arg1, uint arg2, uint arg3, uint arg4, float4 arg5) {
+    MeshInstance ret = (MeshInstance)0;
+    ret.world_from_local = arg0;
+    ret.cluster_base = arg1;
+    ret.cluster_count = arg2;
+    ret.bvh_root = arg3;
+    ret.pad0_ = arg4;
+    ret.bounding_sphere = arg5;
+    return ret;
+}
+
+BvhNode ConstructBvhNode(float4 arg0, float4 arg1, float arg2, uint arg3, uint arg4, uint arg5) {
+    BvhNode ret = (BvhNode)0;
+    ret.bounding_sphere = arg0;
+    ret.lod_sphere = arg1;
+    ret.max_parent_error = arg2;
+    ret.first = arg3;
+    ret.count = arg4;
+    ret.leaf = arg5;
+    return ret;
+}
+
+void process(uint2 entry_2)
+{
+    uint i_2 = 0u;
+
+    MeshInstance instance_1 = ConstructMeshInstance(float4x4(asfloat(instances.Load4(entry_2.x*96+0+0)), asfloat(instances.Load4(entry_2.x*96+0+16)), asfloat(instances.Load4(entry_2.x*96+0+32)), asfloat(instances.Load4(entry_2.x*96+0+48))), asuint(instances.Load(entry_2.x*96+64)), asuint(instances.Load(entry_2.x*96+68)), asuint(instances.Load(entry_2.x*96+72)), asuint(instances.Load(entry_2.x*96+76)), asfloat(instances.Load4(entry_2.x*96+80)));
+    BvhNode node = ConstructBvhNode(asfloat(nodes.Load4((instance_1.bvh_root + entry_2.y)*48+0)), asfloat(nodes.Load4((instance_1.bvh_root + entry_2.y)*48+16)), asfloat(nodes.Load((instance_1.bvh_root + entry_2.y)*48+32)), asuint(nodes.Load((instance_1.bvh_root + entry_2.y)*48+36)), asuint(nodes.Load((instance_1.bvh_root + entry_2.y)*48+40)), asuint(nodes.Load((instance_1.bvh_root + entry_2.y)*48+44)));
+    float4x4 m = instance_1.world_from_local;
+    const float _e12 = max_scale(m);
+    const float4 _e14 = transform_sphere(m, _e12, node.bounding_sphere);
+    const bool _e15 = sphere_in_frustum(_e14);
+    if (!(_e15)) {
+        return;
+    }
+    const float3 _e17 = view_camera();
+    const float _e18 = view_pixels_per_unit(_e12);
+    const float4 _e20 = transform_sphere(m, _e12, node.lod_sphere);
+    const bool _e22 = is_error_visible(_e17, _e18, _e20, node.max_parent_error);
+    if (!(_e22)) {
+        return;
+    }
+    if ((node.leaf != 0u)) {
+        push_clusters(entry_2.x, (instance_1.cluster_base + node.first), node.count);
+        return;
+    }
+    bool loop_init_2 = true;
+    while(true) {
+        if (!loop_init_2) {
+            uint _e42 = i_2;
+            i_2 = (_e42 + 1u);
+        }
+        loop_init_2 = false;
+        uint _e34 = i_2;
+        if ((_e34 < node.count)) {
+        } else {
+            break;
+        }
+        {
+            uint _e39 = i_2;
+            push_node(uint2(entry_2.x, (node.first + _e39)));
+        }
+    }
+    return;
+}
+
+uint NagaBufferLength(ByteAddressBuffer buffer)
+{
+    uint ret;
+    buffer.GetDimensions(ret);
+    return ret;
+}
+
+[numthreads(64, 1, 1)]
+void main()
+{
+    uint taken = NONE;
+    uint2 entry = (uint2)0;
+
+    uint roots = ((NagaBufferLength(instances) - 0) / 96);
+    while(true) {
+        uint _e4 = taken;
+        if ((_e4 == NONE)) {
+            uint _e10; node_queue.InterlockedAdd(0, 1u, _e10);
+            taken = _e10;
+        }
+        uint _e11 = taken;
+        entry = uint2(_e11, 0u);
+        uint _e15 = taken;
+        if ((_e15 >= roots)) {
+            uint _e17 = taken;
+            uint slot = ((_e17 - roots) * 2u);
+            if ((slot < ((NagaBufferLengthRW(node_entries) - 0) / 4))) {
+                uint _e26 = asuint(node_entries.Load(slot*4));
+                uint _e31 = asuint(node_entries.Load((slot + 1u)*4));
+                entry = uint2(_e26, _e31);
+            } else {
+                entry = (4294967295u).xx;
+            }
+            uint _e36 = entry.x;
+            uint _e40 = entry.y;
+            if (((_e36 == EMPTY_SLOT) || (_e40 == EMPTY_SLOT))) {
+                uint _e46 = asuint(node_queue.Load(8));
+                if ((_e46 == 0u)) {
+                    break;
+                }
+                continue;
+            }
+            node_entries.Store(slot*4, asuint(EMPTY_SLOT));
+            node_entries.Store((slot + 1u)*4, asuint(EMPTY_SLOT));
+        }
+        taken = NONE;
+        uint2 _e58 = entry;
+        process(_e58);
+        uint _e62; node_queue.InterlockedAdd(8, -1u, _e62);
+    }
+    return;
+}
@@ -0,0 +1,311 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+struct _mslBufferSizes {
+    uint size1;
+    uint size2;
+    uint size4;
+    uint size6;
+};
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    metal::float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    metal::float4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    metal::float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    metal::float4 bounding_sphere;
+};
+struct BvhNode {
+    metal::float4 bounding_sphere;
+    metal::float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct WorkQueue {
+    metal::atomic_uint inner[4];
+};
+struct DrawArgs {
+    metal::atomic_uint inner[4];
+};
+struct type_5 {
+    metal::float4 inner[6];
+};
+struct View {
+    metal::float4x4 view_proj;
+    metal::float4x4 inv_view_proj;
+    type_5 frustum;
+    metal::float2 viewport_size;
+    float error_threshold;
+};
+typedef BvhNode type_10[1];
+typedef MeshInstance type_11[1];
+struct type_12 {
+    metal::atomic_uint inner[4];
+};
+typedef metal::atomic_uint type_13[1];
+typedef metal::uint2 type_15[1];
+constant uint QUEUE_DISPATCH_X = 0u;
+constant uint QUEUE_COUNT = 3u;
+constant uint DRAW_INSTANCE_COUNT = 1u;
+constant uint PRIMITIVE_ID_BITS = 10u;
+constant uint NODE_QUEUE_NEXT = 0u;
+constant uint NODE_QUEUE_COUNT = 1u;
+constant uint NODE_QUEUE_PENDING = 2u;
+constant uint EMPTY_SLOT = 4294967295u;
+constant uint NONE = 4294967295u;
+
+bool sphere_in_frustum(
+    metal::float4 sphere,
+    constant View& view
+) {
+    int i = 0;
+#define LOOP_IS_REACHABLE if (volatile bool unpredictable_jump_over_loop = true; unpredictable_jump_over_loop)
+    bool loop_init = true;
+    LOOP_IS_REACHABLE while(true) {
+        if (!loop_init) {
+            int _e25 = i;
+            i = _e25 + 1;
+        }
+        loop_init = false;
+        int _e3 = i;
+        if (_e3 < 6) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i;
+            metal::float4 _e10 = view.frustum.inner[_e8];
+            int _e16 = i;
+            float _e19 = view.frustum.inner[_e16].w;
+            if ((metal::dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w)) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(
+    metal::float4x4 world_from_local
+) {
+    return metal::max(metal::length(world_from_local[0].xyz), metal::max(metal::length(world_from_local[1].xyz), metal::length(world_from_local[2].xyz)));
+}
+
+metal::float4 transform_sphere(
+    metal::float4x4 world_from_local_1,
+    float scale,
+    metal::float4 sphere_1
+) {
+    return metal::float4((world_from_local_1 * metal::float4(sphere_1.xyz, 1.0)).xyz, sphere_1.w * scale);
+}
+
+metal::float3 view_camera(
+    constant View& view
+) {
+    metal::float4x4 _e2 = view.inv_view_proj;
+    metal::float4 camera_1 = _e2 * metal::float4(0.0, 0.0, 1.0, 0.0);
+    return camera_1.xyz / metal::float3(camera_1.w);
+}
+
+float view_pixels_per_unit(
+    float scale_1,
+    constant View& view
+) {
+    metal::float4x4 vp = view.view_proj;
+    float _e15 = view.viewport_size.y;
+    return ((metal::length(metal::float3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1;
+}
+
+bool is_error_visible(
+    metal::float3 camera,
+    float pixels_per_unit,
+    metal::float4 sphere_2,
+    float error,
+    constant View& view
+) {
+    float distance = metal::max(metal::length(sphere_2.xyz - camera) - sphere_2.w, 0.0);
+    float _e13 = view.error_threshold;
+    return error > ((_e13 * distance) / pixels_per_unit);
+}
+
+void push_node(
+    metal::uint2 entry_1,
+    device type_12& node_queue,
+    device type_13& node_entries,
+    constant _mslBufferSizes& _buffer_sizes
+) {
+    uint _e4 = metal::atomic_fetch_add_explicit(&node_queue.inner[1], 1u, metal::memory_order_relaxed);
+    if (_e4 < ((1 + (_buffer_sizes.size4 - 0 - 4) / 4) / 2u)) {
+        uint _e13 = metal::atomic_fetch_add_explicit(&node_queue.inner[2], 1u, metal::memory_order_relaxed);
+        metal::atomic_store_explicit(&node_entries[_e4 * 2u], entry_1.x, metal::memory_order_relaxed);
+        metal::atomic_store_explicit(&node_entries[(_e4 * 2u) + 1u], entry_1.y, metal::memory_order_relaxed);
+        return;
+    } else {
+        return;
+    }
+}
+
+void push_clusters(
+    uint instance,
+    uint first,
+    uint count,
+    device WorkQueue& work_queue,
+    device type_15& queue,
+    constant _mslBufferSizes& _buffer_sizes
+) {
+    uint i_1 = 0u;
+    uint _e5 = metal::atomic_fetch_add_explicit(&work_queue.inner[3], count, metal::memory_order_relaxed);
+    uint _e13 = metal::atomic_fetch_max_explicit(&work_queue.inner[0], ((_e5 + count) + 63u) / 64u, metal::memory_order_relaxed);
+    bool loop_init_1 = true;
+    LOOP_IS_REACHABLE while(true) {
+        if (!loop_init_1) {
+            uint _e31 = i_1;
+            i_1 = _e31 + 1u;
+        }
+        loop_init_1 = false;
+        uint _e16 = i_1;
+        uint _e18 = i_1;
+        if ((_e16 < count) && ((_e5 + _e18) < (1 + (_buffer_sizes.size6 - 0 - 8) / 8))) {
+        } else {
+            break;
+        }
+        {
+            uint _e25 = i_1;
+            uint _e28 = i_1;
+            queue[_e5 + _e25] = metal::uint2(instance, first + _e28);
+        }
+    }
+    return;
+}
+
+void process(
+    metal::uint2 entry_2,
+    constant View& view,
+    device type_10 const& nodes,
+    device type_11 const& instances,
+    device type_12& node_queue,
+    device type_13& node_entries,
+    device WorkQueue& work_queue,
+    device type_15& queue,
+    constant _mslBufferSizes& _buffer_sizes
+) {
+    uint i_2 = 0u;
+    MeshInstance instance_1 = instances[entry_2.x];
+    BvhNode node = nodes[instance_1.bvh_root + entry_2.y];
+    metal::float4x4 m = instance_1.world_from_local;
+    float _e12 = max_scale(m);
+    metal::float4 _e14 = transform_sphere(m, _e12, node.bounding_sphere);
+    bool _e15 = sphere_in_frustum(_e14, view);
+    if (!(_e15)) {
+        return;
+    }
+    metal::float3 _e17 = view_camera(view);
+    float _e18 = view_pixels_per_unit(_e12, view);
+    metal::float4 _e20 = transform_sphere(m, _e12, node.lod_sphere);
+    bool _e22 = is_error_visible(_e17, _e18, _e20, node.max_parent_error, view);
+    if (!(_e22)) {
+        return;
+    }
+    if (node.leaf != 0u) {
+        push_clusters(entry_2.x, instance_1.cluster_base + node.first, node.count, work_queue, queue, _buffer_sizes);
+        return;
+    }
+    bool loop_init_2 = true;
+    LOOP_IS_REACHABLE while(true) {
+        if (!loop_init_2) {
+            uint _e42 = i_2;
+            i_2 = _e42 + 1u;
+        }
+        loop_init_2 = false;
+        uint _e34 = i_2;
+        if (_e34 < node.count) {
+        } else {
+            break;
+        }
+        {
+            uint _e39 = i_2;
+            push_node(metal::uint2(entry_2.x, node.first + _e39), node_queue, node_entries, _buffer_sizes);
+        }
+    }
+    return;
+}
+
+kernel void main_(
+  constant View& view [[buffer(6)]]
+, device type_10 const& nodes [[buffer(0)]]
+, device type_11 const& instances [[buffer(1)]]
+, device type_12& node_queue [[buffer(2)]]
+, device type_13& node_entries [[buffer(3)]]
+, device WorkQueue& work_queue [[buffer(4)]]
+, device type_15& queue [[buffer(5)]]
+, constant _mslBufferSizes& _buffer_sizes [[buffer(7)]]
+) {
+    uint taken = NONE;
+    metal::uint2 entry = {};
+    uint roots = 1 + (_buffer_sizes.size2 - 0 - 96) / 96;
+    LOOP_IS_REACHABLE while(true) {
+        uint _e4 = taken;
+        if (_e4 == NONE) {
+            uint _e10 = metal::atomic_fetch_add_explicit(&node_queue.inner[0], 1u, metal::memory_order_relaxed);
+            taken = _e10;
+        }
+        uint _e11 = taken;
+        entry = metal::uint2(_e11, 0u);
+        uint _e15 = taken;
+        if (_e15 >= roots) {
+            uint _e17 = taken;
+            uint slot = (_e17 - roots) * 2u;
+            if (slot < (1 + (_buffer_sizes.size4 - 0 - 4) / 4)) {
+                uint _e26 = metal::atomic_load_explicit(&node_entries[slot], metal::memory_order_relaxed);
+                uint _e31 = metal::atomic_load_explicit(&node_entries[slot + 1u], metal::memory_order_relaxed);
+                entry = metal::uint2(_e26, _e31);
+            } else {
+                entry = metal::uint2(4294967295u);
+            }
+            uint _e36 = entry.x;
+            uint _e40 = entry.y;
+            if ((_e36 == EMPTY_SLOT) || (_e40 == EMPTY_SLOT)) {
+                uint _e46 = metal::atomic_load_explicit(&node_queue.inner[2], metal::memory_order_relaxed);
+                if (_e46 == 0u) {
+                    break;
+                }
+                continue;
+            }
+            metal::atomic_store_explicit(&node_entries[slot], EMPTY_SLOT, metal::memory_order_relaxed);
+            metal::atomic_store_explicit(&node_entries[slot + 1u], EMPTY_SLOT, metal::memory_order_relaxed);
+        }
+        taken = NONE;
+        metal::uint2 _e58 = entry;
+        process(_e58, view, nodes, instances, node_queue, node_entries, work_queue, queue, _buffer_sizes);
+        uint _e62 = metal::atomic_fetch_sub_explicit(&node_queue.inner[2], 1u, metal::memory_order_relaxed);
+    }
+    return;
+}
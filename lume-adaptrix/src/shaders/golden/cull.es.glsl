@@ -0,0 +1,164 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    vec4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    vec4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    mat4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    vec4 bounding_sphere;
+};
+struct BvhNode {
+    vec4 bounding_sphere;
+    vec4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct View {
+    mat4x4 view_proj;
+    mat4x4 inv_view_proj;
+    vec4 frustum[6];
+    vec2 viewport_size;
+    float error_threshold;
+};
+const uint QUEUE_DISPATCH_X = 0u;
+const uint QUEUE_COUNT = 3u;
+const uint DRAW_INSTANCE_COUNT = 1u;
+const uint PRIMITIVE_ID_BITS = 10u;
+
+layout(std140, binding = 6) uniform View_block_0Compute { View _group_1_binding_0_cs; };
+
+layout(std430, binding = 0) readonly buffer type_10_block_1Compute { Cluster _group_0_binding_0_cs[]; };
+
+layout(std430, binding = 1) readonly buffer type_11_block_2Compute { MeshInstance _group_0_binding_1_cs[]; };
+
+layout(std430, binding = 2) buffer type_13_block_3Compute { uvec2 _group_0_binding_2_cs[]; };
+
+layout(std430, binding = 3) buffer DrawArgs_block_4Compute { uint _group_0_binding_3_cs[4]; };
+
+layout(std430, binding = 4) buffer WorkQueue_block_5Compute { uint _group_0_binding_4_cs[4]; };
+
+layout(std430, binding = 5) readonly buffer type_13_block_6Compute { uvec2 _group_0_binding_5_cs[]; };
+
+
+bool sphere_in_frustum(vec4 sphere) {
+    int i = 0;
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            int _e25 = i;
+            i = (_e25 + 1);
+        }
+        loop_init = false;
+        int _e3 = i;
+        if ((_e3 < 6)) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i;
+            vec4 _e10 = _group_1_binding_0_cs.frustum[_e8];
+            int _e16 = i;
+            float _e19 = _group_1_binding_0_cs.frustum[_e16].w;
+            if (((dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w))) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(mat4x4 world_from_local) {
+    return max(length(world_from_local[0].xyz), max(length(world_from_local[1].xyz), length(world_from_local[2].xyz)));
+}
+
+vec4 transform_sphere(mat4x4 world_from_local_1, float scale, vec4 sphere_1) {
+    return vec4((world_from_local_1 * vec4(sphere_1.xyz, 1.0)).xyz, (sphere_1.w * scale));
+}
+
+vec3 view_camera() {
+    mat4x4 _e2 = _group_1_binding_0_cs.inv_view_proj;
+    vec4 camera_1 = (_e2 * vec4(0.0, 0.0, 1.0, 0.0));
+    return (camera_1.xyz / vec3(camera_1.w));
+}
+
+float view_pixels_per_unit(float scale_1) {
+    mat4x4 vp = _group_1_binding_0_cs.view_proj;
+    float _e15 = _group_1_binding_0_cs.viewport_size.y;
+    return (((length(vec3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1);
+}
+
+bool is_error_visible(vec3 camera, float pixels_per_unit, vec4 sphere_2, float error) {
+    float distance_ = max((length((sphere_2.xyz - camera)) - sphere_2.w), 0.0);
+    float _e13 = _group_1_binding_0_cs.error_threshold;
+    return (error > ((_e13 * distance_) / pixels_per_unit));
+}
+
+void main() {
+    uvec3 global_id = gl_GlobalInvocationID;
+    uint _e4 = _group_0_binding_4_cs[3];
+    if ((global_id.x >= min(_e4, uint(_group_0_binding_5_cs.length())))) {
+        return;
+    }
+    uvec2 entry = _group_0_binding_5_cs[global_id.x];
+    if (((entry.x >= uint(_group_0_binding_1_cs.length())) || (entry.y >= uint(_group_0_binding_0_cs.length())))) {
+        return;
+    }
+    MeshInstance instance = _group_0_binding_1_cs[entry.x];
+    Cluster cluster = _group_0_binding_0_cs[entry.y];
+    mat4x4 m = instance.world_from_local;
+    float _e31 = max_scale(m);
+    vec4 _e33 = transform_sphere(m, _e31, cluster.bounding_sphere);
+    bool _e34 = sphere_in_frustum(_e33);
+    if (!(_e34)) {
+        return;
+    }
+    vec3 _e36 = view_camera();
+    float _e37 = view_pixels_per_unit(_e31);
+    bool _e39 = is_error_visible(_e36, _e37, _e33, cluster.error_metric);
+    if (_e39) {
+        return;
+    }
+    vec4 _e41 = transform_sphere(m, _e31, cluster.parent_sphere);
+    bool _e43 = is_error_visible(_e36, _e37, _e41, cluster.parent_error);
+    if (!(_e43)) {
+        return;
+    }
+    uint _e48 = atomicAdd(_group_0_binding_3_cs[1], 1u);
+    if ((_e48 < uint(_group_0_binding_2_cs.length()))) {
+        _group_0_binding_2_cs[_e48] = entry;
+        return;
+    } else {
+        return;
+    }
+}
+
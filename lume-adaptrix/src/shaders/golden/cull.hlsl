@@ -0,0 +1,203 @@
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    float4 parent_sphere;
+};
+
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+
+struct MeshInstance {
+    row_major float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    float4 bounding_sphere;
+};
+
+struct BvhNode {
+    float4 bounding_sphere;
+    float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+
+struct View {
+    row_major float4x4 view_proj;
+    row_major float4x4 inv_view_proj;
+    float4 frustum[6];
+    float2 viewport_size;
+    float error_threshold;
+    int _end_pad_0;
+};
+
+static const uint QUEUE_DISPATCH_X = 0u;
+static const uint QUEUE_COUNT = 3u;
+static const uint DRAW_INSTANCE_COUNT = 1u;
+static const uint PRIMITIVE_ID_BITS = 10u;
+
+cbuffer view : register(b0, space1) { View view; }
+ByteAddressBuffer clusters : register(t0);
+ByteAddressBuffer instances : register(t1);
+RWByteAddressBuffer visible_clusters : register(u2);
+RWByteAddressBuffer draw_args : register(u3);
+RWByteAddressBuffer work_queue : register(u4);
+ByteAddressBuffer queue : register(t5);
+
+bool sphere_in_frustum(float4 sphere)
+{
+    int i = 0;
+
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            int _e25 = i;
+            i = (_e25 + 1);
+        }
+        loop_init = false;
+        int _e3 = i;
+        if ((_e3 < 6)) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i;
+            float4 _e10 = view.frustum[_e8];
+            int _e16 = i;
+            float _e19 = view.frustum[_e16].w;
+            if (((dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w))) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(float4x4 world_from_local)
+{
+    return max(length(world_from_local[0].xyz), max(length(world_from_local[1].xyz), length(world_from_local[2].xyz)));
+}
+
+float4 transform_sphere(float4x4 world_from_local_1, float scale, float4 sphere_1)
+{
+    return float4(mul(float4(sphere_1.xyz, 1.0), world_from_local_1).xyz, (sphere_1.w * scale));
+}
+
+float3 view_camera()
+{
+    float4x4 _e2 = view.inv_view_proj;
+    float4 camera_1 = mul(float4(0.0, 0.0, 1.0, 0.0), _e2);
+    return (camera_1.xyz / (camera_1.w).xxx);
+}
+
+float view_pixels_per_unit(float scale_1)
+{
+    float4x4 vp = view.view_proj;
+    float _e15 = view.viewport_size.y;
+    return (((length(float3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1);
+}
+
+bool is_error_visible(float3 camera, float pixels_per_unit, float4 sphere_2, float error)
+{
+    float distance_ = max((length((sphere_2.xyz - camera)) - sphere_2.w), 0.0);
+    float _e13 = view.error_threshold;
+    return (error > ((_e13 * distance_) / pixels_per_unit));
+}
+
+uint NagaBufferLength(ByteAddressBuffer buffer)
+{
+    uint ret;
+    buffer.GetDimensions(ret);
+    return ret;
+}
+
+MeshInstance ConstructMeshInstance(float4x4 arg0, uint arg1, uint arg2, uint arg3, uint arg4, float4 arg5) {
+    MeshInstance ret = (MeshInstance)0;
+    ret.world_from_local = arg0;
+    ret.cluster_base = arg1;
+    ret.cluster_count = arg2;
+    ret.bvh_root = arg3;
+    ret.pad0_ = arg4;
+    ret.bounding_sphere = arg5;
+    return ret;
+}
+
+Cluster ConstructCluster(uint arg0, uint arg1, uint arg2, uint arg3, float4 arg4, float arg5, float arg6, float arg7, float arg8, float4 arg9) {
+    Cluster ret = (Cluster)0;
+    ret.vertex_offset = arg0;
+    ret.triangle_offset = arg1;
+    ret.vertex_count = arg2;
+    ret.triangle_count = arg3;
+    ret.bounding_sphere = arg4;
+    ret.error_metric = arg5;
+    ret.parent_error = arg6;
+    ret.pad0_ = arg7;
+    ret.pad1_ = arg8;
+    ret.parent_sphere = arg9;
+    return ret;
+}
+
+uint NagaBufferLengthRW(RWByteAddressBuffer buffer)
+{
+    uint ret;
+    buffer.GetDimensions(ret);
+    return ret;
+}
+
+[numthreads(64, 1, 1)]
+void main(uint3 global_id : SV_DispatchThreadID)
+{
+    uint _e4 = asuint(work_queue.Load(12));
+    if ((global_id.x >= min(_e4, ((NagaBufferLength(queue) - 0) / 8)))) {
+        return;
+    }
+    uint2 entry = asuint(queue.Load2(global_id.x*8));
+    if (((entry.x >= ((NagaBufferLength(instances) - 0) / 96)) || (entry.y >= ((NagaBufferLength(clusters) - 0) / 64)))) {
+        return;
+    }
+    MeshInstance instance = ConstructMeshInstance(float4x4(asfloat(instances.Load4(entry.x*96+0+0)), asfloat(instances.Load4(entry.x*96+0+16)), asfloat(instances.Load4(entry.x*96+0+32)), asfloat(instances.Load4(entry.x*96+0+48))), asuint(instances.Load(entry.x*96+64)), asuint(instances.Load(entry.x*96+68)), asuint(instances.Load(entry.x*96+72)), asuint(instances.Load(entry.x*96+76)), asfloat(instances.Load4(entry.x*96+80)));
+    Cluster cluster = ConstructCluster(asuint(clusters.Load(entry.y*64+0)), asuint(clusters.Load(entry.y*64+4)), asuint(clusters.Load(entry.y*64+8)), asuint(clusters.Load(entry.y*64+12)), asfloat(clusters.Load4(entry.y*64+16)), asfloat(clusters.Load(entry.y*64+32)), asfloat(clusters.Load(entry.y*64+36)), asfloat(clusters.Load(entry.y*64+40)), asfloat(clusters.Load(entry.y*64+44)), asfloat(clusters.Load4(entry.y*64+48)));
+    float4x4 m = instance.world_from_local;
+    const float _e31 = max_scale(m);
+    const float4 _e33 = transform_sphere(m, _e31, cluster.bounding_sphere);
+    const bool _e34 = sphere_in_frustum(_e33);
+    if (!(_e34)) {
+        return;
+    }
+    const float3 _e36 = view_camera();
+    const float _e37 = view_pixels_per_unit(_e31);
+    const bool _e39 = is_error_visible(_e36, _e37, _e33, cluster.error_metric);
+    if (_e39) {
+        return;
+    }
+    const float4 _e41 = transform_sphere(m, _e31, cluster.parent_sphere);
+    const bool _e43 = is_error_visible(_e36, _e37, _e41, cluster.parent_error);
+    if (!(_e43)) {
+        return;
+    }
+    uint _e48; draw_args.InterlockedAdd(4, 1u, _e48);
+    if ((_e48 < ((NagaBufferLengthRW(visible_clusters) - 0) / 8))) {
+        visible_clusters.Store2(_e48*8, asuint(entry));
+        return;
+    } else {
+        return;
+    }
+}
@@ -0,0 +1,198 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+struct _mslBufferSizes {
+    uint size1;
+    uint size2;
+    uint size3;
+    uint size6;
+};
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    metal::float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    metal::float4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    metal::float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    metal::float4 bounding_sphere;
+};
+struct BvhNode {
+    metal::float4 bounding_sphere;
+    metal::float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct WorkQueue {
+    metal::atomic_uint inner[4];
+};
+struct DrawArgs {
+    metal::atomic_uint inner[4];
+};
+struct type_5 {
+    metal::float4 inner[6];
+};
+struct View {
+    metal::float4x4 view_proj;
+    metal::float4x4 inv_view_proj;
+    type_5 frustum;
+    metal::float2 viewport_size;
+    float error_threshold;
+};
+typedef Cluster type_10[1];
+typedef MeshInstance type_11[1];
+typedef metal::uint2 type_13[1];
+constant uint QUEUE_DISPATCH_X = 0u;
+constant uint QUEUE_COUNT = 3u;
+constant uint DRAW_INSTANCE_COUNT = 1u;
+constant uint PRIMITIVE_ID_BITS = 10u;
+
+bool sphere_in_frustum(
+    metal::float4 sphere,
+    constant View& view
+) {
+    int i = 0;
+#define LOOP_IS_REACHABLE if (volatile bool unpredictable_jump_over_loop = true; unpredictable_jump_over_loop)
+    bool loop_init = true;
+    LOOP_IS_REACHABLE while(true) {
+        if (!loop_init) {
+            int _e25 = i;
+            i = _e25 + 1;
+        }
+        loop_init = false;
+        int _e3 = i;
+        if (_e3 < 6) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i;
+            metal::float4 _e10 = view.frustum.inner[_e8];
+            int _e16 = i;
+            float _e19 = view.frustum.inner[_e16].w;
+            if ((metal::dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w)) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(
+    metal::float4x4 world_from_local
+) {
+    return metal::max(metal::length(world_from_local[0].xyz), metal::max(metal::length(world_from_local[1].xyz), metal::length(world_from_local[2].xyz)));
+}
+
+metal::float4 transform_sphere(
+    metal::float4x4 world_from_local_1,
+    float scale,
+    metal::float4 sphere_1
+) {
+    return metal::float4((world_from_local_1 * metal::float4(sphere_1.xyz, 1.0)).xyz, sphere_1.w * scale);
+}
+
+metal::float3 view_camera(
+    constant View& view
+) {
+    metal::float4x4 _e2 = view.inv_view_proj;
+    metal::float4 camera_1 = _e2 * metal::float4(0.0, 0.0, 1.0, 0.0);
+    return camera_1.xyz / metal::float3(camera_1.w);
+}
+
+float view_pixels_per_unit(
+    float scale_1,
+    constant View& view
+) {
+    metal::float4x4 vp = view.view_proj;
+    float _e15 = view.viewport_size.y;
+    return ((metal::length(metal::float3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1;
+}
+
+bool is_error_visible(
+    metal::float3 camera,
+    float pixels_per_unit,
+    metal::float4 sphere_2,
+    float error,
+    constant View& view
+) {
+    float distance = metal::max(metal::length(sphere_2.xyz - camera) - sphere_2.w, 0.0);
+    float _e13 = view.error_threshold;
+    return error > ((_e13 * distance) / pixels_per_unit);
+}
+
+struct main_Input {
+};
+kernel void main_(
+  metal::uint3 global_id [[thread_position_in_grid]]
+, constant View& view [[buffer(6)]]
+, device type_10 const& clusters [[buffer(0)]]
+, device type_11 const& instances [[buffer(1)]]
+, device type_13& visible_clusters [[buffer(2)]]
+, device DrawArgs& draw_args [[buffer(3)]]
+, device WorkQueue const& work_queue [[buffer(4)]]
+, device type_13 const& queue [[buffer(5)]]
+, constant _mslBufferSizes& _buffer_sizes [[buffer(7)]]
+) {
+    uint _e4 = metal::atomic_load_explicit(&work_queue.inner[3], metal::memory_order_relaxed);
+    if (global_id.x >= metal::min(_e4, 1 + (_buffer_sizes.size6 - 0 - 8) / 8)) {
+        return;
+    }
+    metal::uint2 entry = queue[global_id.x];
+    if ((entry.x >= (1 + (_buffer_sizes.size2 - 0 - 96) / 96)) || (entry.y >= (1 + (_buffer_sizes.size1 - 0 - 64) / 64))) {
+        return;
+    }
+    MeshInstance instance = instances[entry.x];
+    Cluster cluster = clusters[entry.y];
+    metal::float4x4 m = instance.world_from_local;
+    float _e31 = max_scale(m);
+    metal::float4 _e33 = transform_sphere(m, _e31, cluster.bounding_sphere);
+    bool _e34 = sphere_in_frustum(_e33, view);
+    if (!(_e34)) {
+        return;
+    }
+    metal::float3 _e36 = view_camera(view);
+    float _e37 = view_pixels_per_unit(_e31, view);
+    bool _e39 = is_error_visible(_e36, _e37, _e33, cluster.error_metric, view);
+    if (_e39) {
+        return;
+    }
+    metal::float4 _e41 = transform_sphere(m, _e31, cluster.parent_sphere);
+    bool _e43 = is_error_visible(_e36, _e37, _e41, cluster.parent_error, view);
+    if (!(_e43)) {
+        return;
+    }
+    uint _e48 = metal::atomic_fetch_add_explicit(&draw_args.inner[1], 1u, metal::memory_order_relaxed);
+    if (_e48 < (1 + (_buffer_sizes.size3 - 0 - 8) / 8)) {
+        visible_clusters[_e48] = entry;
+        return;
+    } else {
+        return;
+    }
+}
@@ -0,0 +1,71 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+layout(local_size_x = 8, local_size_y = 8, local_size_z = 1) in;
+
+layout(binding = 0,r32f) readonly uniform highp image2D _group_0_binding_0_cs;
+
+layout(binding = 1,r32f) writeonly uniform highp image2D _group_0_binding_1_cs;
+
+
+float load_source(uvec2 coord) {
+    vec4 _e2 = imageLoad(_group_0_binding_0_cs, ivec2(coord));
+    return _e2.x;
+}
+
+void main() {
+    uvec3 global_id = gl_GlobalInvocationID;
+    float depth = 0.0;
+    uint y = 0u;
+    uint x = 0u;
+    uvec2 size = uvec2(imageSize(_group_0_binding_1_cs).xy);
+    if (((global_id.x >= size.x) || (global_id.y >= size.y))) {
+        return;
+    }
+    uvec2 source_size = uvec2(imageSize(_group_0_binding_0_cs).xy);
+    uvec2 start = (global_id.xy * 2u);
+    uvec2 end = min(mix((start + uvec2(2u)), source_size, equal(global_id.xy, (size - uvec2(1u)))), source_size);
+    y = start.y;
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            uint _e46 = y;
+            y = (_e46 + 1u);
+        }
+        loop_init = false;
+        uint _e29 = y;
+        if ((_e29 < end.y)) {
+        } else {
+            break;
+        }
+        {
+            x = start.x;
+            bool loop_init_1 = true;
+            while(true) {
+                if (!loop_init_1) {
+                    uint _e43 = x;
+                    x = (_e43 + 1u);
+                }
+                loop_init_1 = false;
+                uint _e34 = x;
+                if ((_e34 < end.x)) {
+                } else {
+                    break;
+                }
+                {
+                    float _e37 = depth;
+                    uint _e38 = x;
+                    uint _e39 = y;
+                    float _e41 = load_source(uvec2(_e38, _e39));
+                    depth = max(_e37, _e41);
+                }
+            }
+        }
+    }
+    float _e51 = depth;
+    imageStore(_group_0_binding_1_cs, ivec2(global_id.xy), vec4(_e51, 0.0, 0.0, 0.0));
+    return;
+}
+
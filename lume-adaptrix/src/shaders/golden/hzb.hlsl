@@ -0,0 +1,78 @@
+RWTexture2D<float> source : register(u0);
+RWTexture2D<float> destination : register(u1);
+
+float load_source(uint2 coord)
+{
+    float4 _e2 = source.Load(coord);
+    return _e2.x;
+}
+
+uint2 NagaRWDimensions2D(RWTexture2D<float> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(ret.x, ret.y);
+    return ret.xy;
+}
+
+uint2 NagaRWDimensions2D(RWTexture2D<float> tex)
+{
+    uint4 ret;
+    tex.GetDimensions(ret.x, ret.y);
+    return ret.xy;
+}
+
+[numthreads(8, 8, 1)]
+void main(uint3 global_id : SV_DispatchThreadID)
+{
+    float depth = 0.0;
+    uint y = (uint)0;
+    uint x = (uint)0;
+
+    uint2 size = NagaRWDimensions2D(destination);
+    if (((global_id.x >= size.x) || (global_id.y >= size.y))) {
+        return;
+    }
+    uint2 source_size = NagaRWDimensions2D(source);
+    uint2 start = (global_id.xy * 2u);
+    uint2 end = min(((global_id.xy == (size - (1u).xx)) ? source_size : (start + (2u).xx)), source_size);
+    y = start.y;
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            uint _e46 = y;
+            y = (_e46 + 1u);
+        }
+        loop_init = false;
+        uint _e29 = y;
+        if ((_e29 < end.y)) {
+        } else {
+            break;
+        }
+        {
+            x = start.x;
+            bool loop_init_1 = true;
+            while(true) {
+                if (!loop_init_1) {
+                    uint _e43 = x;
+                    x = (_e43 + 1u);
+                }
+                loop_init_1 = false;
+                uint _e34 = x;
+                if ((_e34 < end.x)) {
+                } else {
+                    break;
+                }
+                {
+                    float _e37 = depth;
+                    uint _e38 = x;
+                    uint _e39 = y;
+                    const float _e41 = load_source(uint2(_e38, _e39));
+                    depth = max(_e37, _e41);
+                }
+            }
+        }
+    }
+    float _e51 = depth;
+    destination[global_id.xy] = float4(_e51, 0.0, 0.0, 0.0);
+    return;
+}
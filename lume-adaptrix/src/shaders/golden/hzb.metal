@@ -0,0 +1,74 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+
+float load_source(
+    metal::uint2 coord,
+    metal::texture2d<float, metal::access::read> source
+) {
+    metal::float4 _e2 = source.read(metal::uint2(coord));
+    return _e2.x;
+}
+
+struct main_Input {
+};
+kernel void main_(
+  metal::uint3 global_id [[thread_position_in_grid]]
+, metal::texture2d<float, metal::access::read> source [[texture(0)]]
+, metal::texture2d<float, metal::access::write> destination [[texture(1)]]
+) {
+    float depth = 0.0;
+    uint y = {};
+    uint x = {};
+    metal::uint2 size = metal::uint2(destination.get_width(), destination.get_height());
+    if ((global_id.x >= size.x) || (global_id.y >= size.y)) {
+        return;
+    }
+    metal::uint2 source_size = metal::uint2(source.get_width(), source.get_height());
+    metal::uint2 start = global_id.xy * 2u;
+    metal::uint2 end = metal::min(metal::select(start + metal::uint2(2u), source_size, global_id.xy == (size - metal::uint2(1u))), source_size);
+    y = start.y;
+#define LOOP_IS_REACHABLE if (volatile bool unpredictable_jump_over_loop = true; unpredictable_jump_over_loop)
+    bool loop_init = true;
+    LOOP_IS_REACHABLE while(true) {
+        if (!loop_init) {
+            uint _e46 = y;
+            y = _e46 + 1u;
+        }
+        loop_init = false;
+        uint _e29 = y;
+        if (_e29 < end.y) {
+        } else {
+            break;
+        }
+        {
+            x = start.x;
+            bool loop_init_1 = true;
+            LOOP_IS_REACHABLE while(true) {
+                if (!loop_init_1) {
+                    uint _e43 = x;
+                    x = _e43 + 1u;
+                }
+                loop_init_1 = false;
+                uint _e34 = x;
+                if (_e34 < end.x) {
+                } else {
+                    break;
+                }
+                {
+                    float _e37 = depth;
+                    uint _e38 = x;
+                    uint _e39 = y;
+                    float _e41 = load_source(metal::uint2(_e38, _e39), source);
+                    depth = metal::max(_e37, _e41);
+                }
+            }
+        }
+    }
+    float _e51 = depth;
+    destination.write(metal::float4(_e51, 0.0, 0.0, 0.0), metal::uint2(global_id.xy));
+    return;
+}
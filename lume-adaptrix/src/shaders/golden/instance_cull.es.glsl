@@ -0,0 +1,157 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+layout(local_size_x = 64, local_size_y = 1, local_size_z = 1) in;
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    vec4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    vec4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    mat4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    vec4 bounding_sphere;
+};
+struct BvhNode {
+    vec4 bounding_sphere;
+    vec4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct View {
+    mat4x4 view_proj;
+    mat4x4 inv_view_proj;
+    vec4 frustum[6];
+    vec2 viewport_size;
+    float error_threshold;
+};
+const uint QUEUE_DISPATCH_X = 0u;
+const uint QUEUE_COUNT = 3u;
+const uint DRAW_INSTANCE_COUNT = 1u;
+const uint PRIMITIVE_ID_BITS = 10u;
+
+layout(std140, binding = 3) uniform View_block_0Compute { View _group_1_binding_0_cs; };
+
+layout(std430, binding = 0) readonly buffer type_10_block_1Compute { MeshInstance _group_0_binding_0_cs[]; };
+
+layout(std430, binding = 1) buffer WorkQueue_block_2Compute { uint _group_0_binding_1_cs[4]; };
+
+layout(std430, binding = 2) buffer type_12_block_3Compute { uvec2 _group_0_binding_2_cs[]; };
+
+
+bool sphere_in_frustum(vec4 sphere) {
+    int i_1 = 0;
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            int _e25 = i_1;
+            i_1 = (_e25 + 1);
+        }
+        loop_init = false;
+        int _e3 = i_1;
+        if ((_e3 < 6)) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i_1;
+            vec4 _e10 = _group_1_binding_0_cs.frustum[_e8];
+            int _e16 = i_1;
+            float _e19 = _group_1_binding_0_cs.frustum[_e16].w;
+            if (((dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w))) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(mat4x4 world_from_local) {
+    return max(length(world_from_local[0].xyz), max(length(world_from_local[1].xyz), length(world_from_local[2].xyz)));
+}
+
+vec4 transform_sphere(mat4x4 world_from_local_1, float scale, vec4 sphere_1) {
+    return vec4((world_from_local_1 * vec4(sphere_1.xyz, 1.0)).xyz, (sphere_1.w * scale));
+}
+
+vec3 view_camera() {
+    mat4x4 _e2 = _group_1_binding_0_cs.inv_view_proj;
+    vec4 camera_1 = (_e2 * vec4(0.0, 0.0, 1.0, 0.0));
+    return (camera_1.xyz / vec3(camera_1.w));
+}
+
+float view_pixels_per_unit(float scale_1) {
+    mat4x4 vp = _group_1_binding_0_cs.view_proj;
+    float _e15 = _group_1_binding_0_cs.viewport_size.y;
+    return (((length(vec3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1);
+}
+
+bool is_error_visible(vec3 camera, float pixels_per_unit, vec4 sphere_2, float error) {
+    float distance_ = max((length((sphere_2.xyz - camera)) - sphere_2.w), 0.0);
+    float _e13 = _group_1_binding_0_cs.error_threshold;
+    return (error > ((_e13 * distance_) / pixels_per_unit));
+}
+
+void main() {
+    uvec3 global_id = gl_GlobalInvocationID;
+    uint i = 0u;
+    if ((global_id.x >= uint(_group_0_binding_0_cs.length()))) {
+        return;
+    }
+    MeshInstance instance = _group_0_binding_0_cs[global_id.x];
+    mat4x4 m = instance.world_from_local;
+    float _e10 = max_scale(m);
+    vec4 _e12 = transform_sphere(m, _e10, instance.bounding_sphere);
+    bool _e16 = sphere_in_frustum(_e12);
+    if (((instance.cluster_count == 0u) || !(_e16))) {
+        return;
+    }
+    uint _e22 = atomicAdd(_group_0_binding_1_cs[3], instance.cluster_count);
+    uint _e31 = atomicMax(_group_0_binding_1_cs[0], (((_e22 + instance.cluster_count) + 63u) / 64u));
+    bool loop_init_1 = true;
+    while(true) {
+        if (!loop_init_1) {
+            uint _e52 = i;
+            i = (_e52 + 1u);
+        }
+        loop_init_1 = false;
+        uint _e34 = i;
+        uint _e37 = i;
+        if (((_e34 < instance.cluster_count) && ((_e22 + _e37) < uint(_group_0_binding_2_cs.length())))) {
+        } else {
+            break;
+        }
+        {
+            uint _e44 = i;
+            uint _e49 = i;
+            _group_0_binding_2_cs[(_e22 + _e44)] = uvec2(global_id.x, (instance.cluster_base + _e49));
+        }
+    }
+    return;
+}
+
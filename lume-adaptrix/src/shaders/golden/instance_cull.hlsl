@@ -0,0 +1,185 @@
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    float4 parent_sphere;
+};
+
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+
+struct MeshInstance {
+    row_major float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    float4 bounding_sphere;
+};
+
+struct BvhNode {
+    float4 bounding_sphere;
+    float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+
+struct View {
+    row_major float4x4 view_proj;
+    row_major float4x4 inv_view_proj;
+    float4 frustum[6];
+    float2 viewport_size;
+    float error_threshold;
+    int _end_pad_0;
+};
+
+static const uint QUEUE_DISPATCH_X = 0u;
+static const uint QUEUE_COUNT = 3u;
+static const uint DRAW_INSTANCE_COUNT = 1u;
+static const uint PRIMITIVE_ID_BITS = 10u;
+
+cbuffer view : register(b0, space1) { View view; }
+ByteAddressBuffer instances : register(t0);
+RWByteAddressBuffer work_queue : register(u1);
+RWByteAddressBuffer queue : register(u2);
+
+bool sphere_in_frustum(float4 sphere)
+{
+    int i_1 = 0;
+
+    bool loop_init = true;
+    while(true) {
+        if (!loop_init) {
+            int _e25 = i_1;
+            i_1 = (_e25 + 1);
+        }
+        loop_init = false;
+        int _e3 = i_1;
+        if ((_e3 < 6)) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i_1;
+            float4 _e10 = view.frustum[_e8];
+            int _e16 = i_1;
+            float _e19 = view.frustum[_e16].w;
+            if (((dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w))) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(float4x4 world_from_local)
+{
+    return max(length(world_from_local[0].xyz), max(length(world_from_local[1].xyz), length(world_from_local[2].xyz)));
+}
+
+float4 transform_sphere(float4x4 world_from_local_1, float scale, float4 sphere_1)
+{
+    return float4(mul(float4(sphere_1.xyz, 1.0), world_from_local_1).xyz, (sphere_1.w * scale));
+}
+
+float3 view_camera()
+{
+    float4x4 _e2 = view.inv_view_proj;
+    float4 camera_1 = mul(float4(0.0, 0.0, 1.0, 0.0), _e2);
+    return (camera_1.xyz / (camera_1.w).xxx);
+}
+
+float view_pixels_per_unit(float scale_1)
+{
+    float4x4 vp = view.view_proj;
+    float _e15 = view.viewport_size.y;
+    return (((length(float3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1);
+}
+
+bool is_error_visible(float3 camera, float pixels_per_unit, float4 sphere_2, float error)
+{
+    float distance_ = max((length((sphere_2.xyz - camera)) - sphere_2.w), 0.0);
+    float _e13 = view.error_threshold;
+    return (error > ((_e13 * distance_) / pixels_per_unit));
+}
+
+uint NagaBufferLength(ByteAddressBuffer buffer)
+{
+    uint ret;
+    buffer.GetDimensions(ret);
+    return ret;
+}
+
+MeshInstance ConstructMeshInstance(float4x4 arg0, uint arg1, uint arg2, uint arg3, uint arg4, float4 arg5) {
+    MeshInstance ret = (MeshInstance)0;
+    ret.world_from_local = arg0;
+    ret.cluster_base = arg1;
+    ret.cluster_count = arg2;
+    ret.bvh_root = arg3;
+    ret.pad0_ = arg4;
+    ret.bounding_sphere = arg5;
+    return ret;
+}
+
+uint NagaBufferLengthRW(RWByteAddressBuffer buffer)
+{
+    uint ret;
+    buffer.GetDimensions(ret);
+    return ret;
+}
+
+[numthreads(64, 1, 1)]
+void main(uint3 global_id : SV_DispatchThreadID)
+{
+    uint i = 0u;
+
+    if ((global_id.x >= ((NagaBufferLength(instances) - 0) / 96))) {
+        return;
+    }
+    MeshInstance instance = ConstructMeshInstance(float4x4(asfloat(instances.Load4(global_id.x*96+0+0)), asfloat(instances.Load4(global_id.x*96+0+16)), asfloat(instances.Load4(global_id.x*96+0+32)), asfloat(instances.Load4(global_id.x*96+0+48))), asuint(instances.Load(global_id.x*96+64)), asuint(instances.Load(global_id.x*96+68)), asuint(instances.Load(global_id.x*96+72)), asuint(instances.Load(global_id.x*96+76)), asfloat(instances.Load4(global_id.x*96+80)));
+    float4x4 m = instance.world_from_local;
+    const float _e10 = max_scale(m);
+    const float4 _e12 = transform_sphere(m, _e10, instance.bounding_sphere);
+    const bool _e16 = sphere_in_frustum(_e12);
+    if (((instance.cluster_count == 0u) || !(_e16))) {
+        return;
+    }
+    uint _e22; work_queue.InterlockedAdd(12, instance.cluster_count, _e22);
+    uint _e31; work_queue.InterlockedMax(0, (((_e22 + instance.cluster_count) + 63u) / 64u), _e31);
+    bool loop_init_1 = true;
+    while(true) {
+        if (!loop_init_1) {
+            uint _e52 = i;
+            i = (_e52 + 1u);
+        }
+        loop_init_1 = false;
+        uint _e34 = i;
+        uint _e37 = i;
+        if (((_e34 < instance.cluster_count) && ((_e22 + _e37) < ((NagaBufferLengthRW(queue) - 0) / 8)))) {
+        } else {
+            break;
+        }
+        {
+            uint _e44 = i;
+            uint _e49 = i;
+            queue.Store2((_e22 + _e44)*8, asuint(uint2(global_id.x, (instance.cluster_base + _e49))));
+        }
+    }
+    return;
+}
@@ -0,0 +1,191 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+struct _mslBufferSizes {
+    uint size1;
+    uint size3;
+};
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    metal::float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    metal::float4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    metal::float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    metal::float4 bounding_sphere;
+};
+struct BvhNode {
+    metal::float4 bounding_sphere;
+    metal::float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct WorkQueue {
+    metal::atomic_uint inner[4];
+};
+struct DrawArgs {
+    metal::atomic_uint inner[4];
+};
+struct type_5 {
+    metal::float4 inner[6];
+};
+struct View {
+    metal::float4x4 view_proj;
+    metal::float4x4 inv_view_proj;
+    type_5 frustum;
+    metal::float2 viewport_size;
+    float error_threshold;
+};
+typedef MeshInstance type_10[1];
+typedef metal::uint2 type_12[1];
+constant uint QUEUE_DISPATCH_X = 0u;
+constant uint QUEUE_COUNT = 3u;
+constant uint DRAW_INSTANCE_COUNT = 1u;
+constant uint PRIMITIVE_ID_BITS = 10u;
+
+bool sphere_in_frustum(
+    metal::float4 sphere,
+    constant View& view
+) {
+    int i_1 = 0;
+#define LOOP_IS_REACHABLE if (volatile bool unpredictable_jump_over_loop = true; unpredictable_jump_over_loop)
+    bool loop_init = true;
+    LOOP_IS_REACHABLE while(true) {
+        if (!loop_init) {
+            int _e25 = i_1;
+            i_1 = _e25 + 1;
+        }
+        loop_init = false;
+        int _e3 = i_1;
+        if (_e3 < 6) {
+        } else {
+            break;
+        }
+        {
+            int _e8 = i_1;
+            metal::float4 _e10 = view.frustum.inner[_e8];
+            int _e16 = i_1;
+            float _e19 = view.frustum.inner[_e16].w;
+            if ((metal::dot(_e10.xyz, sphere.xyz) + _e19) < -(sphere.w)) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+float max_scale(
+    metal::float4x4 world_from_local
+) {
+    return metal::max(metal::length(world_from_local[0].xyz), metal::max(metal::length(world_from_local[1].xyz), metal::length(world_from_local[2].xyz)));
+}
+
+metal::float4 transform_sphere(
+    metal::float4x4 world_from_local_1,
+    float scale,
+    metal::float4 sphere_1
+) {
+    return metal::float4((world_from_local_1 * metal::float4(sphere_1.xyz, 1.0)).xyz, sphere_1.w * scale);
+}
+
+metal::float3 view_camera(
+    constant View& view
+) {
+    metal::float4x4 _e2 = view.inv_view_proj;
+    metal::float4 camera_1 = _e2 * metal::float4(0.0, 0.0, 1.0, 0.0);
+    return camera_1.xyz / metal::float3(camera_1.w);
+}
+
+float view_pixels_per_unit(
+    float scale_1,
+    constant View& view
+) {
+    metal::float4x4 vp = view.view_proj;
+    float _e15 = view.viewport_size.y;
+    return ((metal::length(metal::float3(vp[0].y, vp[1].y, vp[2].y)) * _e15) * 0.5) * scale_1;
+}
+
+bool is_error_visible(
+    metal::float3 camera,
+    float pixels_per_unit,
+    metal::float4 sphere_2,
+    float error,
+    constant View& view
+) {
+    float distance = metal::max(metal::length(sphere_2.xyz - camera) - sphere_2.w, 0.0);
+    float _e13 = view.error_threshold;
+    return error > ((_e13 * distance) / pixels_per_unit);
+}
+
+struct main_Input {
+};
+kernel void main_(
+  metal::uint3 global_id [[thread_position_in_grid]]
+, constant View& view [[buffer(3)]]
+, device type_10 const& instances [[buffer(0)]]
+, device WorkQueue& work_queue [[buffer(1)]]
+, device type_12& queue [[buffer(2)]]
+, constant _mslBufferSizes& _buffer_sizes [[buffer(4)]]
+) {
+    uint i = 0u;
+    if (global_id.x >= (1 + (_buffer_sizes.size1 - 0 - 96) / 96)) {
+        return;
+    }
+    MeshInstance instance = instances[global_id.x];
+    metal::float4x4 m = instance.world_from_local;
+    float _e10 = max_scale(m);
+    metal::float4 _e12 = transform_sphere(m, _e10, instance.bounding_sphere);
+    bool _e16 = sphere_in_frustum(_e12, view);
+    if ((instance.cluster_count == 0u) || !(_e16)) {
+        return;
+    }
+    uint _e22 = metal::atomic_fetch_add_explicit(&work_queue.inner[3], instance.cluster_count, metal::memory_order_relaxed);
+    uint _e31 = metal::atomic_fetch_max_explicit(&work_queue.inner[0], ((_e22 + instance.cluster_count) + 63u) / 64u, metal::memory_order_relaxed);
+    bool loop_init_1 = true;
+    LOOP_IS_REACHABLE while(true) {
+        if (!loop_init_1) {
+            uint _e52 = i;
+            i = _e52 + 1u;
+        }
+        loop_init_1 = false;
+        uint _e34 = i;
+        uint _e37 = i;
+        if ((_e34 < instance.cluster_count) && ((_e22 + _e37) < (1 + (_buffer_sizes.size3 - 0 - 8) / 8))) {
+        } else {
+            break;
+        }
+        {
+            uint _e44 = i;
+            uint _e49 = i;
+            queue[_e22 + _e44] = metal::uint2(global_id.x, instance.cluster_base + _e49);
+        }
+    }
+    return;
+}
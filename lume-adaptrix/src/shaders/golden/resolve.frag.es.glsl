@@ -0,0 +1,111 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    vec4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    vec4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    mat4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    vec4 bounding_sphere;
+};
+struct BvhNode {
+    vec4 bounding_sphere;
+    vec4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct View {
+    mat4x4 view_proj;
+    mat4x4 inv_view_proj;
+    vec4 frustum[6];
+    vec2 viewport_size;
+    float error_threshold;
+};
+struct VertexOutput {
+    vec4 position;
+    vec2 uv;
+};
+const uint QUEUE_DISPATCH_X = 0u;
+const uint QUEUE_COUNT = 3u;
+const uint DRAW_INSTANCE_COUNT = 1u;
+const uint PRIMITIVE_ID_BITS = 10u;
+
+layout(std430, binding = 0) readonly buffer type_7_block_0Fragment { Cluster _group_0_binding_0_fs[]; };
+
+layout(std430, binding = 1) readonly buffer type_8_block_1Fragment { AdaptrixVertex _group_0_binding_1_fs[]; };
+
+layout(std430, binding = 2) readonly buffer type_9_block_2Fragment { uint _group_0_binding_2_fs[]; };
+
+layout(std430, binding = 3) readonly buffer type_10_block_3Fragment { MeshInstance _group_0_binding_3_fs[]; };
+
+layout(binding = 5) uniform highp usampler2D _group_1_binding_1_fs;
+
+layout(location = 0) smooth in vec2 _vs2fs_location0;
+layout(location = 0) out vec4 _fs2p_location0;
+
+float hash(uint n) {
+    uint m = ((n * 1103515245u) + 12345u);
+    return (float((m & 2147483647u)) / 2147483600.0);
+}
+
+void main() {
+    VertexOutput in_ = VertexOutput(gl_FragCoord, _vs2fs_location0);
+    ivec2 pixel = ivec2(in_.position.xy);
+    uvec4 vis_data = texelFetch(_group_1_binding_1_fs, pixel, 0);
+    uint id = vis_data.y;
+    if ((id == 0u)) {
+        _fs2p_location0 = vec4(0.05, 0.05, 0.07, 1.0);
+        return;
+    }
+    uint instance_id = vis_data.x;
+    uint seed = ((id >> PRIMITIVE_ID_BITS) - 1u);
+    uint triangle_id = (id & 1023u);
+    float _e22 = hash(seed);
+    float _e25 = hash((seed + 1u));
+    float _e28 = hash((seed + 2u));
+    vec3 albedo = vec3(_e22, _e25, _e28);
+    Cluster cluster = _group_0_binding_0_fs[seed];
+    uint i0_ = _group_0_binding_2_fs[((cluster.triangle_offset + (triangle_id * 3u)) + 0u)];
+    uint i1_ = _group_0_binding_2_fs[((cluster.triangle_offset + (triangle_id * 3u)) + 1u)];
+    uint i2_ = _group_0_binding_2_fs[((cluster.triangle_offset + (triangle_id * 3u)) + 2u)];
+    AdaptrixVertex v0_ = _group_0_binding_1_fs[(cluster.vertex_offset + i0_)];
+    AdaptrixVertex v1_ = _group_0_binding_1_fs[(cluster.vertex_offset + i1_)];
+    AdaptrixVertex v2_ = _group_0_binding_1_fs[(cluster.vertex_offset + i2_)];
+    mat4x4 m_1 = _group_0_binding_3_fs[instance_id].world_from_local;
+    vec3 p0_ = (m_1 * vec4(v0_.px, v0_.py, v0_.pz, 1.0)).xyz;
+    vec3 p1_ = (m_1 * vec4(v1_.px, v1_.py, v1_.pz, 1.0)).xyz;
+    vec3 p2_ = (m_1 * vec4(v2_.px, v2_.py, v2_.pz, 1.0)).xyz;
+    vec3 normal = normalize(cross((p1_ - p0_), (p2_ - p0_)));
+    vec3 light_dir = normalize(vec3(1.0, 1.0, 2.0));
+    float diff = max(dot(normal, light_dir), 0.3);
+    _fs2p_location0 = vec4((albedo * diff), 1.0);
+    return;
+}
+
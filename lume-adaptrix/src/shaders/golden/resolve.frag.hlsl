@@ -0,0 +1,139 @@
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    float4 parent_sphere;
+};
+
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+
+struct MeshInstance {
+    row_major float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    float4 bounding_sphere;
+};
+
+struct BvhNode {
+    float4 bounding_sphere;
+    float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+
+struct View {
+    row_major float4x4 view_proj;
+    row_major float4x4 inv_view_proj;
+    float4 frustum[6];
+    float2 viewport_size;
+    float error_threshold;
+    int _end_pad_0;
+};
+
+struct VertexOutput {
+    float4 position : SV_Position;
+    float2 uv : LOC0;
+};
+
+static const uint QUEUE_DISPATCH_X = 0u;
+static const uint QUEUE_COUNT = 3u;
+static const uint DRAW_INSTANCE_COUNT = 1u;
+static const uint PRIMITIVE_ID_BITS = 10u;
+
+ByteAddressBuffer clusters : register(t0);
+ByteAddressBuffer vertices_ : register(t1);
+ByteAddressBuffer indices_ : register(t2);
+ByteAddressBuffer instances : register(t3);
+cbuffer view : register(b0, space1) { View view; }
+Texture2D<uint4> vis_buffer : register(t1, space1);
+
+struct FragmentInput_main {
+    float2 uv : LOC0;
+    float4 position : SV_Position;
+};
+
+float hash(uint n)
+{
+    uint m = ((n * 1103515245u) + 12345u);
+    return (float((m & 2147483647u)) / 2147483600.0);
+}
+
+Cluster ConstructCluster(uint arg0, uint arg1, uint arg2, uint arg3, float4 arg4, float arg5, float arg6, float arg7, float arg8, float4 arg9) {
+    Cluster ret = (Cluster)0;
+    ret.vertex_offset = arg0;
+    ret.triangle_offset = arg1;
+    ret.vertex_count = arg2;
+    ret.triangle_count = arg3;
+    ret.bounding_sphere = arg4;
+    ret.error_metric = arg5;
+    ret.parent_error = arg6;
+    ret.pad0_ = arg7;
+    ret.pad1_ = arg8;
+    ret.parent_sphere = arg9;
+    return ret;
+}
+
+AdaptrixVertex ConstructAdaptrixVertex(float arg0, float arg1, float arg2, float arg3, float arg4, float arg5, float arg6, float arg7) {
+    AdaptrixVertex ret = (AdaptrixVertex)0;
+    ret.px = arg0;
+    ret.py = arg1;
+    ret.pz = arg2;
+    ret.nx = arg3;
+    ret.ny = arg4;
+    ret.nz = arg5;
+    ret.u = arg6;
+    ret.v = arg7;
+    return ret;
+}
+
+float4 main(FragmentInput_main fragmentinput_main) : SV_Target0
+{
+    VertexOutput in_ = { fragmentinput_main.position, fragmentinput_main.uv };
+    int2 pixel = int2(in_.position.xy);
+    uint4 vis_data = vis_buffer.Load(int3(pixel, 0));
+    uint id = vis_data.y;
+    if ((id == 0u)) {
+        return float4(0.05, 0.05, 0.07, 1.0);
+    }
+    uint instance_id = vis_data.x;
+    uint seed = ((id >> PRIMITIVE_ID_BITS) - 1u);
+    uint triangle_id = (id & 1023u);
+    const float _e22 = hash(seed);
+    const float _e25 = hash((seed + 1u));
+    const float _e28 = hash((seed + 2u));
+    float3 albedo = float3(_e22, _e25, _e28);
+    Cluster cluster = ConstructCluster(asuint(clusters.Load(seed*64+0)), asuint(clusters.Load(seed*64+4)), asuint(clusters.Load(seed*64+8)), asuint(clusters.Load(seed*64+12)), asfloat(clusters.Load4(seed*64+16)), asfloat(clusters.Load(seed*64+32)), asfloat(clusters.Load(seed*64+36)), asfloat(clusters.Load(seed*64+40)), asfloat(clusters.Load(seed*64+44)), asfloat(clusters.Load4(seed*64+48)));
+    uint i0_ = asuint(indices_.Load(((cluster.triangle_offset + (triangle_id * 3u)) + 0u)*4));
+    uint i1_ = asuint(indices_.Load(((cluster.triangle_offset + (triangle_id * 3u)) + 1u)*4));
+    uint i2_ = asuint(indices_.Load(((cluster.triangle_offset + (triangle_id * 3u)) + 2u)*4));
+    AdaptrixVertex v0_ = ConstructAdaptrixVertex(asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+0)), asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+4)), asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+8)), asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+12)), asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+16)), asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+20)), asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+24)), asfloat(vertices_.Load((cluster.vertex_offset + i0_)*32+28)));
+    AdaptrixVertex v1_ = ConstructAdaptrixVertex(asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+0)), asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+4)), asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+8)), asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+12)), asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+16)), asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+20)), asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+24)), asfloat(vertices_.Load((cluster.vertex_offset + i1_)*32+28)));
+    AdaptrixVertex v2_ = ConstructAdaptrixVertex(asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+0)), asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+4)), asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+8)), asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+12)), asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+16)), asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+20)), asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+24)), asfloat(vertices_.Load((cluster.vertex_offset + i2_)*32+28)));
+    float4x4 m_1 = float4x4(asfloat(instances.Load4(0+instance_id*96+0)), asfloat(instances.Load4(0+instance_id*96+16)), asfloat(instances.Load4(0+instance_id*96+32)), asfloat(instances.Load4(0+instance_id*96+48)));
+    float3 p0_ = mul(float4(v0_.px, v0_.py, v0_.pz, 1.0), m_1).xyz;
+    float3 p1_ = mul(float4(v1_.px, v1_.py, v1_.pz, 1.0), m_1).xyz;
+    float3 p2_ = mul(float4(v2_.px, v2_.py, v2_.pz, 1.0), m_1).xyz;
+    float3 normal = normalize(cross((p1_ - p0_), (p2_ - p0_)));
+    float3 light_dir = normalize(float3(1.0, 1.0, 2.0));
+    float diff = max(dot(normal, light_dir), 0.3);
+    return float4((albedo * diff), 1.0);
+}
@@ -0,0 +1,133 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+struct _mslBufferSizes {
+    uint size0;
+    uint size1;
+    uint size2;
+    uint size3;
+};
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    metal::float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    metal::float4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    metal::float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    metal::float4 bounding_sphere;
+};
+struct BvhNode {
+    metal::float4 bounding_sphere;
+    metal::float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct WorkQueue {
+    metal::atomic_uint inner[4];
+};
+struct DrawArgs {
+    metal::atomic_uint inner[4];
+};
+struct type_5 {
+    metal::float4 inner[6];
+};
+struct View {
+    metal::float4x4 view_proj;
+    metal::float4x4 inv_view_proj;
+    type_5 frustum;
+    metal::float2 viewport_size;
+    float error_threshold;
+};
+typedef Cluster type_7[1];
+typedef AdaptrixVertex type_8[1];
+typedef uint type_9[1];
+typedef MeshInstance type_10[1];
+struct VertexOutput {
+    metal::float4 position;
+    metal::float2 uv;
+};
+constant uint QUEUE_DISPATCH_X = 0u;
+constant uint QUEUE_COUNT = 3u;
+constant uint DRAW_INSTANCE_COUNT = 1u;
+constant uint PRIMITIVE_ID_BITS = 10u;
+
+float hash(
+    uint n
+) {
+    uint m = (n * 1103515245u) + 12345u;
+    return static_cast<float>(m & 2147483647u) / 2147483600.0;
+}
+
+struct main_Input {
+    metal::float2 uv [[user(loc0), center_perspective]];
+};
+struct main_Output {
+    metal::float4 member [[color(0)]];
+};
+fragment main_Output main_(
+  main_Input varyings [[stage_in]]
+, metal::float4 position [[position]]
+, device type_7 const& clusters [[buffer(0)]]
+, device type_8 const& vertices [[buffer(1)]]
+, device type_9 const& indices [[buffer(2)]]
+, device type_10 const& instances [[buffer(3)]]
+, metal::texture2d<uint, metal::access::sample> vis_buffer [[texture(0)]]
+, constant _mslBufferSizes& _buffer_sizes [[buffer(5)]]
+) {
+    const VertexOutput in = { position, varyings.uv };
+    metal::int2 pixel = static_cast<metal::int2>(in.position.xy);
+    metal::uint4 vis_data = vis_buffer.read(metal::uint2(pixel), 0);
+    uint id = vis_data.y;
+    if (id == 0u) {
+        return main_Output { metal::float4(0.05, 0.05, 0.07, 1.0) };
+    }
+    uint instance_id = vis_data.x;
+    uint seed = (id >> PRIMITIVE_ID_BITS) - 1u;
+    uint triangle_id = id & 1023u;
+    float _e22 = hash(seed);
+    float _e25 = hash(seed + 1u);
+    float _e28 = hash(seed + 2u);
+    metal::float3 albedo = metal::float3(_e22, _e25, _e28);
+    Cluster cluster = clusters[seed];
+    uint i0_ = indices[(cluster.triangle_offset + (triangle_id * 3u)) + 0u];
+    uint i1_ = indices[(cluster.triangle_offset + (triangle_id * 3u)) + 1u];
+    uint i2_ = indices[(cluster.triangle_offset + (triangle_id * 3u)) + 2u];
+    AdaptrixVertex v0_ = vertices[cluster.vertex_offset + i0_];
+    AdaptrixVertex v1_ = vertices[cluster.vertex_offset + i1_];
+    AdaptrixVertex v2_ = vertices[cluster.vertex_offset + i2_];
+    metal::float4x4 m_1 = instances[instance_id].world_from_local;
+    metal::float3 p0_ = (m_1 * metal::float4(v0_.px, v0_.py, v0_.pz, 1.0)).xyz;
+    metal::float3 p1_ = (m_1 * metal::float4(v1_.px, v1_.py, v1_.pz, 1.0)).xyz;
+    metal::float3 p2_ = (m_1 * metal::float4(v2_.px, v2_.py, v2_.pz, 1.0)).xyz;
+    metal::float3 normal = metal::normalize(metal::cross(p1_ - p0_, p2_ - p0_));
+    metal::float3 light_dir = metal::normalize(metal::float3(1.0, 1.0, 2.0));
+    float diff = metal::max(metal::dot(normal, light_dir), 0.3);
+    return main_Output { metal::float4(albedo * diff, 1.0) };
+}
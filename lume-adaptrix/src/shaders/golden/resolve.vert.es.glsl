@@ -0,0 +1,25 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+struct VertexOutput {
+    vec4 position;
+    vec2 uv;
+};
+layout(location = 0) smooth out vec2 _vs2fs_location0;
+
+void main() {
+    uint vertex_index = uint(gl_VertexID);
+    VertexOutput out_ = VertexOutput(vec4(0.0), vec2(0.0));
+    float x = ((float((int(vertex_index) / 2)) * 4.0) - 1.0);
+    float y = ((float((int(vertex_index) % 2)) * 4.0) - 1.0);
+    out_.position = vec4(x, y, 0.0, 1.0);
+    out_.uv = vec2(((x * 0.5) + 0.5), (1.0 - ((y * 0.5) + 0.5)));
+    VertexOutput _e34 = out_;
+    gl_Position = _e34.position;
+    _vs2fs_location0 = _e34.uv;
+    gl_Position.yz = vec2(-gl_Position.y, gl_Position.z * 2.0 - gl_Position.w);
+    return;
+}
+
@@ -0,0 +1,23 @@
+struct VertexOutput {
+    float4 position : SV_Position;
+    float2 uv : LOC0;
+};
+
+struct VertexOutput_main {
+    float2 uv : LOC0;
+    float4 position : SV_Position;
+};
+
+VertexOutput_main main(uint vertex_index : SV_VertexID)
+{
+    VertexOutput out_ = (VertexOutput)0;
+
+    float x = ((float((int(vertex_index) / 2)) * 4.0) - 1.0);
+    float y = ((float((int(vertex_index) % 2)) * 4.0) - 1.0);
+    out_.position = float4(x, y, 0.0, 1.0);
+    out_.uv = float2(((x * 0.5) + 0.5), (1.0 - ((y * 0.5) + 0.5)));
+    VertexOutput _e34 = out_;
+    const VertexOutput vertexoutput = _e34;
+    const VertexOutput_main vertexoutput_1 = { vertexoutput.uv, vertexoutput.position };
+    return vertexoutput_1;
+}
@@ -0,0 +1,29 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+struct VertexOutput {
+    metal::float4 position;
+    metal::float2 uv;
+};
+
+struct main_Input {
+};
+struct main_Output {
+    metal::float4 position [[position]];
+    metal::float2 uv [[user(loc0), center_perspective]];
+};
+vertex main_Output main_(
+  uint vertex_index [[vertex_id]]
+) {
+    VertexOutput out = {};
+    float x = (static_cast<float>(static_cast<int>(vertex_index) / 2) * 4.0) - 1.0;
+    float y = (static_cast<float>(static_cast<int>(vertex_index) % 2) * 4.0) - 1.0;
+    out.position = metal::float4(x, y, 0.0, 1.0);
+    out.uv = metal::float2((x * 0.5) + 0.5, 1.0 - ((y * 0.5) + 0.5));
+    VertexOutput _e34 = out;
+    const auto _tmp = _e34;
+    return main_Output { _tmp.position, _tmp.uv };
+}
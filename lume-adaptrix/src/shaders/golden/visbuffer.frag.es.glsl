@@ -0,0 +1,79 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    vec4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    vec4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    mat4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    vec4 bounding_sphere;
+};
+struct BvhNode {
+    vec4 bounding_sphere;
+    vec4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct View {
+    mat4x4 view_proj;
+    mat4x4 inv_view_proj;
+    vec4 frustum[6];
+    vec2 viewport_size;
+    float error_threshold;
+};
+struct VertexOutput {
+    vec4 position;
+    uint cluster_id;
+    uint triangle_id;
+    uint instance_id;
+};
+struct FragmentOutput {
+    uvec2 vis_data;
+};
+const uint QUEUE_DISPATCH_X = 0u;
+const uint QUEUE_COUNT = 3u;
+const uint DRAW_INSTANCE_COUNT = 1u;
+const uint PRIMITIVE_ID_BITS = 10u;
+
+layout(location = 0) flat in uint _vs2fs_location0;
+layout(location = 1) flat in uint _vs2fs_location1;
+layout(location = 2) flat in uint _vs2fs_location2;
+layout(location = 0) out uvec2 _fs2p_location0;
+
+void main() {
+    VertexOutput in_ = VertexOutput(gl_FragCoord, _vs2fs_location0, _vs2fs_location1, _vs2fs_location2);
+    FragmentOutput out_ = FragmentOutput(uvec2(0u));
+    uint id = (((in_.cluster_id + 1u) << PRIMITIVE_ID_BITS) | (in_.triangle_id & 1023u));
+    out_.vis_data = uvec2(in_.instance_id, id);
+    FragmentOutput _e14 = out_;
+    _fs2p_location0 = _e14.vis_data;
+    return;
+}
+
@@ -0,0 +1,85 @@
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    float4 parent_sphere;
+};
+
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+
+struct MeshInstance {
+    row_major float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    float4 bounding_sphere;
+};
+
+struct BvhNode {
+    float4 bounding_sphere;
+    float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+
+struct View {
+    row_major float4x4 view_proj;
+    row_major float4x4 inv_view_proj;
+    float4 frustum[6];
+    float2 viewport_size;
+    float error_threshold;
+    int _end_pad_0;
+};
+
+struct VertexOutput {
+    float4 position : SV_Position;
+    nointerpolation uint cluster_id : LOC0;
+    nointerpolation uint triangle_id : LOC1;
+    nointerpolation uint instance_id : LOC2;
+};
+
+struct FragmentOutput {
+    nointerpolation uint2 vis_data : SV_Target0;
+};
+
+static const uint QUEUE_DISPATCH_X = 0u;
+static const uint QUEUE_COUNT = 3u;
+static const uint DRAW_INSTANCE_COUNT = 1u;
+static const uint PRIMITIVE_ID_BITS = 10u;
+
+struct FragmentInput_main {
+    nointerpolation uint cluster_id : LOC0;
+    nointerpolation uint triangle_id : LOC1;
+    nointerpolation uint instance_id : LOC2;
+    float4 position : SV_Position;
+};
+
+FragmentOutput main(FragmentInput_main fragmentinput_main)
+{
+    VertexOutput in_ = { fragmentinput_main.position, fragmentinput_main.cluster_id, fragmentinput_main.triangle_id, fragmentinput_main.instance_id };
+    FragmentOutput out_ = (FragmentOutput)0;
+
+    uint id = (((in_.cluster_id + 1u) << PRIMITIVE_ID_BITS) | (in_.triangle_id & 1023u));
+    out_.vis_data = uint2(in_.instance_id, id);
+    FragmentOutput _e14 = out_;
+    const FragmentOutput fragmentoutput = _e14;
+    return fragmentoutput;
+}
@@ -0,0 +1,94 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    metal::float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    metal::float4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    metal::float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    metal::float4 bounding_sphere;
+};
+struct BvhNode {
+    metal::float4 bounding_sphere;
+    metal::float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct WorkQueue {
+    metal::atomic_uint inner[4];
+};
+struct DrawArgs {
+    metal::atomic_uint inner[4];
+};
+struct type_5 {
+    metal::float4 inner[6];
+};
+struct View {
+    metal::float4x4 view_proj;
+    metal::float4x4 inv_view_proj;
+    type_5 frustum;
+    metal::float2 viewport_size;
+    float error_threshold;
+};
+struct VertexOutput {
+    metal::float4 position;
+    uint cluster_id;
+    uint triangle_id;
+    uint instance_id;
+};
+struct FragmentOutput {
+    metal::uint2 vis_data;
+};
+constant uint QUEUE_DISPATCH_X = 0u;
+constant uint QUEUE_COUNT = 3u;
+constant uint DRAW_INSTANCE_COUNT = 1u;
+constant uint PRIMITIVE_ID_BITS = 10u;
+
+struct main_Input {
+    uint cluster_id [[user(loc0), flat]];
+    uint triangle_id [[user(loc1), flat]];
+    uint instance_id [[user(loc2), flat]];
+};
+struct main_Output {
+    metal::uint2 vis_data [[color(0)]];
+};
+fragment main_Output main_(
+  main_Input varyings [[stage_in]]
+, metal::float4 position [[position]]
+) {
+    const VertexOutput in = { position, varyings.cluster_id, varyings.triangle_id, varyings.instance_id };
+    FragmentOutput out = {};
+    uint id = ((in.cluster_id + 1u) << PRIMITIVE_ID_BITS) | (in.triangle_id & 1023u);
+    out.vis_data = metal::uint2(in.instance_id, id);
+    FragmentOutput _e14 = out;
+    const auto _tmp = _e14;
+    return main_Output { _tmp.vis_data };
+}
@@ -0,0 +1,141 @@
+#version 310 es
+
+precision highp float;
+precision highp int;
+
+uniform uint naga_vs_first_instance;
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    vec4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    vec4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    mat4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    vec4 bounding_sphere;
+};
+struct BvhNode {
+    vec4 bounding_sphere;
+    vec4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct View {
+    mat4x4 view_proj;
+    mat4x4 inv_view_proj;
+    vec4 frustum[6];
+    vec2 viewport_size;
+    float error_threshold;
+};
+struct VertexOutput {
+    vec4 position;
+    uint cluster_id;
+    uint triangle_id;
+    uint instance_id;
+};
+const uint QUEUE_DISPATCH_X = 0u;
+const uint QUEUE_COUNT = 3u;
+const uint DRAW_INSTANCE_COUNT = 1u;
+const uint PRIMITIVE_ID_BITS = 10u;
+
+layout(std430, binding = 0) readonly buffer type_7_block_0Vertex { Cluster _group_0_binding_0_vs[]; };
+
+layout(std430, binding = 1) readonly buffer type_8_block_1Vertex { AdaptrixVertex _group_0_binding_1_vs[]; };
+
+layout(std430, binding = 2) readonly buffer type_9_block_2Vertex { uint _group_0_binding_2_vs[]; };
+
+layout(std430, binding = 3) readonly buffer type_11_block_3Vertex { uvec2 _group_0_binding_3_vs[]; };
+
+layout(std430, binding = 4) readonly buffer type_12_block_4Vertex { MeshInstance _group_0_binding_4_vs[]; };
+
+layout(std140, binding = 5) uniform View_block_5Vertex { View _group_1_binding_0_vs; };
+
+layout(location = 0) flat out uint _vs2fs_location0;
+layout(location = 1) flat out uint _vs2fs_location1;
+layout(location = 2) flat out uint _vs2fs_location2;
+
+VertexOutput dummy_output() {
+    VertexOutput out_1 = VertexOutput(vec4(0.0), 0u, 0u, 0u);
+    out_1.position = vec4(0.0, 0.0, 2.0, 0.0);
+    VertexOutput _e7 = out_1;
+    return _e7;
+}
+
+void main() {
+    uint instance_idx = (uint(gl_InstanceID) + naga_vs_first_instance);
+    uint vertex_idx = uint(gl_VertexID);
+    VertexOutput out_ = VertexOutput(vec4(0.0), 0u, 0u, 0u);
+    if ((instance_idx >= uint(_group_0_binding_3_vs.length()))) {
+        VertexOutput _e5 = dummy_output();
+        gl_Position = _e5.position;
+        _vs2fs_location0 = _e5.cluster_id;
+        _vs2fs_location1 = _e5.triangle_id;
+        _vs2fs_location2 = _e5.instance_id;
+        gl_Position.yz = vec2(-gl_Position.y, gl_Position.z * 2.0 - gl_Position.w);
+        return;
+    }
+    uvec2 entry = _group_0_binding_3_vs[instance_idx];
+    uint cluster_id = entry.y;
+    if (((cluster_id >= uint(_group_0_binding_0_vs.length())) || (entry.x >= uint(_group_0_binding_4_vs.length())))) {
+        VertexOutput _e18 = dummy_output();
+        gl_Position = _e18.position;
+        _vs2fs_location0 = _e18.cluster_id;
+        _vs2fs_location1 = _e18.triangle_id;
+        _vs2fs_location2 = _e18.instance_id;
+        gl_Position.yz = vec2(-gl_Position.y, gl_Position.z * 2.0 - gl_Position.w);
+        return;
+    }
+    Cluster cluster = _group_0_binding_0_vs[cluster_id];
+    uint triangle_id = (vertex_idx / 3u);
+    uint local_v_idx = (vertex_idx % 3u);
+    if ((triangle_id >= cluster.triangle_count)) {
+        VertexOutput _e28 = dummy_output();
+        gl_Position = _e28.position;
+        _vs2fs_location0 = _e28.cluster_id;
+        _vs2fs_location1 = _e28.triangle_id;
+        _vs2fs_location2 = _e28.instance_id;
+        gl_Position.yz = vec2(-gl_Position.y, gl_Position.z * 2.0 - gl_Position.w);
+        return;
+    }
+    uint index_idx = ((cluster.triangle_offset + (triangle_id * 3u)) + local_v_idx);
+    uint _e37 = _group_0_binding_2_vs[index_idx];
+    uint v_idx = (cluster.vertex_offset + _e37);
+    AdaptrixVertex vertex = _group_0_binding_1_vs[v_idx];
+    mat4x4 _e46 = _group_1_binding_0_vs.view_proj;
+    mat4x4 _e51 = _group_0_binding_4_vs[entry.x].world_from_local;
+    out_.position = ((_e46 * _e51) * vec4(vertex.px, vertex.py, vertex.pz, 1.0));
+    out_.cluster_id = cluster_id;
+    out_.triangle_id = triangle_id;
+    out_.instance_id = entry.x;
+    VertexOutput _e63 = out_;
+    gl_Position = _e63.position;
+    _vs2fs_location0 = _e63.cluster_id;
+    _vs2fs_location1 = _e63.triangle_id;
+    _vs2fs_location2 = _e63.instance_id;
+    gl_Position.yz = vec2(-gl_Position.y, gl_Position.z * 2.0 - gl_Position.w);
+    return;
+}
+
@@ -0,0 +1,164 @@
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    float4 parent_sphere;
+};
+
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+
+struct MeshInstance {
+    row_major float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    float4 bounding_sphere;
+};
+
+struct BvhNode {
+    float4 bounding_sphere;
+    float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+
+struct View {
+    row_major float4x4 view_proj;
+    row_major float4x4 inv_view_proj;
+    float4 frustum[6];
+    float2 viewport_size;
+    float error_threshold;
+    int _end_pad_0;
+};
+
+struct VertexOutput {
+    float4 position : SV_Position;
+    nointerpolation uint cluster_id : LOC0;
+    nointerpolation uint triangle_id : LOC1;
+    nointerpolation uint instance_id : LOC2;
+};
+
+static const uint QUEUE_DISPATCH_X = 0u;
+static const uint QUEUE_COUNT = 3u;
+static const uint DRAW_INSTANCE_COUNT = 1u;
+static const uint PRIMITIVE_ID_BITS = 10u;
+
+ByteAddressBuffer clusters : register(t0);
+ByteAddressBuffer vertices_ : register(t1);
+ByteAddressBuffer indices_ : register(t2);
+ByteAddressBuffer visible_clusters : register(t3);
+ByteAddressBuffer instances : register(t4);
+cbuffer view : register(b0, space1) { View view; }
+
+struct VertexOutput_main {
+    nointerpolation uint cluster_id : LOC0;
+    nointerpolation uint triangle_id : LOC1;
+    nointerpolation uint instance_id : LOC2;
+    float4 position : SV_Position;
+};
+
+VertexOutput dummy_output()
+{
+    VertexOutput out_1 = (VertexOutput)0;
+
+    out_1.position = float4(0.0, 0.0, 2.0, 0.0);
+    VertexOutput _e7 = out_1;
+    const VertexOutput vertexoutput = _e7;
+    return vertexoutput;
+}
+
+uint NagaBufferLength(ByteAddressBuffer buffer)
+{
+    uint ret;
+    buffer.GetDimensions(ret);
+    return ret;
+}
+
+Cluster ConstructCluster(uint arg0, uint arg1, uint arg2, uint arg3, float4 arg4, float arg5, float arg6, float arg7, float arg8, float4 arg9) {
+    Cluster ret = (Cluster)0;
+    ret.vertex_offset = arg0;
+    ret.triangle_offset = arg1;
+    ret.vertex_count = arg2;
+    ret.triangle_count = arg3;
+    ret.bounding_sphere = arg4;
+    ret.error_metric = arg5;
+    ret.parent_error = arg6;
+    ret.pad0_ = arg7;
+    ret.pad1_ = arg8;
+    ret.parent_sphere = arg9;
+    return ret;
+}
+
+AdaptrixVertex ConstructAdaptrixVertex(float arg0, float arg1, float arg2, float arg3, float arg4, float arg5, float arg6, float arg7) {
+    AdaptrixVertex ret = (AdaptrixVertex)0;
+    ret.px = arg0;
+    ret.py = arg1;
+    ret.pz = arg2;
+    ret.nx = arg3;
+    ret.ny = arg4;
+    ret.nz = arg5;
+    ret.u = arg6;
+    ret.v = arg7;
+    return ret;
+}
+
+VertexOutput_main main(uint instance_idx : SV_InstanceID, uint vertex_idx : SV_VertexID)
+{
+    VertexOutput out_ = (VertexOutput)0;
+
+    if ((instance_idx >= ((NagaBufferLength(visible_clusters) - 0) / 8))) {
+        const VertexOutput _e5 = dummy_output();
+        const VertexOutput vertexoutput_1 = _e5;
+        const VertexOutput_main vertexoutput_1_ = { vertexoutput_1.cluster_id, vertexoutput_1.triangle_id, vertexoutput_1.instance_id, vertexoutput_1.position };
+        return vertexoutput_1_;
+    }
+    uint2 entry = asuint(visible_clusters.Load2(instance_idx*8));
+    uint cluster_id_1 = entry.y;
+    if (((cluster_id_1 >= ((NagaBufferLength(clusters) - 0) / 64)) || (entry.x >= ((NagaBufferLength(instances) - 0) / 96)))) {
+        const VertexOutput _e18 = dummy_output();
+        const VertexOutput vertexoutput_2 = _e18;
+        const VertexOutput_main vertexoutput_2_ = { vertexoutput_2.cluster_id, vertexoutput_2.triangle_id, vertexoutput_2.instance_id, vertexoutput_2.position };
+        return vertexoutput_2_;
+    }
+    Cluster cluster = ConstructCluster(asuint(clusters.Load(cluster_id_1*64+0)), asuint(clusters.Load(cluster_id_1*64+4)), asuint(clusters.Load(cluster_id_1*64+8)), asuint(clusters.Load(cluster_id_1*64+12)), asfloat(clusters.Load4(cluster_id_1*64+16)), asfloat(clusters.Load(cluster_id_1*64+32)), asfloat(clusters.Load(cluster_id_1*64+36)), asfloat(clusters.Load(cluster_id_1*64+40)), asfloat(clusters.Load(cluster_id_1*64+44)), asfloat(clusters.Load4(cluster_id_1*64+48)));
+    uint triangle_id_1 = (vertex_idx / 3u);
+    uint local_v_idx = (vertex_idx % 3u);
+    if ((triangle_id_1 >= cluster.triangle_count)) {
+        const VertexOutput _e28 = dummy_output();
+        const VertexOutput vertexoutput_3 = _e28;
+        const VertexOutput_main vertexoutput_3_ = { vertexoutput_3.cluster_id, vertexoutput_3.triangle_id, vertexoutput_3.instance_id, vertexoutput_3.position };
+        return vertexoutput_3_;
+    }
+    uint index_idx = ((cluster.triangle_offset + (triangle_id_1 * 3u)) + local_v_idx);
+    uint _e37 = asuint(indices_.Load(index_idx*4));
+    uint v_idx = (cluster.vertex_offset + _e37);
+    AdaptrixVertex vertex = ConstructAdaptrixVertex(asfloat(vertices_.Load(v_idx*32+0)), asfloat(vertices_.Load(v_idx*32+4)), asfloat(vertices_.Load(v_idx*32+8)), asfloat(vertices_.Load(v_idx*32+12)), asfloat(vertices_.Load(v_idx*32+16)), asfloat(vertices_.Load(v_idx*32+20)), asfloat(vertices_.Load(v_idx*32+24)), asfloat(vertices_.Load(v_idx*32+28)));
+    float4x4 _e46 = view.view_proj;
+    float4x4 _e51 = float4x4(asfloat(instances.Load4(0+entry.x*96+0)), asfloat(instances.Load4(0+entry.x*96+16)), asfloat(instances.Load4(0+entry.x*96+32)), asfloat(instances.Load4(0+entry.x*96+48)));
+    out_.position = mul(float4(vertex.px, vertex.py, vertex.pz, 1.0), mul(_e51, _e46));
+    out_.cluster_id = cluster_id_1;
+    out_.triangle_id = triangle_id_1;
+    out_.instance_id = entry.x;
+    VertexOutput _e63 = out_;
+    const VertexOutput vertexoutput_4 = _e63;
+    const VertexOutput_main vertexoutput_4_ = { vertexoutput_4.cluster_id, vertexoutput_4.triangle_id, vertexoutput_4.instance_id, vertexoutput_4.position };
+    return vertexoutput_4_;
+}
@@ -0,0 +1,146 @@
+// language: metal2.1
+#include <metal_stdlib>
+#include <simd/simd.h>
+
+using metal::uint;
+
+struct _mslBufferSizes {
+    uint size0;
+    uint size1;
+    uint size2;
+    uint size3;
+    uint size4;
+};
+
+struct Cluster {
+    uint vertex_offset;
+    uint triangle_offset;
+    uint vertex_count;
+    uint triangle_count;
+    metal::float4 bounding_sphere;
+    float error_metric;
+    float parent_error;
+    float pad0_;
+    float pad1_;
+    metal::float4 parent_sphere;
+};
+struct AdaptrixVertex {
+    float px;
+    float py;
+    float pz;
+    float nx;
+    float ny;
+    float nz;
+    float u;
+    float v;
+};
+struct MeshInstance {
+    metal::float4x4 world_from_local;
+    uint cluster_base;
+    uint cluster_count;
+    uint bvh_root;
+    uint pad0_;
+    metal::float4 bounding_sphere;
+};
+struct BvhNode {
+    metal::float4 bounding_sphere;
+    metal::float4 lod_sphere;
+    float max_parent_error;
+    uint first;
+    uint count;
+    uint leaf;
+};
+struct WorkQueue {
+    metal::atomic_uint inner[4];
+};
+struct DrawArgs {
+    metal::atomic_uint inner[4];
+};
+struct type_5 {
+    metal::float4 inner[6];
+};
+struct View {
+    metal::float4x4 view_proj;
+    metal::float4x4 inv_view_proj;
+    type_5 frustum;
+    metal::float2 viewport_size;
+    float error_threshold;
+};
+typedef Cluster type_7[1];
+typedef AdaptrixVertex type_8[1];
+typedef uint type_9[1];
+typedef metal::uint2 type_11[1];
+typedef MeshInstance type_12[1];
+struct VertexOutput {
+    metal::float4 position;
+    uint cluster_id;
+    uint triangle_id;
+    uint instance_id;
+};
+constant uint QUEUE_DISPATCH_X = 0u;
+constant uint QUEUE_COUNT = 3u;
+constant uint DRAW_INSTANCE_COUNT = 1u;
+constant uint PRIMITIVE_ID_BITS = 10u;
+
+VertexOutput dummy_output(
+) {
+    VertexOutput out_1 = {};
+    out_1.position = metal::float4(0.0, 0.0, 2.0, 0.0);
+    VertexOutput _e7 = out_1;
+    return _e7;
+}
+
+struct main_Input {
+};
+struct main_Output {
+    metal::float4 position [[position]];
+    uint cluster_id [[user(loc0), flat]];
+    uint triangle_id [[user(loc1), flat]];
+    uint instance_id [[user(loc2), flat]];
+};
+vertex main_Output main_(
+  uint instance_idx [[instance_id]]
+, uint vertex_idx [[vertex_id]]
+, device type_7 const& clusters [[buffer(0)]]
+, device type_8 const& vertices [[buffer(1)]]
+, device type_9 const& indices [[buffer(2)]]
+, device type_11 const& visible_clusters [[buffer(3)]]
+, device type_12 const& instances [[buffer(4)]]
+, constant View& view [[buffer(5)]]
+, constant _mslBufferSizes& _buffer_sizes [[buffer(6)]]
+) {
+    VertexOutput out = {};
+    if (instance_idx >= (1 + (_buffer_sizes.size3 - 0 - 8) / 8)) {
+        VertexOutput _e5 = dummy_output();
+        const auto _tmp = _e5;
+        return main_Output { _tmp.position, _tmp.cluster_id, _tmp.triangle_id, _tmp.instance_id };
+    }
+    metal::uint2 entry = visible_clusters[instance_idx];
+    uint cluster_id = entry.y;
+    if ((cluster_id >= (1 + (_buffer_sizes.size0 - 0 - 64) / 64)) || (entry.x >= (1 + (_buffer_sizes.size4 - 0 - 96) / 96))) {
+        VertexOutput _e18 = dummy_output();
+        const auto _tmp = _e18;
+        return main_Output { _tmp.position, _tmp.cluster_id, _tmp.triangle_id, _tmp.instance_id };
+    }
+    Cluster cluster = clusters[cluster_id];
+    uint triangle_id = vertex_idx / 3u;
+    uint local_v_idx = vertex_idx % 3u;
+    if (triangle_id >= cluster.triangle_count) {
+        VertexOutput _e28 = dummy_output();
+        const auto _tmp = _e28;
+        return main_Output { _tmp.position, _tmp.cluster_id, _tmp.triangle_id, _tmp.instance_id };
+    }
+    uint index_idx = (cluster.triangle_offset + (triangle_id * 3u)) + local_v_idx;
+    uint _e37 = indices[index_idx];
+    uint v_idx = cluster.vertex_offset + _e37;
+    AdaptrixVertex vertex_ = vertices[v_idx];
+    metal::float4x4 _e46 = view.view_proj;
+    metal::float4x4 _e51 = instances[entry.x].world_from_local;
+    out.position = (_e46 * _e51) * metal::float4(vertex_.px, vertex_.py, vertex_.pz, 1.0);
+    out.cluster_id = cluster_id;
+    out.triangle_id = triangle_id;
+    out.instance_id = entry.x;
+    VertexOutput _e63 = out;
+    const auto _tmp = _e63;
+    return main_Output { _tmp.position, _tmp.cluster_id, _tmp.triangle_id, _tmp.instance_id };
+}
@@ -0,0 +1,42 @@
+// One level of the hierarchical-Z buffer: every texel keeps the farthest depth of the source
+// texels it covers. The first level halves the VisBuffer depth (`HZB_FROM_DEPTH`), every
+// further level halves the one before it, down to 1x1.
+//
+// Sizes round down, so the last column and row of an odd-sized source are folded into the
+// last destination texel, which then covers three source texels instead of two. Texel `i`
+// of level `l` therefore covers depth texels from `i << (l + 1)`, and the last one all the rest.
+
+#ifdef HZB_FROM_DEPTH
+@group(0) @binding(0) var source: texture_depth_2d;
+#else
+// Read as a storage texture so every level of the HZB stays in the same image layout.
+@group(0) @binding(0) var source: texture_storage_2d<r32float, read>;
+#endif
+@group(0) @binding(1) var destination: texture_storage_2d<r32float, write>;
+
+fn load_source(coord: vec2<u32>) -> f32 {
+#ifdef HZB_FROM_DEPTH
+    return textureLoad(source, coord, 0);
+#else
+    return textureLoad(source, coord).r;
+#endif
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if (global_id.x >= size.x || global_id.y >= size.y) {
+        return;
+    }
+    let source_size = textureDimensions(source);
+    let start = global_id.xy * 2u;
+    let end = min(select(start + 2u, source_size, global_id.xy == size - 1u), source_size);
+
+    var depth = 0.0;
+    for (var y = start.y; y < end.y; y = y + 1u) {
+        for (var x = start.x; x < end.x; x = x + 1u) {
+            depth = max(depth, load_source(vec2<u32>(x, y)));
+        }
+    }
+    textureStore(destination, global_id.xy, vec4<f32>(depth, 0.0, 0.0, 0.0));
+}
@@ -0,0 +1,41 @@
+// First culling stage: tests every instance's bounds and expands the survivors into a queue
+// of (instance, cluster) pairs for `cull.wgsl`, one per cluster of the instance's mesh.
+
+#include "culling.wgsl"
+#ifdef OCCLUSION_FIRST_PASS
+#include "occlusion.wgsl"
+#endif
+
+@group(0) @binding(0) var<storage, read> instances: array<MeshInstance>;
+@group(0) @binding(1) var<storage, read_write> work_queue: WorkQueue;
+@group(0) @binding(2) var<storage, read_write> queue: array<vec2<u32>>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= arrayLength(&instances)) {
+        return;
+    }
+    let instance = instances[global_id.x];
+    let m = instance.world_from_local;
+    let sphere = transform_sphere(m, max_scale(m), instance.bounding_sphere);
+    if (instance.cluster_count == 0u || !sphere_in_frustum(sphere)) {
+        return;
+    }
+
+#ifdef OCCLUSION_FIRST_PASS
+    // Hidden last frame: the second pass tests every cluster against this frame's HZB instead.
+    if (is_occluded(sphere)) {
+        let first = reserve_occluded(instance.cluster_count);
+        for (var i = 0u; i < instance.cluster_count && first + i < arrayLength(&occluded); i = i + 1u) {
+            occluded[first + i] = vec2<u32>(global_id.x, instance.cluster_base + i);
+        }
+        return;
+    }
+#endif
+
+    let first = atomicAdd(&work_queue[QUEUE_COUNT], instance.cluster_count);
+    atomicMax(&work_queue[QUEUE_DISPATCH_X], (first + instance.cluster_count + 63u) / 64u);
+    for (var i = 0u; i < instance.cluster_count && first + i < arrayLength(&queue); i = i + 1u) {
+        queue[first + i] = vec2<u32>(global_id.x, instance.cluster_base + i);
+    }
+}
@@ -0,0 +1,53 @@
+// Two-pass occlusion culling. The first pass tests against last frame's HZB, as seen from the
+// view it was built with, and queues what it rejects. After the survivors are drawn and the
+// HZB is rebuilt, the second pass re-tests the queue against it with this frame's view.
+
+#include "common.wgsl"
+
+@group(2) @binding(0) var hzb: texture_2d<f32>;
+@group(2) @binding(1) var<uniform> hzb_view: View;
+// (instance, cluster) pairs rejected by the first pass, consumed by the second.
+@group(2) @binding(2) var<storage, read_write> occluded_queue: WorkQueue;
+@group(2) @binding(3) var<storage, read_write> occluded: array<vec2<u32>>;
+
+// Whether the world-space `sphere` lies behind the farthest depth `hzb` holds over the
+// rectangle it covers. Spheres reaching in front of the near plane are never occluded.
+fn is_occluded(sphere: vec4<f32>) -> bool {
+    var ndc_min = vec3<f32>(1.0);
+    var ndc_max = vec2<f32>(-1.0);
+    for (var i = 0u; i < 8u; i = i + 1u) {
+        let corner = vec3<f32>(vec3<u32>(i, i >> 1u, i >> 2u) & vec3<u32>(1u)) * 2.0 - 1.0;
+        let clip = hzb_view.view_proj * vec4<f32>(sphere.xyz + corner * sphere.w, 1.0);
+        if (clip.w <= 0.0 || clip.z < 0.0) {
+            return false;
+        }
+        let ndc = clip.xyz / clip.w;
+        ndc_min = min(ndc_min, ndc);
+        ndc_max = max(ndc_max, ndc.xy);
+    }
+
+    // The depth texels covered, then the level where they span at most two texels each way.
+    let uv_min = clamp(ndc_min.xy * 0.5 + 0.5, vec2<f32>(0.0), vec2<f32>(1.0));
+    let uv_max = clamp(ndc_max * 0.5 + 0.5, vec2<f32>(0.0), vec2<f32>(1.0));
+    let extent = (uv_max - uv_min) * hzb_view.viewport_size;
+    let level = min(u32(max(ceil(log2(max(max(extent.x, extent.y), 1.0))) - 1.0, 0.0)), textureNumLevels(hzb) - 1u);
+    let last = textureDimensions(hzb, level) - 1u;
+    let texel_min = min(vec2<u32>(uv_min * hzb_view.viewport_size) >> vec2<u32>(level + 1u), last);
+    let texel_max = min(vec2<u32>(uv_max * hzb_view.viewport_size) >> vec2<u32>(level + 1u), last);
+
+    // naga only accepts signed levels in `textureLoad`.
+    let lod = i32(level);
+    let depth = max(
+        max(textureLoad(hzb, texel_min, lod).r, textureLoad(hzb, vec2<u32>(texel_max.x, texel_min.y), lod).r),
+        max(textureLoad(hzb, vec2<u32>(texel_min.x, texel_max.y), lod).r, textureLoad(hzb, texel_max, lod).r),
+    );
+    return ndc_min.z > depth;
+}
+
+// Reserves `count` entries of `occluded`, growing the second pass's dispatch to cover them;
+// returns the first. Entries past the end of `occluded` are dropped by both passes.
+fn reserve_occluded(count: u32) -> u32 {
+    let first = atomicAdd(&occluded_queue[QUEUE_COUNT], count);
+    atomicMax(&occluded_queue[QUEUE_DISPATCH_X], (first + count + 63u) / 64u);
+    return first;
+}
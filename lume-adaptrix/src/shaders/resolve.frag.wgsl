@@ -1,32 +1,9 @@
-struct Cluster {
-    vertex_offset: u32,
-    triangle_offset: u32,
-    vertex_count: u32,
-    triangle_count: u32,
-    bounding_sphere: vec4<f32>,
-    error_metric: f32,
-    parent_error: f32,
-    pad0: f32,
-    pad1: f32,
-};
-
-struct AdaptrixVertex {
-    px: f32, py: f32, pz: f32,
-    nx: f32, ny: f32, nz: f32,
-    u: f32, v: f32,
-};
-
-struct View {
-    view_proj: mat4x4<f32>,
-    inv_view_proj: mat4x4<f32>,
-    frustum: array<vec4<f32>, 6>,
-    viewport_size: vec2<f32>,
-    error_threshold: f32,
-};
+#include "common.wgsl"
 
 @group(0) @binding(0) var<storage, read> clusters: array<Cluster>;
 @group(0) @binding(1) var<storage, read> vertices: array<AdaptrixVertex>;
 @group(0) @binding(2) var<storage, read> indices: array<u32>;
+@group(0) @binding(3) var<storage, read> instances: array<MeshInstance>;
 
 @group(1) @binding(0) var<uniform> view: View;
 @group(1) @binding(1) var vis_buffer: texture_2d<u32>; 
@@ -52,13 +29,21 @@ fn main(in: VertexOutput) -> @location(0) vec4<f32> {
         return vec4<f32>(0.05, 0.05, 0.07, 1.0);
     }
     
-    let cluster_id = id >> 10u;
-    let triangle_id = id & 0x3FFu;
+    let instance_id = vis_data.x;
+    let cluster_id = (id >> PRIMITIVE_ID_BITS) - 1u;
+    let triangle_id = id & ((1u << PRIMITIVE_ID_BITS) - 1u);
     
-    // --- 调试模式：显示集群颜色 ---
-    let r = hash(cluster_id);
-    let g = hash(cluster_id + 1u);
-    let b = hash(cluster_id + 2u);
+    // 调试视图由排列键 DEBUG_VIEW 选择：CLUSTERS（默认）、TRIANGLES、SHADED
+#ifdef DEBUG_VIEW_TRIANGLES
+    let seed = cluster_id * 128u + triangle_id;
+#else
+    let seed = cluster_id;
+#endif
+#ifdef DEBUG_VIEW_SHADED
+    let albedo = vec3<f32>(0.8, 0.8, 0.8);
+#else
+    let albedo = vec3<f32>(hash(seed), hash(seed + 1u), hash(seed + 2u));
+#endif
     
     // 加入简单的法线光照来增加立体感
     let cluster = clusters[cluster_id];
@@ -69,13 +54,14 @@ fn main(in: VertexOutput) -> @location(0) vec4<f32> {
     let v1 = vertices[cluster.vertex_offset + i1];
     let v2 = vertices[cluster.vertex_offset + i2];
     
-    let p0 = vec3(v0.px, v0.py, v0.pz);
-    let p1 = vec3(v1.px, v1.py, v1.pz);
-    let p2 = vec3(v2.px, v2.py, v2.pz);
+    let m = instances[instance_id].world_from_local;
+    let p0 = (m * vec4(v0.px, v0.py, v0.pz, 1.0)).xyz;
+    let p1 = (m * vec4(v1.px, v1.py, v1.pz, 1.0)).xyz;
+    let p2 = (m * vec4(v2.px, v2.py, v2.pz, 1.0)).xyz;
     let normal = normalize(cross(p1 - p0, p2 - p0));
     
     let light_dir = normalize(vec3<f32>(1.0, 1.0, 2.0));
     let diff = max(dot(normal, light_dir), 0.3);
     
-    return vec4<f32>(vec3(r, g, b) * diff, 1.0);
+    return vec4<f32>(albedo * diff, 1.0);
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
+#include "common.wgsl"
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) @interpolate(flat) cluster_id: u32,
     @location(1) @interpolate(flat) triangle_id: u32,
+    @location(2) @interpolate(flat) instance_id: u32,
 };
 
 struct FragmentOutput {
@@ -10,10 +13,9 @@ struct FragmentOutput {
 
 @fragment
 fn main(in: VertexOutput) -> FragmentOutput {
-    let depth = bitcast<u32>(in.position.z);
-    let id = (in.cluster_id << 10u) | (in.triangle_id & 0x3FFu);
+    let id = ((in.cluster_id + 1u) << PRIMITIVE_ID_BITS) | (in.triangle_id & ((1u << PRIMITIVE_ID_BITS) - 1u));
     
     var out: FragmentOutput;
-    out.vis_data = vec2<u32>(depth, id);
+    out.vis_data = vec2<u32>(in.instance_id, id);
     return out;
 }
\ No newline at end of file
@@ -1,33 +1,11 @@
-struct Cluster {
-    vertex_offset: u32,
-    triangle_offset: u32,
-    vertex_count: u32,
-    triangle_count: u32,
-    bounding_sphere: vec4<f32>,
-    error_metric: f32,
-    parent_error: f32,
-    pad0: f32,
-    pad1: f32,
-};
-
-struct AdaptrixVertex {
-    px: f32, py: f32, pz: f32,
-    nx: f32, ny: f32, nz: f32,
-    u: f32, v: f32,
-};
-
-struct View {
-    view_proj: mat4x4<f32>,
-    inv_view_proj: mat4x4<f32>,
-    frustum: array<vec4<f32>, 6>,
-    viewport_size: vec2<f32>,
-    error_threshold: f32,
-};
+#include "common.wgsl"
 
 @group(0) @binding(0) var<storage, read> clusters: array<Cluster>;
 @group(0) @binding(1) var<storage, read> vertices: array<AdaptrixVertex>;
 @group(0) @binding(2) var<storage, read> indices: array<u32>;
-@group(0) @binding(3) var<storage, read> visible_clusters: array<u32>;
+// (instance, cluster) pairs written by the cull pass.
+@group(0) @binding(3) var<storage, read> visible_clusters: array<vec2<u32>>;
+@group(0) @binding(4) var<storage, read> instances: array<MeshInstance>;
 
 @group(1) @binding(0) var<uniform> view: View;
 
@@ -35,14 +13,19 @@ struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) @interpolate(flat) cluster_id: u32,
     @location(1) @interpolate(flat) triangle_id: u32,
+    @location(2) @interpolate(flat) instance_id: u32,
 };
 
 @vertex
 fn main(@builtin(instance_index) instance_idx: u32, @builtin(vertex_index) vertex_idx: u32) -> VertexOutput {
     // 从可见集群列表中获取 ID
-    let cluster_id = visible_clusters[instance_idx];
+    if (instance_idx >= arrayLength(&visible_clusters)) {
+        return dummy_output();
+    }
+    let entry = visible_clusters[instance_idx];
+    let cluster_id = entry.y;
     
-    if (cluster_id >= arrayLength(&clusters)) {
+    if (cluster_id >= arrayLength(&clusters) || entry.x >= arrayLength(&instances)) {
         return dummy_output();
     }
 
@@ -61,9 +44,10 @@ fn main(@builtin(instance_index) instance_idx: u32, @builtin(vertex_index) verte
     
     var out: VertexOutput;
     // 直接投影，不再手动翻转 Y
-    out.position = view.view_proj * vec4<f32>(vertex.px, vertex.py, vertex.pz, 1.0);
+    out.position = view.view_proj * instances[entry.x].world_from_local * vec4<f32>(vertex.px, vertex.py, vertex.pz, 1.0);
     out.cluster_id = cluster_id;
     out.triangle_id = triangle_id;
+    out.instance_id = entry.x;
     return out;
 }
 
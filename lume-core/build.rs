@@ -0,0 +1,45 @@
+//! Exposes the version of naga Cargo resolved as `LUME_NAGA_VERSION`, so the shader cache
+//! stops serving SPIR-V written by an older naga once the dependency is bumped.
+
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let ancestors = |var: &str| std::env::var_os(var).map(PathBuf::from).into_iter().flat_map(|dir| dir.ancestors().map(Path::to_path_buf).collect::<Vec<_>>());
+    // The lock file sits in the workspace root, above this crate when building in the
+    // workspace and above the target directory when built as a dependency.
+    let lock = ancestors("CARGO_MANIFEST_DIR").chain(ancestors("OUT_DIR"))
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lock| lock.is_file());
+
+    let version = lock.as_deref().and_then(|lock| {
+        println!("cargo:rerun-if-changed={}", lock.display());
+        naga_version(&std::fs::read_to_string(lock).ok()?)
+    });
+    let version = version.unwrap_or_else(|| {
+        println!("cargo:warning=Could not find the resolved naga version; cached shaders are not invalidated by naga updates");
+        "unknown".to_string()
+    });
+    println!("cargo:rustc-env=LUME_NAGA_VERSION={}", version);
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+}
+
+/// The version of the `naga` package in `lock` that lume-core's requirement selects.
+fn naga_version(lock: &str) -> Option<String> {
+    let major = naga_major();
+    lock.split("[[package]]").find_map(|package| {
+        let field = |name: &str| package.lines()
+            .find_map(|line| line.strip_prefix(name)?.trim().strip_prefix('=')?.trim().strip_prefix('"')?.strip_suffix('"'));
+        (field("name ") == Some("naga")).then(|| field("version "))?
+            .filter(|version| major.is_none() || version.split('.').next() == major.as_deref())
+            .map(str::to_string)
+    })
+}
+
+/// Major version of lume-core's naga requirement in Cargo.toml, which tells it apart from
+/// other versions of naga elsewhere in the dependency graph.
+fn naga_major() -> Option<String> {
+    let manifest = std::fs::read_to_string("Cargo.toml").ok()?;
+    manifest.lines()
+        .find_map(|line| line.strip_prefix("naga = ")?.split("version = \"").nth(1)?.split('.').next().map(str::to_string))
+}
@@ -15,13 +15,16 @@ pub trait Device: Sized + Clone {
     type BindGroupLayout: BindGroupLayout;
     type BindGroup: BindGroup;
     type Semaphore: Semaphore;
+    type TimelineSemaphore: TimelineSemaphore;
     type Fence: Fence;
 
     /// Wait for the device to be idle.
     fn wait_idle(&self) -> crate::LumeResult<()>;
 
-    fn create_command_pool(&self) -> crate::LumeResult<Self::CommandPool>;
+    /// Create a command pool whose command buffers are submitted to `queue`.
+    fn create_command_pool(&self, queue: QueueType) -> crate::LumeResult<Self::CommandPool>;
     fn create_semaphore(&self) -> crate::LumeResult<Self::Semaphore>;
+    fn create_timeline_semaphore(&self, initial_value: u64) -> crate::LumeResult<Self::TimelineSemaphore>;
     fn create_fence(&self, signaled: bool) -> crate::LumeResult<Self::Fence>;
 
     fn create_swapchain(
@@ -30,6 +33,10 @@ pub trait Device: Sized + Clone {
         descriptor: SwapchainDescriptor,
     ) -> crate::LumeResult<Self::Swapchain>;
 
+    /// Create a swapchain of offscreen textures for headless rendering.
+    /// It is driven by `begin_frame`/`end_frame` exactly like a window swapchain.
+    fn create_offscreen_swapchain(&self, descriptor: OffscreenSwapchainDescriptor) -> crate::LumeResult<Self::Swapchain>;
+
     fn create_shader_module(&self, code: &[u32]) -> crate::LumeResult<Self::ShaderModule>;
     fn create_render_pass(&self, descriptor: RenderPassDescriptor) -> crate::LumeResult<Self::RenderPass>;
     fn create_pipeline_layout(&self, descriptor: PipelineLayoutDescriptor<Self>) -> crate::LumeResult<Self::PipelineLayout>;
@@ -43,6 +50,19 @@ pub trait Device: Sized + Clone {
     fn create_bind_group_layout(&self, descriptor: BindGroupLayoutDescriptor) -> crate::LumeResult<Self::BindGroupLayout>;
     fn create_bind_group(&self, descriptor: BindGroupDescriptor<Self>) -> crate::LumeResult<Self::BindGroup>;
 
+    /// Create a bind group layout for every group in `reflection` and a pipeline layout over them.
+    fn create_pipeline_layout_from_reflection(&self, reflection: &crate::shader::ShaderReflection) -> crate::LumeResult<ReflectedLayout<Self>> {
+        let bind_group_layouts = reflection.bind_group_layouts().into_iter()
+            .map(|descriptor| self.create_bind_group_layout(descriptor))
+            .collect::<crate::LumeResult<Vec<_>>>()?;
+        let layout_refs: Vec<&Self::BindGroupLayout> = bind_group_layouts.iter().collect();
+        let pipeline_layout = self.create_pipeline_layout(PipelineLayoutDescriptor {
+            bind_group_layouts: &layout_refs,
+            push_constant_ranges: &reflection.push_constant_ranges,
+        })?;
+        Ok(ReflectedLayout { pipeline_layout, bind_group_layouts })
+    }
+
     // --- High-level Backend Engine API ---
     
     /// Starts a new frame, handles synchronization, and returns the frame index and swapchain image index.
@@ -51,17 +71,39 @@ pub trait Device: Sized + Clone {
     /// Submits the recorded command buffers for the current frame and presents.
     fn end_frame(&self, swapchain: &mut Self::Swapchain, token: FrameToken, command_buffers: &[&Self::CommandBuffer]) -> crate::LumeResult<()>;
 
-    /// Submit command buffers to the graphics queue.
+    /// Submit command buffers to `queue`; they must come from a pool created for the same queue type.
+    /// Execution waits until every `timeline_waits` semaphore reaches its value, and each
+    /// `timeline_signals` semaphore is set to its value once the command buffers complete.
+    #[allow(clippy::too_many_arguments)]
     fn submit(
         &self,
+        queue: QueueType,
         command_buffers: &[&Self::CommandBuffer],
         wait_semaphores: &[&Self::Semaphore],
         signal_semaphores: &[&Self::Semaphore],
+        timeline_waits: &[(&Self::TimelineSemaphore, u64)],
+        timeline_signals: &[(&Self::TimelineSemaphore, u64)],
         fence: Option<&Self::Fence>,
     ) -> crate::LumeResult<()>;
 
     fn wait_for_fences(&self, fences: &[&Self::Fence], wait_all: bool, timeout: u64) -> crate::LumeResult<()>;
     fn reset_fences(&self, fences: &[&Self::Fence]) -> crate::LumeResult<()>;
+
+    /// Copy a whole texture into host memory, blocking until the GPU is done.
+    /// The bytes are tightly packed rows of mip 0 in the texture's `TextureFormat`.
+    fn read_texture(&self, texture: &Self::Texture) -> crate::LumeResult<Vec<u8>>;
+
+    /// Read back the image most recently presented by `end_frame` on an offscreen swapchain.
+    fn read_presented_image(&self, swapchain: &Self::Swapchain) -> crate::LumeResult<image::RgbaImage>;
+}
+
+/// The hardware queue work is recorded for and submitted to. Backends without dedicated
+/// compute or transfer queues fall back to the graphics queue.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum QueueType {
+    Graphics,
+    Compute,
+    Transfer,
 }
 
 pub struct FrameToken {
@@ -91,15 +133,67 @@ pub trait CommandBuffer {
     fn bind_graphics_pipeline(&mut self, pipeline: &<Self::Device as Device>::GraphicsPipeline);
     fn bind_compute_pipeline(&mut self, pipeline: &<Self::Device as Device>::ComputePipeline);
     fn bind_vertex_buffer(&mut self, buffer: &<Self::Device as Device>::Buffer);
+    fn bind_index_buffer(&mut self, buffer: &<Self::Device as Device>::Buffer, format: IndexFormat);
     fn bind_bind_group(&mut self, index: u32, bind_group: &<Self::Device as Device>::BindGroup);
+    /// Update push constants of the currently bound pipeline's layout. `offset` and `data.len()` must be multiples of 4.
+    fn set_push_constants(&mut self, stages: ShaderStage, offset: u32, data: &[u8]);
     fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32);
     fn set_scissor(&mut self, x: i32, y: i32, width: u32, height: u32);
     fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32);
+    fn draw_indexed(&mut self, index_count: u32, instance_count: u32, first_index: u32, base_vertex: i32, first_instance: u32);
     fn dispatch(&mut self, x: u32, y: u32, z: u32);
+
+    /// Draw with `DrawIndirectArgs` read from `buffer`, `stride` bytes apart.
+    fn draw_indirect(&mut self, buffer: &<Self::Device as Device>::Buffer, offset: u64, draw_count: u32, stride: u32);
+    /// Like `draw_indirect`, but the draw count is a `u32` read from `count_buffer`, clamped to `max_draw_count`.
+    fn draw_indirect_count(&mut self, buffer: &<Self::Device as Device>::Buffer, offset: u64, count_buffer: &<Self::Device as Device>::Buffer, count_offset: u64, max_draw_count: u32, stride: u32);
+    /// Draw with `DrawIndexedIndirectArgs` read from `buffer`, `stride` bytes apart.
+    fn draw_indexed_indirect(&mut self, buffer: &<Self::Device as Device>::Buffer, offset: u64, draw_count: u32, stride: u32);
+    /// Dispatch with the workgroup counts in a `DispatchIndirectArgs` read from `buffer`.
+    fn dispatch_indirect(&mut self, buffer: &<Self::Device as Device>::Buffer, offset: u64);
     fn copy_buffer_to_buffer(&mut self, source: &<Self::Device as Device>::Buffer, destination: &<Self::Device as Device>::Buffer, size: u64);
     fn copy_buffer_to_texture(&mut self, buffer: &<Self::Device as Device>::Buffer, texture: &<Self::Device as Device>::Texture, width: u32, height: u32);
+    /// Copy a region of a texture into a buffer, transitioning the texture as needed.
+    fn copy_texture_to_buffer(&mut self, texture: &<Self::Device as Device>::Texture, buffer: &<Self::Device as Device>::Buffer, region: TextureCopyRegion);
     fn texture_barrier(&mut self, texture: &<Self::Device as Device>::Texture, old_layout: ImageLayout, new_layout: ImageLayout);
+    /// Make earlier shader and transfer writes visible to later shaders, indirect arguments
+    /// and transfers.
     fn compute_barrier(&mut self);
+    /// Move a buffer between queues. Record it on the releasing queue and again on the
+    /// acquiring queue, ordering the two submissions with a semaphore.
+    fn buffer_ownership_barrier(&mut self, buffer: &<Self::Device as Device>::Buffer, src_queue: QueueType, dst_queue: QueueType);
+    /// Move a texture between queues, keeping its current layout. Recorded like `buffer_ownership_barrier`.
+    fn texture_ownership_barrier(&mut self, texture: &<Self::Device as Device>::Texture, src_queue: QueueType, dst_queue: QueueType);
+}
+
+/// Layout of one record consumed by `draw_indirect` and `draw_indirect_count`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawIndirectArgs {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+/// Layout of one record consumed by `draw_indexed_indirect`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawIndexedIndirectArgs {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub base_vertex: i32,
+    pub first_instance: u32,
+}
+
+/// Layout of the record consumed by `dispatch_indirect`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DispatchIndirectArgs {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
 }
 
 pub struct RenderingDescriptor<'a, D: Device> {
@@ -117,17 +211,26 @@ pub struct RenderingAttachment<'a, D: Device> {
     pub clear_value: ClearValue,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum AttachmentLoadOp {
     Load,
     Clear,
     DontCare,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IndexFormat {
+    Uint16,
+    Uint32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum AttachmentStoreOp {
     Store,
     DontCare,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ClearValue {
     Color([f32; 4]),
     DepthStencil(f32, u32),
@@ -139,6 +242,15 @@ pub trait PipelineLayout {}
 pub trait GraphicsPipeline: Send + Sync {}
 pub trait ComputePipeline: Send + Sync {}
 pub trait Semaphore: Send + Sync {}
+
+/// A semaphore carrying a monotonically increasing 64-bit value.
+pub trait TimelineSemaphore: Send + Sync {
+    fn current_value(&self) -> crate::LumeResult<u64>;
+    /// Set the value from the host. It must be greater than the current value.
+    fn signal(&self, value: u64) -> crate::LumeResult<()>;
+    /// Block until the value is at least `value`, or fail after `timeout` nanoseconds.
+    fn wait(&self, value: u64, timeout: u64) -> crate::LumeResult<()>;
+}
 pub trait Fence: Send + Sync {}
 pub trait Framebuffer {}
 pub trait TextureView {}
@@ -169,6 +281,8 @@ pub struct FrameSync<D: Device> {
 pub struct RenderPassDescriptor {
     pub color_format: TextureFormat,
     pub depth_stencil_format: Option<TextureFormat>,
+    /// `Load` keeps what earlier passes drew into the attachments instead of clearing them.
+    pub load_op: AttachmentLoadOp,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -177,9 +291,35 @@ pub enum TextureFormat {
     Rgba8UnormSrgb,
     Rgba8Unorm,
     Rg32Uint,
+    R32Float,
     Depth32Float,
 }
 
+impl TextureFormat {
+    /// Size of one texel in bytes.
+    pub fn texel_size(&self) -> u32 {
+        match self {
+            TextureFormat::Rg32Uint => 8,
+            _ => 4,
+        }
+    }
+}
+
+/// Convert tightly packed texels of a color format to an RGBA8 image.
+pub fn texels_to_rgba_image(format: TextureFormat, width: u32, height: u32, mut data: Vec<u8>) -> crate::LumeResult<image::RgbaImage> {
+    match format {
+        TextureFormat::Bgra8UnormSrgb => {
+            for texel in data.chunks_exact_mut(4) {
+                texel.swap(0, 2);
+            }
+        }
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => {}
+        _ => return Err(crate::LumeError::Generic("Only 8-bit color formats can be converted to an RGBA image")),
+    }
+    image::RgbaImage::from_raw(width, height, data)
+        .ok_or(crate::LumeError::Generic("Texel data does not match the image size"))
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ImageLayout {
     Undefined,
@@ -187,12 +327,17 @@ pub enum ImageLayout {
     TransferSrc,
     TransferDst,
     ShaderReadOnly,
+    ColorAttachment,
+    DepthStencilAttachment,
+    Present,
 }
 
 pub struct TextureDescriptor {
     pub width: u32,
     pub height: u32,
     pub depth: u32,
+    /// At least 1; `mip_level_count(width, height)` for a full chain.
+    pub mip_level_count: u32,
     pub format: TextureFormat,
     pub usage: TextureUsage,
 }
@@ -238,10 +383,38 @@ pub enum AddressMode {
 
 pub struct TextureViewDescriptor {
     pub format: Option<TextureFormat>,
+    pub base_mip_level: u32,
+    /// `None` for every level from `base_mip_level` on. Storage views must cover one level.
+    pub mip_level_count: Option<u32>,
+}
+
+/// Number of levels in a full mip chain of a `width` x `height` texture, down to 1x1.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - (width | height).max(1).leading_zeros()
+}
+
+/// Size of mip `level`: each dimension halved `level` times, rounding down, but at least 1.
+pub fn mip_extent(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Layouts created by `Device::create_pipeline_layout_from_reflection`, indexed by group.
+pub struct ReflectedLayout<D: Device> {
+    pub pipeline_layout: D::PipelineLayout,
+    pub bind_group_layouts: Vec<D::BindGroupLayout>,
 }
 
 pub struct PipelineLayoutDescriptor<'a, D: Device> {
     pub bind_group_layouts: &'a [&'a D::BindGroupLayout],
+    pub push_constant_ranges: &'a [PushConstantRange],
+}
+
+/// A byte range of push constants visible to `stages`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PushConstantRange {
+    pub stages: ShaderStage,
+    pub offset: u32,
+    pub size: u32,
 }
 
 pub struct GraphicsPipelineDescriptor<'a, D: Device> {
@@ -254,14 +427,14 @@ pub struct GraphicsPipelineDescriptor<'a, D: Device> {
     pub depth_stencil: Option<DepthStencilState>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct DepthStencilState {
     pub format: TextureFormat,
     pub depth_write_enabled: bool,
     pub depth_compare: CompareFunction,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum CompareFunction {
     Never,
     Less,
@@ -281,21 +454,21 @@ pub enum PrimitiveTopology {
     TriangleList,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct VertexAttribute {
     pub location: u32,
     pub format: VertexFormat,
     pub offset: u32,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum VertexFormat {
     Float32x2,
     Float32x3,
     Float32x4,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct VertexLayout {
     pub array_stride: u32,
     pub attributes: Vec<VertexAttribute>,
@@ -314,6 +487,34 @@ pub struct SwapchainDescriptor {
     // Add format/vsync options later
 }
 
+pub struct OffscreenSwapchainDescriptor {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    pub image_count: u32,
+}
+
+/// A rectangle of a texture and its placement in a buffer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextureCopyRegion {
+    /// Byte offset of the first texel in the buffer.
+    pub buffer_offset: u64,
+    /// Bytes between the starts of consecutive rows in the buffer, or 0 for tightly packed rows.
+    /// Must be a multiple of the texel size.
+    pub bytes_per_row: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TextureCopyRegion {
+    /// The whole `width` x `height` texture, tightly packed at the start of the buffer.
+    pub fn full(width: u32, height: u32) -> Self {
+        Self { buffer_offset: 0, bytes_per_row: 0, x: 0, y: 0, width, height }
+    }
+}
+
 pub struct BufferDescriptor {
     pub size: u64,
     pub usage: BufferUsage,
@@ -344,6 +545,7 @@ pub struct BindGroupLayoutDescriptor {
     pub entries: Vec<BindGroupLayoutEntry>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct BindGroupLayoutEntry {
     pub binding: u32,
     pub visibility: ShaderStage,
@@ -1,13 +1,13 @@
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum LumeError {
     InstanceCreationFailed(String),
     DeviceCreationFailed(String),
     SurfaceCreationFailed(String),
     ResourceCreationFailed(String),
     PipelineCreationFailed(String),
-    ShaderCompilationFailed(String),
+    ShaderCompilationFailed(Box<crate::shader::ShaderError>),
     SubmissionFailed(String),
     BackendError(String),
     OutOfMemory,
@@ -33,4 +33,10 @@ impl fmt::Display for LumeError {
 
 impl std::error::Error for LumeError {}
 
+impl From<crate::shader::ShaderError> for LumeError {
+    fn from(error: crate::shader::ShaderError) -> Self {
+        LumeError::ShaderCompilationFailed(Box::new(error))
+    }
+}
+
 pub type LumeResult<T> = Result<T, LumeError>;
@@ -4,6 +4,7 @@ use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 pub enum Backend {
     Vulkan,
     Metal,
+    Cpu,
 }
 
 pub struct InstanceDescriptor<'a> {
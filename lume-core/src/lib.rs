@@ -2,6 +2,8 @@ pub mod instance;
 pub mod device;
 pub mod shader;
 pub mod error;
+#[cfg(feature = "recording")]
+pub mod recording;
 
 pub use instance::{Instance, InstanceDescriptor, Backend};
 pub use device::Device;
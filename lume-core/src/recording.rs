@@ -0,0 +1,974 @@
+//! A `Device` that executes nothing and records everything, for unit testing renderer code
+//! written against the generic API. Every resource gets a `ResourceId`; creation
+//! descriptors are kept by the device and submitted command buffers are kept as a
+//! `CommandLog` that can be searched and asserted on.
+//!
+//! ```ignore
+//! let log = device.submitted();
+//! let cull = log.dispatch_with(&cull_pipeline);
+//! let draw = log.draw_with(&visbuffer_pipeline);
+//! log.assert_barrier_between(cull, draw);
+//! ```
+
+use crate::device::*;
+use crate::{LumeError, LumeResult};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Identifies a resource created by a `RecordingDevice`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct ResourceId(pub u64);
+
+/// The descriptor a resource was created with, with referenced resources replaced by their ids.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ResourceDescriptor {
+    CommandPool { queue: QueueType },
+    Semaphore,
+    TimelineSemaphore { initial_value: u64 },
+    Fence { signaled: bool },
+    Swapchain { width: u32, height: u32, format: TextureFormat, image_count: u32 },
+    ShaderModule { code: Vec<u32> },
+    RenderPass { color_format: TextureFormat, depth_stencil_format: Option<TextureFormat>, load_op: AttachmentLoadOp },
+    PipelineLayout { bind_group_layouts: Vec<ResourceId>, push_constant_ranges: Vec<PushConstantRange> },
+    GraphicsPipeline {
+        vertex_shader: ResourceId,
+        fragment_shader: ResourceId,
+        render_pass: ResourceId,
+        layout: ResourceId,
+        vertex_layout: Option<VertexLayout>,
+        depth_stencil: Option<DepthStencilState>,
+    },
+    ComputePipeline { shader: ResourceId, layout: ResourceId },
+    Framebuffer { render_pass: ResourceId, attachments: Vec<ResourceId>, width: u32, height: u32 },
+    Buffer { size: u64, usage: BufferUsage, mapped_at_creation: bool },
+    Texture { width: u32, height: u32, depth: u32, mip_level_count: u32, format: TextureFormat, usage: TextureUsage },
+    TextureView { texture: ResourceId, format: Option<TextureFormat>, base_mip_level: u32, mip_level_count: Option<u32> },
+    Sampler { min_filter: FilterMode, mag_filter: FilterMode, address_mode_u: AddressMode, address_mode_v: AddressMode },
+    BindGroupLayout { entries: Vec<BindGroupLayoutEntry> },
+    /// Each entry is a binding and the buffer, texture view or sampler bound to it.
+    BindGroup { layout: ResourceId, entries: Vec<(u32, ResourceId)> },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Creation {
+    pub id: ResourceId,
+    pub descriptor: ResourceDescriptor,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RecordedAttachment {
+    pub view: ResourceId,
+    pub layout: ImageLayout,
+    pub load_op: AttachmentLoadOp,
+    pub store_op: AttachmentStoreOp,
+    pub clear_value: ClearValue,
+}
+
+/// One `CommandBuffer` call, with resources replaced by their ids.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    BeginRenderPass { render_pass: ResourceId, framebuffer: ResourceId, clear_color: [f32; 4] },
+    EndRenderPass,
+    BeginRendering {
+        color_attachments: Vec<RecordedAttachment>,
+        depth_attachment: Option<RecordedAttachment>,
+        stencil_attachment: Option<RecordedAttachment>,
+        view_mask: u32,
+    },
+    EndRendering,
+    BindGraphicsPipeline(ResourceId),
+    BindComputePipeline(ResourceId),
+    BindVertexBuffer(ResourceId),
+    BindIndexBuffer { buffer: ResourceId, format: IndexFormat },
+    BindBindGroup { index: u32, bind_group: ResourceId },
+    SetPushConstants { stages: ShaderStage, offset: u32, data: Vec<u8> },
+    SetViewport { x: f32, y: f32, width: f32, height: f32 },
+    SetScissor { x: i32, y: i32, width: u32, height: u32 },
+    Draw { vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32 },
+    DrawIndexed { index_count: u32, instance_count: u32, first_index: u32, base_vertex: i32, first_instance: u32 },
+    Dispatch { x: u32, y: u32, z: u32 },
+    DrawIndirect { buffer: ResourceId, offset: u64, draw_count: u32, stride: u32 },
+    DrawIndirectCount {
+        buffer: ResourceId,
+        offset: u64,
+        count_buffer: ResourceId,
+        count_offset: u64,
+        max_draw_count: u32,
+        stride: u32,
+    },
+    DrawIndexedIndirect { buffer: ResourceId, offset: u64, draw_count: u32, stride: u32 },
+    DispatchIndirect { buffer: ResourceId, offset: u64 },
+    CopyBufferToBuffer { source: ResourceId, destination: ResourceId, size: u64 },
+    CopyBufferToTexture { buffer: ResourceId, texture: ResourceId, width: u32, height: u32 },
+    CopyTextureToBuffer { texture: ResourceId, buffer: ResourceId, region: TextureCopyRegion },
+    TextureBarrier { texture: ResourceId, old_layout: ImageLayout, new_layout: ImageLayout },
+    ComputeBarrier,
+    BufferOwnershipBarrier { buffer: ResourceId, src_queue: QueueType, dst_queue: QueueType },
+    TextureOwnershipBarrier { texture: ResourceId, src_queue: QueueType, dst_queue: QueueType },
+}
+
+impl Command {
+    pub fn is_draw(&self) -> bool {
+        matches!(
+            self,
+            Command::Draw { .. }
+                | Command::DrawIndexed { .. }
+                | Command::DrawIndirect { .. }
+                | Command::DrawIndirectCount { .. }
+                | Command::DrawIndexedIndirect { .. }
+        )
+    }
+
+    pub fn is_dispatch(&self) -> bool {
+        matches!(self, Command::Dispatch { .. } | Command::DispatchIndirect { .. })
+    }
+
+    pub fn is_barrier(&self) -> bool {
+        matches!(
+            self,
+            Command::TextureBarrier { .. }
+                | Command::ComputeBarrier
+                | Command::BufferOwnershipBarrier { .. }
+                | Command::TextureOwnershipBarrier { .. }
+        )
+    }
+}
+
+/// A command together with the state bound when it was recorded.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordedCommand {
+    pub command: Command,
+    pub graphics_pipeline: Option<ResourceId>,
+    pub compute_pipeline: Option<ResourceId>,
+    /// Bound bind groups, sorted by index.
+    pub bind_groups: Vec<(u32, ResourceId)>,
+}
+
+impl RecordedCommand {
+    pub fn bind_group(&self, index: u32) -> Option<ResourceId> {
+        self.bind_groups.iter().find(|(i, _)| *i == index).map(|(_, id)| *id)
+    }
+}
+
+/// Commands in recording order. The `expect` style helpers panic with the whole log on failure.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CommandLog {
+    pub commands: Vec<RecordedCommand>,
+}
+
+impl CommandLog {
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn command(&self, index: usize) -> &Command {
+        &self.commands[index].command
+    }
+
+    pub fn position(&self, predicate: impl Fn(&RecordedCommand) -> bool) -> Option<usize> {
+        self.commands.iter().position(predicate)
+    }
+
+    /// Index of the first command matching `predicate`; `what` describes it in the panic message.
+    pub fn expect(&self, what: &str, predicate: impl Fn(&RecordedCommand) -> bool) -> usize {
+        self.position(predicate).unwrap_or_else(|| panic!("No {} was recorded:\n{}", what, self))
+    }
+
+    /// Index of the first dispatch recorded with `pipeline` bound.
+    pub fn dispatch_with(&self, pipeline: &RecordingComputePipeline) -> usize {
+        self.expect(&format!("dispatch with compute pipeline {:?}", pipeline.id), |c| {
+            c.command.is_dispatch() && c.compute_pipeline == Some(pipeline.id)
+        })
+    }
+
+    /// Index of the first draw recorded with `pipeline` bound.
+    pub fn draw_with(&self, pipeline: &RecordingGraphicsPipeline) -> usize {
+        self.expect(&format!("draw with graphics pipeline {:?}", pipeline.id), |c| {
+            c.command.is_draw() && c.graphics_pipeline == Some(pipeline.id)
+        })
+    }
+
+    pub fn has_barrier_between(&self, first: usize, second: usize) -> bool {
+        first < second && self.commands[first + 1..second].iter().any(|c| c.command.is_barrier())
+    }
+
+    pub fn assert_before(&self, first: usize, second: usize) {
+        assert!(first < second, "Command {} was expected before command {}:\n{}", first, second, self);
+    }
+
+    /// Asserts that `first` comes before `second` with at least one barrier in between.
+    pub fn assert_barrier_between(&self, first: usize, second: usize) {
+        self.assert_before(first, second);
+        assert!(
+            self.has_barrier_between(first, second),
+            "No barrier between command {} and command {}:\n{}",
+            first, second, self
+        );
+    }
+}
+
+impl fmt::Display for CommandLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in self.commands.iter().enumerate() {
+            writeln!(f, "{:4}: {:?}", i, c.command)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Submission {
+    pub queue: QueueType,
+    pub command_buffers: Vec<ResourceId>,
+    pub timeline_waits: Vec<(ResourceId, u64)>,
+    pub timeline_signals: Vec<(ResourceId, u64)>,
+    pub commands: CommandLog,
+}
+
+pub struct RecordingDeviceInner {
+    next_id: AtomicU64,
+    created: Mutex<Vec<Creation>>,
+    submissions: Mutex<Vec<Submission>>,
+}
+
+/// See the module documentation.
+#[derive(Clone)]
+pub struct RecordingDevice {
+    pub inner: Arc<RecordingDeviceInner>,
+}
+
+impl Default for RecordingDevice {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(RecordingDeviceInner {
+                next_id: AtomicU64::new(0),
+                created: Mutex::new(Vec::new()),
+                submissions: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+impl RecordingDevice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_id(&self) -> ResourceId {
+        ResourceId(self.inner.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, descriptor: ResourceDescriptor) -> ResourceId {
+        let id = self.next_id();
+        self.inner.created.lock().unwrap().push(Creation { id, descriptor });
+        id
+    }
+
+    /// Every resource created so far, in creation order.
+    pub fn created(&self) -> Vec<Creation> {
+        self.inner.created.lock().unwrap().clone()
+    }
+
+    pub fn descriptor(&self, id: ResourceId) -> Option<ResourceDescriptor> {
+        self.inner.created.lock().unwrap().iter().find(|c| c.id == id).map(|c| c.descriptor.clone())
+    }
+
+    pub fn submissions(&self) -> Vec<Submission> {
+        self.inner.submissions.lock().unwrap().clone()
+    }
+
+    /// The commands of every submission so far, concatenated in submission order.
+    pub fn submitted(&self) -> CommandLog {
+        let submissions = self.inner.submissions.lock().unwrap();
+        CommandLog { commands: submissions.iter().flat_map(|s| s.commands.commands.iter().cloned()).collect() }
+    }
+
+    fn create_swapchain_views(&self, width: u32, height: u32, format: TextureFormat, image_count: u32) -> RecordingSwapchain {
+        let id = self.record(ResourceDescriptor::Swapchain { width, height, format, image_count });
+        let views = (0..image_count).map(|_| RecordingTextureView { id: self.next_id(), texture: None }).collect();
+        RecordingSwapchain { id, width, height, views, current_image: 0, presented_image: None }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordingBuffer {
+    pub id: ResourceId,
+    pub size: u64,
+    pub usage: BufferUsage,
+    /// Host copy of the contents; commands never touch it.
+    pub data: Arc<Mutex<Vec<u8>>>,
+}
+
+impl RecordingBuffer {
+    fn range(&self, offset: u64, len: usize) -> LumeResult<std::ops::Range<usize>> {
+        let end = offset.checked_add(len as u64).filter(|&end| end <= self.size).ok_or_else(|| {
+            LumeError::BackendError(format!(
+                "Buffer access of {} bytes at offset {} exceeds buffer size {}",
+                len, offset, self.size
+            ))
+        })?;
+        Ok(offset as usize..end as usize)
+    }
+}
+
+impl Buffer for RecordingBuffer {
+    fn write_data(&self, offset: u64, data: &[u8]) -> LumeResult<()> {
+        let range = self.range(offset, data.len())?;
+        self.data.lock().unwrap()[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn read_data(&self, offset: u64, data: &mut [u8]) -> LumeResult<()> {
+        let range = self.range(offset, data.len())?;
+        data.copy_from_slice(&self.data.lock().unwrap()[range]);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordingTexture {
+    pub id: ResourceId,
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+}
+
+impl Texture for RecordingTexture {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingTextureView {
+    pub id: ResourceId,
+    /// `None` for swapchain images.
+    pub texture: Option<ResourceId>,
+}
+
+impl TextureView for RecordingTextureView {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingSwapchain {
+    pub id: ResourceId,
+    pub width: u32,
+    pub height: u32,
+    pub views: Vec<RecordingTextureView>,
+    pub current_image: u32,
+    pub presented_image: Option<u32>,
+}
+
+impl Swapchain for RecordingSwapchain {
+    type TextureView = RecordingTextureView;
+
+    fn present(&mut self, image_index: u32, _wait_semaphores: &[&impl Semaphore]) -> LumeResult<()> {
+        self.presented_image = Some(image_index);
+        Ok(())
+    }
+
+    fn acquire_next_image(&mut self, _signal_semaphore: &impl Semaphore) -> LumeResult<u32> {
+        let image_index = self.current_image;
+        self.current_image = (self.current_image + 1) % self.views.len() as u32;
+        Ok(image_index)
+    }
+
+    fn get_view(&self, index: u32) -> &Self::TextureView {
+        &self.views[index as usize]
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordingTimelineSemaphore {
+    pub id: ResourceId,
+    value: Arc<Mutex<u64>>,
+}
+
+impl TimelineSemaphore for RecordingTimelineSemaphore {
+    fn current_value(&self) -> LumeResult<u64> {
+        Ok(*self.value.lock().unwrap())
+    }
+
+    fn signal(&self, value: u64) -> LumeResult<()> {
+        let mut current = self.value.lock().unwrap();
+        if value <= *current {
+            return Err(LumeError::BackendError(format!(
+                "Timeline semaphore signaled with {} but is already at {}",
+                value, *current
+            )));
+        }
+        *current = value;
+        Ok(())
+    }
+
+    fn wait(&self, value: u64, _timeout: u64) -> LumeResult<()> {
+        // Submissions complete immediately, so a value not reached yet never will be.
+        let current = *self.value.lock().unwrap();
+        if current < value {
+            return Err(LumeError::BackendError(format!(
+                "Wait for timeline semaphore timed out at {} before reaching {}",
+                current, value
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordingSampler {
+    pub id: ResourceId,
+}
+
+impl Sampler for RecordingSampler {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingShaderModule {
+    pub id: ResourceId,
+}
+
+impl ShaderModule for RecordingShaderModule {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingRenderPass {
+    pub id: ResourceId,
+}
+
+impl RenderPass for RecordingRenderPass {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingPipelineLayout {
+    pub id: ResourceId,
+}
+
+impl PipelineLayout for RecordingPipelineLayout {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingGraphicsPipeline {
+    pub id: ResourceId,
+}
+
+impl GraphicsPipeline for RecordingGraphicsPipeline {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingComputePipeline {
+    pub id: ResourceId,
+}
+
+impl ComputePipeline for RecordingComputePipeline {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingFramebuffer {
+    pub id: ResourceId,
+}
+
+impl Framebuffer for RecordingFramebuffer {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingBindGroupLayout {
+    pub id: ResourceId,
+}
+
+impl BindGroupLayout for RecordingBindGroupLayout {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingBindGroup {
+    pub id: ResourceId,
+}
+
+impl BindGroup for RecordingBindGroup {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingSemaphore {
+    pub id: ResourceId,
+}
+
+impl Semaphore for RecordingSemaphore {}
+
+#[derive(Clone, Debug)]
+pub struct RecordingFence {
+    pub id: ResourceId,
+}
+
+impl Fence for RecordingFence {}
+
+pub struct RecordingCommandPool {
+    pub id: ResourceId,
+    pub queue: QueueType,
+    device: RecordingDevice,
+}
+
+impl CommandPool for RecordingCommandPool {
+    type Device = RecordingDevice;
+    type CommandBuffer = RecordingCommandBuffer;
+
+    fn allocate_command_buffer(&self) -> LumeResult<RecordingCommandBuffer> {
+        Ok(RecordingCommandBuffer {
+            id: self.device.next_id(),
+            queue: self.queue,
+            log: CommandLog::default(),
+            graphics_pipeline: None,
+            compute_pipeline: None,
+            bind_groups: Vec::new(),
+        })
+    }
+}
+
+pub struct RecordingCommandBuffer {
+    pub id: ResourceId,
+    pub queue: QueueType,
+    pub log: CommandLog,
+    graphics_pipeline: Option<ResourceId>,
+    compute_pipeline: Option<ResourceId>,
+    bind_groups: Vec<(u32, ResourceId)>,
+}
+
+impl RecordingCommandBuffer {
+    fn push(&mut self, command: Command) {
+        self.log.commands.push(RecordedCommand {
+            command,
+            graphics_pipeline: self.graphics_pipeline,
+            compute_pipeline: self.compute_pipeline,
+            bind_groups: self.bind_groups.clone(),
+        });
+    }
+
+    fn attachment(attachment: &RenderingAttachment<RecordingDevice>) -> RecordedAttachment {
+        RecordedAttachment {
+            view: attachment.view.id,
+            layout: attachment.layout,
+            load_op: attachment.load_op,
+            store_op: attachment.store_op,
+            clear_value: attachment.clear_value,
+        }
+    }
+}
+
+impl CommandBuffer for RecordingCommandBuffer {
+    type Device = RecordingDevice;
+
+    fn reset(&mut self) -> LumeResult<()> {
+        self.log.commands.clear();
+        self.graphics_pipeline = None;
+        self.compute_pipeline = None;
+        self.bind_groups.clear();
+        Ok(())
+    }
+
+    fn begin(&mut self) -> LumeResult<()> {
+        Ok(())
+    }
+
+    fn end(&mut self) -> LumeResult<()> {
+        Ok(())
+    }
+
+    fn begin_render_pass(&mut self, render_pass: &RecordingRenderPass, framebuffer: &RecordingFramebuffer, clear_color: [f32; 4]) {
+        self.push(Command::BeginRenderPass { render_pass: render_pass.id, framebuffer: framebuffer.id, clear_color });
+    }
+
+    fn end_render_pass(&mut self) {
+        self.push(Command::EndRenderPass);
+    }
+
+    fn begin_rendering(&mut self, descriptor: RenderingDescriptor<RecordingDevice>) {
+        self.push(Command::BeginRendering {
+            color_attachments: descriptor.color_attachments.iter().map(Self::attachment).collect(),
+            depth_attachment: descriptor.depth_attachment.as_ref().map(Self::attachment),
+            stencil_attachment: descriptor.stencil_attachment.as_ref().map(Self::attachment),
+            view_mask: descriptor.view_mask,
+        });
+    }
+
+    fn end_rendering(&mut self) {
+        self.push(Command::EndRendering);
+    }
+
+    fn bind_graphics_pipeline(&mut self, pipeline: &RecordingGraphicsPipeline) {
+        self.graphics_pipeline = Some(pipeline.id);
+        self.push(Command::BindGraphicsPipeline(pipeline.id));
+    }
+
+    fn bind_compute_pipeline(&mut self, pipeline: &RecordingComputePipeline) {
+        self.compute_pipeline = Some(pipeline.id);
+        self.push(Command::BindComputePipeline(pipeline.id));
+    }
+
+    fn bind_vertex_buffer(&mut self, buffer: &RecordingBuffer) {
+        self.push(Command::BindVertexBuffer(buffer.id));
+    }
+
+    fn bind_index_buffer(&mut self, buffer: &RecordingBuffer, format: IndexFormat) {
+        self.push(Command::BindIndexBuffer { buffer: buffer.id, format });
+    }
+
+    fn bind_bind_group(&mut self, index: u32, bind_group: &RecordingBindGroup) {
+        match self.bind_groups.binary_search_by_key(&index, |(i, _)| *i) {
+            Ok(slot) => self.bind_groups[slot].1 = bind_group.id,
+            Err(slot) => self.bind_groups.insert(slot, (index, bind_group.id)),
+        }
+        self.push(Command::BindBindGroup { index, bind_group: bind_group.id });
+    }
+
+    fn set_push_constants(&mut self, stages: ShaderStage, offset: u32, data: &[u8]) {
+        self.push(Command::SetPushConstants { stages, offset, data: data.to_vec() });
+    }
+
+    fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.push(Command::SetViewport { x, y, width, height });
+    }
+
+    fn set_scissor(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        self.push(Command::SetScissor { x, y, width, height });
+    }
+
+    fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32) {
+        self.push(Command::Draw { vertex_count, instance_count, first_vertex, first_instance });
+    }
+
+    fn draw_indexed(&mut self, index_count: u32, instance_count: u32, first_index: u32, base_vertex: i32, first_instance: u32) {
+        self.push(Command::DrawIndexed { index_count, instance_count, first_index, base_vertex, first_instance });
+    }
+
+    fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.push(Command::Dispatch { x, y, z });
+    }
+
+    fn draw_indirect(&mut self, buffer: &RecordingBuffer, offset: u64, draw_count: u32, stride: u32) {
+        self.push(Command::DrawIndirect { buffer: buffer.id, offset, draw_count, stride });
+    }
+
+    fn draw_indirect_count(&mut self, buffer: &RecordingBuffer, offset: u64, count_buffer: &RecordingBuffer, count_offset: u64, max_draw_count: u32, stride: u32) {
+        self.push(Command::DrawIndirectCount {
+            buffer: buffer.id,
+            offset,
+            count_buffer: count_buffer.id,
+            count_offset,
+            max_draw_count,
+            stride,
+        });
+    }
+
+    fn draw_indexed_indirect(&mut self, buffer: &RecordingBuffer, offset: u64, draw_count: u32, stride: u32) {
+        self.push(Command::DrawIndexedIndirect { buffer: buffer.id, offset, draw_count, stride });
+    }
+
+    fn dispatch_indirect(&mut self, buffer: &RecordingBuffer, offset: u64) {
+        self.push(Command::DispatchIndirect { buffer: buffer.id, offset });
+    }
+
+    fn copy_buffer_to_buffer(&mut self, source: &RecordingBuffer, destination: &RecordingBuffer, size: u64) {
+        self.push(Command::CopyBufferToBuffer { source: source.id, destination: destination.id, size });
+    }
+
+    fn copy_buffer_to_texture(&mut self, buffer: &RecordingBuffer, texture: &RecordingTexture, width: u32, height: u32) {
+        self.push(Command::CopyBufferToTexture { buffer: buffer.id, texture: texture.id, width, height });
+    }
+
+    fn copy_texture_to_buffer(&mut self, texture: &RecordingTexture, buffer: &RecordingBuffer, region: TextureCopyRegion) {
+        self.push(Command::CopyTextureToBuffer { texture: texture.id, buffer: buffer.id, region });
+    }
+
+    fn texture_barrier(&mut self, texture: &RecordingTexture, old_layout: ImageLayout, new_layout: ImageLayout) {
+        self.push(Command::TextureBarrier { texture: texture.id, old_layout, new_layout });
+    }
+
+    fn compute_barrier(&mut self) {
+        self.push(Command::ComputeBarrier);
+    }
+
+    fn buffer_ownership_barrier(&mut self, buffer: &RecordingBuffer, src_queue: QueueType, dst_queue: QueueType) {
+        self.push(Command::BufferOwnershipBarrier { buffer: buffer.id, src_queue, dst_queue });
+    }
+
+    fn texture_ownership_barrier(&mut self, texture: &RecordingTexture, src_queue: QueueType, dst_queue: QueueType) {
+        self.push(Command::TextureOwnershipBarrier { texture: texture.id, src_queue, dst_queue });
+    }
+}
+
+impl Device for RecordingDevice {
+    type Buffer = RecordingBuffer;
+    type Texture = RecordingTexture;
+    type TextureView = RecordingTextureView;
+    type Sampler = RecordingSampler;
+    type ShaderModule = RecordingShaderModule;
+    type RenderPass = RecordingRenderPass;
+    type PipelineLayout = RecordingPipelineLayout;
+    type GraphicsPipeline = RecordingGraphicsPipeline;
+    type ComputePipeline = RecordingComputePipeline;
+    type CommandPool = RecordingCommandPool;
+    type CommandBuffer = RecordingCommandBuffer;
+    type Framebuffer = RecordingFramebuffer;
+    type Swapchain = RecordingSwapchain;
+    type BindGroupLayout = RecordingBindGroupLayout;
+    type BindGroup = RecordingBindGroup;
+    type Semaphore = RecordingSemaphore;
+    type TimelineSemaphore = RecordingTimelineSemaphore;
+    type Fence = RecordingFence;
+
+    fn wait_idle(&self) -> LumeResult<()> {
+        Ok(())
+    }
+
+    fn create_command_pool(&self, queue: QueueType) -> LumeResult<RecordingCommandPool> {
+        let id = self.record(ResourceDescriptor::CommandPool { queue });
+        Ok(RecordingCommandPool { id, queue, device: self.clone() })
+    }
+
+    fn create_semaphore(&self) -> LumeResult<RecordingSemaphore> {
+        Ok(RecordingSemaphore { id: self.record(ResourceDescriptor::Semaphore) })
+    }
+
+    fn create_timeline_semaphore(&self, initial_value: u64) -> LumeResult<RecordingTimelineSemaphore> {
+        let id = self.record(ResourceDescriptor::TimelineSemaphore { initial_value });
+        Ok(RecordingTimelineSemaphore { id, value: Arc::new(Mutex::new(initial_value)) })
+    }
+
+    fn create_fence(&self, signaled: bool) -> LumeResult<RecordingFence> {
+        Ok(RecordingFence { id: self.record(ResourceDescriptor::Fence { signaled }) })
+    }
+
+    fn create_swapchain(&self, _surface: &impl crate::instance::Surface, descriptor: SwapchainDescriptor) -> LumeResult<RecordingSwapchain> {
+        Ok(self.create_swapchain_views(descriptor.width, descriptor.height, TextureFormat::Bgra8UnormSrgb, 3))
+    }
+
+    fn create_offscreen_swapchain(&self, descriptor: OffscreenSwapchainDescriptor) -> LumeResult<RecordingSwapchain> {
+        Ok(self.create_swapchain_views(descriptor.width, descriptor.height, descriptor.format, descriptor.image_count))
+    }
+
+    fn create_shader_module(&self, code: &[u32]) -> LumeResult<RecordingShaderModule> {
+        Ok(RecordingShaderModule { id: self.record(ResourceDescriptor::ShaderModule { code: code.to_vec() }) })
+    }
+
+    fn create_render_pass(&self, descriptor: RenderPassDescriptor) -> LumeResult<RecordingRenderPass> {
+        let id = self.record(ResourceDescriptor::RenderPass {
+            color_format: descriptor.color_format,
+            depth_stencil_format: descriptor.depth_stencil_format,
+            load_op: descriptor.load_op,
+        });
+        Ok(RecordingRenderPass { id })
+    }
+
+    fn create_pipeline_layout(&self, descriptor: PipelineLayoutDescriptor<Self>) -> LumeResult<RecordingPipelineLayout> {
+        let id = self.record(ResourceDescriptor::PipelineLayout {
+            bind_group_layouts: descriptor.bind_group_layouts.iter().map(|l| l.id).collect(),
+            push_constant_ranges: descriptor.push_constant_ranges.to_vec(),
+        });
+        Ok(RecordingPipelineLayout { id })
+    }
+
+    fn create_graphics_pipeline(&self, descriptor: GraphicsPipelineDescriptor<Self>) -> LumeResult<RecordingGraphicsPipeline> {
+        let id = self.record(ResourceDescriptor::GraphicsPipeline {
+            vertex_shader: descriptor.vertex_shader.id,
+            fragment_shader: descriptor.fragment_shader.id,
+            render_pass: descriptor.render_pass.id,
+            layout: descriptor.layout.id,
+            vertex_layout: descriptor.vertex_layout,
+            depth_stencil: descriptor.depth_stencil,
+        });
+        Ok(RecordingGraphicsPipeline { id })
+    }
+
+    fn create_compute_pipeline(&self, descriptor: ComputePipelineDescriptor<Self>) -> LumeResult<RecordingComputePipeline> {
+        let id = self.record(ResourceDescriptor::ComputePipeline { shader: descriptor.shader.id, layout: descriptor.layout.id });
+        Ok(RecordingComputePipeline { id })
+    }
+
+    fn create_framebuffer(&self, descriptor: FramebufferDescriptor<Self>) -> LumeResult<RecordingFramebuffer> {
+        let id = self.record(ResourceDescriptor::Framebuffer {
+            render_pass: descriptor.render_pass.id,
+            attachments: descriptor.attachments.iter().map(|v| v.id).collect(),
+            width: descriptor.width,
+            height: descriptor.height,
+        });
+        Ok(RecordingFramebuffer { id })
+    }
+
+    fn create_buffer(&self, descriptor: BufferDescriptor) -> LumeResult<RecordingBuffer> {
+        let id = self.record(ResourceDescriptor::Buffer {
+            size: descriptor.size,
+            usage: descriptor.usage,
+            mapped_at_creation: descriptor.mapped_at_creation,
+        });
+        Ok(RecordingBuffer {
+            id,
+            size: descriptor.size,
+            usage: descriptor.usage,
+            data: Arc::new(Mutex::new(vec![0; descriptor.size as usize])),
+        })
+    }
+
+    fn create_texture(&self, descriptor: TextureDescriptor) -> LumeResult<RecordingTexture> {
+        let id = self.record(ResourceDescriptor::Texture {
+            width: descriptor.width,
+            height: descriptor.height,
+            depth: descriptor.depth,
+            mip_level_count: descriptor.mip_level_count,
+            format: descriptor.format,
+            usage: descriptor.usage,
+        });
+        Ok(RecordingTexture { id, width: descriptor.width, height: descriptor.height, format: descriptor.format })
+    }
+
+    fn create_texture_view(&self, texture: &RecordingTexture, descriptor: TextureViewDescriptor) -> LumeResult<RecordingTextureView> {
+        let id = self.record(ResourceDescriptor::TextureView {
+            texture: texture.id,
+            format: descriptor.format,
+            base_mip_level: descriptor.base_mip_level,
+            mip_level_count: descriptor.mip_level_count,
+        });
+        Ok(RecordingTextureView { id, texture: Some(texture.id) })
+    }
+
+    fn create_sampler(&self, descriptor: SamplerDescriptor) -> LumeResult<RecordingSampler> {
+        let id = self.record(ResourceDescriptor::Sampler {
+            min_filter: descriptor.min_filter,
+            mag_filter: descriptor.mag_filter,
+            address_mode_u: descriptor.address_mode_u,
+            address_mode_v: descriptor.address_mode_v,
+        });
+        Ok(RecordingSampler { id })
+    }
+
+    fn create_bind_group_layout(&self, descriptor: BindGroupLayoutDescriptor) -> LumeResult<RecordingBindGroupLayout> {
+        Ok(RecordingBindGroupLayout { id: self.record(ResourceDescriptor::BindGroupLayout { entries: descriptor.entries }) })
+    }
+
+    fn create_bind_group(&self, descriptor: BindGroupDescriptor<Self>) -> LumeResult<RecordingBindGroup> {
+        let entries = descriptor.entries.iter().map(|entry| {
+            let resource = match &entry.resource {
+                BindingResource::Buffer(buffer) => buffer.id,
+                BindingResource::TextureView(view) => view.id,
+                BindingResource::Sampler(sampler) => sampler.id,
+            };
+            (entry.binding, resource)
+        }).collect();
+        Ok(RecordingBindGroup { id: self.record(ResourceDescriptor::BindGroup { layout: descriptor.layout.id, entries }) })
+    }
+
+    fn begin_frame(&self, swapchain: &mut RecordingSwapchain) -> LumeResult<FrameToken> {
+        let image_index = swapchain.acquire_next_image(&RecordingSemaphore { id: swapchain.id })?;
+        Ok(FrameToken { frame_index: 0, image_index })
+    }
+
+    fn end_frame(&self, swapchain: &mut RecordingSwapchain, token: FrameToken, command_buffers: &[&RecordingCommandBuffer]) -> LumeResult<()> {
+        self.submit(QueueType::Graphics, command_buffers, &[], &[], &[], &[], None)?;
+        swapchain.present(token.image_index, &[&RecordingSemaphore { id: swapchain.id }])
+    }
+
+    fn submit(
+        &self,
+        queue: QueueType,
+        command_buffers: &[&RecordingCommandBuffer],
+        _wait_semaphores: &[&RecordingSemaphore],
+        _signal_semaphores: &[&RecordingSemaphore],
+        timeline_waits: &[(&RecordingTimelineSemaphore, u64)],
+        timeline_signals: &[(&RecordingTimelineSemaphore, u64)],
+        _fence: Option<&RecordingFence>,
+    ) -> LumeResult<()> {
+        if let Some(cmd) = command_buffers.iter().find(|cmd| cmd.queue != queue) {
+            return Err(LumeError::SubmissionFailed(format!(
+                "Command buffer recorded for the {:?} queue submitted to the {:?} queue",
+                cmd.queue, queue
+            )));
+        }
+        for (semaphore, value) in timeline_signals {
+            semaphore.signal(*value)?;
+        }
+        self.inner.submissions.lock().unwrap().push(Submission {
+            queue,
+            command_buffers: command_buffers.iter().map(|cmd| cmd.id).collect(),
+            timeline_waits: timeline_waits.iter().map(|(s, v)| (s.id, *v)).collect(),
+            timeline_signals: timeline_signals.iter().map(|(s, v)| (s.id, *v)).collect(),
+            commands: CommandLog { commands: command_buffers.iter().flat_map(|cmd| cmd.log.commands.iter().cloned()).collect() },
+        });
+        Ok(())
+    }
+
+    fn wait_for_fences(&self, _fences: &[&RecordingFence], _wait_all: bool, _timeout: u64) -> LumeResult<()> {
+        Ok(())
+    }
+
+    fn reset_fences(&self, _fences: &[&RecordingFence]) -> LumeResult<()> {
+        Ok(())
+    }
+
+    /// Textures have no contents; this returns zeroed texels of the right size.
+    fn read_texture(&self, texture: &RecordingTexture) -> LumeResult<Vec<u8>> {
+        Ok(vec![0; (texture.width * texture.height * texture.format.texel_size()) as usize])
+    }
+
+    fn read_presented_image(&self, swapchain: &RecordingSwapchain) -> LumeResult<image::RgbaImage> {
+        swapchain.presented_image.ok_or(LumeError::Generic("No image has been presented yet"))?;
+        Ok(image::RgbaImage::new(swapchain.width, swapchain.height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compute_pipeline(device: &RecordingDevice) -> RecordingComputePipeline {
+        let shader = device.create_shader_module(&[0x0723_0203]).unwrap();
+        let layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[], push_constant_ranges: &[] }).unwrap();
+        device.create_compute_pipeline(ComputePipelineDescriptor { shader: &shader, layout: &layout }).unwrap()
+    }
+
+    #[test]
+    fn records_commands_with_bound_state() {
+        let device = RecordingDevice::new();
+        let pipeline = compute_pipeline(&device);
+        let buffer = device.create_buffer(BufferDescriptor { size: 64, usage: BufferUsage::STORAGE, mapped_at_creation: false }).unwrap();
+        let layout = device.create_bind_group_layout(BindGroupLayoutDescriptor {
+            entries: vec![BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::COMPUTE, ty: BindingType::StorageBuffer }],
+        }).unwrap();
+        let bind_group = device.create_bind_group(BindGroupDescriptor {
+            layout: &layout,
+            entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(&buffer) }],
+        }).unwrap();
+
+        let pool = device.create_command_pool(QueueType::Compute).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.bind_compute_pipeline(&pipeline);
+        cmd.bind_bind_group(0, &bind_group);
+        cmd.dispatch(4, 1, 1);
+        cmd.compute_barrier();
+        cmd.dispatch_indirect(&buffer, 0);
+        device.submit(QueueType::Compute, &[&cmd], &[], &[], &[], &[], None).unwrap();
+
+        let log = device.submitted();
+        let first = log.dispatch_with(&pipeline);
+        assert_eq!(*log.command(first), Command::Dispatch { x: 4, y: 1, z: 1 });
+        assert_eq!(log.commands[first].bind_group(0), Some(bind_group.id));
+        let second = log.expect("indirect dispatch", |c| matches!(c.command, Command::DispatchIndirect { .. }));
+        log.assert_barrier_between(first, second);
+        assert!(!log.has_barrier_between(second, first));
+
+        assert_eq!(
+            device.descriptor(bind_group.id),
+            Some(ResourceDescriptor::BindGroup { layout: layout.id, entries: vec![(0, buffer.id)] })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No barrier between")]
+    fn missing_barrier_panics() {
+        let device = RecordingDevice::new();
+        let pipeline = compute_pipeline(&device);
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.bind_compute_pipeline(&pipeline);
+        cmd.dispatch(1, 1, 1);
+        cmd.dispatch(1, 1, 1);
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).unwrap();
+        device.submitted().assert_barrier_between(1, 2);
+    }
+
+    #[test]
+    fn rejects_submission_to_another_queue() {
+        let device = RecordingDevice::new();
+        let pool = device.create_command_pool(QueueType::Transfer).unwrap();
+        let cmd = pool.allocate_command_buffer().unwrap();
+        assert!(device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).is_err());
+        assert!(device.submissions().is_empty());
+    }
+}
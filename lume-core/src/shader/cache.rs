@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::{compile_shader, preprocess, ShaderError, ShaderSource, SPIRV_MAGIC};
+
+/// Version of naga the cached SPIR-V was written by, as resolved by Cargo (see build.rs).
+const NAGA_VERSION: &str = env!("LUME_NAGA_VERSION");
+
+/// Compiled SPIR-V keyed by a hash of everything that affects the output: the source
+/// (after preprocessing), stage, defines, naga version and SPIR-V writer options.
+///
+/// Entries are kept in memory and, for caches created with `new`, written to one `.spv`
+/// file per key. Unreadable or corrupt files are treated as misses; failing to write one
+/// only logs a warning.
+pub struct ShaderCache {
+    dir: Option<PathBuf>,
+    memory: Mutex<HashMap<u64, Vec<u32>>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl ShaderCache {
+    /// A cache persisted in `dir`, which is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: Some(dir.into()), ..Self::in_memory() }
+    }
+
+    pub fn in_memory() -> Self {
+        Self { dir: None, memory: Mutex::new(HashMap::new()), hits: AtomicUsize::new(0), misses: AtomicUsize::new(0) }
+    }
+
+    /// Like `compile_shader`, returning the cached words if this source was compiled before.
+    pub fn compile(&self, source: ShaderSource) -> Result<Vec<u32>, ShaderError> {
+        let key = Self::key(&source)?;
+        if let Some(spirv) = self.memory.lock().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(spirv.clone());
+        }
+        if let Some(spirv) = self.read(key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            self.memory.lock().unwrap().insert(key, spirv.clone());
+            return Ok(spirv);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let spirv = compile_shader(source)?;
+        self.write(key, &spirv);
+        self.memory.lock().unwrap().insert(key, spirv.clone());
+        Ok(spirv)
+    }
+
+    /// The cache key of `source`. WGSL files are preprocessed so that edits to included files change it.
+    pub fn key(source: &ShaderSource) -> Result<u64, ShaderError> {
+        let mut hasher = Fnv1a::default();
+        hasher.write_str(NAGA_VERSION);
+        hasher.write_str(env!("CARGO_PKG_VERSION"));
+        hasher.write_str(&format!("{:?}", naga::back::spv::Options::default()));
+        match source {
+            ShaderSource::Wgsl(source) => {
+                hasher.write_str("wgsl");
+                hasher.write_str(source);
+            }
+            ShaderSource::WgslFile { path, resolver, defines } => {
+                hasher.write_str("wgsl");
+                hasher.write_str(&preprocess(path, *resolver, defines)?.source);
+            }
+            ShaderSource::Glsl { source, stage, defines } => {
+                hasher.write_str("glsl");
+                hasher.write_str(&format!("{:?}", stage));
+                let mut defines: Vec<_> = defines.iter().collect();
+                defines.sort();
+                for (name, value) in defines {
+                    hasher.write_str(name);
+                    hasher.write_str(value);
+                }
+                hasher.write_str(source);
+            }
+            ShaderSource::SpirV(bytes) => {
+                hasher.write_str("spirv");
+                hasher.write(bytes);
+            }
+        }
+        Ok(hasher.0)
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    fn path(&self, key: u64) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{:016x}.spv", key)))
+    }
+
+    fn read(&self, key: u64) -> Option<Vec<u32>> {
+        let bytes = std::fs::read(self.path(key)?).ok()?;
+        if !bytes.len().is_multiple_of(4) {
+            return None;
+        }
+        let words: Vec<u32> = bytes.chunks_exact(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+        (words.first() == Some(&SPIRV_MAGIC)).then_some(words)
+    }
+
+    fn write(&self, key: u64, spirv: &[u32]) {
+        let (Some(dir), Some(path)) = (&self.dir, self.path(key)) else {
+            return;
+        };
+        let bytes: Vec<u8> = spirv.iter().flat_map(|w| w.to_le_bytes()).collect();
+        // Write to a temporary file first so concurrent readers never see a partial entry.
+        let temp = path.with_extension(format!("spv.{}.tmp", std::process::id()));
+        let result = std::fs::create_dir_all(dir)
+            .and_then(|_| std::fs::write(&temp, &bytes))
+            .and_then(|_| std::fs::rename(&temp, &path));
+        if let Err(e) = result {
+            log::warn!("Failed to write shader cache entry {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust versions.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Length-prefixed so that consecutive strings cannot run into each other.
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shader::MemoryResolver;
+
+    const SOURCE: &str = "@compute @workgroup_size(SIZE) fn main() {}";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lume-shader-cache-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn source<'a>(resolver: &'a MemoryResolver, size: &str) -> ShaderSource<'a> {
+        let defines = [("SIZE".to_string(), size.to_string())].into_iter().collect();
+        ShaderSource::WgslFile { path: "main.wgsl", resolver, defines }
+    }
+
+    #[test]
+    fn hits_on_identical_sources_only() {
+        let resolver = MemoryResolver::new(&[("main.wgsl", SOURCE)]);
+        let cache = ShaderCache::in_memory();
+        let first = cache.compile(source(&resolver, "64")).unwrap();
+        assert_eq!(cache.compile(source(&resolver, "64")).unwrap(), first);
+        assert_ne!(cache.compile(source(&resolver, "32")).unwrap(), first);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    }
+
+    #[test]
+    fn persists_to_disk_and_ignores_corrupt_entries() {
+        let dir = temp_dir("persist");
+        let resolver = MemoryResolver::new(&[("main.wgsl", SOURCE)]);
+        let spirv = ShaderCache::new(&dir).compile(source(&resolver, "8")).unwrap();
+
+        let cache = ShaderCache::new(&dir);
+        assert_eq!(cache.compile(source(&resolver, "8")).unwrap(), spirv);
+        assert_eq!((cache.hits(), cache.misses()), (1, 0));
+
+        let key = ShaderCache::key(&source(&resolver, "8")).unwrap();
+        std::fs::write(dir.join(format!("{:016x}.spv", key)), [1, 2, 3]).unwrap();
+        let cache = ShaderCache::new(&dir);
+        assert_eq!(cache.compile(source(&resolver, "8")).unwrap(), spirv);
+        assert_eq!(cache.misses(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use codespan_reporting::diagnostic::{Diagnostic, Label};
+use codespan_reporting::files::{Files, SimpleFiles};
+use codespan_reporting::term::{self, termcolor::NoColor};
+use std::fmt;
+use std::ops::Range;
+use super::Preprocessed;
+
+/// The compilation step a `ShaderError` comes from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShaderErrorKind {
+    Preprocess,
+    Parse,
+    Validation,
+    /// Writing the target language failed.
+    Backend,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A shader compilation failure. Displaying it prints `diagnostic`, a rendering in the
+/// style of rustc with source snippets, or just the message if there is no source location.
+#[derive(Clone, Debug)]
+pub struct ShaderError {
+    pub kind: ShaderErrorKind,
+    pub severity: Severity,
+    pub message: String,
+    /// Original file of the primary location, before preprocessing.
+    pub file: Option<String>,
+    /// Byte range of the primary location in `file`.
+    pub span: Option<Range<usize>>,
+    /// 1-based line and column of the primary location in `file`.
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub diagnostic: String,
+}
+
+impl ShaderError {
+    pub(crate) fn new(kind: ShaderErrorKind, message: String) -> Self {
+        Self {
+            kind,
+            severity: Severity::Error,
+            message,
+            file: None,
+            span: None,
+            line: None,
+            column: None,
+            diagnostic: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.diagnostic.is_empty() {
+            return write!(f, "{}", self.diagnostic.trim_end());
+        }
+        match (&self.file, self.line, self.column) {
+            (Some(file), Some(line), Some(column)) => write!(f, "{}:{}:{}: {}", file, line, column, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+/// The files a shader was compiled from, used to locate and render errors.
+pub(crate) struct SourceMap<'a> {
+    files: SimpleFiles<&'a str, &'a str>,
+    preprocessed: Option<&'a Preprocessed>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn single(name: &'a str, source: &'a str) -> Self {
+        let mut files = SimpleFiles::new();
+        files.add(name, source);
+        Self { files, preprocessed: None }
+    }
+
+    pub fn preprocessed(preprocessed: &'a Preprocessed) -> Self {
+        let mut files = SimpleFiles::new();
+        for file in preprocessed.files() {
+            files.add(file.name.as_str(), file.source.as_str());
+        }
+        Self { files, preprocessed: Some(preprocessed) }
+    }
+
+    /// Maps a byte range of the compiled source to a file and a range within it.
+    fn map(&self, range: Range<usize>) -> Option<(usize, Range<usize>)> {
+        match self.preprocessed {
+            Some(preprocessed) => preprocessed.map_range(range),
+            None => Some((0, range)),
+        }
+    }
+
+    /// Builds an error whose first label is the primary location. Labels are byte ranges of
+    /// the compiled source; those that cannot be mapped back to a file are dropped.
+    pub fn error(&self, kind: ShaderErrorKind, message: String, labels: Vec<(Range<usize>, String)>, notes: Vec<String>) -> ShaderError {
+        let labels: Vec<(usize, Range<usize>, String)> = labels.into_iter()
+            .filter_map(|(range, label)| self.map(range).map(|(file, range)| (file, range, label)))
+            .collect();
+
+        let mut error = ShaderError::new(kind, message.clone());
+        if let Some((file, range, _)) = labels.first() {
+            error.file = self.files.name(*file).ok().map(|name| name.to_string());
+            if let Ok(location) = self.files.location(*file, range.start) {
+                error.line = Some(location.line_number as u32);
+                error.column = Some(location.column_number as u32);
+            }
+            error.span = Some(range.clone());
+        }
+
+        let diagnostic = Diagnostic::error()
+            .with_message(message)
+            .with_labels(labels.into_iter().enumerate().map(|(i, (file, range, label))| {
+                let label_style = if i == 0 { Label::primary(file, range) } else { Label::secondary(file, range) };
+                label_style.with_message(label)
+            }).collect())
+            .with_notes(notes);
+        let mut writer = NoColor::new(Vec::new());
+        if term::emit(&mut writer, &term::Config::default(), &self.files, &diagnostic).is_ok() {
+            error.diagnostic = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+        }
+        error
+    }
+}
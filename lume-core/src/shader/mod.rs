@@ -0,0 +1,551 @@
+use naga::front::glsl;
+use naga::back::spv;
+use crate::device::{BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, PushConstantRange, ShaderStage, VertexFormat};
+
+mod cache;
+mod error;
+mod permutation;
+mod preprocess;
+mod reload;
+mod target;
+
+pub use cache::ShaderCache;
+pub use error::{Severity, ShaderError, ShaderErrorKind};
+pub use permutation::{KeyValue, Permutation, PermutationKey, PipelinePermutations, ShaderPermutations};
+pub use preprocess::{preprocess, FileResolver, MemoryResolver, Preprocessed, ShaderFile, ShaderResolver};
+pub use reload::{ComputePipelineId, GraphicsPipelineId, ReloadEvent, ShaderId, ShaderReloader};
+pub use target::{compile_shader_to, ShaderOutput, ShaderTarget};
+use error::SourceMap;
+
+pub(crate) const SPIRV_MAGIC: u32 = 0x0723_0203;
+
+pub enum ShaderSource<'a> {
+    Glsl {
+        source: &'a str,
+        stage: naga::ShaderStage,
+        defines: naga::FastHashMap<String, String>,
+    },
+    Wgsl(&'a str),
+    /// WGSL file run through `preprocess` first; errors refer to the original files and lines.
+    WgslFile {
+        path: &'a str,
+        resolver: &'a dyn ShaderResolver,
+        defines: naga::FastHashMap<String, String>,
+    },
+    /// Little-endian SPIR-V binary, e.g. from `include_bytes!`. It is parsed and validated by
+    /// naga for errors and reflection, and `compile_shader` passes it through unchanged.
+    SpirV(&'a [u8]),
+}
+
+/// SPIR-V together with the resource interface it was compiled from.
+pub struct CompiledShader {
+    pub spirv: Vec<u32>,
+    pub reflection: ShaderReflection,
+}
+
+pub fn compile_shader(source: ShaderSource) -> Result<Vec<u32>, ShaderError> {
+    let spirv = match source {
+        ShaderSource::SpirV(bytes) => Some(spirv_words(bytes)?),
+        _ => None,
+    };
+    let (module, info) = parse_and_validate(source)?;
+    match spirv {
+        Some(spirv) => Ok(spirv),
+        None => write_spirv(&module, &info),
+    }
+}
+
+/// Like `compile_shader`, but also reflects the module's bindings and entry points.
+pub fn compile_shader_reflected(source: ShaderSource) -> Result<CompiledShader, ShaderError> {
+    let spirv = match source {
+        ShaderSource::SpirV(bytes) => Some(spirv_words(bytes)?),
+        _ => None,
+    };
+    let (module, info) = parse_and_validate(source)?;
+    Ok(CompiledShader {
+        spirv: match spirv {
+            Some(spirv) => spirv,
+            None => write_spirv(&module, &info)?,
+        },
+        reflection: ShaderReflection::from_module(&module, &info),
+    })
+}
+
+/// Compiles only the entry point `name` of a module with several, renamed to `main` as the
+/// backends expect, and reflects the bindings it uses. This builds e.g. the vertex and
+/// fragment shaders of a pipeline from one WGSL file.
+pub fn compile_entry_point(source: ShaderSource, name: &str) -> Result<CompiledShader, ShaderError> {
+    let (mut module, _) = parse_and_validate(source)?;
+    let index = module.entry_points.iter().position(|ep| ep.name == name).ok_or_else(|| {
+        let available: Vec<&str> = module.entry_points.iter().map(|ep| ep.name.as_str()).collect();
+        ShaderError::new(ShaderErrorKind::Validation, format!("No entry point '{}', expected one of: {}", name, available.join(", ")))
+    })?;
+    let mut entry_point = module.entry_points.swap_remove(index);
+    entry_point.name = "main".to_string();
+    module.entry_points = vec![entry_point];
+
+    let info = validator().validate(&module)
+        .map_err(|e| ShaderError::new(ShaderErrorKind::Validation, format!("Entry point '{}': {}", name, e)))?;
+    let used: Vec<(u32, u32)> = module.global_variables.iter()
+        .filter(|&(handle, _)| !info.get_entry_point(0)[handle].is_empty())
+        .filter_map(|(_, global)| global.binding.as_ref().map(|b| (b.group, b.binding)))
+        .collect();
+    let mut reflection = ShaderReflection::from_module(&module, &info);
+    reflection.bindings.retain(|b| used.contains(&(b.group, b.binding)));
+    Ok(CompiledShader { spirv: write_spirv(&module, &info)?, reflection })
+}
+
+pub fn reflect_shader(source: ShaderSource) -> Result<ShaderReflection, ShaderError> {
+    let (module, info) = parse_and_validate(source)?;
+    Ok(ShaderReflection::from_module(&module, &info))
+}
+
+fn parse_and_validate(source: ShaderSource) -> Result<(naga::Module, naga::valid::ModuleInfo), ShaderError> {
+    let preprocessed;
+    let (text, sources) = match &source {
+        ShaderSource::Wgsl(src) => (*src, SourceMap::single("wgsl", src)),
+        ShaderSource::Glsl { source, .. } => (*source, SourceMap::single("glsl", source)),
+        ShaderSource::WgslFile { path, resolver, defines } => {
+            preprocessed = preprocess(path, *resolver, defines)?;
+            (preprocessed.source.as_str(), SourceMap::preprocessed(&preprocessed))
+        }
+        ShaderSource::SpirV(bytes) => return parse_spirv(bytes),
+    };
+
+    let module = match source {
+        ShaderSource::Glsl { stage, defines, .. } => {
+            let mut parser = glsl::Frontend::default();
+            let options = glsl::Options {
+                stage,
+                defines,
+            };
+            parser.parse(&options, text).map_err(|e| {
+                let message = e.errors.first().map_or_else(|| "GLSL parse error".to_string(), |e| e.kind.to_string());
+                let labels = e.errors.iter().filter_map(|e| Some((e.meta.to_range()?, e.kind.to_string()))).collect();
+                sources.error(ShaderErrorKind::Parse, message, labels, Vec::new())
+            })?
+        }
+        _ => naga::front::wgsl::Frontend::new().parse(text).map_err(|e| {
+            let labels = e.labels().filter_map(|(span, label)| Some((span.to_range()?, label.to_string()))).collect();
+            sources.error(ShaderErrorKind::Parse, e.message().to_string(), labels, Vec::new())
+        })?,
+    };
+
+    let info = validator().validate(&module).map_err(|e| {
+        let labels = e.spans().filter_map(|(span, label)| Some((span.to_range()?, label.clone()))).collect();
+        let mut notes = Vec::new();
+        let mut cause = std::error::Error::source(&e);
+        while let Some(next) = cause {
+            notes.push(next.to_string());
+            cause = next.source();
+        }
+        sources.error(ShaderErrorKind::Validation, e.to_string(), labels, notes)
+    })?;
+
+    Ok((module, info))
+}
+
+/// SPIR-V has no source to point errors at, so they are reported with messages only.
+fn parse_spirv(bytes: &[u8]) -> Result<(naga::Module, naga::valid::ModuleInfo), ShaderError> {
+    let options = naga::front::spv::Options::default();
+    let module = naga::front::spv::Frontend::new(spirv_words(bytes)?.into_iter(), &options).parse()
+        .map_err(|e| ShaderError::new(ShaderErrorKind::Parse, format!("SPIR-V parse error: {}", e)))?;
+    let info = validator().validate(&module)
+        .map_err(|e| ShaderError::new(ShaderErrorKind::Validation, e.to_string()))?;
+    Ok((module, info))
+}
+
+fn validator() -> naga::valid::Validator {
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+}
+
+/// Words of a SPIR-V binary, checking its length and magic number but not its contents.
+/// Prefer `ShaderSource::SpirV` unless naga's SPIR-V frontend rejects the module.
+pub fn spirv_words(bytes: &[u8]) -> Result<Vec<u32>, ShaderError> {
+    if !bytes.len().is_multiple_of(4) || bytes.len() < 20 {
+        return Err(ShaderError::new(ShaderErrorKind::Parse, format!("SPIR-V binary of {} bytes is not a whole number of words with a header", bytes.len())));
+    }
+    let words: Vec<u32> = bytes.chunks_exact(4).map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+    match words[0] {
+        SPIRV_MAGIC => Ok(words),
+        magic if magic.swap_bytes() == SPIRV_MAGIC => Err(ShaderError::new(ShaderErrorKind::Parse, "Big-endian SPIR-V is not supported".to_string())),
+        magic => Err(ShaderError::new(ShaderErrorKind::Parse, format!("Invalid SPIR-V magic number {:#010x}", magic))),
+    }
+}
+
+fn write_spirv(module: &naga::Module, info: &naga::valid::ModuleInfo) -> Result<Vec<u32>, ShaderError> {
+    let write_options = spv::Options::default();
+    let spv = spv::write_vec(module, info, &write_options, None)
+        .map_err(|e| ShaderError::new(ShaderErrorKind::Backend, format!("SPIR-V write error: {}", e)))?;
+
+    Ok(spv)
+}
+
+/// A resource binding declared with `@group(g) @binding(b)` or `layout(set = g, binding = b)`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReflectedBinding {
+    pub group: u32,
+    pub binding: u32,
+    pub name: Option<String>,
+    pub ty: BindingType,
+    /// Stages of the entry points that use the binding, or of every entry point if none does.
+    pub visibility: ShaderStage,
+}
+
+/// A vertex shader input with a `@location`. `format` is `None` for types without a `VertexFormat`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VertexInput {
+    pub location: u32,
+    pub name: Option<String>,
+    pub format: Option<VertexFormat>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReflectedEntryPoint {
+    pub name: String,
+    pub stage: ShaderStage,
+    /// `[0, 0, 0]` for non-compute stages.
+    pub workgroup_size: [u32; 3],
+    /// Sorted by location; empty for non-vertex stages.
+    pub vertex_inputs: Vec<VertexInput>,
+}
+
+/// The resource interface of one or more shader modules.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ShaderReflection {
+    /// Sorted by group, then binding.
+    pub bindings: Vec<ReflectedBinding>,
+    /// At most one range, covering every push constant block of the reflected stages.
+    pub push_constant_ranges: Vec<PushConstantRange>,
+    pub entry_points: Vec<ReflectedEntryPoint>,
+}
+
+impl ShaderReflection {
+    pub fn from_module(module: &naga::Module, info: &naga::valid::ModuleInfo) -> Self {
+        let entry_points: Vec<ReflectedEntryPoint> = module.entry_points.iter().map(|ep| ReflectedEntryPoint {
+            name: ep.name.clone(),
+            stage: stage_flags(ep.stage),
+            workgroup_size: if ep.stage == naga::ShaderStage::Compute { ep.workgroup_size } else { [0; 3] },
+            vertex_inputs: if ep.stage == naga::ShaderStage::Vertex { vertex_inputs(module, &ep.function) } else { Vec::new() },
+        }).collect();
+        let all_stages = entry_points.iter().fold(ShaderStage(0), |stages, ep| stages | ep.stage);
+
+        let mut reflection = ShaderReflection { entry_points, ..Default::default() };
+        for (handle, global) in module.global_variables.iter() {
+            let used_by = module.entry_points.iter().enumerate()
+                .filter(|&(i, _)| !info.get_entry_point(i)[handle].is_empty())
+                .fold(ShaderStage(0), |stages, (_, ep)| stages | stage_flags(ep.stage));
+            let visibility = if used_by.0 == 0 { all_stages } else { used_by };
+
+            if global.space == naga::AddressSpace::PushConstant {
+                let size = module.types[global.ty].inner.size(module.to_ctx());
+                reflection.add_push_constants(PushConstantRange { stages: visibility, offset: 0, size });
+                continue;
+            }
+            let (Some(binding), Some(ty)) = (&global.binding, binding_type(module, global)) else {
+                continue;
+            };
+            reflection.bindings.push(ReflectedBinding {
+                group: binding.group,
+                binding: binding.binding,
+                name: global.name.clone(),
+                ty,
+                visibility,
+            });
+        }
+        reflection.bindings.sort_by_key(|b| (b.group, b.binding));
+        reflection
+    }
+
+    /// Combines the interfaces of shaders used together in one pipeline, e.g. a vertex and a
+    /// fragment shader. Fails if both declare the same binding with different types.
+    pub fn merge(&mut self, other: &ShaderReflection) -> Result<(), String> {
+        for binding in &other.bindings {
+            match self.bindings.iter_mut().find(|b| b.group == binding.group && b.binding == binding.binding) {
+                Some(existing) if existing.ty != binding.ty => {
+                    return Err(format!(
+                        "@group({}) @binding({}) is {:?} in one shader and {:?} in another",
+                        binding.group, binding.binding, existing.ty, binding.ty
+                    ));
+                }
+                Some(existing) => existing.visibility = existing.visibility | binding.visibility,
+                None => self.bindings.push(binding.clone()),
+            }
+        }
+        self.bindings.sort_by_key(|b| (b.group, b.binding));
+        for &range in &other.push_constant_ranges {
+            self.add_push_constants(range);
+        }
+        self.entry_points.extend(other.entry_points.iter().cloned());
+        Ok(())
+    }
+
+    fn add_push_constants(&mut self, range: PushConstantRange) {
+        match self.push_constant_ranges.first_mut() {
+            Some(existing) => {
+                existing.stages = existing.stages | range.stages;
+                existing.size = existing.size.max(range.offset + range.size);
+            }
+            None => self.push_constant_ranges.push(range),
+        }
+    }
+
+    pub fn entry_point(&self, name: &str) -> Option<&ReflectedEntryPoint> {
+        self.entry_points.iter().find(|ep| ep.name == name)
+    }
+
+    /// One descriptor per group up to the highest one used; unused groups are empty.
+    pub fn bind_group_layouts(&self) -> Vec<BindGroupLayoutDescriptor> {
+        let group_count = self.bindings.iter().map(|b| b.group + 1).max().unwrap_or(0);
+        (0..group_count).map(|group| BindGroupLayoutDescriptor { entries: self.group_entries(group) }).collect()
+    }
+
+    fn group_entries(&self, group: u32) -> Vec<BindGroupLayoutEntry> {
+        self.bindings.iter()
+            .filter(|b| b.group == group)
+            .map(|b| BindGroupLayoutEntry { binding: b.binding, visibility: b.visibility, ty: b.ty })
+            .collect()
+    }
+
+    /// Checks hand-written layout entries for `group` against the shader's declarations.
+    pub fn check_bind_group_layout(&self, group: u32, entries: &[BindGroupLayoutEntry]) -> Result<(), String> {
+        for b in self.bindings.iter().filter(|b| b.group == group) {
+            let entry = entries.iter().find(|e| e.binding == b.binding).ok_or_else(|| {
+                format!("@group({}) @binding({}) is missing from the bind group layout", group, b.binding)
+            })?;
+            if entry.ty != b.ty {
+                return Err(format!(
+                    "@group({}) @binding({}) is declared as {:?} but the layout has {:?}",
+                    group, b.binding, b.ty, entry.ty
+                ));
+            }
+            if entry.visibility.0 & b.visibility.0 != b.visibility.0 {
+                return Err(format!(
+                    "@group({}) @binding({}) is used by stages {:?} but only visible to {:?}",
+                    group, b.binding, b.visibility, entry.visibility
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stage_flags(stage: naga::ShaderStage) -> ShaderStage {
+    match stage {
+        naga::ShaderStage::Vertex => ShaderStage::VERTEX,
+        naga::ShaderStage::Fragment => ShaderStage::FRAGMENT,
+        naga::ShaderStage::Compute => ShaderStage::COMPUTE,
+    }
+}
+
+fn binding_type(module: &naga::Module, global: &naga::GlobalVariable) -> Option<BindingType> {
+    match global.space {
+        naga::AddressSpace::Uniform => Some(BindingType::UniformBuffer),
+        naga::AddressSpace::Storage { .. } => Some(BindingType::StorageBuffer),
+        naga::AddressSpace::Handle => {
+            let mut inner = &module.types[global.ty].inner;
+            if let naga::TypeInner::BindingArray { base, .. } = *inner {
+                inner = &module.types[base].inner;
+            }
+            match *inner {
+                naga::TypeInner::Image { class: naga::ImageClass::Storage { .. }, .. } => Some(BindingType::StorageTexture),
+                naga::TypeInner::Image { .. } => Some(BindingType::SampledTexture),
+                naga::TypeInner::Sampler { .. } => Some(BindingType::Sampler),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn vertex_inputs(module: &naga::Module, function: &naga::Function) -> Vec<VertexInput> {
+    let mut inputs = Vec::new();
+    for argument in &function.arguments {
+        match (&argument.binding, &module.types[argument.ty].inner) {
+            (Some(binding), _) => inputs.extend(vertex_input(module, binding, argument.name.clone(), argument.ty)),
+            (None, naga::TypeInner::Struct { members, .. }) => {
+                for member in members {
+                    if let Some(binding) = &member.binding {
+                        inputs.extend(vertex_input(module, binding, member.name.clone(), member.ty));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    inputs.sort_by_key(|input| input.location);
+    inputs
+}
+
+fn vertex_input(module: &naga::Module, binding: &naga::Binding, name: Option<String>, ty: naga::Handle<naga::Type>) -> Option<VertexInput> {
+    let naga::Binding::Location { location, .. } = *binding else {
+        return None;
+    };
+    let format = match module.types[ty].inner {
+        naga::TypeInner::Vector { size, scalar: naga::Scalar::F32 } => match size {
+            naga::VectorSize::Bi => Some(VertexFormat::Float32x2),
+            naga::VectorSize::Tri => Some(VertexFormat::Float32x3),
+            naga::VectorSize::Quad => Some(VertexFormat::Float32x4),
+        },
+        _ => None,
+    };
+    Some(VertexInput { location, name, format })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERTEX: &str = r#"
+        struct View { view_proj: mat4x4<f32> }
+        struct Input {
+            @location(1) uv: vec2<f32>,
+            @location(0) position: vec3<f32>,
+            @builtin(vertex_index) index: u32,
+        }
+        @group(0) @binding(0) var<storage, read> offsets: array<vec4<f32>>;
+        @group(1) @binding(0) var<uniform> view: View;
+        var<push_constant> instance: vec4<f32>;
+
+        @vertex
+        fn main(input: Input) -> @builtin(position) vec4<f32> {
+            return view.view_proj * vec4<f32>(input.position, 1.0) + offsets[input.index] + instance + vec4<f32>(input.uv, 0.0, 0.0);
+        }
+    "#;
+
+    const FRAGMENT: &str = r#"
+        @group(1) @binding(1) var color: texture_2d<f32>;
+        @group(1) @binding(2) var color_sampler: sampler;
+
+        @fragment
+        fn main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+            return textureSample(color, color_sampler, position.xy);
+        }
+    "#;
+
+    #[test]
+    fn reflects_bindings_push_constants_and_vertex_inputs() {
+        let reflection = reflect_shader(ShaderSource::Wgsl(VERTEX)).unwrap();
+        let layouts = reflection.bind_group_layouts();
+        assert_eq!(layouts.len(), 2);
+        assert_eq!(layouts[0].entries, [BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::VERTEX, ty: BindingType::StorageBuffer }]);
+        assert_eq!(layouts[1].entries, [BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::VERTEX, ty: BindingType::UniformBuffer }]);
+        assert_eq!(reflection.push_constant_ranges, [PushConstantRange { stages: ShaderStage::VERTEX, offset: 0, size: 16 }]);
+
+        let inputs = &reflection.entry_point("main").unwrap().vertex_inputs;
+        assert_eq!(inputs.iter().map(|i| (i.location, i.format)).collect::<Vec<_>>(), [
+            (0, Some(VertexFormat::Float32x3)),
+            (1, Some(VertexFormat::Float32x2)),
+        ]);
+    }
+
+    #[test]
+    fn reflects_compute_workgroup_size() {
+        let source = "@group(0) @binding(0) var<storage, read_write> data: array<u32>;\n\
+                      @compute @workgroup_size(8, 4) fn main(@builtin(global_invocation_id) id: vec3<u32>) { data[id.x] = id.y; }";
+        let reflection = reflect_shader(ShaderSource::Wgsl(source)).unwrap();
+        let entry_point = &reflection.entry_points[0];
+        assert_eq!(entry_point.stage, ShaderStage::COMPUTE);
+        assert_eq!(entry_point.workgroup_size, [8, 4, 1]);
+        assert_eq!(reflection.bindings[0].ty, BindingType::StorageBuffer);
+    }
+
+    #[test]
+    fn merges_stages_and_rejects_conflicting_bindings() {
+        let mut reflection = reflect_shader(ShaderSource::Wgsl(VERTEX)).unwrap();
+        reflection.merge(&reflect_shader(ShaderSource::Wgsl(FRAGMENT)).unwrap()).unwrap();
+        let group = &reflection.bind_group_layouts()[1].entries;
+        assert_eq!(group.iter().map(|e| (e.binding, e.ty, e.visibility)).collect::<Vec<_>>(), [
+            (0, BindingType::UniformBuffer, ShaderStage::VERTEX),
+            (1, BindingType::SampledTexture, ShaderStage::FRAGMENT),
+            (2, BindingType::Sampler, ShaderStage::FRAGMENT),
+        ]);
+
+        let conflicting = "@group(0) @binding(0) var<uniform> offsets: vec4<f32>;\n\
+                           @fragment fn main() -> @location(0) vec4<f32> { return offsets; }";
+        let error = reflection.merge(&reflect_shader(ShaderSource::Wgsl(conflicting)).unwrap()).unwrap_err();
+        assert!(error.contains("@group(0) @binding(0)"), "{}", error);
+    }
+
+    #[test]
+    fn maps_errors_in_included_files() {
+        let resolver = MemoryResolver::new(&[
+            ("main.wgsl", "#include \"types.wgsl\"\n@compute @workgroup_size(1) fn main() {}"),
+            ("types.wgsl", "struct Ok { x: u32 }\nstruct Broken { x: Missing }"),
+        ]);
+        let source = ShaderSource::WgslFile { path: "main.wgsl", resolver: &resolver, defines: Default::default() };
+        let error = compile_shader(source).err().unwrap();
+        assert_eq!(error.kind, ShaderErrorKind::Parse);
+        assert_eq!((error.file.as_deref(), error.line, error.column), (Some("types.wgsl"), Some(2), Some(20)));
+        assert_eq!(error.span, Some(40..47));
+        let rendered = error.to_string();
+        assert!(rendered.contains("types.wgsl:2:20"), "{}", rendered);
+        assert!(rendered.contains("struct Broken { x: Missing }"), "{}", rendered);
+
+        let resolver = MemoryResolver::new(&[("main.wgsl", "#define SIZE 64\n@compute @workgroup_size(SIZE) fn main() {}")]);
+        let source = ShaderSource::WgslFile { path: "main.wgsl", resolver: &resolver, defines: Default::default() };
+        assert_eq!(reflect_shader(source).unwrap().entry_points[0].workgroup_size, [64, 1, 1]);
+    }
+
+    #[test]
+    fn reports_validation_errors_with_source() {
+        let source = "@compute @workgroup_size(1)\nfn main() {\n    let x: u32 = 1.0;\n}";
+        let error = compile_shader(ShaderSource::Wgsl(source)).err().unwrap();
+        assert_eq!(error.severity, Severity::Error);
+        assert_eq!(error.line, Some(3));
+        assert!(error.to_string().contains("let x: u32 = 1.0;"), "{}", error);
+
+        let error = crate::LumeError::from(error);
+        assert!(matches!(error, crate::LumeError::ShaderCompilationFailed(_)));
+    }
+
+    #[test]
+    fn checks_hand_written_layouts() {
+        let reflection = reflect_shader(ShaderSource::Wgsl(VERTEX)).unwrap();
+        let uniform = BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT, ty: BindingType::UniformBuffer };
+        assert!(reflection.check_bind_group_layout(1, &[uniform]).is_ok());
+        assert!(reflection.check_bind_group_layout(0, &[uniform]).is_err());
+        assert!(reflection.check_bind_group_layout(0, &[]).is_err());
+    }
+
+    #[test]
+    fn passes_spirv_through_with_reflection() {
+        let compiled = compile_shader_reflected(ShaderSource::Wgsl(VERTEX)).unwrap();
+        let bytes: Vec<u8> = compiled.spirv.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let passed = compile_shader_reflected(ShaderSource::SpirV(&bytes)).unwrap();
+        assert_eq!(passed.spirv, compiled.spirv);
+        let interface = |r: &ShaderReflection| r.bindings.iter().map(|b| (b.group, b.binding, b.ty, b.visibility)).collect::<Vec<_>>();
+        assert_eq!(interface(&passed.reflection), interface(&compiled.reflection));
+        assert_eq!(passed.reflection.push_constant_ranges, compiled.reflection.push_constant_ranges);
+
+        assert!(compile_shader(ShaderSource::SpirV(&bytes[..bytes.len() - 2])).is_err());
+        let mut corrupt = bytes.clone();
+        corrupt[0] = 0;
+        let error = compile_shader(ShaderSource::SpirV(&corrupt)).unwrap_err();
+        assert!(error.message.contains("magic"), "{}", error);
+    }
+
+    #[test]
+    fn compiles_selected_entry_point() {
+        let source = r#"
+            @group(0) @binding(0) var<uniform> offset: vec4<f32>;
+            @group(0) @binding(1) var<uniform> tint: vec4<f32>;
+            @vertex fn vs_main() -> @builtin(position) vec4<f32> { return offset; }
+            @fragment fn fs_main() -> @location(0) vec4<f32> { return tint; }
+        "#;
+        let fragment = compile_entry_point(ShaderSource::Wgsl(source), "fs_main").unwrap();
+        assert_eq!(fragment.reflection.entry_points.len(), 1);
+        assert_eq!((fragment.reflection.entry_points[0].name.as_str(), fragment.reflection.entry_points[0].stage), ("main", ShaderStage::FRAGMENT));
+        assert_eq!(fragment.reflection.bindings.iter().map(|b| b.binding).collect::<Vec<_>>(), [1]);
+
+        let bytes: Vec<u8> = fragment.spirv.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let reflection = reflect_shader(ShaderSource::SpirV(&bytes)).unwrap();
+        assert_eq!(reflection.entry_point("main").unwrap().stage, ShaderStage::FRAGMENT);
+
+        let error = compile_entry_point(ShaderSource::Wgsl(source), "cs_main").err().unwrap();
+        assert!(error.message.contains("vs_main, fs_main"), "{}", error);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use crate::{Device, LumeResult};
+use super::{compile_shader_reflected, CompiledShader, ShaderError, ShaderErrorKind, ShaderResolver, ShaderSource};
+
+/// A compile-time option of a shader with permutations.
+///
+/// Boolean keys are `#define`d by name when set. Enumerated keys define `{name}_{value}`
+/// for the selected value, so shaders branch with `#ifdef DEBUG_VIEW_CLUSTERS`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PermutationKey {
+    Bool { name: String, default: bool },
+    /// The first value is the default; a key without values fails to resolve.
+    Enum { name: String, values: Vec<String> },
+}
+
+impl PermutationKey {
+    pub fn bool(name: &str, default: bool) -> Self {
+        PermutationKey::Bool { name: name.to_string(), default }
+    }
+
+    pub fn enumerated(name: &str, values: &[&str]) -> Self {
+        PermutationKey::Enum { name: name.to_string(), values: values.iter().map(|v| v.to_string()).collect() }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            PermutationKey::Bool { name, .. } | PermutationKey::Enum { name, .. } => name,
+        }
+    }
+
+    fn default_value(&self) -> Option<KeyValue> {
+        match self {
+            PermutationKey::Bool { default, .. } => Some(KeyValue::Bool(*default)),
+            PermutationKey::Enum { values, .. } => values.first().map(|value| KeyValue::Enum(value.clone())),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum KeyValue {
+    Bool(bool),
+    Enum(String),
+}
+
+impl From<bool> for KeyValue {
+    fn from(value: bool) -> Self {
+        KeyValue::Bool(value)
+    }
+}
+
+impl From<&str> for KeyValue {
+    fn from(value: &str) -> Self {
+        KeyValue::Enum(value.to_string())
+    }
+}
+
+/// Values for some of a shader's keys; the others take their defaults.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Permutation(BTreeMap<String, KeyValue>);
+
+impl Permutation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, key: &str, value: impl Into<KeyValue>) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<KeyValue>) {
+        self.0.insert(key.to_string(), value.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&KeyValue> {
+        self.0.get(key)
+    }
+}
+
+/// A WGSL file compiled on demand for each combination of its keys. Variants are cached,
+/// so requesting the same permutation again, with or without its defaults spelled out, is free.
+pub struct ShaderPermutations<'r> {
+    path: String,
+    resolver: &'r dyn ShaderResolver,
+    keys: Vec<PermutationKey>,
+    variants: Mutex<HashMap<Permutation, Arc<CompiledShader>>>,
+}
+
+impl<'r> ShaderPermutations<'r> {
+    pub fn new(path: &str, resolver: &'r dyn ShaderResolver, keys: Vec<PermutationKey>) -> Self {
+        Self { path: path.to_string(), resolver, keys, variants: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn keys(&self) -> &[PermutationKey] {
+        &self.keys
+    }
+
+    /// `permutation` with defaults filled in, or an error naming an unknown key or value.
+    pub fn resolve(&self, permutation: &Permutation) -> Result<Permutation, ShaderError> {
+        let error = |message: String| ShaderError::new(ShaderErrorKind::Preprocess, format!("{}: {}", self.path, message));
+        if let Some(name) = permutation.0.keys().find(|name| !self.keys.iter().any(|k| k.name() == name.as_str())) {
+            return Err(error(format!("Unknown permutation key '{}'", name)));
+        }
+        let mut resolved = Permutation::new();
+        for key in &self.keys {
+            let Some(value) = permutation.get(key.name()).cloned().or_else(|| key.default_value()) else {
+                return Err(error(format!("Permutation key '{}' has no values", key.name())));
+            };
+            let valid = match (key, &value) {
+                (PermutationKey::Bool { .. }, KeyValue::Bool(_)) => true,
+                (PermutationKey::Enum { values, .. }, KeyValue::Enum(value)) => values.contains(value),
+                _ => false,
+            };
+            if !valid {
+                return Err(error(format!("Invalid value {:?} for permutation key '{}'", value, key.name())));
+            }
+            resolved.0.insert(key.name().to_string(), value);
+        }
+        Ok(resolved)
+    }
+
+    /// The defines `permutation` compiles with.
+    pub fn defines(&self, permutation: &Permutation) -> Result<naga::FastHashMap<String, String>, ShaderError> {
+        let resolved = self.resolve(permutation)?;
+        Ok(resolved.0.into_iter().filter_map(|(name, value)| match value {
+            KeyValue::Bool(true) => Some((name, String::new())),
+            KeyValue::Bool(false) => None,
+            KeyValue::Enum(value) => Some((format!("{}_{}", name, value), String::new())),
+        }).collect())
+    }
+
+    /// Compiles `permutation` unless an equivalent one was compiled before.
+    pub fn variant(&self, permutation: &Permutation) -> Result<Arc<CompiledShader>, ShaderError> {
+        let resolved = self.resolve(permutation)?;
+        if let Some(variant) = self.variants.lock().unwrap().get(&resolved) {
+            return Ok(variant.clone());
+        }
+        let defines = self.defines(&resolved)?;
+        let variant = Arc::new(compile_shader_reflected(ShaderSource::WgslFile { path: &self.path, resolver: self.resolver, defines })?);
+        self.variants.lock().unwrap().insert(resolved, variant.clone());
+        Ok(variant)
+    }
+
+    /// Number of variants compiled so far.
+    pub fn compiled(&self) -> usize {
+        self.variants.lock().unwrap().len()
+    }
+}
+
+type BuildFn<'a, D, P> = Box<dyn Fn(&D, &Permutation) -> LumeResult<P> + 'a>;
+
+/// Pipelines created on first request for each permutation, typically from the shaders of
+/// `ShaderPermutations::variant`. Permutations are compared as given, so pass resolved ones
+/// if callers may or may not spell out defaults.
+pub struct PipelinePermutations<'a, D: Device, P> {
+    build: BuildFn<'a, D, P>,
+    pipelines: HashMap<Permutation, P>,
+}
+
+impl<'a, D: Device, P> PipelinePermutations<'a, D, P> {
+    pub fn new(build: impl Fn(&D, &Permutation) -> LumeResult<P> + 'a) -> Self {
+        Self { build: Box::new(build), pipelines: HashMap::new() }
+    }
+
+    pub fn get(&mut self, device: &D, permutation: &Permutation) -> LumeResult<&P> {
+        if !self.pipelines.contains_key(permutation) {
+            let pipeline = (self.build)(device, permutation)?;
+            self.pipelines.insert(permutation.clone(), pipeline);
+        }
+        Ok(&self.pipelines[permutation])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shader::MemoryResolver;
+
+    const SOURCE: &str = "
+#ifdef WIDE
+const SIZE: u32 = 128u;
+#else
+const SIZE: u32 = 32u;
+#endif
+#ifdef MODE_SQUARE
+fn f(x: u32) -> u32 { return x * x; }
+#endif
+#ifdef MODE_DOUBLE
+fn f(x: u32) -> u32 { return x * 2u; }
+#endif
+@group(0) @binding(0) var<storage, read_write> data: array<u32>;
+@compute @workgroup_size(SIZE) fn main(@builtin(global_invocation_id) id: vec3<u32>) { data[id.x] = f(data[id.x]); }
+";
+
+    #[test]
+    fn compiles_and_caches_variants_by_resolved_keys() {
+        let resolver = MemoryResolver::new(&[("main.wgsl", SOURCE)]);
+        let keys = vec![PermutationKey::bool("WIDE", false), PermutationKey::enumerated("MODE", &["SQUARE", "DOUBLE"])];
+        let shader = ShaderPermutations::new("main.wgsl", &resolver, keys);
+
+        let default = shader.variant(&Permutation::new()).unwrap();
+        assert_eq!(default.reflection.entry_point("main").unwrap().workgroup_size, [32, 1, 1]);
+        let explicit = shader.variant(&Permutation::new().with("WIDE", false).with("MODE", "SQUARE")).unwrap();
+        assert!(Arc::ptr_eq(&default, &explicit));
+
+        let wide = shader.variant(&Permutation::new().with("WIDE", true).with("MODE", "DOUBLE")).unwrap();
+        assert_eq!(wide.reflection.entry_point("main").unwrap().workgroup_size, [128, 1, 1]);
+        assert_ne!(wide.spirv, default.spirv);
+        assert_eq!(shader.compiled(), 2);
+
+        assert!(shader.variant(&Permutation::new().with("MODE", "TRIPLE")).is_err());
+        assert!(shader.variant(&Permutation::new().with("MODE", true)).is_err());
+        assert!(shader.variant(&Permutation::new().with("FAST", true)).is_err());
+
+        let empty = ShaderPermutations::new("main.wgsl", &resolver, vec![PermutationKey::enumerated("MODE", &[])]);
+        assert!(empty.variant(&Permutation::new()).is_err());
+        assert!(empty.variant(&Permutation::new().with("MODE", "SQUARE")).is_err());
+    }
+}
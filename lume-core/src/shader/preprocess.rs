@@ -0,0 +1,295 @@
+//! A small C-style preprocessor for WGSL. Lines starting with `#` are directives:
+//!
+//! - `#include "path"` splices in another file. Each file is included at most once, so
+//!   shared declarations need no include guards.
+//! - `#define NAME [value]` and `#undef NAME`. Defined names are replaced by their value
+//!   wherever they appear as a whole identifier.
+//! - `#ifdef NAME`, `#ifndef NAME`, `#else` and `#endif`.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use super::error::{ShaderError, ShaderErrorKind, SourceMap};
+
+/// A shader file returned by a `ShaderResolver`.
+#[derive(Clone, Debug)]
+pub struct ShaderFile {
+    /// Name used for include-once tracking and in error messages.
+    pub name: String,
+    pub source: String,
+}
+
+/// Looks up the files named by `#include`, e.g. on disk or among `include_str!` sources.
+pub trait ShaderResolver {
+    /// `includer` is the name of the file containing the `#include`, or `None` for the root file.
+    fn resolve(&self, path: &str, includer: Option<&str>) -> Result<ShaderFile, String>;
+}
+
+/// Resolves paths relative to the including file first, then relative to `root`.
+pub struct FileResolver {
+    pub root: PathBuf,
+}
+
+impl FileResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ShaderResolver for FileResolver {
+    fn resolve(&self, path: &str, includer: Option<&str>) -> Result<ShaderFile, String> {
+        let relative = includer.and_then(|i| Path::new(i).parent()).map(|dir| dir.join(path));
+        let full = relative.filter(|p| p.is_file()).unwrap_or_else(|| self.root.join(path));
+        let source = std::fs::read_to_string(&full).map_err(|e| format!("Failed to read {}: {}", full.display(), e))?;
+        Ok(ShaderFile { name: full.display().to_string(), source })
+    }
+}
+
+/// Resolves paths among in-memory sources, relative to the including file first.
+#[derive(Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let mut resolver = Self::default();
+        for &(name, source) in files {
+            resolver.add(name, source);
+        }
+        resolver
+    }
+
+    pub fn add(&mut self, name: &str, source: &str) {
+        self.files.insert(name.to_string(), source.to_string());
+    }
+}
+
+impl ShaderResolver for MemoryResolver {
+    fn resolve(&self, path: &str, includer: Option<&str>) -> Result<ShaderFile, String> {
+        let relative = includer.and_then(|i| i.rsplit_once('/')).map(|(dir, _)| format!("{}/{}", dir, path));
+        let name = relative.filter(|name| self.files.contains_key(name)).unwrap_or_else(|| path.to_string());
+        let source = self.files.get(&name).ok_or_else(|| format!("Shader file '{}' not found", path))?;
+        Ok(ShaderFile { name, source: source.clone() })
+    }
+}
+
+/// Preprocessor output, with the original file and line of every output line.
+#[derive(Clone, Debug)]
+pub struct Preprocessed {
+    pub source: String,
+    files: Vec<ShaderFile>,
+    /// Index into `files` and 1-based line for each output line.
+    lines: Vec<(usize, u32)>,
+}
+
+impl Preprocessed {
+    /// Every file that was included, the root file first.
+    pub fn files(&self) -> &[ShaderFile] {
+        &self.files
+    }
+
+    /// Original file and line of the 1-based output `line`.
+    pub fn location(&self, line: u32) -> Option<(&str, u32)> {
+        let &(file, line) = self.lines.get((line as usize).checked_sub(1)?)?;
+        Some((&self.files[file].name, line))
+    }
+
+    /// Maps a byte range of the output to an index into `files` and a range in that file.
+    /// Ranges are cut off at the end of their first line; columns shifted by define
+    /// substitution are not corrected.
+    pub fn map_range(&self, range: Range<usize>) -> Option<(usize, Range<usize>)> {
+        let start = range.start.min(self.source.len());
+        let output_line = self.source[..start].matches('\n').count();
+        let column = start - self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let &(file, line) = self.lines.get(output_line)?;
+
+        let source = &self.files[file].source;
+        let line_start: usize = source.split_inclusive('\n').take(line as usize - 1).map(str::len).sum();
+        let line_end = source[line_start..].find('\n').map_or(source.len(), |i| line_start + i);
+        let start = (line_start + column).min(line_end);
+        let end = (start + range.len()).min(line_end).max(start);
+        Some((file, start..end))
+    }
+}
+
+struct Conditional {
+    active: bool,
+    seen_else: bool,
+}
+
+struct Preprocessor<'r> {
+    resolver: &'r dyn ShaderResolver,
+    defines: HashMap<String, String>,
+    /// Files currently being processed, innermost last.
+    stack: Vec<String>,
+    output: Preprocessed,
+}
+
+/// Preprocess the file `path` and everything it includes, starting with `defines` defined.
+pub fn preprocess(path: &str, resolver: &dyn ShaderResolver, defines: &naga::FastHashMap<String, String>) -> Result<Preprocessed, ShaderError> {
+    let mut preprocessor = Preprocessor {
+        resolver,
+        defines: defines.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        stack: Vec::new(),
+        output: Preprocessed { source: String::new(), files: Vec::new(), lines: Vec::new() },
+    };
+    let file = resolver.resolve(path, None).map_err(|e| ShaderError::new(ShaderErrorKind::Preprocess, e))?;
+    preprocessor.process(file)?;
+    Ok(preprocessor.output)
+}
+
+impl Preprocessor<'_> {
+    fn process(&mut self, file: ShaderFile) -> Result<(), ShaderError> {
+        self.stack.push(file.name.clone());
+        self.output.files.push(file.clone());
+        let file_index = self.output.files.len() - 1;
+        let mut conditionals: Vec<Conditional> = Vec::new();
+        let mut line_start = 0;
+
+        for (i, line) in file.source.split_inclusive('\n').enumerate() {
+            let line_range = line_start..line_start + line.trim_end().len();
+            line_start += line.len();
+            let line = line.trim_end_matches(['\n', '\r']);
+            let line_number = i as u32 + 1;
+            let error = |message: String| {
+                SourceMap::single(&file.name, &file.source).error(ShaderErrorKind::Preprocess, message, vec![(line_range.clone(), String::new())], Vec::new())
+            };
+            let active = conditionals.iter().all(|c| c.active);
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    self.output.source.push_str(&self.substitute(line));
+                    self.output.source.push('\n');
+                    self.output.lines.push((file_index, line_number));
+                }
+                continue;
+            };
+            let (keyword, rest) = directive.trim().split_once(char::is_whitespace).unwrap_or((directive.trim(), ""));
+            let rest = rest.trim();
+
+            match keyword {
+                "ifdef" | "ifndef" => {
+                    let defined = self.defines.contains_key(rest);
+                    conditionals.push(Conditional { active: defined == (keyword == "ifdef"), seen_else: false });
+                }
+                "else" => {
+                    let conditional = conditionals.last_mut().ok_or_else(|| error("#else without #ifdef".to_string()))?;
+                    if conditional.seen_else {
+                        return Err(error("Duplicate #else".to_string()));
+                    }
+                    conditional.active = !conditional.active;
+                    conditional.seen_else = true;
+                }
+                "endif" => {
+                    conditionals.pop().ok_or_else(|| error("#endif without #ifdef".to_string()))?;
+                }
+                _ if !active => {}
+                "include" => {
+                    let path = rest.strip_prefix('"').and_then(|p| p.strip_suffix('"'))
+                        .ok_or_else(|| error(format!("Expected a quoted path after #include, found '{}'", rest)))?;
+                    let included = self.resolver.resolve(path, Some(&file.name)).map_err(error)?;
+                    if self.stack.contains(&included.name) {
+                        return Err(error(format!("'{}' includes itself", included.name)));
+                    }
+                    if !self.output.files.iter().any(|f| f.name == included.name) {
+                        self.process(included)?;
+                    }
+                }
+                "define" => {
+                    let (name, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    if name.is_empty() {
+                        return Err(error("Expected a name after #define".to_string()));
+                    }
+                    self.defines.insert(name.to_string(), value.trim().to_string());
+                }
+                "undef" => {
+                    self.defines.remove(rest);
+                }
+                _ => return Err(error(format!("Unknown directive '#{}'", keyword))),
+            }
+        }
+
+        if !conditionals.is_empty() {
+            let end = file.source.len();
+            return Err(SourceMap::single(&file.name, &file.source)
+                .error(ShaderErrorKind::Preprocess, "Unterminated #ifdef".to_string(), vec![(end..end, String::new())], Vec::new()));
+        }
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Replaces whole identifiers that are defined by their value.
+    fn substitute(&self, line: &str) -> String {
+        if self.defines.is_empty() {
+            return line.to_string();
+        }
+        let mut result = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            let (before, from_start) = rest.split_at(start);
+            result.push_str(before);
+            let end = from_start.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(from_start.len());
+            let (identifier, after) = from_start.split_at(end);
+            // Skip suffixes of numbers such as `1u` or `0x3F`.
+            let in_number = before.ends_with(|c: char| c.is_ascii_digit());
+            match self.defines.get(identifier) {
+                Some(value) if !in_number => result.push_str(value),
+                _ => result.push_str(identifier),
+            }
+            rest = after;
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(files: &[(&str, &str)], defines: &[(&str, &str)]) -> Result<Preprocessed, ShaderError> {
+        let defines = defines.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect();
+        preprocess("main.wgsl", &MemoryResolver::new(files), &defines)
+    }
+
+    #[test]
+    fn includes_each_file_once_and_maps_lines() {
+        let output = run(&[
+            ("main.wgsl", "#include \"lib/a.wgsl\"\n#include \"lib/b.wgsl\"\nfn main() {}"),
+            ("lib/a.wgsl", "#include \"common.wgsl\"\nfn a() {}"),
+            ("lib/b.wgsl", "#include \"common.wgsl\"\nfn b() {}"),
+            ("lib/common.wgsl", "struct Common { x: u32 }"),
+        ], &[]).unwrap();
+        assert_eq!(output.source, "struct Common { x: u32 }\nfn a() {}\nfn b() {}\nfn main() {}\n");
+        assert_eq!(output.location(1), Some(("lib/common.wgsl", 1)));
+        assert_eq!(output.location(3), Some(("lib/b.wgsl", 2)));
+        assert_eq!(output.location(4), Some(("main.wgsl", 3)));
+        assert_eq!(output.location(5), None);
+    }
+
+    #[test]
+    fn applies_defines_and_conditionals() {
+        let output = run(&[(
+            "main.wgsl",
+            "#define SIZE 64u\n#ifdef FAST\nconst mode = 1u;\n#else\nconst mode = 2u;\n#endif\n#ifndef FAST\nconst size = SIZE + 1u;\n#endif",
+        )], &[("FAST", "")]).unwrap();
+        assert_eq!(output.source, "const mode = 1u;\n");
+
+        let output = run(&[("main.wgsl", "#define N 4\nvar<private> a: array<f32, N>; const SIZE_N = 1u + N;")], &[]).unwrap();
+        assert_eq!(output.source, "var<private> a: array<f32, 4>; const SIZE_N = 1u + 4;\n");
+    }
+
+    #[test]
+    fn reports_errors_at_the_directive() {
+        let cycle = run(&[("main.wgsl", "#include \"a.wgsl\""), ("a.wgsl", "\n#include \"main.wgsl\"")], &[]).unwrap_err();
+        assert_eq!((cycle.file.as_deref(), cycle.line, cycle.column), (Some("a.wgsl"), Some(2), Some(1)));
+        assert_eq!(cycle.message, "'main.wgsl' includes itself");
+        assert_eq!(cycle.span, Some(1..21));
+        let missing = run(&[("main.wgsl", "#include \"missing.wgsl\"")], &[]).unwrap_err();
+        assert_eq!(missing.message, "Shader file 'missing.wgsl' not found");
+        assert!(missing.to_string().contains("#include \"missing.wgsl\""), "{}", missing);
+        assert!(run(&[("main.wgsl", "#ifdef A\n")], &[]).unwrap_err().message.contains("Unterminated"));
+        assert!(run(&[("main.wgsl", "#pragma once")], &[]).unwrap_err().message.contains("Unknown directive"));
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use notify::{EventKind, RecursiveMode, Watcher};
+use crate::{Device, LumeError, LumeResult};
+use super::{compile_shader, preprocess, FileResolver, ShaderSource};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ShaderId(pub usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct GraphicsPipelineId(pub usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ComputePipelineId(pub usize);
+
+/// Outcome of reloading a shader whose files changed on disk.
+#[derive(Debug)]
+pub enum ReloadEvent {
+    /// The shader and every pipeline using it were rebuilt.
+    Reloaded(ShaderId),
+    /// Compiling the shader or rebuilding a pipeline failed; the previous objects stay in use.
+    Failed(ShaderId, LumeError),
+}
+
+type GraphicsBuildFn<'a, D> = Box<dyn Fn(&D, &<D as Device>::ShaderModule, &<D as Device>::ShaderModule) -> LumeResult<<D as Device>::GraphicsPipeline> + 'a>;
+type ComputeBuildFn<'a, D> = Box<dyn Fn(&D, &<D as Device>::ShaderModule) -> LumeResult<<D as Device>::ComputePipeline> + 'a>;
+
+type Rebuilt<D> = (Vec<(usize, <D as Device>::GraphicsPipeline)>, Vec<(usize, <D as Device>::ComputePipeline)>);
+
+struct WatchedSource {
+    path: String,
+    defines: naga::FastHashMap<String, String>,
+    resolver: FileResolver,
+    /// Every file the shader was preprocessed from at the last successful compile, canonicalized
+    /// to compare with the paths of watcher events.
+    files: Vec<PathBuf>,
+}
+
+/// Notifications from the OS for the directories holding watched files. Directories rather
+/// than the files, so editors that save by replacing a file are still seen.
+struct FileWatcher {
+    watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    directories: HashSet<PathBuf>,
+}
+
+struct WatchedShader<D: Device> {
+    source: WatchedSource,
+    module: D::ShaderModule,
+}
+
+struct GraphicsEntry<'a, D: Device> {
+    vertex: ShaderId,
+    fragment: ShaderId,
+    build: GraphicsBuildFn<'a, D>,
+    pipeline: D::GraphicsPipeline,
+}
+
+struct ComputeEntry<'a, D: Device> {
+    shader: ShaderId,
+    build: ComputeBuildFn<'a, D>,
+    pipeline: D::ComputePipeline,
+}
+
+/// WGSL shaders loaded from disk whose modules and pipelines are rebuilt when the files,
+/// including `#include`d ones, change.
+///
+/// Pipelines are registered with a callback that creates them from the current modules, so
+/// the callback captures whatever else the pipeline needs (layout, render pass, ...).
+/// The files are watched with the OS's change notifications, which queue up until `poll`, the
+/// only place objects are replaced.
+pub struct ShaderReloader<'a, D: Device> {
+    shaders: Vec<WatchedShader<D>>,
+    graphics: Vec<GraphicsEntry<'a, D>>,
+    compute: Vec<ComputeEntry<'a, D>>,
+    /// Started by the first `add_shader`.
+    watcher: Option<FileWatcher>,
+}
+
+impl<D: Device> Default for ShaderReloader<'_, D> {
+    fn default() -> Self {
+        Self { shaders: Vec::new(), graphics: Vec::new(), compute: Vec::new(), watcher: None }
+    }
+}
+
+impl<'a, D: Device> ShaderReloader<'a, D> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compiles `path`, resolved against `root`, and watches it and its includes.
+    pub fn add_shader(&mut self, device: &D, root: impl Into<PathBuf>, path: &str, defines: naga::FastHashMap<String, String>) -> LumeResult<ShaderId> {
+        let mut source = WatchedSource { path: path.to_string(), defines, resolver: FileResolver::new(root), files: Vec::new() };
+        let (spirv, files) = source.compile()?;
+        let watcher = match &mut self.watcher {
+            Some(watcher) => watcher,
+            None => self.watcher.insert(FileWatcher::new()?),
+        };
+        watcher.watch(&files)?;
+        source.files = files;
+        let module = device.create_shader_module(&spirv)?;
+        self.shaders.push(WatchedShader { source, module });
+        Ok(ShaderId(self.shaders.len() - 1))
+    }
+
+    pub fn module(&self, id: ShaderId) -> &D::ShaderModule {
+        &self.shaders[id.0].module
+    }
+
+    pub fn add_graphics_pipeline(
+        &mut self,
+        device: &D,
+        vertex: ShaderId,
+        fragment: ShaderId,
+        build: impl Fn(&D, &D::ShaderModule, &D::ShaderModule) -> LumeResult<D::GraphicsPipeline> + 'a,
+    ) -> LumeResult<GraphicsPipelineId> {
+        let pipeline = build(device, self.module(vertex), self.module(fragment))?;
+        self.graphics.push(GraphicsEntry { vertex, fragment, build: Box::new(build), pipeline });
+        Ok(GraphicsPipelineId(self.graphics.len() - 1))
+    }
+
+    pub fn add_compute_pipeline(
+        &mut self,
+        device: &D,
+        shader: ShaderId,
+        build: impl Fn(&D, &D::ShaderModule) -> LumeResult<D::ComputePipeline> + 'a,
+    ) -> LumeResult<ComputePipelineId> {
+        let pipeline = build(device, self.module(shader))?;
+        self.compute.push(ComputeEntry { shader, build: Box::new(build), pipeline });
+        Ok(ComputePipelineId(self.compute.len() - 1))
+    }
+
+    pub fn graphics_pipeline(&self, id: GraphicsPipelineId) -> &D::GraphicsPipeline {
+        &self.graphics[id.0].pipeline
+    }
+
+    pub fn compute_pipeline(&self, id: ComputePipelineId) -> &D::ComputePipeline {
+        &self.compute[id.0].pipeline
+    }
+
+    /// Rebuilds shaders whose files changed since the last poll, along with their pipelines. Call this at a frame boundary, outside of command recording: when anything
+    /// was rebuilt it waits for the device to go idle before dropping the old objects.
+    ///
+    /// Failures are logged and returned; the shader is retried once its files change again.
+    pub fn poll(&mut self, device: &D) -> Vec<ReloadEvent> {
+        let mut events = Vec::new();
+        let Some(watcher) = &self.watcher else { return events };
+        let changed = watcher.changed();
+        if changed.is_empty() {
+            return events;
+        }
+
+        let mut pending: BTreeMap<ShaderId, D::ShaderModule> = BTreeMap::new();
+        for id in (0..self.shaders.len()).map(ShaderId) {
+            let source = &mut self.shaders[id.0].source;
+            if !source.files.iter().any(|file| changed.contains(file)) {
+                continue;
+            }
+            let result = source.compile().and_then(|(spirv, files)| Ok((device.create_shader_module(&spirv)?, files)));
+            match result {
+                Ok((module, files)) => {
+                    source.files = files;
+                    pending.insert(id, module);
+                }
+                Err(e) => events.push(self.failed(id, e)),
+            }
+        }
+        // New includes are watched too; a failure only delays noticing their edits.
+        if let Some(watcher) = &mut self.watcher {
+            for id in pending.keys() {
+                if let Err(e) = watcher.watch(&self.shaders[id.0].source.files) {
+                    log::warn!("{}", e);
+                }
+            }
+        }
+
+        // A pipeline that fails to build rejects the new modules it uses, which changes what the
+        // other pipelines are built from, so start over until every remaining pipeline builds.
+        let (graphics, compute) = loop {
+            match self.build_pipelines(device, &pending) {
+                Ok(pipelines) => break pipelines,
+                Err((shaders, e)) => {
+                    for id in shaders {
+                        pending.remove(&id);
+                        events.push(self.failed(id, e.clone()));
+                    }
+                }
+            }
+        };
+
+        if pending.is_empty() {
+            return events;
+        }
+        if let Err(e) = device.wait_idle() {
+            log::error!("Failed to wait for the device before swapping shaders: {}", e);
+            return events;
+        }
+        for (id, module) in pending {
+            log::info!("Reloaded shader {}", self.shaders[id.0].source.path);
+            self.shaders[id.0].module = module;
+            events.push(ReloadEvent::Reloaded(id));
+        }
+        for (index, pipeline) in graphics {
+            self.graphics[index].pipeline = pipeline;
+        }
+        for (index, pipeline) in compute {
+            self.compute[index].pipeline = pipeline;
+        }
+        events
+    }
+
+    fn failed(&self, id: ShaderId, e: LumeError) -> ReloadEvent {
+        log::error!("Failed to reload shader {}:\n{}", self.shaders[id.0].source.path, e);
+        ReloadEvent::Failed(id, e)
+    }
+
+    /// Builds every pipeline using a shader in `pending`, or returns the pending shaders of the
+    /// first pipeline that fails.
+    fn build_pipelines(&self, device: &D, pending: &BTreeMap<ShaderId, D::ShaderModule>) -> Result<Rebuilt<D>, (Vec<ShaderId>, LumeError)> {
+        let module = |id: ShaderId| pending.get(&id).unwrap_or_else(|| self.module(id));
+        let changed = |ids: &[ShaderId]| -> Vec<ShaderId> { ids.iter().copied().filter(|id| pending.contains_key(id)).collect() };
+
+        let mut graphics = Vec::new();
+        for (index, entry) in self.graphics.iter().enumerate() {
+            let shaders = changed(&[entry.vertex, entry.fragment]);
+            if !shaders.is_empty() {
+                let pipeline = (entry.build)(device, module(entry.vertex), module(entry.fragment)).map_err(|e| (shaders, e))?;
+                graphics.push((index, pipeline));
+            }
+        }
+        let mut compute = Vec::new();
+        for (index, entry) in self.compute.iter().enumerate() {
+            let shaders = changed(&[entry.shader]);
+            if !shaders.is_empty() {
+                let pipeline = (entry.build)(device, module(entry.shader)).map_err(|e| (shaders, e))?;
+                compute.push((index, pipeline));
+            }
+        }
+        Ok((graphics, compute))
+    }
+}
+
+impl WatchedSource {
+    fn compile(&self) -> LumeResult<(Vec<u32>, Vec<PathBuf>)> {
+        let preprocessed = preprocess(&self.path, &self.resolver, &self.defines)?;
+        let files = preprocessed.files().iter().map(|file| canonical(Path::new(&file.name))).collect();
+        let spirv = compile_shader(ShaderSource::WgslFile { path: &self.path, resolver: &self.resolver, defines: self.defines.clone() })?;
+        Ok((spirv, files))
+    }
+}
+
+impl FileWatcher {
+    fn new() -> LumeResult<Self> {
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(sender)
+            .map_err(|e| LumeError::BackendError(format!("Failed to watch shader files: {}", e)))?;
+        Ok(Self { watcher, events, directories: HashSet::new() })
+    }
+
+    fn watch(&mut self, files: &[PathBuf]) -> LumeResult<()> {
+        for directory in files.iter().filter_map(|file| file.parent()) {
+            if self.directories.contains(directory) {
+                continue;
+            }
+            self.watcher.watch(directory, RecursiveMode::NonRecursive)
+                .map_err(|e| LumeError::BackendError(format!("Failed to watch {}: {}", directory.display(), e)))?;
+            self.directories.insert(directory.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Paths created, written or removed since the last call.
+    fn changed(&self) -> HashSet<PathBuf> {
+        let mut changed = HashSet::new();
+        for event in self.events.try_iter() {
+            match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => changed.extend(event.paths),
+                Ok(_) => {}
+                Err(e) => log::warn!("Shader file watcher error: {}", e),
+            }
+        }
+        changed
+    }
+}
+
+/// `path` as watcher events name it, or unchanged if it does not exist.
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(all(test, feature = "recording"))]
+mod tests {
+    use super::*;
+    use crate::device::PipelineLayoutDescriptor;
+    use crate::recording::{RecordingDevice, ResourceDescriptor};
+    use std::cell::Cell;
+    use std::time::{Duration, Instant};
+
+    /// Polls until the watcher reports the last edit, giving it time to deliver every event
+    /// of the write first.
+    fn poll_edit(reloader: &mut ShaderReloader<RecordingDevice>, device: &RecordingDevice) -> Vec<ReloadEvent> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            std::thread::sleep(Duration::from_millis(100));
+            let events = reloader.poll(device);
+            if !events.is_empty() || Instant::now() > deadline {
+                return events;
+            }
+        }
+    }
+
+    fn shader_code(device: &RecordingDevice, reloader: &ShaderReloader<RecordingDevice>, id: ShaderId) -> Vec<u32> {
+        match device.descriptor(reloader.module(id).id) {
+            Some(ResourceDescriptor::ShaderModule { code }) => code,
+            other => panic!("Expected a shader module, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reloads_on_include_change_and_keeps_pipeline_on_failure() {
+        let dir = std::env::temp_dir().join(format!("lume-shader-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let common = dir.join("common.wgsl");
+        std::fs::write(&common, "const SIZE: u32 = 64u;").unwrap();
+        std::fs::write(dir.join("main.wgsl"), "#include \"common.wgsl\"\n@compute @workgroup_size(SIZE) fn main() {}").unwrap();
+
+        let device = RecordingDevice::new();
+        let layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[], push_constant_ranges: &[] }).unwrap();
+        let reject = Cell::new(false);
+        let mut reloader = ShaderReloader::new();
+        let shader = reloader.add_shader(&device, &dir, "main.wgsl", Default::default()).unwrap();
+        let pipeline = reloader.add_compute_pipeline(&device, shader, |device, module| {
+            if reject.get() {
+                return Err(LumeError::PipelineCreationFailed("rejected".to_string()));
+            }
+            device.create_compute_pipeline(crate::device::ComputePipelineDescriptor { shader: module, layout: &layout })
+        }).unwrap();
+        assert!(reloader.poll(&device).is_empty());
+
+        let (code, first) = (shader_code(&device, &reloader, shader), reloader.compute_pipeline(pipeline).id);
+        std::fs::write(&common, "const SIZE: u32 = 32u;").unwrap();
+        assert!(matches!(poll_edit(&mut reloader, &device)[..], [ReloadEvent::Reloaded(id)] if id == shader));
+        assert_ne!(shader_code(&device, &reloader, shader), code);
+        let second = reloader.compute_pipeline(pipeline).id;
+        assert_ne!(second, first);
+
+        std::fs::write(&common, "const SIZE: u32 = ;").unwrap();
+        let events = poll_edit(&mut reloader, &device);
+        assert!(matches!(&events[..], [ReloadEvent::Failed(_, LumeError::ShaderCompilationFailed(_))]));
+        assert!(reloader.poll(&device).is_empty(), "An unchanged broken shader is not recompiled");
+        assert_eq!(reloader.compute_pipeline(pipeline).id, second);
+
+        reject.set(true);
+        std::fs::write(&common, "const SIZE: u32 = 16u;").unwrap();
+        assert!(matches!(&poll_edit(&mut reloader, &device)[..], [ReloadEvent::Failed(_, LumeError::PipelineCreationFailed(_))]));
+        assert_eq!(reloader.compute_pipeline(pipeline).id, second);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
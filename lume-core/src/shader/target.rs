@@ -0,0 +1,157 @@
+use naga::back::{glsl, hlsl, msl};
+use crate::device::BindingType;
+use super::{parse_and_validate, write_spirv, ShaderError, ShaderErrorKind, ShaderReflection, ShaderSource};
+
+/// Output language of `compile_shader_to`, with the options that affect it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ShaderTarget {
+    SpirV,
+    /// Metal Shading Language `(major, minor)`. Every entry point gets slots in `(group,
+    /// binding)` order, counted separately for buffers, textures and samplers, followed by
+    /// the buffer holding the sizes of runtime-sized arrays.
+    Msl { version: (u8, u8) },
+    /// GLSL ES, e.g. 310 for compute and storage buffers. A GLSL shader holds one entry
+    /// point, named `main` in the output. `binding`s are numbered in `(group, binding)` order.
+    GlslEs { version: u16, entry_point: String },
+    /// HLSL shader model `(major, minor)`, 5.0 to 6.7. `@group(g) @binding(b)` becomes
+    /// register `b` in space `g`.
+    Hlsl { shader_model: (u8, u8) },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ShaderOutput {
+    SpirV(Vec<u32>),
+    Source(String),
+}
+
+impl ShaderOutput {
+    pub fn spirv(&self) -> Option<&[u32]> {
+        match self {
+            ShaderOutput::SpirV(spirv) => Some(spirv),
+            ShaderOutput::Source(_) => None,
+        }
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            ShaderOutput::Source(source) => Some(source),
+            ShaderOutput::SpirV(_) => None,
+        }
+    }
+}
+
+/// Like `compile_shader`, but for any `ShaderTarget`.
+pub fn compile_shader_to(source: ShaderSource, target: &ShaderTarget) -> Result<ShaderOutput, ShaderError> {
+    if *target == ShaderTarget::SpirV {
+        return super::compile_shader(source).map(ShaderOutput::SpirV);
+    }
+    let (module, info) = parse_and_validate(source)?;
+    let backend_error = |language: &str, e: &dyn std::fmt::Display| ShaderError::new(ShaderErrorKind::Backend, format!("{} write error: {}", language, e));
+    let bindings = ShaderReflection::from_module(&module, &info).bindings;
+
+    let source = match target {
+        ShaderTarget::SpirV => return write_spirv(&module, &info).map(ShaderOutput::SpirV),
+        ShaderTarget::Msl { version } => {
+            let mut resources = msl::EntryPointResources::default();
+            let (mut buffers, mut textures, mut samplers) = (0, 0, 0);
+            for binding in &bindings {
+                let bind_target = match binding.ty {
+                    BindingType::UniformBuffer | BindingType::StorageBuffer => {
+                        buffers += 1;
+                        msl::BindTarget { buffer: Some(buffers - 1), ..Default::default() }
+                    }
+                    BindingType::SampledTexture | BindingType::StorageTexture => {
+                        textures += 1;
+                        msl::BindTarget { texture: Some(textures - 1), ..Default::default() }
+                    }
+                    BindingType::Sampler => {
+                        samplers += 1;
+                        msl::BindTarget { sampler: Some(msl::BindSamplerTarget::Resource(samplers - 1)), ..Default::default() }
+                    }
+                };
+                resources.resources.insert(naga::ResourceBinding { group: binding.group, binding: binding.binding }, bind_target);
+            }
+            resources.sizes_buffer = Some(buffers);
+            let options = msl::Options {
+                lang_version: *version,
+                per_entry_point_map: module.entry_points.iter().map(|ep| (ep.name.clone(), resources.clone())).collect(),
+                ..Default::default()
+            };
+            let (source, _) = msl::write_string(&module, &info, &options, &msl::PipelineOptions::default())
+                .map_err(|e| backend_error("MSL", &e))?;
+            source
+        }
+        ShaderTarget::GlslEs { version, entry_point } => {
+            let stage = module.entry_points.iter().find(|ep| ep.name == *entry_point).map(|ep| ep.stage)
+                .ok_or_else(|| ShaderError::new(ShaderErrorKind::Backend, format!("No entry point '{}'", entry_point)))?;
+            let options = glsl::Options {
+                version: glsl::Version::new_gles(*version),
+                binding_map: bindings.iter().enumerate()
+                    .map(|(i, b)| (naga::ResourceBinding { group: b.group, binding: b.binding }, i as u8))
+                    .collect(),
+                ..Default::default()
+            };
+            let pipeline_options = glsl::PipelineOptions { shader_stage: stage, entry_point: entry_point.clone(), multiview: None };
+            let mut source = String::new();
+            glsl::Writer::new(&mut source, &module, &info, &options, &pipeline_options, naga::proc::BoundsCheckPolicies::default())
+                .and_then(|mut writer| writer.write())
+                .map_err(|e| backend_error("GLSL", &e))?;
+            source
+        }
+        ShaderTarget::Hlsl { shader_model } => {
+            let shader_model = match shader_model {
+                (5, 0) => hlsl::ShaderModel::V5_0,
+                (5, 1) => hlsl::ShaderModel::V5_1,
+                (6, 0) => hlsl::ShaderModel::V6_0,
+                (6, 1) => hlsl::ShaderModel::V6_1,
+                (6, 2) => hlsl::ShaderModel::V6_2,
+                (6, 3) => hlsl::ShaderModel::V6_3,
+                (6, 4) => hlsl::ShaderModel::V6_4,
+                (6, 5) => hlsl::ShaderModel::V6_5,
+                (6, 6) => hlsl::ShaderModel::V6_6,
+                (6, 7) => hlsl::ShaderModel::V6_7,
+                (major, minor) => return Err(ShaderError::new(ShaderErrorKind::Backend, format!("Unsupported HLSL shader model {}.{}", major, minor))),
+            };
+            let options = hlsl::Options { shader_model, ..Default::default() };
+            let mut source = String::new();
+            hlsl::Writer::new(&mut source, &options).write(&module, &info, None)
+                .map_err(|e| backend_error("HLSL", &e))?;
+            source
+        }
+    };
+    Ok(ShaderOutput::Source(source))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADER: &str = r#"
+        @group(0) @binding(0) var<storage, read_write> data: array<u32>;
+        @group(1) @binding(0) var<uniform> scale: u32;
+        @compute @workgroup_size(64) fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            if (id.x < arrayLength(&data)) { data[id.x] *= scale; }
+        }
+    "#;
+
+    #[test]
+    fn writes_every_target() {
+        let spirv = compile_shader_to(ShaderSource::Wgsl(SHADER), &ShaderTarget::SpirV).unwrap();
+        assert_eq!(spirv.spirv().unwrap()[0], super::super::SPIRV_MAGIC);
+
+        let msl = compile_shader_to(ShaderSource::Wgsl(SHADER), &ShaderTarget::Msl { version: (2, 1) }).unwrap();
+        let msl = msl.source().unwrap();
+        assert!(msl.contains("[[buffer(0)]]") && msl.contains("[[buffer(1)]]") && msl.contains("[[buffer(2)]]"), "{}", msl);
+
+        let glsl = compile_shader_to(ShaderSource::Wgsl(SHADER), &ShaderTarget::GlslEs { version: 310, entry_point: "main".to_string() }).unwrap();
+        let glsl = glsl.source().unwrap();
+        assert!(glsl.starts_with("#version 310 es"), "{}", glsl);
+        assert!(glsl.contains("layout(std430, binding = 0)") && glsl.contains("layout(std140, binding = 1)"), "{}", glsl);
+
+        let hlsl = compile_shader_to(ShaderSource::Wgsl(SHADER), &ShaderTarget::Hlsl { shader_model: (5, 1) }).unwrap();
+        assert!(hlsl.source().unwrap().contains("register(b0, space1)"), "{}", hlsl.source().unwrap());
+
+        assert!(compile_shader_to(ShaderSource::Wgsl(SHADER), &ShaderTarget::GlslEs { version: 310, entry_point: "cs".to_string() }).is_err());
+        assert!(compile_shader_to(ShaderSource::Wgsl(SHADER), &ShaderTarget::Hlsl { shader_model: (4, 0) }).is_err());
+    }
+}
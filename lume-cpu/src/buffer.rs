@@ -0,0 +1,41 @@
+use lume_core::device::BufferUsage;
+use lume_core::{LumeError, LumeResult};
+use std::sync::{Arc, Mutex};
+
+pub struct CpuBufferInner {
+    pub data: Mutex<Vec<u8>>,
+    pub size: u64,
+    pub usage: BufferUsage,
+}
+
+/// A buffer in host memory. Every buffer is mappable, regardless of `mapped_at_creation`.
+#[derive(Clone)]
+pub struct CpuBuffer {
+    pub inner: Arc<CpuBufferInner>,
+}
+
+impl CpuBuffer {
+    fn range(&self, offset: u64, len: usize) -> LumeResult<std::ops::Range<usize>> {
+        let end = offset.checked_add(len as u64).filter(|&end| end <= self.inner.size).ok_or_else(|| {
+            LumeError::BackendError(format!(
+                "Buffer access of {} bytes at offset {} exceeds buffer size {}",
+                len, offset, self.inner.size
+            ))
+        })?;
+        Ok(offset as usize..end as usize)
+    }
+}
+
+impl lume_core::device::Buffer for CpuBuffer {
+    fn write_data(&self, offset: u64, data: &[u8]) -> LumeResult<()> {
+        let range = self.range(offset, data.len())?;
+        self.inner.data.lock().unwrap()[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn read_data(&self, offset: u64, data: &mut [u8]) -> LumeResult<()> {
+        let range = self.range(offset, data.len())?;
+        data.copy_from_slice(&self.inner.data.lock().unwrap()[range]);
+        Ok(())
+    }
+}
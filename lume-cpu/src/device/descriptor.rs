@@ -0,0 +1,51 @@
+use lume_core::device::BindingType;
+use lume_core::{LumeError, LumeResult};
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::{CpuBuffer, CpuDevice, CpuTextureView};
+
+pub struct CpuBindGroupLayout {
+    pub entries: HashMap<u32, BindingType>,
+}
+
+impl lume_core::device::BindGroupLayout for CpuBindGroupLayout {}
+
+pub(crate) enum CpuBindingResource {
+    Buffer(CpuBuffer),
+    TextureView(CpuTextureView),
+    Sampler,
+}
+
+#[derive(Clone)]
+pub struct CpuBindGroup {
+    pub(crate) entries: Arc<HashMap<u32, CpuBindingResource>>,
+}
+
+impl lume_core::device::BindGroup for CpuBindGroup {}
+
+impl CpuDevice {
+    pub fn create_bind_group_layout_impl(&self, descriptor: lume_core::device::BindGroupLayoutDescriptor) -> LumeResult<CpuBindGroupLayout> {
+        Ok(CpuBindGroupLayout {
+            entries: descriptor.entries.iter().map(|e| (e.binding, e.ty)).collect(),
+        })
+    }
+
+    pub fn create_bind_group_impl(&self, descriptor: lume_core::device::BindGroupDescriptor<Self>) -> LumeResult<CpuBindGroup> {
+        let mut entries = HashMap::new();
+        for entry in descriptor.entries {
+            if !descriptor.layout.entries.contains_key(&entry.binding) {
+                return Err(LumeError::ResourceCreationFailed(format!(
+                    "Binding {} is not declared in the bind group layout",
+                    entry.binding
+                )));
+            }
+            let resource = match entry.resource {
+                lume_core::device::BindingResource::Buffer(buffer) => CpuBindingResource::Buffer(buffer.clone()),
+                lume_core::device::BindingResource::TextureView(view) => CpuBindingResource::TextureView(view.clone()),
+                lume_core::device::BindingResource::Sampler(_) => CpuBindingResource::Sampler,
+            };
+            entries.insert(entry.binding, resource);
+        }
+        Ok(CpuBindGroup { entries: Arc::new(entries) })
+    }
+}
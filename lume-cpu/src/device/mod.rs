@@ -0,0 +1,45 @@
+use std::sync::{Arc, Mutex};
+
+pub struct CpuDeviceInner {
+    // Frame-in-flight bookkeeping, kept so frame indices match the GPU backends.
+    pub current_frame: Mutex<usize>,
+    pub frames_in_flight: usize,
+}
+
+#[derive(Clone)]
+pub struct CpuDevice {
+    pub inner: Arc<CpuDeviceInner>,
+}
+
+impl std::ops::Deref for CpuDevice {
+    type Target = CpuDeviceInner;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl CpuDevice {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(CpuDeviceInner {
+                current_frame: Mutex::new(0),
+                frames_in_flight: 2,
+            }),
+        }
+    }
+}
+
+impl Default for CpuDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Submodules for implementation
+pub(crate) mod resource;
+mod pipeline;
+mod descriptor;
+mod queue;
+
+pub use descriptor::{CpuBindGroup, CpuBindGroupLayout};
+pub(crate) use descriptor::CpuBindingResource;
@@ -0,0 +1,54 @@
+use lume_core::{LumeError, LumeResult};
+use std::sync::Arc;
+use crate::interp::Program;
+use crate::CpuDevice;
+
+impl CpuDevice {
+    pub fn create_shader_module_impl(&self, code: &[u32]) -> LumeResult<crate::CpuShaderModule> {
+        let options = naga::front::spv::Options {
+            adjust_coordinate_space: false,
+            strict_capabilities: false,
+            block_ctx_dump_prefix: None,
+        };
+        let module = naga::front::spv::Frontend::new(code.iter().cloned(), &options)
+            .parse()
+            .map_err(|e| LumeError::ResourceCreationFailed(format!("Failed to parse SPIR-V: {}", e)))?;
+
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+            .map_err(|e| LumeError::ResourceCreationFailed(format!("Failed to validate shader module: {}", e.into_inner())))?;
+
+        Ok(crate::CpuShaderModule { module: Arc::new(module) })
+    }
+
+    pub fn create_render_pass_impl(&self, descriptor: lume_core::device::RenderPassDescriptor) -> LumeResult<crate::CpuRenderPass> {
+        Ok(crate::CpuRenderPass {
+            color_format: descriptor.color_format,
+            depth_stencil_format: descriptor.depth_stencil_format,
+            load_op: descriptor.load_op,
+        })
+    }
+
+    pub fn create_pipeline_layout_impl(&self, descriptor: lume_core::device::PipelineLayoutDescriptor<Self>) -> LumeResult<crate::CpuPipelineLayout> {
+        Ok(crate::CpuPipelineLayout {
+            bind_group_count: descriptor.bind_group_layouts.len(),
+        })
+    }
+
+    pub fn create_graphics_pipeline_impl(&self, _descriptor: lume_core::device::GraphicsPipelineDescriptor<Self>) -> LumeResult<crate::CpuGraphicsPipeline> {
+        Ok(crate::CpuGraphicsPipeline)
+    }
+
+    pub fn create_compute_pipeline_impl(&self, descriptor: lume_core::device::ComputePipelineDescriptor<Self>) -> LumeResult<crate::CpuComputePipeline> {
+        let program = Program::new((*descriptor.shader.module).clone())?;
+        Ok(crate::CpuComputePipeline { program: Arc::new(program) })
+    }
+
+    pub fn create_framebuffer_impl(&self, descriptor: lume_core::device::FramebufferDescriptor<Self>) -> LumeResult<crate::CpuFramebuffer> {
+        Ok(crate::CpuFramebuffer {
+            attachments: descriptor.attachments.iter().map(|&view| view.clone()).collect(),
+            width: descriptor.width,
+            height: descriptor.height,
+        })
+    }
+}
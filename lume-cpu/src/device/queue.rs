@@ -0,0 +1,197 @@
+use lume_core::{LumeError, LumeResult};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::CpuDevice;
+
+impl CpuDevice {
+    fn execute(&self, command_buffers: &[&crate::CpuCommandBuffer]) -> LumeResult<()> {
+        for command_buffer in command_buffers {
+            command_buffer
+                .execute()
+                .map_err(|e| LumeError::SubmissionFailed(format!("Failed to execute command buffer: {}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+impl lume_core::Device for CpuDevice {
+    type CommandBuffer = crate::CpuCommandBuffer;
+    type CommandPool = crate::CpuCommandPool;
+    type Swapchain = crate::CpuSwapchain;
+    type ShaderModule = crate::CpuShaderModule;
+    type RenderPass = crate::CpuRenderPass;
+    type PipelineLayout = crate::CpuPipelineLayout;
+    type GraphicsPipeline = crate::CpuGraphicsPipeline;
+    type ComputePipeline = crate::CpuComputePipeline;
+    type Semaphore = crate::CpuSemaphore;
+    type TimelineSemaphore = crate::CpuTimelineSemaphore;
+    type Framebuffer = crate::CpuFramebuffer;
+    type TextureView = crate::CpuTextureView;
+    type Texture = crate::CpuTexture;
+    type Sampler = crate::CpuSampler;
+    type Buffer = crate::CpuBuffer;
+    type BindGroupLayout = crate::CpuBindGroupLayout;
+    type BindGroup = crate::CpuBindGroup;
+    type Fence = crate::CpuFence;
+
+    /// Submissions run to completion before `submit` returns, so the device is always idle.
+    fn wait_idle(&self) -> LumeResult<()> {
+        Ok(())
+    }
+
+    fn create_semaphore(&self) -> LumeResult<Self::Semaphore> {
+        Ok(crate::CpuSemaphore)
+    }
+
+    fn create_timeline_semaphore(&self, initial_value: u64) -> LumeResult<Self::TimelineSemaphore> {
+        Ok(crate::CpuTimelineSemaphore::new(initial_value))
+    }
+
+    fn create_fence(&self, signaled: bool) -> LumeResult<Self::Fence> {
+        Ok(crate::CpuFence {
+            signaled: Arc::new(AtomicBool::new(signaled)),
+        })
+    }
+
+    fn wait_for_fences(&self, fences: &[&Self::Fence], wait_all: bool, _timeout: u64) -> LumeResult<()> {
+        // Nothing is ever pending, so an unsignaled fence would never be signaled.
+        let ready = if wait_all {
+            fences.iter().all(|f| f.is_signaled())
+        } else {
+            fences.iter().any(|f| f.is_signaled())
+        };
+        if ready {
+            Ok(())
+        } else {
+            Err(LumeError::BackendError("Wait for fences failed: fence was never submitted".to_string()))
+        }
+    }
+
+    fn reset_fences(&self, fences: &[&Self::Fence]) -> LumeResult<()> {
+        for fence in fences {
+            fence.signaled.store(false, Ordering::Release);
+        }
+        Ok(())
+    }
+
+    fn create_command_pool(&self, _queue: lume_core::device::QueueType) -> LumeResult<Self::CommandPool> {
+        Ok(crate::CpuCommandPool { device: self.clone() })
+    }
+
+    fn submit(
+        &self,
+        _queue: lume_core::device::QueueType,
+        command_buffers: &[&Self::CommandBuffer],
+        _wait_semaphores: &[&Self::Semaphore],
+        _signal_semaphores: &[&Self::Semaphore],
+        timeline_waits: &[(&Self::TimelineSemaphore, u64)],
+        timeline_signals: &[(&Self::TimelineSemaphore, u64)],
+        fence: Option<&Self::Fence>,
+    ) -> LumeResult<()> {
+        // Work runs immediately, so every awaited value must already have been reached.
+        for (semaphore, value) in timeline_waits {
+            if semaphore.value() < *value {
+                return Err(LumeError::SubmissionFailed(format!(
+                    "Submission waits for timeline value {} but the semaphore is at {}",
+                    value,
+                    semaphore.value()
+                )));
+            }
+        }
+        self.execute(command_buffers)?;
+        for (semaphore, value) in timeline_signals {
+            lume_core::device::TimelineSemaphore::signal(*semaphore, *value)?;
+        }
+        if let Some(fence) = fence {
+            fence.signaled.store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+
+    fn create_buffer(&self, descriptor: lume_core::device::BufferDescriptor) -> LumeResult<Self::Buffer> {
+        self.create_buffer_impl(descriptor)
+    }
+
+    fn create_texture(&self, descriptor: lume_core::device::TextureDescriptor) -> LumeResult<Self::Texture> {
+        self.create_texture_impl(descriptor)
+    }
+
+    fn create_texture_view(&self, texture: &Self::Texture, descriptor: lume_core::device::TextureViewDescriptor) -> LumeResult<Self::TextureView> {
+        self.create_texture_view_impl(texture, descriptor)
+    }
+
+    fn create_sampler(&self, descriptor: lume_core::device::SamplerDescriptor) -> LumeResult<Self::Sampler> {
+        self.create_sampler_impl(descriptor)
+    }
+
+    fn create_offscreen_swapchain(&self, descriptor: lume_core::device::OffscreenSwapchainDescriptor) -> LumeResult<Self::Swapchain> {
+        self.create_offscreen_swapchain_impl(descriptor)
+    }
+
+    fn create_shader_module(&self, code: &[u32]) -> LumeResult<Self::ShaderModule> {
+        self.create_shader_module_impl(code)
+    }
+
+    fn create_render_pass(&self, descriptor: lume_core::device::RenderPassDescriptor) -> LumeResult<Self::RenderPass> {
+        self.create_render_pass_impl(descriptor)
+    }
+
+    fn create_pipeline_layout(&self, descriptor: lume_core::device::PipelineLayoutDescriptor<Self>) -> LumeResult<Self::PipelineLayout> {
+        self.create_pipeline_layout_impl(descriptor)
+    }
+
+    fn create_graphics_pipeline(&self, descriptor: lume_core::device::GraphicsPipelineDescriptor<Self>) -> LumeResult<Self::GraphicsPipeline> {
+        self.create_graphics_pipeline_impl(descriptor)
+    }
+
+    fn create_compute_pipeline(&self, descriptor: lume_core::device::ComputePipelineDescriptor<Self>) -> LumeResult<Self::ComputePipeline> {
+        self.create_compute_pipeline_impl(descriptor)
+    }
+
+    fn create_framebuffer(&self, descriptor: lume_core::device::FramebufferDescriptor<Self>) -> LumeResult<Self::Framebuffer> {
+        self.create_framebuffer_impl(descriptor)
+    }
+
+    fn create_bind_group_layout(&self, descriptor: lume_core::device::BindGroupLayoutDescriptor) -> LumeResult<Self::BindGroupLayout> {
+        self.create_bind_group_layout_impl(descriptor)
+    }
+
+    fn create_bind_group(&self, descriptor: lume_core::device::BindGroupDescriptor<Self>) -> LumeResult<Self::BindGroup> {
+        self.create_bind_group_impl(descriptor)
+    }
+
+    fn create_swapchain(&self, surface: &impl lume_core::instance::Surface, descriptor: lume_core::device::SwapchainDescriptor) -> LumeResult<Self::Swapchain> {
+        self.create_swapchain_impl(surface, descriptor)
+    }
+
+    fn begin_frame(&self, swapchain: &mut Self::Swapchain) -> LumeResult<lume_core::device::FrameToken> {
+        let frame_index = *self.inner.current_frame.lock().unwrap();
+        let image_index = lume_core::device::Swapchain::acquire_next_image(swapchain, &crate::CpuSemaphore)?;
+
+        Ok(lume_core::device::FrameToken {
+            frame_index,
+            image_index,
+        })
+    }
+
+    fn end_frame(&self, swapchain: &mut Self::Swapchain, token: lume_core::device::FrameToken, command_buffers: &[&Self::CommandBuffer]) -> LumeResult<()> {
+        self.execute(command_buffers)?;
+        lume_core::device::Swapchain::present(swapchain, token.image_index, &[&crate::CpuSemaphore])?;
+
+        let mut current_frame = self.inner.current_frame.lock().unwrap();
+        *current_frame = (*current_frame + 1) % self.inner.frames_in_flight;
+        Ok(())
+    }
+
+    fn read_texture(&self, texture: &Self::Texture) -> LumeResult<Vec<u8>> {
+        let range = texture.inner.mip_range(0, 1);
+        Ok(texture.inner.data.lock().unwrap()[range].to_vec())
+    }
+
+    fn read_presented_image(&self, swapchain: &Self::Swapchain) -> LumeResult<image::RgbaImage> {
+        let index = swapchain.presented_image.ok_or(LumeError::Generic("No image has been presented yet"))?;
+        let texture = &swapchain.textures[index as usize].inner;
+        let data = texture.data.lock().unwrap().clone();
+        lume_core::device::texels_to_rgba_image(texture.format, texture.width, texture.height, data)
+    }
+}
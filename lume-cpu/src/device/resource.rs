@@ -0,0 +1,89 @@
+use lume_core::{LumeError, LumeResult};
+use std::sync::{Arc, Mutex};
+use crate::CpuDevice;
+
+impl CpuDevice {
+    pub fn create_buffer_impl(&self, descriptor: lume_core::device::BufferDescriptor) -> LumeResult<crate::CpuBuffer> {
+        Ok(crate::CpuBuffer {
+            inner: Arc::new(crate::buffer::CpuBufferInner {
+                data: Mutex::new(vec![0; descriptor.size as usize]),
+                size: descriptor.size,
+                usage: descriptor.usage,
+            }),
+        })
+    }
+
+    pub fn create_texture_impl(&self, descriptor: lume_core::device::TextureDescriptor) -> LumeResult<crate::CpuTexture> {
+        let mut inner = crate::texture::CpuTextureInner {
+            width: descriptor.width,
+            height: descriptor.height,
+            mip_level_count: descriptor.mip_level_count.max(1),
+            format: descriptor.format,
+            usage: descriptor.usage,
+            data: Mutex::new(Vec::new()),
+        };
+        let size = inner.mip_range(0, inner.mip_level_count).end;
+        *inner.data.get_mut().unwrap() = vec![0; size];
+        Ok(crate::CpuTexture { inner: Arc::new(inner) })
+    }
+
+    pub fn create_sampler_impl(&self, descriptor: lume_core::device::SamplerDescriptor) -> LumeResult<crate::CpuSampler> {
+        Ok(crate::CpuSampler { descriptor })
+    }
+
+    pub fn create_texture_view_impl(&self, texture: &crate::CpuTexture, descriptor: lume_core::device::TextureViewDescriptor) -> LumeResult<crate::CpuTextureView> {
+        let levels = texture.inner.mip_level_count;
+        if descriptor.base_mip_level >= levels {
+            return Err(LumeError::ResourceCreationFailed(format!("Mip level {} of a texture with {} levels", descriptor.base_mip_level, levels)));
+        }
+        let mip_level_count = descriptor.mip_level_count.unwrap_or(levels - descriptor.base_mip_level);
+        if mip_level_count == 0 || descriptor.base_mip_level + mip_level_count > levels {
+            return Err(LumeError::ResourceCreationFailed(format!(
+                "Mip levels {}..{} of a texture with {} levels", descriptor.base_mip_level, descriptor.base_mip_level + mip_level_count, levels
+            )));
+        }
+        Ok(crate::CpuTextureView {
+            texture: texture.inner.clone(),
+            format: descriptor.format.unwrap_or(texture.inner.format),
+            base_mip_level: descriptor.base_mip_level,
+            mip_level_count,
+        })
+    }
+
+    pub fn create_swapchain_impl(
+        &self,
+        _surface: &impl lume_core::instance::Surface,
+        descriptor: lume_core::device::SwapchainDescriptor,
+    ) -> LumeResult<crate::CpuSwapchain> {
+        self.create_offscreen_swapchain_impl(lume_core::device::OffscreenSwapchainDescriptor {
+            width: descriptor.width,
+            height: descriptor.height,
+            format: lume_core::device::TextureFormat::Bgra8UnormSrgb,
+            image_count: 3,
+        })
+    }
+
+    pub fn create_offscreen_swapchain_impl(&self, descriptor: lume_core::device::OffscreenSwapchainDescriptor) -> LumeResult<crate::CpuSwapchain> {
+        let mut textures = Vec::new();
+        let mut views = Vec::new();
+        for _ in 0..descriptor.image_count {
+            let texture = self.create_texture_impl(lume_core::device::TextureDescriptor {
+                width: descriptor.width,
+                height: descriptor.height,
+                depth: 1,
+                mip_level_count: 1,
+                format: descriptor.format,
+                usage: lume_core::device::TextureUsage::RENDER_ATTACHMENT | lume_core::device::TextureUsage::COPY_SRC,
+            })?;
+            views.push(self.create_texture_view_impl(&texture, lume_core::device::TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None })?);
+            textures.push(texture);
+        }
+
+        Ok(crate::CpuSwapchain {
+            textures,
+            views,
+            current_image: 0,
+            presented_image: None,
+        })
+    }
+}
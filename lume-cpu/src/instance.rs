@@ -0,0 +1,36 @@
+use lume_core::{Instance, InstanceDescriptor, LumeResult};
+use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
+use crate::CpuDevice;
+
+pub struct CpuInstance {
+    name: String,
+}
+
+/// The CPU backend never presents to a window; swapchains created from this
+/// surface render into offscreen textures.
+pub struct CpuSurface;
+
+impl lume_core::instance::Surface for CpuSurface {}
+
+impl Instance for CpuInstance {
+    type Device = CpuDevice;
+    type Surface = CpuSurface;
+
+    fn new(descriptor: InstanceDescriptor) -> LumeResult<Self> {
+        log::info!("Creating CPU reference instance for {}", descriptor.name);
+        Ok(Self { name: descriptor.name.to_string() })
+    }
+
+    fn create_surface(
+        &self,
+        _display_handle: impl HasDisplayHandle,
+        _window_handle: impl HasWindowHandle,
+    ) -> LumeResult<Self::Surface> {
+        Ok(CpuSurface)
+    }
+
+    fn request_device(&self, _surface: Option<&Self::Surface>) -> LumeResult<Self::Device> {
+        log::info!("Selected CPU reference device for {}", self.name);
+        Ok(CpuDevice::new())
+    }
+}
@@ -0,0 +1,265 @@
+use super::value::{Pointee, Scalar, Value, Vector};
+use lume_core::{LumeError, LumeResult};
+
+/// Byte-level access to host memory following naga's layout rules.
+pub struct Layout<'a> {
+    pub module: &'a naga::Module,
+    pub layouter: &'a naga::proc::Layouter,
+}
+
+fn out_of_bounds<T>(offset: u32, size: u32, len: usize) -> LumeResult<T> {
+    Err(LumeError::BackendError(format!(
+        "Out of bounds access: {} bytes at offset {} in a {} byte region",
+        size, offset, len
+    )))
+}
+
+fn vector_size(size: naga::VectorSize) -> usize {
+    size as usize
+}
+
+fn read_scalar(bytes: &[u8], offset: u32, scalar: naga::Scalar) -> LumeResult<Scalar> {
+    let width = if scalar.kind == naga::ScalarKind::Bool { 4 } else { scalar.width as u32 };
+    let start = offset as usize;
+    let Some(raw) = bytes.get(start..start + width as usize) else {
+        return out_of_bounds(offset, width, bytes.len());
+    };
+    let word = |n: usize| -> [u8; 4] { raw[n..n + 4].try_into().unwrap() };
+    let dword = || -> [u8; 8] { raw[..8].try_into().unwrap() };
+    Ok(match (scalar.kind, scalar.width) {
+        (naga::ScalarKind::Bool, _) => Scalar::Bool(u32::from_le_bytes(word(0)) != 0),
+        (naga::ScalarKind::Sint, 4) => Scalar::I32(i32::from_le_bytes(word(0))),
+        (naga::ScalarKind::Uint, 4) => Scalar::U32(u32::from_le_bytes(word(0))),
+        (naga::ScalarKind::Float, 4) => Scalar::F32(f32::from_le_bytes(word(0))),
+        (naga::ScalarKind::Sint, 8) => Scalar::I64(i64::from_le_bytes(dword())),
+        (naga::ScalarKind::Uint, 8) => Scalar::U64(u64::from_le_bytes(dword())),
+        (naga::ScalarKind::Float, 8) => Scalar::F64(f64::from_le_bytes(dword())),
+        other => return Err(LumeError::BackendError(format!("Unsupported scalar type in memory: {:?}", other))),
+    })
+}
+
+fn write_scalar(bytes: &mut [u8], offset: u32, value: Scalar) -> LumeResult<()> {
+    let mut buf = [0u8; 8];
+    let width = match value {
+        Scalar::Bool(v) => {
+            buf[..4].copy_from_slice(&(v as u32).to_le_bytes());
+            4
+        }
+        Scalar::I32(v) => {
+            buf[..4].copy_from_slice(&v.to_le_bytes());
+            4
+        }
+        Scalar::U32(v) => {
+            buf[..4].copy_from_slice(&v.to_le_bytes());
+            4
+        }
+        Scalar::F32(v) => {
+            buf[..4].copy_from_slice(&v.to_le_bytes());
+            4
+        }
+        Scalar::I64(v) => {
+            buf.copy_from_slice(&v.to_le_bytes());
+            8
+        }
+        Scalar::U64(v) => {
+            buf.copy_from_slice(&v.to_le_bytes());
+            8
+        }
+        Scalar::F64(v) => {
+            buf.copy_from_slice(&v.to_le_bytes());
+            8
+        }
+    };
+    let start = offset as usize;
+    let len = bytes.len();
+    let Some(dst) = bytes.get_mut(start..start + width) else {
+        return out_of_bounds(offset, width as u32, len);
+    };
+    dst.copy_from_slice(&buf[..width]);
+    Ok(())
+}
+
+impl Layout<'_> {
+    pub fn size(&self, ty: naga::Handle<naga::Type>) -> u32 {
+        self.layouter[ty].size
+    }
+
+    /// Stride between matrix columns, which follows vector alignment rules.
+    pub fn column_stride(&self, rows: naga::VectorSize, scalar: naga::Scalar) -> u32 {
+        let rows = match rows {
+            naga::VectorSize::Tri => 4,
+            other => vector_size(other) as u32,
+        };
+        rows * scalar.width as u32
+    }
+
+    pub fn zero(&self, ty: naga::Handle<naga::Type>) -> LumeResult<Value> {
+        Ok(match self.module.types[ty].inner {
+            naga::TypeInner::Scalar(scalar) | naga::TypeInner::Atomic(scalar) => Value::Scalar(Scalar::zero(scalar)?),
+            naga::TypeInner::Vector { size, scalar } => {
+                Value::Vector(Vector::new(&vec![Scalar::zero(scalar)?; vector_size(size)]))
+            }
+            naga::TypeInner::Matrix { columns, rows, scalar } => Value::Composite(vec![
+                Value::Vector(Vector::new(&vec![Scalar::zero(scalar)?; vector_size(rows)]));
+                vector_size(columns)
+            ]),
+            naga::TypeInner::Array { base, size: naga::ArraySize::Constant(count), .. } => {
+                Value::Composite(vec![self.zero(base)?; count.get() as usize])
+            }
+            naga::TypeInner::Struct { ref members, .. } => {
+                Value::Composite(members.iter().map(|m| self.zero(m.ty)).collect::<LumeResult<_>>()?)
+            }
+            ref other => {
+                return Err(LumeError::BackendError(format!("Cannot build a zero value of type {:?}", other)));
+            }
+        })
+    }
+
+    pub fn load(&self, bytes: &[u8], offset: u32, pointee: Pointee) -> LumeResult<Value> {
+        match pointee {
+            Pointee::Scalar(scalar) => Ok(Value::Scalar(read_scalar(bytes, offset, scalar)?)),
+            Pointee::Vector(size, scalar) => {
+                let step = scalar.width as u32;
+                let comps = (0..vector_size(size) as u32)
+                    .map(|i| read_scalar(bytes, offset + i * step, scalar))
+                    .collect::<LumeResult<Vec<_>>>()?;
+                Ok(Value::Vector(Vector::new(&comps)))
+            }
+            Pointee::Type(ty) => self.load_type(bytes, offset, ty),
+        }
+    }
+
+    fn load_type(&self, bytes: &[u8], offset: u32, ty: naga::Handle<naga::Type>) -> LumeResult<Value> {
+        match self.module.types[ty].inner {
+            naga::TypeInner::Scalar(scalar) | naga::TypeInner::Atomic(scalar) => {
+                self.load(bytes, offset, Pointee::Scalar(scalar))
+            }
+            naga::TypeInner::Vector { size, scalar } => self.load(bytes, offset, Pointee::Vector(size, scalar)),
+            naga::TypeInner::Matrix { columns, rows, scalar } => {
+                let stride = self.column_stride(rows, scalar);
+                let cols = (0..vector_size(columns) as u32)
+                    .map(|c| self.load(bytes, offset + c * stride, Pointee::Vector(rows, scalar)))
+                    .collect::<LumeResult<Vec<_>>>()?;
+                Ok(Value::Composite(cols))
+            }
+            naga::TypeInner::Array { base, size, stride } => {
+                let count = match size {
+                    naga::ArraySize::Constant(count) => count.get(),
+                    naga::ArraySize::Dynamic => (bytes.len() as u32).saturating_sub(offset) / stride,
+                };
+                let items = (0..count)
+                    .map(|i| self.load_type(bytes, offset + i * stride, base))
+                    .collect::<LumeResult<Vec<_>>>()?;
+                Ok(Value::Composite(items))
+            }
+            naga::TypeInner::Struct { ref members, .. } => {
+                let items = members
+                    .iter()
+                    .map(|m| self.load_type(bytes, offset + m.offset, m.ty))
+                    .collect::<LumeResult<Vec<_>>>()?;
+                Ok(Value::Composite(items))
+            }
+            ref other => Err(LumeError::BackendError(format!("Cannot load a value of type {:?}", other))),
+        }
+    }
+
+    pub fn store(&self, bytes: &mut [u8], offset: u32, pointee: Pointee, value: &Value) -> LumeResult<()> {
+        match (pointee, value) {
+            (Pointee::Scalar(_), Value::Scalar(s)) => write_scalar(bytes, offset, *s),
+            (Pointee::Vector(_, scalar), Value::Vector(v)) => {
+                for (i, c) in v.as_slice().iter().enumerate() {
+                    write_scalar(bytes, offset + i as u32 * scalar.width as u32, *c)?;
+                }
+                Ok(())
+            }
+            (Pointee::Type(ty), value) => self.store_type(bytes, offset, ty, value),
+            (pointee, value) => Err(LumeError::BackendError(format!("Cannot store {:?} through a {:?} pointer", value, pointee))),
+        }
+    }
+
+    fn store_type(&self, bytes: &mut [u8], offset: u32, ty: naga::Handle<naga::Type>, value: &Value) -> LumeResult<()> {
+        match (&self.module.types[ty].inner, value) {
+            (&naga::TypeInner::Scalar(scalar) | &naga::TypeInner::Atomic(scalar), value) => {
+                self.store(bytes, offset, Pointee::Scalar(scalar), value)
+            }
+            (&naga::TypeInner::Vector { size, scalar }, value) => self.store(bytes, offset, Pointee::Vector(size, scalar), value),
+            (&naga::TypeInner::Matrix { rows, scalar, .. }, Value::Composite(cols)) => {
+                let stride = self.column_stride(rows, scalar);
+                for (c, column) in cols.iter().enumerate() {
+                    self.store(bytes, offset + c as u32 * stride, Pointee::Vector(rows, scalar), column)?;
+                }
+                Ok(())
+            }
+            (&naga::TypeInner::Array { base, stride, .. }, Value::Composite(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    self.store_type(bytes, offset + i as u32 * stride, base, item)?;
+                }
+                Ok(())
+            }
+            (naga::TypeInner::Struct { members, .. }, Value::Composite(items)) => {
+                for (member, item) in members.iter().zip(items) {
+                    self.store_type(bytes, offset + member.offset, member.ty, item)?;
+                }
+                Ok(())
+            }
+            (other, value) => Err(LumeError::BackendError(format!("Cannot store {:?} as {:?}", value, other))),
+        }
+    }
+
+    /// Resolves a constant index into a pointer to a struct member, array element,
+    /// vector component or matrix column.
+    pub fn access(&self, pointee: Pointee, index: u32) -> LumeResult<(u32, Pointee)> {
+        let bad = || LumeError::BackendError(format!("Index {} is out of range for {:?}", index, pointee));
+        match pointee {
+            Pointee::Vector(size, scalar) => {
+                if index as usize >= vector_size(size) {
+                    return Err(bad());
+                }
+                Ok((index * scalar.width as u32, Pointee::Scalar(scalar)))
+            }
+            Pointee::Scalar(_) => Err(bad()),
+            Pointee::Type(ty) => match self.module.types[ty].inner {
+                naga::TypeInner::Vector { size, scalar } => self.access(Pointee::Vector(size, scalar), index),
+                naga::TypeInner::Matrix { columns, rows, scalar } => {
+                    if index as usize >= vector_size(columns) {
+                        return Err(bad());
+                    }
+                    Ok((index * self.column_stride(rows, scalar), Pointee::Vector(rows, scalar)))
+                }
+                naga::TypeInner::Array { base, size, stride } => {
+                    if let naga::ArraySize::Constant(count) = size
+                        && index >= count.get()
+                    {
+                        return Err(bad());
+                    }
+                    Ok((index * stride, Pointee::Type(base)))
+                }
+                naga::TypeInner::Struct { ref members, .. } => {
+                    let member = members.get(index as usize).ok_or_else(bad)?;
+                    Ok((member.offset, Pointee::Type(member.ty)))
+                }
+                _ => Err(bad()),
+            },
+        }
+    }
+
+    /// Number of elements in the runtime-sized array reachable through `pointee`,
+    /// given the size of the region the pointer lives in.
+    pub fn array_length(&self, pointee: Pointee, offset: u32, region_len: usize) -> LumeResult<u32> {
+        let Pointee::Type(ty) = pointee else {
+            return Err(LumeError::BackendError("arrayLength requires a pointer to an array".to_string()));
+        };
+        let (array_offset, stride) = match self.module.types[ty].inner {
+            naga::TypeInner::Array { stride, .. } => (0, stride),
+            naga::TypeInner::Struct { ref members, .. } => {
+                let last = members.last().ok_or(LumeError::Generic("arrayLength on an empty struct"))?;
+                match self.module.types[last.ty].inner {
+                    naga::TypeInner::Array { stride, .. } => (last.offset, stride),
+                    _ => return Err(LumeError::BackendError("arrayLength requires a runtime-sized array".to_string())),
+                }
+            }
+            _ => return Err(LumeError::BackendError("arrayLength requires a runtime-sized array".to_string())),
+        };
+        Ok((region_len as u32).saturating_sub(offset + array_offset) / stride)
+    }
+}
@@ -0,0 +1,820 @@
+//! A small interpreter for naga IR, used to run compute shaders on the host.
+
+mod memory;
+mod value;
+
+use lume_core::device::TextureFormat;
+use lume_core::{LumeError, LumeResult};
+use memory::Layout;
+use naga::Handle;
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex};
+use value::{Pointee, Pointer, Region, Scalar, Value, Vector};
+
+/// Host copy of the mip levels of a texture view bound to a dispatch.
+pub struct ImageData {
+    /// Size of the first level of the view.
+    pub width: u32,
+    pub height: u32,
+    pub mip_level_count: u32,
+    pub format: TextureFormat,
+    /// The levels one after another, each in tightly packed rows.
+    pub data: Vec<u8>,
+}
+
+impl ImageData {
+    /// Size and byte offset of `level`, relative to the first level of the view.
+    fn level(&self, level: i64) -> Option<(u32, u32, usize)> {
+        if level < 0 || level >= self.mip_level_count as i64 {
+            return None;
+        }
+        let mut offset = 0;
+        for l in 0..level as u32 {
+            let (width, height) = lume_core::device::mip_extent(self.width, self.height, l);
+            offset += width as usize * height as usize * self.format.texel_size() as usize;
+        }
+        let (width, height) = lume_core::device::mip_extent(self.width, self.height, level as u32);
+        Some((width, height, offset))
+    }
+}
+
+/// What a `@group(g) @binding(b)` slot is bound to for a dispatch.
+#[derive(Clone, Copy, Debug)]
+pub enum Slot {
+    Buffer(usize),
+    Image(usize),
+    Sampler,
+}
+
+/// Resources visible to a dispatch. Buffers and images are copied in by the caller
+/// and handed back once all invocations have finished.
+#[derive(Default)]
+pub struct DispatchResources {
+    pub buffers: Vec<Vec<u8>>,
+    pub images: Vec<ImageData>,
+    pub bindings: HashMap<(u32, u32), Slot>,
+    pub push_constants: Vec<u8>,
+}
+
+struct Shared {
+    buffers: Vec<Vec<u8>>,
+    images: Vec<ImageData>,
+}
+
+/// A validated module with one compute entry point selected.
+pub struct Program {
+    module: naga::Module,
+    layouter: naga::proc::Layouter,
+    entry: usize,
+    constants: Vec<Value>,
+    uses_barriers: bool,
+}
+
+fn unsupported<T>(what: impl std::fmt::Debug) -> LumeResult<T> {
+    Err(LumeError::BackendError(format!("{:?} is not supported by the CPU backend", what)))
+}
+
+impl Program {
+    pub fn new(module: naga::Module) -> LumeResult<Self> {
+        let mut layouter = naga::proc::Layouter::default();
+        layouter
+            .update(module.to_ctx())
+            .map_err(|e| LumeError::PipelineCreationFailed(format!("Failed to lay out shader types: {}", e)))?;
+
+        let entry = module
+            .entry_points
+            .iter()
+            .position(|ep| ep.stage == naga::ShaderStage::Compute && ep.name == "main")
+            .or_else(|| module.entry_points.iter().position(|ep| ep.stage == naga::ShaderStage::Compute))
+            .ok_or_else(|| LumeError::PipelineCreationFailed("Shader has no compute entry point".to_string()))?;
+
+        let mut constants = Vec::with_capacity(module.global_expressions.len());
+        {
+            let layout = Layout { module: &module, layouter: &layouter };
+            for (_, expr) in module.global_expressions.iter() {
+                let value = eval_pure(&layout, &constants, expr, &mut |h| Ok(constants_get(&constants, h)))
+                    .map_err(|e| LumeError::PipelineCreationFailed(format!("Failed to evaluate constant: {}", e)))?;
+                constants.push(value);
+            }
+        }
+
+        let uses_barriers = module.functions.iter().any(|(_, f)| block_has_barrier(&f.body))
+            || block_has_barrier(&module.entry_points[entry].function.body);
+
+        Ok(Self { module, layouter, entry, constants, uses_barriers })
+    }
+
+    fn layout(&self) -> Layout<'_> {
+        Layout { module: &self.module, layouter: &self.layouter }
+    }
+
+    pub fn workgroup_size(&self) -> [u32; 3] {
+        self.module.entry_points[self.entry].workgroup_size
+    }
+
+    /// Runs `groups` workgroups of the entry point against `resources`.
+    pub fn dispatch(&self, resources: DispatchResources, groups: [u32; 3]) -> LumeResult<DispatchResources> {
+        let bindings = resources.bindings;
+        let push_constants = resources.push_constants;
+        let shared = Mutex::new(Shared { buffers: resources.buffers, images: resources.images });
+        let [sx, sy, sz] = self.workgroup_size();
+        let invocations = (sx * sy * sz) as usize;
+
+        for gz in 0..groups[2] {
+            for gy in 0..groups[1] {
+                for gx in 0..groups[0] {
+                    // Workgroup variables and push constants live in shared memory for the lifetime of the group.
+                    let base = shared.lock().unwrap().buffers.len();
+                    let mut workgroup = vec![None; self.module.global_variables.len()];
+                    {
+                        let mut shared = shared.lock().unwrap();
+                        for (handle, var) in self.module.global_variables.iter() {
+                            let size = self.layout().size(var.ty) as usize;
+                            let memory = match var.space {
+                                naga::AddressSpace::WorkGroup => vec![0; size],
+                                naga::AddressSpace::PushConstant => {
+                                    let mut memory = push_constants.clone();
+                                    memory.resize(size, 0);
+                                    memory
+                                }
+                                _ => continue,
+                            };
+                            workgroup[handle.index()] = Some(shared.buffers.len());
+                            shared.buffers.push(memory);
+                        }
+                    }
+
+                    let builtins = |index: usize| Builtins {
+                        local: [index as u32 % sx, (index as u32 / sx) % sy, index as u32 / (sx * sy)],
+                        local_index: index as u32,
+                        workgroup: [gx, gy, gz],
+                        num_workgroups: groups,
+                        size: [sx, sy, sz],
+                    };
+
+                    if self.uses_barriers {
+                        let barrier = WorkgroupBarrier::new(invocations);
+                        let results: Vec<LumeResult<()>> = std::thread::scope(|scope| {
+                            let handles: Vec<_> = (0..invocations)
+                                .map(|index| {
+                                    let (shared, barrier, workgroup, bindings) = (&shared, &barrier, &workgroup, &bindings);
+                                    scope.spawn(move || {
+                                        let result = Invocation::new(self, shared, Some(barrier), workgroup, bindings)
+                                            .and_then(|mut inv| inv.run(&builtins(index)));
+                                        barrier.retire();
+                                        result
+                                    })
+                                })
+                                .collect();
+                            handles.into_iter().map(|h| h.join().unwrap()).collect()
+                        });
+                        results.into_iter().collect::<LumeResult<()>>()?;
+                    } else {
+                        for index in 0..invocations {
+                            Invocation::new(self, &shared, None, &workgroup, &bindings)?.run(&builtins(index))?;
+                        }
+                    }
+
+                    shared.lock().unwrap().buffers.truncate(base);
+                }
+            }
+        }
+
+        let shared = shared.into_inner().unwrap();
+        Ok(DispatchResources { buffers: shared.buffers, images: shared.images, bindings, push_constants })
+    }
+}
+
+fn constants_get(constants: &[Value], handle: Handle<naga::Expression>) -> Value {
+    constants[handle.index()].clone()
+}
+
+fn block_has_barrier(block: &naga::Block) -> bool {
+    block.iter().any(|stmt| match stmt {
+        naga::Statement::Barrier(_) | naga::Statement::WorkGroupUniformLoad { .. } => true,
+        naga::Statement::Block(b) => block_has_barrier(b),
+        naga::Statement::If { accept, reject, .. } => block_has_barrier(accept) || block_has_barrier(reject),
+        naga::Statement::Switch { cases, .. } => cases.iter().any(|c| block_has_barrier(&c.body)),
+        naga::Statement::Loop { body, continuing, .. } => block_has_barrier(body) || block_has_barrier(continuing),
+        _ => false,
+    })
+}
+
+/// Evaluates expressions that have no side effects and need no invocation state.
+fn eval_pure(
+    layout: &Layout,
+    constants: &[Value],
+    expr: &naga::Expression,
+    get: &mut dyn FnMut(Handle<naga::Expression>) -> LumeResult<Value>,
+) -> LumeResult<Value> {
+    use naga::Expression as E;
+    let module = layout.module;
+    Ok(match *expr {
+        E::Literal(literal) => Value::Scalar(Scalar::from_literal(literal)),
+        E::Constant(h) => constants[module.constants[h].init.index()].clone(),
+        E::Override(h) => match module.overrides[h].init {
+            Some(init) => constants[init.index()].clone(),
+            None => return Err(LumeError::BackendError(format!("Override {:?} has no value", module.overrides[h].name))),
+        },
+        E::ZeroValue(ty) => layout.zero(ty)?,
+        E::Compose { ty, ref components } => {
+            let values = components.iter().map(|&c| get(c)).collect::<LumeResult<Vec<_>>>()?;
+            match module.types[ty].inner {
+                naga::TypeInner::Vector { .. } => {
+                    let mut comps = Vec::new();
+                    for v in &values {
+                        comps.extend(v.components()?);
+                    }
+                    Value::Vector(Vector::new(&comps))
+                }
+                _ => Value::Composite(values),
+            }
+        }
+        E::Access { base, index } => {
+            let index = get(index)?.scalar()?.as_index()?;
+            let index = u32::try_from(index)
+                .map_err(|_| LumeError::BackendError(format!("Negative index {}", index)))?;
+            access(layout, get(base)?, index)?
+        }
+        E::AccessIndex { base, index } => access(layout, get(base)?, index)?,
+        E::Splat { size, value } => Value::Vector(Vector::new(&vec![get(value)?.scalar()?; size as usize])),
+        E::Swizzle { size, vector, pattern } => {
+            let comps = get(vector)?.components()?;
+            let picked: Vec<Scalar> = pattern[..size as usize].iter().map(|&c| comps[c as usize]).collect();
+            Value::Vector(Vector::new(&picked))
+        }
+        E::Unary { op, expr } => value::unary(op, &get(expr)?)?,
+        E::Binary { op, left, right } => value::binary(op, &get(left)?, &get(right)?)?,
+        E::Select { condition, accept, reject } => {
+            let accept = get(accept)?;
+            let reject = get(reject)?;
+            match get(condition)? {
+                Value::Scalar(c) => {
+                    if c.as_bool()? {
+                        accept
+                    } else {
+                        reject
+                    }
+                }
+                Value::Vector(c) => {
+                    let mut i = 0;
+                    accept.zip(&reject, |a, r| {
+                        let pick = c.comps[i].as_bool()?;
+                        i += 1;
+                        Ok(if pick { a } else { r })
+                    })?
+                }
+                other => return unsupported(other),
+            }
+        }
+        E::Relational { fun, argument } => value::relational(fun, &get(argument)?)?,
+        E::Math { fun, arg, arg1, arg2, arg3 } => {
+            let mut args = vec![get(arg)?];
+            for extra in [arg1, arg2, arg3].into_iter().flatten() {
+                args.push(get(extra)?);
+            }
+            value::math(fun, &args)?
+        }
+        E::As { expr, kind, convert } => get(expr)?.map(|s| s.cast(kind, convert))?,
+        ref other => return unsupported(other),
+    })
+}
+
+fn access(layout: &Layout, base: Value, index: u32) -> LumeResult<Value> {
+    let out_of_range = || LumeError::BackendError(format!("Index {} is out of range", index));
+    match base {
+        Value::Pointer(p) => {
+            let (offset, pointee) = layout.access(p.pointee, index)?;
+            Ok(Value::Pointer(Pointer { region: p.region, offset: p.offset + offset, pointee }))
+        }
+        Value::Vector(v) => v.as_slice().get(index as usize).map(|&s| Value::Scalar(s)).ok_or_else(out_of_range),
+        Value::Composite(items) => items.into_iter().nth(index as usize).ok_or_else(out_of_range),
+        other => unsupported(other),
+    }
+}
+
+struct Builtins {
+    local: [u32; 3],
+    local_index: u32,
+    workgroup: [u32; 3],
+    num_workgroups: [u32; 3],
+    size: [u32; 3],
+}
+
+fn uvec3(v: [u32; 3]) -> Value {
+    Value::Vector(Vector::new(&v.map(Scalar::U32)))
+}
+
+impl Builtins {
+    fn value(&self, module: &naga::Module, binding: &Option<naga::Binding>, ty: Handle<naga::Type>) -> LumeResult<Value> {
+        use naga::BuiltIn as B;
+        match *binding {
+            Some(naga::Binding::BuiltIn(builtin)) => Ok(match builtin {
+                B::GlobalInvocationId => {
+                    uvec3([0, 1, 2].map(|i| self.workgroup[i] * self.size[i] + self.local[i]))
+                }
+                B::LocalInvocationId => uvec3(self.local),
+                B::LocalInvocationIndex => Value::Scalar(Scalar::U32(self.local_index)),
+                B::WorkGroupId => uvec3(self.workgroup),
+                B::NumWorkGroups => uvec3(self.num_workgroups),
+                other => return unsupported(other),
+            }),
+            Some(ref other) => unsupported(other),
+            None => match module.types[ty].inner {
+                naga::TypeInner::Struct { ref members, .. } => Ok(Value::Composite(
+                    members
+                        .iter()
+                        .map(|m| self.value(module, &m.binding, m.ty))
+                        .collect::<LumeResult<_>>()?,
+                )),
+                ref other => unsupported(other),
+            },
+        }
+    }
+}
+
+/// A workgroup barrier that tolerates invocations returning early.
+struct WorkgroupBarrier {
+    state: Mutex<BarrierState>,
+    condvar: Condvar,
+}
+
+struct BarrierState {
+    active: usize,
+    waiting: usize,
+    generation: u64,
+}
+
+impl WorkgroupBarrier {
+    fn new(count: usize) -> Self {
+        Self {
+            state: Mutex::new(BarrierState { active: count, waiting: 0, generation: 0 }),
+            condvar: Condvar::new(),
+        }
+    }
+
+    fn release(&self, state: &mut BarrierState) {
+        state.waiting = 0;
+        state.generation += 1;
+        self.condvar.notify_all();
+    }
+
+    fn wait(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.waiting += 1;
+        if state.waiting == state.active {
+            self.release(&mut state);
+            return;
+        }
+        let generation = state.generation;
+        while state.generation == generation {
+            state = self.condvar.wait(state).unwrap();
+        }
+    }
+
+    /// Called once an invocation has finished so it no longer holds up the others.
+    fn retire(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.active -= 1;
+        if state.waiting > 0 && state.waiting == state.active {
+            self.release(&mut state);
+        }
+    }
+}
+
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+    Kill,
+}
+
+struct Frame<'f> {
+    function: &'f naga::Function,
+    args: Vec<Value>,
+    values: Vec<Option<Value>>,
+    locals: Vec<usize>,
+}
+
+struct Invocation<'a> {
+    program: &'a Program,
+    shared: &'a Mutex<Shared>,
+    barrier: Option<&'a WorkgroupBarrier>,
+    locals: Vec<Vec<u8>>,
+    globals: Vec<Option<Value>>,
+}
+
+impl<'a> Invocation<'a> {
+    fn new(
+        program: &'a Program,
+        shared: &'a Mutex<Shared>,
+        barrier: Option<&'a WorkgroupBarrier>,
+        workgroup: &[Option<usize>],
+        bindings: &HashMap<(u32, u32), Slot>,
+    ) -> LumeResult<Self> {
+        let mut inv = Self { program, shared, barrier, locals: Vec::new(), globals: Vec::new() };
+        let layout = program.layout();
+        for (handle, var) in program.module.global_variables.iter() {
+            let slot = var.binding.as_ref().and_then(|b| bindings.get(&(b.group, b.binding)));
+            let pointer = |region| Value::Pointer(Pointer { region, offset: 0, pointee: Pointee::Type(var.ty) });
+            let value = match var.space {
+                naga::AddressSpace::Uniform | naga::AddressSpace::Storage { .. } => match slot {
+                    Some(Slot::Buffer(index)) => Some(pointer(Region::Shared(*index))),
+                    _ => None,
+                },
+                naga::AddressSpace::Handle => match slot {
+                    Some(Slot::Image(index)) => Some(Value::Image(*index)),
+                    Some(Slot::Sampler) => Some(Value::Sampler(0)),
+                    _ => None,
+                },
+                naga::AddressSpace::WorkGroup | naga::AddressSpace::PushConstant => {
+                    workgroup[handle.index()].map(|index| pointer(Region::Shared(index)))
+                }
+                naga::AddressSpace::Private => {
+                    let region = inv.locals.len();
+                    inv.locals.push(vec![0; layout.size(var.ty) as usize]);
+                    if let Some(init) = var.init {
+                        layout.store(&mut inv.locals[region], 0, Pointee::Type(var.ty), &program.constants[init.index()])?;
+                    }
+                    Some(pointer(Region::Local(region)))
+                }
+                _ => None,
+            };
+            inv.globals.push(value);
+        }
+        Ok(inv)
+    }
+
+    fn run(&mut self, builtins: &Builtins) -> LumeResult<()> {
+        let module = &self.program.module;
+        let function = &module.entry_points[self.program.entry].function;
+        let args = function
+            .arguments
+            .iter()
+            .map(|arg| builtins.value(module, &arg.binding, arg.ty))
+            .collect::<LumeResult<Vec<_>>>()?;
+        self.call(function, args).map(|_| ())
+    }
+
+    fn call(&mut self, function: &naga::Function, args: Vec<Value>) -> LumeResult<Option<Value>> {
+        let base = self.locals.len();
+        let mut frame = Frame { function, args, values: vec![None; function.expressions.len()], locals: Vec::new() };
+        for (_, var) in function.local_variables.iter() {
+            let region = self.locals.len();
+            self.locals.push(vec![0; self.program.layout().size(var.ty) as usize]);
+            frame.locals.push(region);
+            if let Some(init) = var.init {
+                let value = self.eval(&mut frame, init)?;
+                self.program.layout().store(&mut self.locals[region], 0, Pointee::Type(var.ty), &value)?;
+            }
+        }
+
+        let flow = self.exec_block(&mut frame, &function.body);
+        self.locals.truncate(base);
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            Flow::Kill => Err(LumeError::BackendError("Invocation was killed".to_string())),
+            _ => Ok(None),
+        }
+    }
+
+    fn with_bytes<R>(&mut self, region: Region, f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+        match region {
+            Region::Local(index) => f(&mut self.locals[index]),
+            Region::Shared(index) => f(&mut self.shared.lock().unwrap().buffers[index]),
+        }
+    }
+
+    fn load(&mut self, pointer: Pointer) -> LumeResult<Value> {
+        let program = self.program;
+        self.with_bytes(pointer.region, |bytes| program.layout().load(bytes, pointer.offset, pointer.pointee))
+    }
+
+    fn store(&mut self, pointer: Pointer, value: &Value) -> LumeResult<()> {
+        let program = self.program;
+        self.with_bytes(pointer.region, |bytes| program.layout().store(bytes, pointer.offset, pointer.pointee, value))
+    }
+
+    fn eval(&mut self, frame: &mut Frame, handle: Handle<naga::Expression>) -> LumeResult<Value> {
+        if let Some(value) = &frame.values[handle.index()] {
+            return Ok(value.clone());
+        }
+        let value = self.compute(frame, handle)?;
+        frame.values[handle.index()] = Some(value.clone());
+        Ok(value)
+    }
+
+    fn compute(&mut self, frame: &mut Frame, handle: Handle<naga::Expression>) -> LumeResult<Value> {
+        use naga::Expression as E;
+        let program = self.program;
+        let function = frame.function;
+        match function.expressions[handle] {
+            E::FunctionArgument(index) => Ok(frame.args[index as usize].clone()),
+            E::GlobalVariable(h) => self.globals[h.index()].clone().ok_or_else(|| {
+                LumeError::BackendError(format!(
+                    "Global variable {:?} has no bound resource",
+                    program.module.global_variables[h].name
+                ))
+            }),
+            E::LocalVariable(h) => Ok(Value::Pointer(Pointer {
+                region: Region::Local(frame.locals[h.index()]),
+                offset: 0,
+                pointee: Pointee::Type(function.local_variables[h].ty),
+            })),
+            E::Load { pointer } => {
+                let pointer = self.eval(frame, pointer)?.pointer()?;
+                self.load(pointer)
+            }
+            E::ArrayLength(expr) => {
+                let pointer = self.eval(frame, expr)?.pointer()?;
+                let len = self.with_bytes(pointer.region, |bytes| bytes.len());
+                Ok(Value::Scalar(Scalar::U32(program.layout().array_length(pointer.pointee, pointer.offset, len)?)))
+            }
+            E::ImageLoad { image, coordinate, level, .. } => {
+                let Value::Image(index) = self.eval(frame, image)? else {
+                    return unsupported("Loading from a non-image value");
+                };
+                let coords = self.eval(frame, coordinate)?.components()?;
+                let x = coords[0].as_index()?;
+                let y = coords.get(1).map(|c| c.as_index()).transpose()?.unwrap_or(0);
+                let level = level.map(|l| self.eval(frame, l)?.scalar()?.as_index()).transpose()?.unwrap_or(0);
+                let shared = self.shared.lock().unwrap();
+                load_texel(&shared.images[index], x, y, level)
+            }
+            E::ImageQuery { image, query } => {
+                let Value::Image(index) = self.eval(frame, image)? else {
+                    return unsupported("Querying a non-image value");
+                };
+                let level = match query {
+                    naga::ImageQuery::Size { level: Some(level) } => self.eval(frame, level)?.scalar()?.as_index()?,
+                    _ => 0,
+                };
+                let shared = self.shared.lock().unwrap();
+                let image = &shared.images[index];
+                Ok(match query {
+                    naga::ImageQuery::Size { .. } => {
+                        let (width, height, _) = image.level(level).unwrap_or((0, 0, 0));
+                        Value::Vector(Vector::new(&[Scalar::U32(width), Scalar::U32(height)]))
+                    }
+                    naga::ImageQuery::NumLevels => Value::Scalar(Scalar::U32(image.mip_level_count)),
+                    _ => Value::Scalar(Scalar::U32(1)),
+                })
+            }
+            ref expr => {
+                let constants = &program.constants;
+                eval_pure(&program.layout(), constants, expr, &mut |h| self.eval(frame, h))
+            }
+        }
+    }
+
+    fn exec_block(&mut self, frame: &mut Frame, block: &naga::Block) -> LumeResult<Flow> {
+        for statement in block.iter() {
+            match self.exec(frame, statement)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn exec(&mut self, frame: &mut Frame, statement: &naga::Statement) -> LumeResult<Flow> {
+        use naga::Statement as S;
+        match *statement {
+            S::Emit(ref range) => {
+                for handle in range.clone() {
+                    let value = self.compute(frame, handle)?;
+                    frame.values[handle.index()] = Some(value);
+                }
+            }
+            S::Block(ref block) => return self.exec_block(frame, block),
+            S::If { condition, ref accept, ref reject } => {
+                let taken = if self.eval(frame, condition)?.scalar()?.as_bool()? { accept } else { reject };
+                return self.exec_block(frame, taken);
+            }
+            S::Switch { selector, ref cases } => {
+                let selector = self.eval(frame, selector)?.scalar()?.as_index()?;
+                let start = cases
+                    .iter()
+                    .position(|c| match c.value {
+                        naga::SwitchValue::I32(v) => v as i64 == selector,
+                        naga::SwitchValue::U32(v) => v as i64 == selector,
+                        naga::SwitchValue::Default => false,
+                    })
+                    .or_else(|| cases.iter().position(|c| c.value == naga::SwitchValue::Default));
+                if let Some(start) = start {
+                    for case in &cases[start..] {
+                        match self.exec_block(frame, &case.body)? {
+                            Flow::Next if case.fall_through => {}
+                            Flow::Next | Flow::Break => break,
+                            flow => return Ok(flow),
+                        }
+                    }
+                }
+            }
+            S::Loop { ref body, ref continuing, break_if } => loop {
+                match self.exec_block(frame, body)? {
+                    Flow::Break => break,
+                    Flow::Next | Flow::Continue => {}
+                    flow => return Ok(flow),
+                }
+                match self.exec_block(frame, continuing)? {
+                    Flow::Next | Flow::Continue => {}
+                    Flow::Break => break,
+                    flow => return Ok(flow),
+                }
+                if let Some(condition) = break_if
+                    && self.eval(frame, condition)?.scalar()?.as_bool()?
+                {
+                    break;
+                }
+            },
+            S::Break => return Ok(Flow::Break),
+            S::Continue => return Ok(Flow::Continue),
+            S::Return { value } => {
+                let value = value.map(|v| self.eval(frame, v)).transpose()?;
+                return Ok(Flow::Return(value));
+            }
+            S::Kill => return Ok(Flow::Kill),
+            S::Barrier(_) => {
+                if let Some(barrier) = self.barrier {
+                    barrier.wait();
+                }
+            }
+            S::Store { pointer, value } => {
+                let pointer = self.eval(frame, pointer)?.pointer()?;
+                let value = self.eval(frame, value)?;
+                self.store(pointer, &value)?;
+            }
+            S::ImageStore { image, coordinate, value, .. } => {
+                let Value::Image(index) = self.eval(frame, image)? else {
+                    return unsupported("Storing to a non-image value");
+                };
+                let coords = self.eval(frame, coordinate)?.components()?;
+                let x = coords[0].as_index()?;
+                let y = coords.get(1).map(|c| c.as_index()).transpose()?.unwrap_or(0);
+                let value = self.eval(frame, value)?;
+                let mut shared = self.shared.lock().unwrap();
+                store_texel(&mut shared.images[index], x, y, 0, &value)?;
+            }
+            S::Atomic { pointer, ref fun, value, result } => {
+                let pointer = self.eval(frame, pointer)?.pointer()?;
+                let operand = self.eval(frame, value)?;
+                let compare = match *fun {
+                    naga::AtomicFunction::Exchange { compare: Some(c) } => Some(self.eval(frame, c)?),
+                    _ => None,
+                };
+                let program = self.program;
+                let old = self.with_bytes(pointer.region, |bytes| -> LumeResult<Value> {
+                    let layout = program.layout();
+                    let old = layout.load(bytes, pointer.offset, pointer.pointee)?;
+                    let new = atomic_op(fun, &old, &operand, compare.as_ref())?;
+                    layout.store(bytes, pointer.offset, pointer.pointee, &new)?;
+                    Ok(old)
+                })?;
+                if let Some(result) = result {
+                    let value = match compare {
+                        Some(compare) => Value::Composite(vec![old.clone(), Value::Scalar(Scalar::Bool(old == compare))]),
+                        None => old,
+                    };
+                    frame.values[result.index()] = Some(value);
+                }
+            }
+            S::WorkGroupUniformLoad { pointer, result } => {
+                if let Some(barrier) = self.barrier {
+                    barrier.wait();
+                }
+                let pointer = self.eval(frame, pointer)?.pointer()?;
+                let value = self.load(pointer)?;
+                if let Some(barrier) = self.barrier {
+                    barrier.wait();
+                }
+                frame.values[result.index()] = Some(value);
+            }
+            S::Call { function, ref arguments, result } => {
+                let args = arguments.iter().map(|&a| self.eval(frame, a)).collect::<LumeResult<Vec<_>>>()?;
+                let program = self.program;
+                let value = self.call(&program.module.functions[function], args)?;
+                if let Some(result) = result {
+                    frame.values[result.index()] = Some(value.unwrap_or(Value::Void));
+                }
+            }
+            ref other => return unsupported(other),
+        }
+        Ok(Flow::Next)
+    }
+}
+
+fn atomic_op(fun: &naga::AtomicFunction, old: &Value, operand: &Value, compare: Option<&Value>) -> LumeResult<Value> {
+    use naga::AtomicFunction as A;
+    use naga::BinaryOperator as B;
+    match *fun {
+        A::Add => value::binary(B::Add, old, operand),
+        A::Subtract => value::binary(B::Subtract, old, operand),
+        A::And => value::binary(B::And, old, operand),
+        A::ExclusiveOr => value::binary(B::ExclusiveOr, old, operand),
+        A::InclusiveOr => value::binary(B::InclusiveOr, old, operand),
+        A::Min => value::math(naga::MathFunction::Min, &[old.clone(), operand.clone()]),
+        A::Max => value::math(naga::MathFunction::Max, &[old.clone(), operand.clone()]),
+        A::Exchange { .. } => Ok(match compare {
+            Some(compare) if old != compare => old.clone(),
+            _ => operand.clone(),
+        }),
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// Decodes one texel into the value a shader sees from `textureLoad`.
+fn decode_texel(format: TextureFormat, bytes: &[u8]) -> Value {
+    let f = |c: f32| Scalar::F32(c);
+    match format {
+        TextureFormat::Depth32Float => Value::Scalar(f(f32::from_le_bytes(bytes[..4].try_into().unwrap()))),
+        TextureFormat::R32Float => {
+            let r = f32::from_le_bytes(bytes[..4].try_into().unwrap());
+            Value::Vector(Vector::new(&[f(r), f(0.0), f(0.0), f(1.0)]))
+        }
+        TextureFormat::Rg32Uint => {
+            let r = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+            let g = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+            Value::Vector(Vector::new(&[Scalar::U32(r), Scalar::U32(g), Scalar::U32(0), Scalar::U32(1)]))
+        }
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8UnormSrgb => {
+            let mut c = [0f32; 4];
+            for i in 0..4 {
+                c[i] = bytes[i] as f32 / 255.0;
+                if format != TextureFormat::Rgba8Unorm && i < 3 {
+                    c[i] = srgb_to_linear(c[i]);
+                }
+            }
+            if format == TextureFormat::Bgra8UnormSrgb {
+                c.swap(0, 2);
+            }
+            Value::Vector(Vector::new(&c.map(f)))
+        }
+    }
+}
+
+/// Encodes a clear color (or depth in the first component) into one texel.
+pub fn encode_color(format: TextureFormat, color: [f32; 4], out: &mut [u8]) -> LumeResult<()> {
+    encode_texel(format, &Value::Vector(Vector::new(&color.map(Scalar::F32))), out)
+}
+
+/// Encodes a shader-visible value into one texel.
+fn encode_texel(format: TextureFormat, value: &Value, out: &mut [u8]) -> LumeResult<()> {
+    let comps = value.components()?;
+    match format {
+        TextureFormat::Depth32Float | TextureFormat::R32Float => out[..4].copy_from_slice(&(comps[0].as_f64() as f32).to_le_bytes()),
+        TextureFormat::Rg32Uint => {
+            for i in 0..2 {
+                let c = comps.get(i).map_or(0, |c| c.as_f64() as u32);
+                out[i * 4..i * 4 + 4].copy_from_slice(&c.to_le_bytes());
+            }
+        }
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb | TextureFormat::Bgra8UnormSrgb => {
+            let mut c = [0f32, 0.0, 0.0, 1.0];
+            for (dst, src) in c.iter_mut().zip(&comps) {
+                *dst = src.as_f64() as f32;
+            }
+            if format == TextureFormat::Bgra8UnormSrgb {
+                c.swap(0, 2);
+            }
+            for i in 0..4 {
+                let v = if format != TextureFormat::Rgba8Unorm && i < 3 { linear_to_srgb(c[i]) } else { c[i] };
+                out[i] = (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn texel_offset(image: &ImageData, x: i64, y: i64, level: i64) -> Option<usize> {
+    let (width, height, offset) = image.level(level)?;
+    if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+        return None;
+    }
+    Some(offset + (y as usize * width as usize + x as usize) * image.format.texel_size() as usize)
+}
+
+fn load_texel(image: &ImageData, x: i64, y: i64, level: i64) -> LumeResult<Value> {
+    let size = image.format.texel_size() as usize;
+    match texel_offset(image, x, y, level) {
+        Some(offset) => Ok(decode_texel(image.format, &image.data[offset..offset + size])),
+        // Out-of-bounds loads return zero, as robust buffer access would on a GPU.
+        None => Ok(decode_texel(image.format, &[0; 8])),
+    }
+}
+
+fn store_texel(image: &mut ImageData, x: i64, y: i64, level: i64, value: &Value) -> LumeResult<()> {
+    let size = image.format.texel_size() as usize;
+    if let Some(offset) = texel_offset(image, x, y, level) {
+        encode_texel(image.format, value, &mut image.data[offset..offset + size])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,705 @@
+use lume_core::{LumeError, LumeResult};
+
+/// A single scalar produced while interpreting a shader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scalar {
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+}
+
+/// Location of a value in interpreter memory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Region {
+    /// Buffers and workgroup variables, visible to every invocation of a dispatch.
+    Shared(usize),
+    /// Private and function variables owned by a single invocation.
+    Local(usize),
+}
+
+/// Type of the value a pointer refers to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pointee {
+    Type(naga::Handle<naga::Type>),
+    Vector(naga::VectorSize, naga::Scalar),
+    Scalar(naga::Scalar),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pointer {
+    pub region: Region,
+    pub offset: u32,
+    pub pointee: Pointee,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Scalar(Scalar),
+    Vector(Vector),
+    /// Structs, arrays and matrices (stored as a list of column vectors).
+    Composite(Vec<Value>),
+    Pointer(Pointer),
+    Image(usize),
+    Sampler(usize),
+    Void,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vector {
+    pub len: usize,
+    pub comps: [Scalar; 4],
+}
+
+impl Vector {
+    pub fn new(comps: &[Scalar]) -> Self {
+        let mut out = [comps[0]; 4];
+        out[..comps.len()].copy_from_slice(comps);
+        Self { len: comps.len(), comps: out }
+    }
+
+    pub fn as_slice(&self) -> &[Scalar] {
+        &self.comps[..self.len]
+    }
+}
+
+fn err<T>(msg: String) -> LumeResult<T> {
+    Err(LumeError::BackendError(msg))
+}
+
+impl Scalar {
+    pub fn zero(scalar: naga::Scalar) -> LumeResult<Self> {
+        Ok(match (scalar.kind, scalar.width) {
+            (naga::ScalarKind::Bool, _) => Scalar::Bool(false),
+            (naga::ScalarKind::Sint, 4) => Scalar::I32(0),
+            (naga::ScalarKind::Uint, 4) => Scalar::U32(0),
+            (naga::ScalarKind::Float, 4) => Scalar::F32(0.0),
+            (naga::ScalarKind::Sint, 8) => Scalar::I64(0),
+            (naga::ScalarKind::Uint, 8) => Scalar::U64(0),
+            (naga::ScalarKind::Float, 8) => Scalar::F64(0.0),
+            other => return err(format!("Unsupported scalar type {:?}", other)),
+        })
+    }
+
+    pub fn from_literal(literal: naga::Literal) -> Self {
+        match literal {
+            naga::Literal::F64(v) => Scalar::F64(v),
+            naga::Literal::F32(v) => Scalar::F32(v),
+            naga::Literal::U32(v) => Scalar::U32(v),
+            naga::Literal::I32(v) => Scalar::I32(v),
+            naga::Literal::U64(v) => Scalar::U64(v),
+            naga::Literal::I64(v) => Scalar::I64(v),
+            naga::Literal::Bool(v) => Scalar::Bool(v),
+            naga::Literal::AbstractInt(v) => Scalar::I64(v),
+            naga::Literal::AbstractFloat(v) => Scalar::F64(v),
+        }
+    }
+
+    pub fn as_bool(self) -> LumeResult<bool> {
+        match self {
+            Scalar::Bool(b) => Ok(b),
+            other => err(format!("Expected a boolean, got {:?}", other)),
+        }
+    }
+
+    /// Interprets an integer scalar as an index.
+    pub fn as_index(self) -> LumeResult<i64> {
+        match self {
+            Scalar::I32(v) => Ok(v as i64),
+            Scalar::U32(v) => Ok(v as i64),
+            Scalar::I64(v) => Ok(v),
+            Scalar::U64(v) => Ok(v as i64),
+            other => err(format!("Expected an integer, got {:?}", other)),
+        }
+    }
+
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Scalar::Bool(b) => b as u32 as f64,
+            Scalar::I32(v) => v as f64,
+            Scalar::U32(v) => v as f64,
+            Scalar::F32(v) => v as f64,
+            Scalar::I64(v) => v as f64,
+            Scalar::U64(v) => v as f64,
+            Scalar::F64(v) => v,
+        }
+    }
+
+    /// Rebuilds a float of the same width as `self` from an `f64` result.
+    fn with_float(self, v: f64) -> Self {
+        match self {
+            Scalar::F64(_) => Scalar::F64(v),
+            _ => Scalar::F32(v as f32),
+        }
+    }
+
+    fn bits(self) -> u64 {
+        match self {
+            Scalar::Bool(b) => b as u64,
+            Scalar::I32(v) => v as u32 as u64,
+            Scalar::U32(v) => v as u64,
+            Scalar::F32(v) => v.to_bits() as u64,
+            Scalar::I64(v) => v as u64,
+            Scalar::U64(v) => v,
+            Scalar::F64(v) => v.to_bits(),
+        }
+    }
+
+    /// Implements `Expression::As`, either as a numeric conversion or a bitcast.
+    pub fn cast(self, kind: naga::ScalarKind, convert: Option<u8>) -> LumeResult<Self> {
+        use naga::ScalarKind as K;
+        let Some(width) = convert else {
+            let bits = self.bits();
+            let wide = matches!(self, Scalar::I64(_) | Scalar::U64(_) | Scalar::F64(_));
+            return Ok(match (kind, wide) {
+                (K::Sint, false) => Scalar::I32(bits as u32 as i32),
+                (K::Uint, false) => Scalar::U32(bits as u32),
+                (K::Float, false) => Scalar::F32(f32::from_bits(bits as u32)),
+                (K::Sint, true) => Scalar::I64(bits as i64),
+                (K::Uint, true) => Scalar::U64(bits),
+                (K::Float, true) => Scalar::F64(f64::from_bits(bits)),
+                _ => return err(format!("Unsupported bitcast of {:?} to {:?}", self, kind)),
+            });
+        };
+        Ok(match (kind, width) {
+            (K::Bool, _) => Scalar::Bool(self.bits() != 0 && self.as_f64() != 0.0),
+            (K::Float, 4) => Scalar::F32(match self {
+                Scalar::I64(v) => v as f32,
+                Scalar::U64(v) => v as f32,
+                other => other.as_f64() as f32,
+            }),
+            (K::Float, 8) => Scalar::F64(self.as_f64()),
+            (K::Sint, 4) => Scalar::I32(match self {
+                Scalar::F32(v) => v as i32,
+                Scalar::F64(v) => v as i32,
+                other => other.bits() as u32 as i32,
+            }),
+            (K::Uint, 4) => Scalar::U32(match self {
+                Scalar::F32(v) => v as u32,
+                Scalar::F64(v) => v as u32,
+                other => other.bits() as u32,
+            }),
+            (K::Sint, 8) => Scalar::I64(match self {
+                Scalar::F32(v) => v as i64,
+                Scalar::F64(v) => v as i64,
+                Scalar::I32(v) => v as i64,
+                other => other.bits() as i64,
+            }),
+            (K::Uint, 8) => Scalar::U64(match self {
+                Scalar::F32(v) => v as u64,
+                Scalar::F64(v) => v as u64,
+                Scalar::I32(v) => v as i64 as u64,
+                other => other.bits(),
+            }),
+            _ => return err(format!("Unsupported conversion of {:?} to {:?}{}", self, kind, width)),
+        })
+    }
+}
+
+impl Value {
+    pub fn scalar(&self) -> LumeResult<Scalar> {
+        match self {
+            Value::Scalar(s) => Ok(*s),
+            other => err(format!("Expected a scalar, got {:?}", other)),
+        }
+    }
+
+    pub fn pointer(&self) -> LumeResult<Pointer> {
+        match self {
+            Value::Pointer(p) => Ok(*p),
+            other => err(format!("Expected a pointer, got {:?}", other)),
+        }
+    }
+
+    /// Returns the components of a scalar or vector value.
+    pub fn components(&self) -> LumeResult<Vec<Scalar>> {
+        match self {
+            Value::Scalar(s) => Ok(vec![*s]),
+            Value::Vector(v) => Ok(v.as_slice().to_vec()),
+            other => err(format!("Expected a scalar or vector, got {:?}", other)),
+        }
+    }
+
+    fn is_matrix(&self) -> bool {
+        match self {
+            Value::Composite(columns) => {
+                !columns.is_empty() && columns.iter().all(|c| matches!(c, Value::Vector(_)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies `f` to each component of a scalar or vector.
+    pub fn map(&self, mut f: impl FnMut(Scalar) -> LumeResult<Scalar>) -> LumeResult<Value> {
+        self.map_dyn(&mut f)
+    }
+
+    fn map_dyn(&self, f: &mut dyn FnMut(Scalar) -> LumeResult<Scalar>) -> LumeResult<Value> {
+        match self {
+            Value::Scalar(s) => Ok(Value::Scalar(f(*s)?)),
+            Value::Vector(v) => {
+                let mut out = *v;
+                for c in &mut out.comps[..v.len] {
+                    *c = f(*c)?;
+                }
+                Ok(Value::Vector(out))
+            }
+            Value::Composite(items) => Ok(Value::Composite(
+                items.iter().map(|i| i.map_dyn(f)).collect::<LumeResult<_>>()?,
+            )),
+            other => err(format!("Cannot apply a component-wise operation to {:?}", other)),
+        }
+    }
+
+    /// Applies `f` component-wise, broadcasting a scalar operand against a vector.
+    pub fn zip(&self, other: &Value, mut f: impl FnMut(Scalar, Scalar) -> LumeResult<Scalar>) -> LumeResult<Value> {
+        self.zip_dyn(other, &mut f)
+    }
+
+    fn zip_dyn(&self, other: &Value, f: &mut dyn FnMut(Scalar, Scalar) -> LumeResult<Scalar>) -> LumeResult<Value> {
+        match (self, other) {
+            (Value::Scalar(a), Value::Scalar(b)) => Ok(Value::Scalar(f(*a, *b)?)),
+            (Value::Vector(a), Value::Vector(b)) if a.len == b.len => {
+                let mut out = *a;
+                for i in 0..a.len {
+                    out.comps[i] = f(a.comps[i], b.comps[i])?;
+                }
+                Ok(Value::Vector(out))
+            }
+            (Value::Vector(_), Value::Scalar(b)) => self.map_dyn(&mut |c| f(c, *b)),
+            (Value::Scalar(a), Value::Vector(b)) => {
+                let mut out = *b;
+                for i in 0..b.len {
+                    out.comps[i] = f(*a, b.comps[i])?;
+                }
+                Ok(Value::Vector(out))
+            }
+            (Value::Composite(a), Value::Composite(b)) if a.len() == b.len() => Ok(Value::Composite(
+                a.iter().zip(b).map(|(x, y)| x.zip_dyn(y, f)).collect::<LumeResult<_>>()?,
+            )),
+            (a, b) => err(format!("Mismatched operands {:?} and {:?}", a, b)),
+        }
+    }
+}
+
+pub fn unary(op: naga::UnaryOperator, value: &Value) -> LumeResult<Value> {
+    value.map(|s| {
+        Ok(match (op, s) {
+            (naga::UnaryOperator::Negate, Scalar::I32(v)) => Scalar::I32(v.wrapping_neg()),
+            (naga::UnaryOperator::Negate, Scalar::I64(v)) => Scalar::I64(v.wrapping_neg()),
+            (naga::UnaryOperator::Negate, Scalar::F32(v)) => Scalar::F32(-v),
+            (naga::UnaryOperator::Negate, Scalar::F64(v)) => Scalar::F64(-v),
+            (naga::UnaryOperator::LogicalNot, Scalar::Bool(v)) => Scalar::Bool(!v),
+            (naga::UnaryOperator::BitwiseNot, Scalar::Bool(v)) => Scalar::Bool(!v),
+            (naga::UnaryOperator::BitwiseNot, Scalar::I32(v)) => Scalar::I32(!v),
+            (naga::UnaryOperator::BitwiseNot, Scalar::U32(v)) => Scalar::U32(!v),
+            (naga::UnaryOperator::BitwiseNot, Scalar::I64(v)) => Scalar::I64(!v),
+            (naga::UnaryOperator::BitwiseNot, Scalar::U64(v)) => Scalar::U64(!v),
+            (op, s) => return err(format!("Unsupported unary {:?} on {:?}", op, s)),
+        })
+    })
+}
+
+fn dot(a: &[Scalar], b: &[Scalar]) -> LumeResult<Scalar> {
+    let mut acc = arith(naga::BinaryOperator::Multiply, a[0], b[0])?;
+    for i in 1..a.len() {
+        acc = arith(naga::BinaryOperator::Add, acc, arith(naga::BinaryOperator::Multiply, a[i], b[i])?)?;
+    }
+    Ok(acc)
+}
+
+fn columns(value: &Value) -> LumeResult<Vec<Vec<Scalar>>> {
+    match value {
+        Value::Composite(cols) => cols.iter().map(|c| c.components()).collect(),
+        other => err(format!("Expected a matrix, got {:?}", other)),
+    }
+}
+
+fn matrix(cols: Vec<Vec<Scalar>>) -> Value {
+    Value::Composite(cols.iter().map(|c| Value::Vector(Vector::new(c))).collect())
+}
+
+pub fn binary(op: naga::BinaryOperator, left: &Value, right: &Value) -> LumeResult<Value> {
+    if op == naga::BinaryOperator::Multiply && (left.is_matrix() || right.is_matrix()) {
+        return match (left, right) {
+            (Value::Composite(_), Value::Composite(_)) => {
+                let a = columns(left)?;
+                let b = columns(right)?;
+                let rows = a[0].len();
+                let out = b
+                    .iter()
+                    .map(|bc| {
+                        (0..rows)
+                            .map(|r| dot(&a.iter().map(|c| c[r]).collect::<Vec<_>>(), bc))
+                            .collect::<LumeResult<Vec<_>>>()
+                    })
+                    .collect::<LumeResult<Vec<_>>>()?;
+                Ok(matrix(out))
+            }
+            (Value::Composite(_), Value::Vector(v)) => {
+                let a = columns(left)?;
+                let rows = a[0].len();
+                let out = (0..rows)
+                    .map(|r| dot(&a.iter().map(|c| c[r]).collect::<Vec<_>>(), v.as_slice()))
+                    .collect::<LumeResult<Vec<_>>>()?;
+                Ok(Value::Vector(Vector::new(&out)))
+            }
+            (Value::Vector(v), Value::Composite(_)) => {
+                let b = columns(right)?;
+                let out = b.iter().map(|c| dot(v.as_slice(), c)).collect::<LumeResult<Vec<_>>>()?;
+                Ok(Value::Vector(Vector::new(&out)))
+            }
+            (Value::Composite(_), Value::Scalar(s)) | (Value::Scalar(s), Value::Composite(_)) => {
+                let m = if left.is_matrix() { left } else { right };
+                m.map(|c| arith(op, c, *s))
+            }
+            (a, b) => err(format!("Unsupported matrix multiply of {:?} and {:?}", a, b)),
+        };
+    }
+    left.zip(right, |a, b| arith(op, a, b))
+}
+
+fn arith(op: naga::BinaryOperator, a: Scalar, b: Scalar) -> LumeResult<Scalar> {
+    use naga::BinaryOperator as B;
+    use Scalar as S;
+
+    macro_rules! int_ops {
+        ($x:expr, $y:expr, $ctor:path, $uty:ty) => {
+            match op {
+                B::Add => $ctor($x.wrapping_add($y)),
+                B::Subtract => $ctor($x.wrapping_sub($y)),
+                B::Multiply => $ctor($x.wrapping_mul($y)),
+                B::Divide => $ctor(if $y == 0 { $x } else { $x.wrapping_div($y) }),
+                B::Modulo => $ctor(if $y == 0 { 0 } else { $x.wrapping_rem($y) }),
+                B::Equal => S::Bool($x == $y),
+                B::NotEqual => S::Bool($x != $y),
+                B::Less => S::Bool($x < $y),
+                B::LessEqual => S::Bool($x <= $y),
+                B::Greater => S::Bool($x > $y),
+                B::GreaterEqual => S::Bool($x >= $y),
+                B::And => $ctor($x & $y),
+                B::ExclusiveOr => $ctor($x ^ $y),
+                B::InclusiveOr => $ctor($x | $y),
+                _ => return err(format!("Unsupported integer operator {:?}", op)),
+            }
+        };
+    }
+
+    macro_rules! float_ops {
+        ($x:expr, $y:expr, $ctor:path) => {
+            match op {
+                B::Add => $ctor($x + $y),
+                B::Subtract => $ctor($x - $y),
+                B::Multiply => $ctor($x * $y),
+                B::Divide => $ctor($x / $y),
+                B::Modulo => $ctor($x - $y * ($x / $y).trunc()),
+                B::Equal => S::Bool($x == $y),
+                B::NotEqual => S::Bool($x != $y),
+                B::Less => S::Bool($x < $y),
+                B::LessEqual => S::Bool($x <= $y),
+                B::Greater => S::Bool($x > $y),
+                B::GreaterEqual => S::Bool($x >= $y),
+                _ => return err(format!("Unsupported float operator {:?}", op)),
+            }
+        };
+    }
+
+    // Shift amounts are always unsigned and taken modulo the bit width.
+    if matches!(op, B::ShiftLeft | B::ShiftRight) {
+        let amount = b.as_index()? as u32;
+        return Ok(match (op, a) {
+            (B::ShiftLeft, S::I32(x)) => S::I32(x.wrapping_shl(amount)),
+            (B::ShiftLeft, S::U32(x)) => S::U32(x.wrapping_shl(amount)),
+            (B::ShiftLeft, S::I64(x)) => S::I64(x.wrapping_shl(amount)),
+            (B::ShiftLeft, S::U64(x)) => S::U64(x.wrapping_shl(amount)),
+            (B::ShiftRight, S::I32(x)) => S::I32(x.wrapping_shr(amount)),
+            (B::ShiftRight, S::U32(x)) => S::U32(x.wrapping_shr(amount)),
+            (B::ShiftRight, S::I64(x)) => S::I64(x.wrapping_shr(amount)),
+            (B::ShiftRight, S::U64(x)) => S::U64(x.wrapping_shr(amount)),
+            _ => return err(format!("Unsupported shift of {:?}", a)),
+        });
+    }
+
+    Ok(match (a, b) {
+        (S::I32(x), S::I32(y)) => int_ops!(x, y, S::I32, u32),
+        (S::U32(x), S::U32(y)) => int_ops!(x, y, S::U32, u32),
+        (S::I64(x), S::I64(y)) => int_ops!(x, y, S::I64, u64),
+        (S::U64(x), S::U64(y)) => int_ops!(x, y, S::U64, u64),
+        (S::F32(x), S::F32(y)) => float_ops!(x, y, S::F32),
+        (S::F64(x), S::F64(y)) => float_ops!(x, y, S::F64),
+        (S::Bool(x), S::Bool(y)) => match op {
+            B::Equal => S::Bool(x == y),
+            B::NotEqual => S::Bool(x != y),
+            B::And | B::LogicalAnd => S::Bool(x && y),
+            B::InclusiveOr | B::LogicalOr => S::Bool(x || y),
+            B::ExclusiveOr => S::Bool(x ^ y),
+            _ => return err(format!("Unsupported boolean operator {:?}", op)),
+        },
+        (a, b) => return err(format!("Mismatched operands {:?} {:?} {:?}", a, op, b)),
+    })
+}
+
+fn float1(s: Scalar, f: impl Fn(f64) -> f64) -> LumeResult<Scalar> {
+    match s {
+        Scalar::F32(v) => Ok(Scalar::F32(f(v as f64) as f32)),
+        Scalar::F64(v) => Ok(Scalar::F64(f(v))),
+        other => err(format!("Expected a float, got {:?}", other)),
+    }
+}
+
+fn float2(a: Scalar, b: Scalar, f: impl Fn(f64, f64) -> f64) -> LumeResult<Scalar> {
+    match (a, b) {
+        (Scalar::F32(x), Scalar::F32(y)) => Ok(Scalar::F32(f(x as f64, y as f64) as f32)),
+        (Scalar::F64(x), Scalar::F64(y)) => Ok(Scalar::F64(f(x, y))),
+        (a, b) => err(format!("Expected floats, got {:?} and {:?}", a, b)),
+    }
+}
+
+fn min_max(a: Scalar, b: Scalar, max: bool) -> LumeResult<Scalar> {
+    let less = arith(naga::BinaryOperator::Less, a, b)?.as_bool()?;
+    Ok(if less != max { a } else { b })
+}
+
+fn unpack_components(words: u32, count: usize, bits: u32, signed: bool, normalized: bool) -> Value {
+    let mask = (1u32 << bits) - 1;
+    let comps: Vec<Scalar> = (0..count)
+        .map(|i| {
+            let raw = (words >> (i as u32 * bits)) & mask;
+            let value = if signed { ((raw << (32 - bits)) as i32 >> (32 - bits)) as f64 } else { raw as f64 };
+            if !normalized {
+                return if signed { Scalar::I32(value as i32) } else { Scalar::U32(value as u32) };
+            }
+            let scale = if signed { (mask >> 1) as f64 } else { mask as f64 };
+            Scalar::F32((value / scale).max(-1.0) as f32)
+        })
+        .collect();
+    Value::Vector(Vector::new(&comps))
+}
+
+fn pack_components(comps: &[Scalar], bits: u32, signed: bool, normalized: bool) -> LumeResult<Value> {
+    let mask = (1u32 << bits) - 1;
+    let mut out = 0u32;
+    for (i, c) in comps.iter().enumerate() {
+        let raw = if normalized {
+            let scale = if signed { (mask >> 1) as f64 } else { mask as f64 };
+            let v = if signed { c.as_f64().clamp(-1.0, 1.0) } else { c.as_f64().clamp(0.0, 1.0) };
+            ((v * scale).round() as i64 as u32) & mask
+        } else {
+            (c.as_index()? as u32) & mask
+        };
+        out |= raw << (i as u32 * bits);
+    }
+    Ok(Value::Scalar(Scalar::U32(out)))
+}
+
+/// Evaluates `Expression::Math`.
+pub fn math(fun: naga::MathFunction, args: &[Value]) -> LumeResult<Value> {
+    use naga::MathFunction as M;
+    let a = &args[0];
+    let arg = |i: usize| -> LumeResult<&Value> {
+        args.get(i).ok_or_else(|| LumeError::BackendError(format!("{:?} is missing argument {}", fun, i)))
+    };
+
+    Ok(match fun {
+        M::Abs => a.map(|s| {
+            Ok(match s {
+                Scalar::I32(v) => Scalar::I32(v.wrapping_abs()),
+                Scalar::I64(v) => Scalar::I64(v.wrapping_abs()),
+                Scalar::U32(_) | Scalar::U64(_) => s,
+                _ => float1(s, f64::abs)?,
+            })
+        })?,
+        M::Min => a.zip(arg(1)?, |x, y| min_max(x, y, false))?,
+        M::Max => a.zip(arg(1)?, |x, y| min_max(x, y, true))?,
+        M::Clamp => a
+            .zip(arg(1)?, |x, lo| min_max(x, lo, true))?
+            .zip(arg(2)?, |x, hi| min_max(x, hi, false))?,
+        M::Saturate => a.map(|s| float1(s, |v| v.clamp(0.0, 1.0)))?,
+        M::Cos => a.map(|s| float1(s, f64::cos))?,
+        M::Cosh => a.map(|s| float1(s, f64::cosh))?,
+        M::Sin => a.map(|s| float1(s, f64::sin))?,
+        M::Sinh => a.map(|s| float1(s, f64::sinh))?,
+        M::Tan => a.map(|s| float1(s, f64::tan))?,
+        M::Tanh => a.map(|s| float1(s, f64::tanh))?,
+        M::Acos => a.map(|s| float1(s, f64::acos))?,
+        M::Asin => a.map(|s| float1(s, f64::asin))?,
+        M::Atan => a.map(|s| float1(s, f64::atan))?,
+        M::Atan2 => a.zip(arg(1)?, |y, x| float2(y, x, f64::atan2))?,
+        M::Asinh => a.map(|s| float1(s, f64::asinh))?,
+        M::Acosh => a.map(|s| float1(s, f64::acosh))?,
+        M::Atanh => a.map(|s| float1(s, f64::atanh))?,
+        M::Radians => a.map(|s| float1(s, f64::to_radians))?,
+        M::Degrees => a.map(|s| float1(s, f64::to_degrees))?,
+        M::Ceil => a.map(|s| float1(s, f64::ceil))?,
+        M::Floor => a.map(|s| float1(s, f64::floor))?,
+        M::Round => a.map(|s| float1(s, f64::round_ties_even))?,
+        M::Fract => a.map(|s| float1(s, |v| v - v.floor()))?,
+        M::Trunc => a.map(|s| float1(s, f64::trunc))?,
+        M::Ldexp => a.zip(arg(1)?, |x, e| Ok(x.with_float(x.as_f64() * 2f64.powi(e.as_index()? as i32))))?,
+        M::Exp => a.map(|s| float1(s, f64::exp))?,
+        M::Exp2 => a.map(|s| float1(s, f64::exp2))?,
+        M::Log => a.map(|s| float1(s, f64::ln))?,
+        M::Log2 => a.map(|s| float1(s, f64::log2))?,
+        M::Pow => a.zip(arg(1)?, |x, y| float2(x, y, f64::powf))?,
+        M::Dot => Value::Scalar(dot(&a.components()?, &arg(1)?.components()?)?),
+        M::Cross => {
+            let x = a.components()?;
+            let y = arg(1)?.components()?;
+            let c = |i: usize, j: usize| -> LumeResult<Scalar> {
+                arith(
+                    naga::BinaryOperator::Subtract,
+                    arith(naga::BinaryOperator::Multiply, x[i], y[j])?,
+                    arith(naga::BinaryOperator::Multiply, x[j], y[i])?,
+                )
+            };
+            Value::Vector(Vector::new(&[c(1, 2)?, c(2, 0)?, c(0, 1)?]))
+        }
+        M::Length => {
+            let comps = a.components()?;
+            comps[0].with_float(comps.iter().map(|c| c.as_f64() * c.as_f64()).sum::<f64>().sqrt()).into()
+        }
+        M::Distance => {
+            let x = a.components()?;
+            let y = arg(1)?.components()?;
+            let d: f64 = x.iter().zip(&y).map(|(p, q)| (p.as_f64() - q.as_f64()).powi(2)).sum();
+            x[0].with_float(d.sqrt()).into()
+        }
+        M::Normalize => {
+            let comps = a.components()?;
+            let len = comps.iter().map(|c| c.as_f64() * c.as_f64()).sum::<f64>().sqrt();
+            a.map(|s| Ok(s.with_float(s.as_f64() / len)))?
+        }
+        M::FaceForward => {
+            let d = dot(&arg(1)?.components()?, &arg(2)?.components()?)?.as_f64();
+            if d < 0.0 { a.clone() } else { unary(naga::UnaryOperator::Negate, a)? }
+        }
+        M::Reflect => {
+            let n = arg(1)?;
+            let d = dot(&a.components()?, &n.components()?)?.as_f64();
+            a.zip(n, |i, n| Ok(i.with_float(i.as_f64() - 2.0 * d * n.as_f64())))?
+        }
+        M::Sign => a.map(|s| {
+            Ok(match s {
+                Scalar::I32(v) => Scalar::I32(v.signum()),
+                Scalar::I64(v) => Scalar::I64(v.signum()),
+                _ => float1(s, |v| if v == 0.0 { 0.0 } else { v.signum() })?,
+            })
+        })?,
+        M::Fma => {
+            let ab = a.zip(arg(1)?, |x, y| float2(x, y, |x, y| x * y))?;
+            ab.zip(arg(2)?, |x, y| float2(x, y, |x, y| x + y))?
+        }
+        M::Mix => {
+            let t = arg(2)?;
+            let diff = arg(1)?.zip(a, |y, x| float2(y, x, |y, x| y - x))?;
+            a.zip(&diff.zip(t, |d, t| float2(d, t, |d, t| d * t))?, |x, d| float2(x, d, |x, d| x + d))?
+        }
+        M::Step => a.zip(arg(1)?, |edge, x| float2(edge, x, |e, x| if x < e { 0.0 } else { 1.0 }))?,
+        M::SmoothStep => {
+            let lo = a;
+            let hi = arg(1)?;
+            let range = hi.zip(lo, |h, l| float2(h, l, |h, l| h - l))?;
+            let t = arg(2)?
+                .zip(lo, |x, l| float2(x, l, |x, l| x - l))?
+                .zip(&range, |d, r| float2(d, r, |d, r| (d / r).clamp(0.0, 1.0)))?;
+            t.map(|s| float1(s, |t| t * t * (3.0 - 2.0 * t)))?
+        }
+        M::Sqrt => a.map(|s| float1(s, f64::sqrt))?,
+        M::InverseSqrt => a.map(|s| float1(s, |v| 1.0 / v.sqrt()))?,
+        M::Transpose => {
+            let cols = columns(a)?;
+            let rows = cols[0].len();
+            matrix((0..rows).map(|r| cols.iter().map(|c| c[r]).collect()).collect())
+        }
+        M::Determinant => {
+            let m: Vec<Vec<f64>> = columns(a)?.iter().map(|c| c.iter().map(|s| s.as_f64()).collect()).collect();
+            let d = match m.len() {
+                2 => m[0][0] * m[1][1] - m[1][0] * m[0][1],
+                3 => {
+                    m[0][0] * (m[1][1] * m[2][2] - m[2][1] * m[1][2])
+                        - m[1][0] * (m[0][1] * m[2][2] - m[2][1] * m[0][2])
+                        + m[2][0] * (m[0][1] * m[1][2] - m[1][1] * m[0][2])
+                }
+                _ => return err("Determinant is only supported for 2x2 and 3x3 matrices".to_string()),
+            };
+            columns(a)?[0][0].with_float(d).into()
+        }
+        M::CountOneBits => a.map(|s| int_bits(s, |v, _| v.count_ones()))?,
+        M::CountLeadingZeros => a.map(|s| int_bits(s, |v, _| v.leading_zeros()))?,
+        M::CountTrailingZeros => a.map(|s| int_bits(s, |v, _| v.trailing_zeros()))?,
+        M::ReverseBits => a.map(|s| int_bits(s, |v, _| v.reverse_bits()))?,
+        M::FirstTrailingBit => a.map(|s| int_bits(s, |v, _| if v == 0 { u32::MAX } else { v.trailing_zeros() }))?,
+        M::FirstLeadingBit => a.map(|s| {
+            int_bits(s, |v, signed| {
+                let v = if signed && (v as i32) < 0 { !v } else { v };
+                if v == 0 { u32::MAX } else { 31 - v.leading_zeros() }
+            })
+        })?,
+        M::ExtractBits => {
+            let offset = arg(1)?.scalar()?.as_index()? as u32;
+            let count = arg(2)?.scalar()?.as_index()? as u32;
+            a.map(|s| {
+                int_bits(s, |v, signed| {
+                    let o = offset.min(32);
+                    let c = count.min(32 - o);
+                    if c == 0 {
+                        return 0;
+                    }
+                    let shifted = v << (32 - o - c);
+                    if signed { ((shifted as i32) >> (32 - c)) as u32 } else { shifted >> (32 - c) }
+                })
+            })?
+        }
+        M::InsertBits => {
+            let offset = arg(2)?.scalar()?.as_index()? as u32;
+            let count = arg(3)?.scalar()?.as_index()? as u32;
+            a.zip(arg(1)?, |e, n| {
+                let o = offset.min(32);
+                let c = count.min(32 - o);
+                let mask = if c == 32 { u32::MAX } else { ((1u32 << c) - 1) << o };
+                let nb = n.bits() as u32;
+                int_bits(e, |v, _| (v & !mask) | ((nb << o) & mask))
+            })?
+        }
+        M::Pack4x8snorm => pack_components(&a.components()?, 8, true, true)?,
+        M::Pack4x8unorm => pack_components(&a.components()?, 8, false, true)?,
+        M::Pack2x16snorm => pack_components(&a.components()?, 16, true, true)?,
+        M::Pack2x16unorm => pack_components(&a.components()?, 16, false, true)?,
+        M::Pack4xI8 => pack_components(&a.components()?, 8, true, false)?,
+        M::Pack4xU8 => pack_components(&a.components()?, 8, false, false)?,
+        M::Unpack4x8snorm => unpack_components(a.scalar()?.bits() as u32, 4, 8, true, true),
+        M::Unpack4x8unorm => unpack_components(a.scalar()?.bits() as u32, 4, 8, false, true),
+        M::Unpack2x16snorm => unpack_components(a.scalar()?.bits() as u32, 2, 16, true, true),
+        M::Unpack2x16unorm => unpack_components(a.scalar()?.bits() as u32, 2, 16, false, true),
+        M::Unpack4xI8 => unpack_components(a.scalar()?.bits() as u32, 4, 8, true, false),
+        M::Unpack4xU8 => unpack_components(a.scalar()?.bits() as u32, 4, 8, false, false),
+        other => return err(format!("Math function {:?} is not supported by the CPU backend", other)),
+    })
+}
+
+fn int_bits(s: Scalar, f: impl Fn(u32, bool) -> u32) -> LumeResult<Scalar> {
+    match s {
+        Scalar::U32(v) => Ok(Scalar::U32(f(v, false))),
+        Scalar::I32(v) => Ok(Scalar::I32(f(v as u32, true) as i32)),
+        other => err(format!("Expected a 32-bit integer, got {:?}", other)),
+    }
+}
+
+pub fn relational(fun: naga::RelationalFunction, value: &Value) -> LumeResult<Value> {
+    use naga::RelationalFunction as R;
+    Ok(match fun {
+        R::All => Value::Scalar(Scalar::Bool(value.components()?.iter().all(|c| *c == Scalar::Bool(true)))),
+        R::Any => Value::Scalar(Scalar::Bool(value.components()?.contains(&Scalar::Bool(true)))),
+        R::IsNan => value.map(|s| Ok(Scalar::Bool(s.as_f64().is_nan())))?,
+        R::IsInf => value.map(|s| Ok(Scalar::Bool(s.as_f64().is_infinite())))?,
+    })
+}
+
+impl From<Scalar> for Value {
+    fn from(s: Scalar) -> Self {
+        Value::Scalar(s)
+    }
+}
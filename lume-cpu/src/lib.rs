@@ -0,0 +1,832 @@
+//! CPU reference backend. Resources live in host memory and compute dispatches are
+//! executed by interpreting the shader's naga IR, so results can be compared
+//! against the GPU backends bit for bit.
+
+mod device;
+mod instance;
+mod swapchain;
+mod pipeline;
+mod buffer;
+mod texture;
+mod interp;
+
+pub use instance::{CpuInstance, CpuSurface};
+pub use device::CpuDevice;
+pub use swapchain::CpuSwapchain;
+pub use texture::{CpuTexture, CpuTextureView, CpuSampler};
+pub use pipeline::*;
+pub use buffer::CpuBuffer;
+pub use device::{CpuBindGroup, CpuBindGroupLayout};
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use lume_core::{LumeError, LumeResult};
+
+pub struct CpuSemaphore;
+
+impl lume_core::device::Semaphore for CpuSemaphore {}
+
+/// Host-side counter; waiters block on a condition variable until it is signaled.
+pub struct CpuTimelineSemaphore {
+    state: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl CpuTimelineSemaphore {
+    pub fn new(initial_value: u64) -> Self {
+        Self { state: Arc::new((Mutex::new(initial_value), Condvar::new())) }
+    }
+
+    pub(crate) fn value(&self) -> u64 {
+        *self.state.0.lock().unwrap()
+    }
+}
+
+impl lume_core::device::TimelineSemaphore for CpuTimelineSemaphore {
+    fn current_value(&self) -> LumeResult<u64> {
+        Ok(self.value())
+    }
+
+    fn signal(&self, value: u64) -> LumeResult<()> {
+        let (lock, condvar) = &*self.state;
+        let mut current = lock.lock().unwrap();
+        if value <= *current {
+            return Err(LumeError::BackendError(format!(
+                "Timeline semaphore signaled with {} but is already at {}",
+                value, *current
+            )));
+        }
+        *current = value;
+        condvar.notify_all();
+        Ok(())
+    }
+
+    fn wait(&self, value: u64, timeout: u64) -> LumeResult<()> {
+        let (lock, condvar) = &*self.state;
+        let current = lock.lock().unwrap();
+        let (current, _) = condvar
+            .wait_timeout_while(current, Duration::from_nanos(timeout), |current| *current < value)
+            .unwrap();
+        if *current < value {
+            return Err(LumeError::BackendError(format!(
+                "Wait for timeline semaphore timed out at {} before reaching {}",
+                *current, value
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Work is executed synchronously at submit, so a fence is just a flag.
+pub struct CpuFence {
+    pub signaled: Arc<AtomicBool>,
+}
+
+impl CpuFence {
+    pub fn is_signaled(&self) -> bool {
+        self.signaled.load(Ordering::Acquire)
+    }
+}
+
+impl lume_core::device::Fence for CpuFence {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lume_core::device::*;
+    use lume_core::{Device, Instance, InstanceDescriptor, Backend};
+
+    fn device() -> CpuDevice {
+        let instance = CpuInstance::new(InstanceDescriptor { name: "lume-cpu test", backend: Backend::Cpu }).unwrap();
+        instance.request_device(None).unwrap()
+    }
+
+    fn storage_buffer(device: &CpuDevice, data: &[u8]) -> CpuBuffer {
+        let buffer = device.create_buffer(BufferDescriptor {
+            size: data.len() as u64,
+            usage: BufferUsage::STORAGE | BufferUsage::COPY_DST,
+            mapped_at_creation: true,
+        }).unwrap();
+        buffer.write_data(0, data).unwrap();
+        buffer
+    }
+
+    fn layout(device: &CpuDevice, ty: &[BindingType]) -> CpuBindGroupLayout {
+        device.create_bind_group_layout(BindGroupLayoutDescriptor {
+            entries: ty.iter().enumerate().map(|(i, &ty)| BindGroupLayoutEntry {
+                binding: i as u32,
+                visibility: ShaderStage::COMPUTE,
+                ty,
+            }).collect(),
+        }).unwrap()
+    }
+
+    fn bind_group(device: &CpuDevice, layout: &CpuBindGroupLayout, buffers: &[&CpuBuffer]) -> CpuBindGroup {
+        bind_resources(device, layout, buffers.iter().map(|&b| BindingResource::Buffer(b)).collect())
+    }
+
+    fn bind_resources(device: &CpuDevice, layout: &CpuBindGroupLayout, resources: Vec<BindingResource<CpuDevice>>) -> CpuBindGroup {
+        device.create_bind_group(BindGroupDescriptor {
+            layout,
+            entries: resources.into_iter().enumerate().map(|(i, resource)| BindGroupEntry {
+                binding: i as u32,
+                resource,
+            }).collect(),
+        }).unwrap()
+    }
+
+    fn run(device: &CpuDevice, spirv: &[u32], groups: &[(&CpuBindGroupLayout, &CpuBindGroup)], workgroups: [u32; 3]) {
+        run_with(device, spirv, groups, |cmd| cmd.dispatch(workgroups[0], workgroups[1], workgroups[2]));
+    }
+
+    fn run_with(device: &CpuDevice, spirv: &[u32], groups: &[(&CpuBindGroupLayout, &CpuBindGroup)], dispatch: impl FnOnce(&mut CpuCommandBuffer)) {
+        let shader = device.create_shader_module(spirv).unwrap();
+        let layouts: Vec<_> = groups.iter().map(|(l, _)| *l).collect();
+        let pipeline_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &layouts, push_constant_ranges: &[] }).unwrap();
+        let pipeline = device.create_compute_pipeline(ComputePipelineDescriptor { shader: &shader, layout: &pipeline_layout }).unwrap();
+
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.begin().unwrap();
+        cmd.bind_compute_pipeline(&pipeline);
+        for (i, (_, group)) in groups.iter().enumerate() {
+            cmd.bind_bind_group(i as u32, group);
+        }
+        dispatch(&mut cmd);
+        cmd.end().unwrap();
+
+        let fence = device.create_fence(false).unwrap();
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], Some(&fence)).unwrap();
+        device.wait_for_fences(&[&fence], true, u64::MAX).unwrap();
+    }
+
+    #[test]
+    fn doubles_buffer_contents() {
+        let device = device();
+        let spirv = lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Glsl {
+            source: include_str!("../../lume-examples/shaders/test.comp"),
+            stage: naga::ShaderStage::Compute,
+            defines: Default::default(),
+        }).unwrap();
+
+        let input: Vec<f32> = (0..64).map(|i| i as f32).collect();
+        let buffer = storage_buffer(&device, bytemuck::cast_slice(&input));
+        let layout = layout(&device, &[BindingType::StorageBuffer]);
+        let group = bind_group(&device, &layout, &[&buffer]);
+        run(&device, &spirv, &[(&layout, &group)], [1, 1, 1]);
+
+        let mut output = vec![0f32; 64];
+        buffer.read_data(0, bytemuck::cast_slice_mut(&mut output)).unwrap();
+        let expected: Vec<f32> = input.iter().map(|v| v * 2.0).collect();
+        assert_eq!(output, expected);
+    }
+
+    fn compile_adaptrix(path: &str, define: Option<&str>) -> Vec<u32> {
+        let resolver = lume_core::shader::MemoryResolver::new(lume_adaptrix::shaders::SOURCES);
+        lume_core::shader::compile_shader(lume_core::shader::ShaderSource::WgslFile {
+            path,
+            resolver: &resolver,
+            defines: define.map(|d| (d.to_string(), String::new())).into_iter().collect(),
+        }).unwrap()
+    }
+
+    /// Group 2 of an occlusion culling pass: `pass` is its `OCCLUSION_*` define, and `queue`
+    /// the header of the occlusion queue bound in `group`.
+    struct Occlusion<'a> {
+        pass: &'a str,
+        layout: &'a CpuBindGroupLayout,
+        group: &'a CpuBindGroup,
+        queue: &'a CpuBuffer,
+    }
+
+    /// The (instance, cluster) pairs queued by the first culling stage, and those `cull.wgsl`
+    /// found visible.
+    struct Culled {
+        queued: Vec<[u32; 2]>,
+        visible: Vec<[u32; 2]>,
+    }
+
+    /// Runs `instance_cull.wgsl`, then `cull.wgsl` indirectly over the queue it fills; returns
+    /// the visible (instance, cluster) pairs.
+    fn cull(clusters: &[lume_adaptrix::Cluster], instances: &[lume_adaptrix::MeshInstance], view: &[f32]) -> Vec<[u32; 2]> {
+        cull_pass(&device(), None, None, clusters, instances, view).visible
+    }
+
+    /// Like `cull`, but with `occlusion` bound, and `bvh_cull.wgsl` over `bvh` as the first
+    /// stage if given. The second occlusion pass skips the first stage and is dispatched from
+    /// the occlusion queue instead.
+    fn cull_pass(
+        device: &CpuDevice,
+        occlusion: Option<Occlusion>,
+        bvh: Option<&[lume_adaptrix::BvhNode]>,
+        clusters: &[lume_adaptrix::Cluster],
+        instances: &[lume_adaptrix::MeshInstance],
+        view: &[f32],
+    ) -> Culled {
+        let define = occlusion.as_ref().map(|o| o.pass);
+        let capacity = clusters.len() * instances.len();
+        let cluster_buffer = storage_buffer(device, bytemuck::cast_slice(clusters));
+        let instance_buffer = storage_buffer(device, bytemuck::cast_slice(instances));
+        let visible_buffer = storage_buffer(device, &vec![0; capacity * 8]);
+        let draw_buffer = storage_buffer(device, bytemuck::cast_slice(&[lume_adaptrix::MAX_CLUSTER_TRIANGLES * 3, 0, 0, 0]));
+        let work_queue = storage_buffer(device, bytemuck::bytes_of(&lume_adaptrix::WorkQueue::EMPTY));
+        let queue_buffer = storage_buffer(device, &vec![0; capacity * 8]);
+        let view_buffer = storage_buffer(device, bytemuck::cast_slice(view));
+
+        let uniform = layout(device, &[BindingType::UniformBuffer]);
+        let view_group = bind_group(device, &uniform, &[&view_buffer]);
+        let occlusion_group = occlusion.as_ref().map(|o| (o.layout, o.group));
+
+        let second_pass = define == Some("OCCLUSION_SECOND_PASS");
+        if let Some(nodes) = bvh {
+            let node_buffer = storage_buffer(device, bytemuck::cast_slice(nodes));
+            let node_queue = storage_buffer(device, bytemuck::bytes_of(&lume_adaptrix::NodeQueue::new(instances.len() as u32)));
+            let node_entries = storage_buffer(device, &vec![0xff; nodes.len() * instances.len() * 8]);
+            let storage = layout(device, &[BindingType::StorageBuffer; 6]);
+            let group = bind_group(device, &storage, &[&node_buffer, &instance_buffer, &node_queue, &node_entries, &work_queue, &queue_buffer]);
+            run(device, &compile_adaptrix("bvh_cull.wgsl", None), &[(&storage, &group), (&uniform, &view_group)], [2, 1, 1]);
+
+            // Drained, and every entry freed for the next frame.
+            let mut header = lume_adaptrix::NodeQueue::new(0);
+            node_queue.read_data(0, bytemuck::bytes_of_mut(&mut header)).unwrap();
+            assert_eq!(header.pending, 0, "{:?}", header);
+            let mut entries = vec![0u32; nodes.len() * instances.len() * 2];
+            node_entries.read_data(0, bytemuck::cast_slice_mut(&mut entries)).unwrap();
+            assert!(entries.iter().all(|&e| e == lume_adaptrix::EMPTY_QUEUE_SLOT));
+        } else if !second_pass {
+            let storage = layout(device, &[BindingType::StorageBuffer; 3]);
+            let group = bind_group(device, &storage, &[&instance_buffer, &work_queue, &queue_buffer]);
+            let spirv = compile_adaptrix("instance_cull.wgsl", define);
+            let groups: Vec<_> = [(&storage, &group), (&uniform, &view_group)].into_iter().chain(occlusion_group).collect();
+            run(device, &spirv, &groups, [instances.len().div_ceil(64) as u32, 1, 1]);
+        }
+
+        let storage = layout(device, &[BindingType::StorageBuffer; 6]);
+        let group = bind_group(device, &storage, &[&cluster_buffer, &instance_buffer, &visible_buffer, &draw_buffer, &work_queue, &queue_buffer]);
+        let args = if second_pass { occlusion.as_ref().unwrap().queue } else { &work_queue };
+        let spirv = compile_adaptrix("cull.wgsl", define);
+        let groups: Vec<_> = [(&storage, &group), (&uniform, &view_group)].into_iter().chain(occlusion_group).collect();
+        run_with(device, &spirv, &groups, |cmd| cmd.dispatch_indirect(args, 0));
+
+        let mut header = lume_adaptrix::WorkQueue::EMPTY;
+        work_queue.read_data(0, bytemuck::bytes_of_mut(&mut header)).unwrap();
+        let mut queued = vec![[0u32; 2]; capacity];
+        queue_buffer.read_data(0, bytemuck::cast_slice_mut(&mut queued)).unwrap();
+        queued.truncate(header.count as usize);
+        let mut draw = [0u32; 4];
+        draw_buffer.read_data(0, bytemuck::cast_slice_mut(&mut draw)).unwrap();
+        let mut visible = vec![[0u32; 2]; capacity];
+        visible_buffer.read_data(0, bytemuck::cast_slice_mut(&mut visible)).unwrap();
+        visible.truncate(draw[1] as usize);
+        Culled { queued, visible }
+    }
+
+    /// The `View` uniform of `common.wgsl`, with the frustum given as planes.
+    fn view_uniform(view_proj: glam::Mat4, frustum: [glam::Vec4; 6], viewport_size: glam::Vec2, error_threshold: f32) -> Vec<f32> {
+        let mut view = Vec::with_capacity(60);
+        view.extend(view_proj.to_cols_array());
+        view.extend(view_proj.inverse().to_cols_array());
+        view.extend(frustum.iter().flat_map(|plane| plane.to_array()));
+        view.extend([viewport_size.x, viewport_size.y, error_threshold, 0.0]);
+        view
+    }
+
+    fn perspective_from(z: f32) -> glam::Mat4 {
+        glam::Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 1000.0)
+            * glam::Mat4::look_at_rh(glam::Vec3::new(0.0, 0.0, z), glam::Vec3::ZERO, glam::Vec3::Y)
+    }
+
+    #[test]
+    fn cull_shader_keeps_clusters_inside_frustum() {
+        use lume_adaptrix::{Cluster, MeshInstance};
+        use glam::Vec4;
+
+        let spheres = [
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(50.0, 0.0, 0.0, 1.0),
+            Vec4::new(10.5, 0.0, 0.0, 1.0),
+            Vec4::new(0.0, -12.0, 0.0, 1.0),
+        ];
+        let clusters: Vec<Cluster> = spheres.iter().map(|&bounding_sphere| Cluster {
+            vertex_offset: 0,
+            triangle_offset: 0,
+            vertex_count: 0,
+            triangle_count: 0,
+            bounding_sphere,
+            error_metric: 0.0,
+            parent_error: 1e10,
+            _padding: [0.0; 2],
+            parent_sphere: Vec4::ZERO,
+        }).collect();
+        let instances = [MeshInstance {
+            world_from_local: glam::Mat4::IDENTITY,
+            cluster_base: 0,
+            cluster_count: clusters.len() as u32,
+            bvh_root: 0, _padding: 0,
+            bounding_sphere: lume_adaptrix::mesh_bounds(&clusters),
+        }];
+
+        // A [-10, 10]^3 box as six inward-facing planes.
+        let planes = [[1.0, 0.0, 0.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0], [0.0, 0.0, -1.0]];
+        let frustum = planes.map(|n| Vec4::new(n[0], n[1], n[2], 10.0));
+        let view = view_uniform(perspective_from(30.0), frustum, glam::Vec2::new(1280.0, 720.0), 1.0);
+        assert_eq!(cull(&clusters, &instances, &view), [[0, 0], [0, 2]]);
+    }
+
+    /// Four leaves, two mid-level clusters simplified from them, and a root, bounded by the root.
+    fn lod_clusters() -> (Vec<lume_adaptrix::Cluster>, glam::Vec4) {
+        use lume_adaptrix::Cluster;
+        use glam::Vec4;
+
+        let mid = [Vec4::new(-2.0, 0.0, 0.0, 2.0), Vec4::new(2.0, 0.0, 0.0, 2.0)];
+        let root = Vec4::new(0.0, 0.0, 0.0, 4.0);
+        let cluster = |sphere: Vec4, error: f32, parent_sphere: Vec4, parent_error: f32| Cluster {
+            bounding_sphere: sphere,
+            error_metric: error,
+            parent_sphere,
+            parent_error,
+            ..bytemuck::Zeroable::zeroed()
+        };
+        let mut clusters: Vec<Cluster> = [-3.0, -1.0, 1.0, 3.0].iter().enumerate()
+            .map(|(i, &x)| cluster(Vec4::new(x, 0.0, 0.0, 1.0), 0.0, mid[i / 2], 0.01))
+            .collect();
+        clusters.extend(mid.map(|sphere| cluster(sphere, 0.01, root, 0.05)));
+        clusters.push(cluster(root, 0.05, root, f32::MAX));
+        (clusters, root)
+    }
+
+    #[test]
+    fn cull_shader_matches_cpu_lod_cut() {
+        use lume_adaptrix::lod::{select_clusters, LodView};
+        use lume_adaptrix::MeshInstance;
+        use glam::{Mat4, Vec2, Vec3, Vec4};
+
+        let (clusters, root) = lod_clusters();
+        let instances = [
+            MeshInstance { world_from_local: Mat4::IDENTITY, cluster_base: 0, cluster_count: 7, bvh_root: 0, _padding: 0, bounding_sphere: root },
+            MeshInstance { world_from_local: Mat4::from_scale_rotation_translation(Vec3::splat(3.0), glam::Quat::from_rotation_y(0.5), Vec3::new(0.0, 0.0, -40.0)), cluster_base: 0, cluster_count: 7, bvh_root: 0, _padding: 0, bounding_sphere: root },
+        ];
+        let frustum = [Vec4::new(0.0, 0.0, 0.0, 1e9); 6];
+        for z in [1.0, 6.0, 12.0, 30.0, 200.0] {
+            let view = LodView::new(perspective_from(z), Vec2::new(1000.0, 1000.0), 1.0);
+            let mut expected: Vec<[u32; 2]> = instances.iter().enumerate()
+                .flat_map(|(i, instance)| select_clusters(&clusters, instance, &view).into_iter().map(move |c| [i as u32, c]))
+                .collect();
+            let mut visible = cull(&clusters, &instances, &view_uniform(perspective_from(z), frustum, Vec2::new(1000.0, 1000.0), 1.0));
+            expected.sort();
+            visible.sort();
+            assert_eq!(visible, expected, "camera at z = {}", z);
+        }
+    }
+
+    /// A 32x32 grid of `instance` around the camera of the returned `view_proj`, so some
+    /// instances are outside the frustum, some straddle it, and the distant ones switch to
+    /// coarser clusters.
+    fn forest(instance: lume_adaptrix::MeshInstance) -> (Vec<lume_adaptrix::MeshInstance>, glam::Mat4) {
+        use glam::{Mat4, Quat, Vec3};
+
+        let instances = (0..32 * 32).map(|i| {
+            let position = Vec3::new((i % 32) as f32 * 12.0 - 186.0, 0.0, (i / 32) as f32 * -12.0 + 40.0);
+            let world_from_local = Mat4::from_scale_rotation_translation(Vec3::splat(1.0 + (i % 3) as f32 * 0.5), Quat::from_rotation_y(i as f32), position);
+            lume_adaptrix::MeshInstance { world_from_local, ..instance }
+        }).collect();
+        let view_proj = Mat4::perspective_rh(1.0, 16.0 / 9.0, 0.1, 300.0) * Mat4::look_at_rh(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, 0.0, -10.0), Vec3::Y);
+        (instances, view_proj)
+    }
+
+    #[test]
+    fn instance_cull_expands_a_forest_of_instances() {
+        use lume_adaptrix::lod::{is_cluster_selected, LodView};
+        use lume_adaptrix::{frustum_planes, is_sphere_in_frustum, MeshInstance};
+        use glam::{Mat4, Vec2, Vec4Swizzles};
+
+        let (clusters, root) = lod_clusters();
+        let (instances, view_proj) = forest(MeshInstance { world_from_local: Mat4::IDENTITY, cluster_base: 0, cluster_count: clusters.len() as u32, bvh_root: 0, _padding: 0, bounding_sphere: root });
+        let viewport = Vec2::new(1280.0, 720.0);
+        let frustum = frustum_planes(view_proj);
+
+        let lod_view = LodView::new(view_proj, viewport, 1.0);
+        let world_sphere = |m: Mat4, sphere: glam::Vec4| {
+            let scale = m.x_axis.xyz().length().max(m.y_axis.xyz().length()).max(m.z_axis.xyz().length());
+            m.transform_point3(sphere.xyz()).extend(sphere.w * scale)
+        };
+        let mut expected = Vec::new();
+        for (i, instance) in instances.iter().enumerate() {
+            if !is_sphere_in_frustum(&frustum, world_sphere(instance.world_from_local, instance.bounding_sphere)) {
+                continue;
+            }
+            for (c, cluster) in clusters.iter().enumerate() {
+                if is_sphere_in_frustum(&frustum, world_sphere(instance.world_from_local, cluster.bounding_sphere))
+                    && is_cluster_selected(cluster, instance.world_from_local, &lod_view) {
+                    expected.push([i as u32, c as u32]);
+                }
+            }
+        }
+        let mut visible = cull(&clusters, &instances, &view_uniform(view_proj, frustum, viewport, 1.0));
+        visible.sort();
+        assert_eq!(visible, expected);
+        let drawn: std::collections::BTreeSet<u32> = visible.iter().map(|&[instance, _]| instance).collect();
+        assert!(drawn.len() > 100 && drawn.len() < instances.len() * 3 / 4, "{} instances drawn", drawn.len());
+        assert!(visible.iter().any(|&[_, c]| c < 4) && visible.iter().any(|&[_, c]| c == 6), "{:?}", visible);
+    }
+
+    #[test]
+    fn bvh_cull_matches_cpu_traversal_and_linear_culling() {
+        use lume_adaptrix::bvh::{build_bvh, traverse};
+        use lume_adaptrix::lod::LodView;
+        use lume_adaptrix::{frustum_planes, MeshInstance};
+        use glam::{Mat4, Vec2};
+
+        // Two copies of the mesh, each with its own BVH, alternating over the forest.
+        let (clusters, root) = lod_clusters();
+        let (nodes, order) = build_bvh(&clusters, &[0, 0, 0, 0, 1, 1, 2]);
+        let mesh: Vec<_> = order.iter().map(|&i| clusters[i]).collect();
+        let clusters = [mesh.clone(), mesh].concat();
+        let nodes = [nodes.clone(), nodes.clone()].concat();
+        let (mut instances, view_proj) = forest(MeshInstance { world_from_local: Mat4::IDENTITY, cluster_base: 0, cluster_count: 7, bvh_root: 0, _padding: 0, bounding_sphere: root });
+        for instance in instances.iter_mut().skip(1).step_by(2) {
+            instance.cluster_base = 7;
+            instance.bvh_root = nodes.len() as u32 / 2;
+        }
+        let viewport = Vec2::new(1280.0, 720.0);
+        let frustum = frustum_planes(view_proj);
+        let view = view_uniform(view_proj, frustum, viewport, 1.0);
+
+        let culled = cull_pass(&device(), None, Some(&nodes), &clusters, &instances, &view);
+        let mut queued = culled.queued;
+        let mut expected = traverse(&nodes, &instances, &frustum, &LodView::new(view_proj, viewport, 1.0));
+        queued.sort();
+        expected.sort();
+        assert_eq!(queued, expected);
+        assert!(queued.iter().any(|&[_, c]| c >= 7));
+
+        // Pruning only drops clusters the second stage would have rejected anyway.
+        let linear = cull_pass(&device(), None, None, &clusters, &instances, &view);
+        let (mut visible, mut linear_visible) = (culled.visible, linear.visible);
+        visible.sort();
+        linear_visible.sort();
+        assert_eq!(visible, linear_visible);
+        assert!(queued.len() * 2 < linear.queued.len(), "{} of {} queued", queued.len(), linear.queued.len());
+    }
+
+    /// An `R32Float` or `Depth32Float` texture with `texels` uploaded to its first level.
+    fn float_texture(device: &CpuDevice, width: u32, height: u32, mip_level_count: u32, format: TextureFormat, texels: &[f32]) -> CpuTexture {
+        let texture = device.create_texture(TextureDescriptor {
+            width,
+            height,
+            depth: 1,
+            mip_level_count,
+            format,
+            usage: TextureUsage::TEXTURE_BINDING | TextureUsage::STORAGE_BINDING | TextureUsage::COPY_DST,
+        }).unwrap();
+        let upload = storage_buffer(device, bytemuck::cast_slice(texels));
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.begin().unwrap();
+        cmd.copy_buffer_to_texture(&upload, &texture, width, height);
+        cmd.end().unwrap();
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).unwrap();
+        texture
+    }
+
+    fn mip_view(device: &CpuDevice, texture: &CpuTexture, level: u32) -> CpuTextureView {
+        device.create_texture_view(texture, TextureViewDescriptor { format: None, base_mip_level: level, mip_level_count: Some(1) }).unwrap()
+    }
+
+    /// Runs `hzb.wgsl` once per level of a half-resolution HZB of `depth`; returns the HZB.
+    fn build_hzb(device: &CpuDevice, depth: &CpuTexture) -> CpuTexture {
+        let (width, height) = mip_extent(depth.inner.width, depth.inner.height, 1);
+        let hzb = float_texture(device, width, height, mip_level_count(width, height), TextureFormat::R32Float, &vec![0.0; (width * height) as usize]);
+        let from_depth = compile_adaptrix("hzb.wgsl", Some("HZB_FROM_DEPTH"));
+        let from_hzb = compile_adaptrix("hzb.wgsl", None);
+        let depth_view = mip_view(device, depth, 0);
+        for level in 0..hzb.inner.mip_level_count {
+            let (source_type, source, spirv) = match level {
+                0 => (BindingType::SampledTexture, depth_view.clone(), &from_depth),
+                _ => (BindingType::StorageTexture, mip_view(device, &hzb, level - 1), &from_hzb),
+            };
+            let destination = mip_view(device, &hzb, level);
+            let layout = layout(device, &[source_type, BindingType::StorageTexture]);
+            let group = bind_resources(device, &layout, vec![BindingResource::TextureView(&source), BindingResource::TextureView(&destination)]);
+            let (width, height) = mip_extent(width, height, level);
+            run(device, spirv, &[(&layout, &group)], [width.div_ceil(8), height.div_ceil(8), 1]);
+        }
+        hzb
+    }
+
+    #[test]
+    fn hzb_shader_keeps_farthest_depth_of_odd_sized_levels() {
+        let device = device();
+        let (width, height) = (13, 7);
+        let depths: Vec<f32> = (0..width * height).map(|i| ((i * 37) % 101) as f32 / 100.0).collect();
+        let depth = float_texture(&device, width, height, 1, TextureFormat::Depth32Float, &depths);
+        let hzb = build_hzb(&device, &depth);
+        assert_eq!(hzb.inner.mip_level_count, 3);
+
+        // Texel i of level l covers depth texels from i << (l + 1), the last one up to the edge.
+        let data = hzb.inner.data.lock().unwrap();
+        let hzb_texels: &[f32] = bytemuck::cast_slice(&data);
+        let depths = &depths;
+        let mut offset = 0;
+        for level in 0..3 {
+            let (w, h) = mip_extent(6, 3, level);
+            let covered = |i: u32, size: u32, end: u32| (i << (level + 1))..if i == size - 1 { end } else { (i + 1) << (level + 1) };
+            for y in 0..h {
+                for x in 0..w {
+                    let expected = covered(y, h, height)
+                        .flat_map(|dy| covered(x, w, width).map(move |dx| depths[(dy * width + dx) as usize]))
+                        .fold(0.0, f32::max);
+                    assert_eq!(hzb_texels[offset + (y * w + x) as usize], expected, "level {} texel ({}, {})", level, x, y);
+                }
+            }
+            offset += (w * h) as usize;
+        }
+        assert_eq!(hzb_texels[offset - 1], depths.iter().copied().fold(0.0, f32::max));
+    }
+
+    #[test]
+    fn writes_to_one_level_survive_a_view_of_all_levels() {
+        let device = device();
+        let spirv = lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Wgsl(
+            "@group(0) @binding(0) var source: texture_2d<f32>;
+             @group(0) @binding(1) var destination: texture_storage_2d<r32float, write>;
+             @compute @workgroup_size(2, 2)
+             fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+                 let texel = textureLoad(source, vec2<i32>(id.xy * 2u), 0);
+                 textureStore(destination, id.xy, texel + vec4<f32>(100.0));
+             }",
+        )).unwrap();
+        let layout = layout(&device, &[BindingType::SampledTexture, BindingType::StorageTexture]);
+
+        // Bind groups iterate in no fixed order, so repeat to write the views back in both.
+        for _ in 0..8 {
+            let texture = float_texture(&device, 4, 4, 3, TextureFormat::R32Float, &(0..16).map(|i| i as f32).collect::<Vec<_>>());
+            let all_levels = device.create_texture_view(&texture, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap();
+            let level_1 = mip_view(&device, &texture, 1);
+            let group = bind_resources(&device, &layout, vec![BindingResource::TextureView(&all_levels), BindingResource::TextureView(&level_1)]);
+            run(&device, &spirv, &[(&layout, &group)], [1, 1, 1]);
+
+            let data = texture.inner.data.lock().unwrap();
+            let texels: &[f32] = bytemuck::cast_slice(&data);
+            assert_eq!(texels[..16], (0..16).map(|i| i as f32).collect::<Vec<_>>());
+            assert_eq!(texels[16..], [100.0, 102.0, 108.0, 110.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn cull_shader_queues_occluded_clusters_for_the_second_pass() {
+        use lume_adaptrix::{Cluster, MeshInstance};
+        use glam::{Vec2, Vec3, Vec4};
+
+        // A wall at z = 0 hides the left half of the screen from a camera at z = 10.
+        let view_proj = perspective_from(10.0);
+        let (width, height) = (64, 48);
+        let wall = view_proj.project_point3(Vec3::ZERO).z;
+        let depths: Vec<f32> = (0..width * height).map(|i| if i % width < width / 2 { wall } else { 1.0 }).collect();
+        let device = device();
+        let hzb_behind_wall = build_hzb(&device, &float_texture(&device, width, height, 1, TextureFormat::Depth32Float, &depths));
+        let hzb_cleared = build_hzb(&device, &float_texture(&device, width, height, 1, TextureFormat::Depth32Float, &vec![1.0; depths.len()]));
+
+        let centers = [
+            Vec3::new(-4.0, 0.0, -5.0), // Behind the wall
+            Vec3::new(-4.0, 0.0, 5.0),  // In front of it
+            Vec3::new(4.0, 0.0, -5.0),  // Beside it
+            Vec3::new(0.0, 0.0, -5.0),  // Half behind it
+            Vec3::new(-2.0, 0.0, 9.5),  // Reaching past the near plane
+        ];
+        let clusters: Vec<Cluster> = centers.iter().map(|c| Cluster {
+            bounding_sphere: c.extend(1.0),
+            parent_error: 1e10,
+            ..bytemuck::Zeroable::zeroed()
+        }).collect();
+        let instances = [
+            MeshInstance { world_from_local: glam::Mat4::IDENTITY, cluster_base: 0, cluster_count: clusters.len() as u32, bvh_root: 0, _padding: 0, bounding_sphere: lume_adaptrix::mesh_bounds(&clusters) },
+            // Only the first cluster, moved farther behind the wall: hidden as a whole.
+            MeshInstance { world_from_local: glam::Mat4::from_translation(Vec3::new(0.0, 0.0, -10.0)), cluster_base: 0, cluster_count: 1, bvh_root: 0, _padding: 0, bounding_sphere: clusters[0].bounding_sphere },
+        ];
+        let frustum = [Vec4::new(0.0, 0.0, 0.0, 1e9); 6];
+        let view = view_uniform(view_proj, frustum, Vec2::new(width as f32, height as f32), 1.0);
+
+        let occluded_queue = storage_buffer(&device, bytemuck::bytes_of(&lume_adaptrix::WorkQueue::EMPTY));
+        let occluded = storage_buffer(&device, &vec![0; clusters.len() * 16]);
+        let view_buffer = storage_buffer(&device, bytemuck::cast_slice(&view));
+        let layout = layout(&device, &[BindingType::SampledTexture, BindingType::UniformBuffer, BindingType::StorageBuffer, BindingType::StorageBuffer]);
+        let occlusion_group = |hzb: &CpuTexture| {
+            let hzb_view = device.create_texture_view(hzb, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap();
+            bind_resources(&device, &layout, vec![
+                BindingResource::TextureView(&hzb_view),
+                BindingResource::Buffer(&view_buffer),
+                BindingResource::Buffer(&occluded_queue),
+                BindingResource::Buffer(&occluded),
+            ])
+        };
+
+        let occlusion = |pass, group| Some(Occlusion { pass, layout: &layout, group, queue: &occluded_queue });
+        let group = occlusion_group(&hzb_behind_wall);
+        assert_eq!(cull_pass(&device, occlusion("OCCLUSION_FIRST_PASS", &group), None, &clusters, &instances, &view).visible, [[0, 1], [0, 2], [0, 3], [0, 4]]);
+        // The hidden instance is queued by the instance pass, the hidden cluster after it.
+        let mut header = lume_adaptrix::WorkQueue::EMPTY;
+        occluded_queue.read_data(0, bytemuck::bytes_of_mut(&mut header)).unwrap();
+        assert_eq!(header, lume_adaptrix::WorkQueue { dispatch: [1, 1, 1], count: 2 });
+        let mut queued = [[0u32; 2]; 2];
+        occluded.read_data(0, bytemuck::cast_slice_mut(&mut queued)).unwrap();
+        assert_eq!(queued, [[1, 0], [0, 0]]);
+
+        // Still hidden if the wall was drawn again this frame, visible once it is gone.
+        assert_eq!(cull_pass(&device, occlusion("OCCLUSION_SECOND_PASS", &group), None, &clusters, &instances, &view).visible, [[0u32; 2]; 0]);
+        let group = occlusion_group(&hzb_cleared);
+        assert_eq!(cull_pass(&device, occlusion("OCCLUSION_SECOND_PASS", &group), None, &clusters, &instances, &view).visible, [[1, 0], [0, 0]]);
+    }
+
+    #[test]
+    fn offscreen_swapchain_presents_cleared_image() {
+        let device = device();
+        let mut swapchain = device
+            .create_offscreen_swapchain(OffscreenSwapchainDescriptor {
+                width: 4,
+                height: 2,
+                format: TextureFormat::Rgba8Unorm,
+                image_count: 2,
+            })
+            .unwrap();
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+
+        let token = device.begin_frame(&mut swapchain).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.begin().unwrap();
+        cmd.begin_rendering(RenderingDescriptor {
+            color_attachments: &[RenderingAttachment {
+                view: &swapchain.views[token.image_index as usize],
+                layout: ImageLayout::General,
+                load_op: AttachmentLoadOp::Clear,
+                store_op: AttachmentStoreOp::Store,
+                clear_value: ClearValue::Color([1.0, 0.0, 1.0, 1.0]),
+            }],
+            depth_attachment: None,
+            stencil_attachment: None,
+            view_mask: 0,
+        });
+        cmd.end_rendering();
+        cmd.end().unwrap();
+        device.end_frame(&mut swapchain, token, &[&cmd]).unwrap();
+
+        let image = device.read_presented_image(&swapchain).unwrap();
+        assert_eq!(image.dimensions(), (4, 2));
+        assert!(image.pixels().all(|p| p.0 == [255, 0, 255, 255]));
+    }
+
+    #[test]
+    fn copies_texture_region_with_row_pitch() {
+        let device = device();
+        let texels: Vec<u32> = (0..32).collect();
+        let upload = storage_buffer(&device, bytemuck::cast_slice(&texels));
+        let texture = device
+            .create_texture(TextureDescriptor {
+                width: 4,
+                height: 4,
+                depth: 1,
+                mip_level_count: 1,
+                format: TextureFormat::Rg32Uint,
+                usage: TextureUsage::COPY_SRC | TextureUsage::COPY_DST,
+            })
+            .unwrap();
+        let readback = storage_buffer(&device, &[0; 64]);
+
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.begin().unwrap();
+        cmd.copy_buffer_to_texture(&upload, &texture, 4, 4);
+        // Empty copies are valid and change nothing.
+        cmd.copy_buffer_to_texture(&upload, &texture, 0, 4);
+        cmd.copy_texture_to_buffer(&texture, &readback, TextureCopyRegion {
+            buffer_offset: 8,
+            bytes_per_row: 24,
+            x: 1,
+            y: 2,
+            width: 2,
+            height: 2,
+        });
+        cmd.end().unwrap();
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).unwrap();
+
+        assert_eq!(device.read_texture(&texture).unwrap(), bytemuck::cast_slice::<u32, u8>(&texels));
+        let mut copied = [0u32; 16];
+        readback.read_data(0, bytemuck::cast_slice_mut(&mut copied)).unwrap();
+        // Texel (x, y) holds [2 * (4y + x), 2 * (4y + x) + 1]; rows start 24 bytes apart.
+        assert_eq!(copied, [0, 0, 18, 19, 20, 21, 0, 0, 26, 27, 28, 29, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rejects_texture_regions_past_the_edge() {
+        let device = device();
+        let texture = float_texture(&device, 4, 4, 1, TextureFormat::R32Float, &[0.0; 16]);
+        let readback = storage_buffer(&device, &[0; 64]);
+        let region = TextureCopyRegion { buffer_offset: 0, bytes_per_row: 0, x: 0, y: 0, width: 1, height: 1 };
+        // Offsets that would wrap around when added to the size must fail too.
+        for region in [TextureCopyRegion { x: 3, width: 2, ..region }, TextureCopyRegion { x: u32::MAX, ..region }, TextureCopyRegion { y: u32::MAX, ..region }] {
+            let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+            let mut cmd = pool.allocate_command_buffer().unwrap();
+            cmd.begin().unwrap();
+            cmd.copy_texture_to_buffer(&texture, &readback, region);
+            cmd.end().unwrap();
+            assert!(device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).is_err(), "{:?}", region);
+        }
+    }
+
+    #[test]
+    fn dispatch_indirect_reads_group_counts_from_buffer() {
+        let device = device();
+        let spirv = lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Glsl {
+            source: include_str!("../../lume-examples/shaders/test.comp"),
+            stage: naga::ShaderStage::Compute,
+            defines: Default::default(),
+        }).unwrap();
+        let shader = device.create_shader_module(&spirv).unwrap();
+
+        let input = vec![1.0f32; 64];
+        let buffer = storage_buffer(&device, bytemuck::cast_slice(&input));
+        let layout = layout(&device, &[BindingType::StorageBuffer]);
+        let group = bind_group(&device, &layout, &[&buffer]);
+        let pipeline_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&layout], push_constant_ranges: &[] }).unwrap();
+        let pipeline = device.create_compute_pipeline(ComputePipelineDescriptor { shader: &shader, layout: &pipeline_layout }).unwrap();
+        // A zero-sized dispatch followed by a single workgroup, 16 bytes in.
+        let args = storage_buffer(&device, bytemuck::cast_slice(&[0u32, 1, 1, 0, 1, 1, 1]));
+
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.begin().unwrap();
+        cmd.bind_compute_pipeline(&pipeline);
+        cmd.bind_bind_group(0, &group);
+        cmd.dispatch_indirect(&args, 0);
+        cmd.dispatch_indirect(&args, 16);
+        cmd.end().unwrap();
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).unwrap();
+
+        let mut output = vec![0f32; 64];
+        buffer.read_data(0, bytemuck::cast_slice_mut(&mut output)).unwrap();
+        assert_eq!(output, vec![2.0; 64]);
+    }
+
+    #[test]
+    fn push_constants_reach_the_shader() {
+        let device = device();
+        let spirv = lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Wgsl(
+            "struct Params { scale: f32, bias: f32 }
+             var<push_constant> params: Params;
+             @group(0) @binding(0) var<storage, read_write> data: array<f32>;
+             @compute @workgroup_size(16)
+             fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+                 data[id.x] = data[id.x] * params.scale + params.bias;
+             }",
+        )).unwrap();
+        let shader = device.create_shader_module(&spirv).unwrap();
+
+        let buffer = storage_buffer(&device, bytemuck::cast_slice(&[1.0f32; 16]));
+        let layout = layout(&device, &[BindingType::StorageBuffer]);
+        let group = bind_group(&device, &layout, &[&buffer]);
+        let pipeline_layout = device.create_pipeline_layout(PipelineLayoutDescriptor {
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[PushConstantRange { stages: ShaderStage::COMPUTE, offset: 0, size: 8 }],
+        }).unwrap();
+        let pipeline = device.create_compute_pipeline(ComputePipelineDescriptor { shader: &shader, layout: &pipeline_layout }).unwrap();
+
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.begin().unwrap();
+        cmd.bind_compute_pipeline(&pipeline);
+        cmd.bind_bind_group(0, &group);
+        cmd.set_push_constants(ShaderStage::COMPUTE, 0, bytemuck::cast_slice(&[3.0f32, 0.0]));
+        cmd.set_push_constants(ShaderStage::COMPUTE, 4, bytemuck::cast_slice(&[0.5f32]));
+        cmd.dispatch(1, 1, 1);
+        cmd.end().unwrap();
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).unwrap();
+
+        let mut output = [0f32; 16];
+        buffer.read_data(0, bytemuck::cast_slice_mut(&mut output)).unwrap();
+        assert_eq!(output, [3.5; 16]);
+    }
+
+    #[test]
+    fn timeline_semaphore_orders_submissions() {
+        let device = device();
+        let timeline = device.create_timeline_semaphore(0).unwrap();
+        let pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = pool.allocate_command_buffer().unwrap();
+        cmd.begin().unwrap();
+        cmd.end().unwrap();
+
+        assert!(device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[(&timeline, 1)], &[], None).is_err());
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[(&timeline, 1)], None).unwrap();
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[(&timeline, 1)], &[(&timeline, 5)], None).unwrap();
+        assert_eq!(timeline.current_value().unwrap(), 5);
+        assert!(timeline.signal(5).is_err());
+
+        let waiter = std::thread::spawn({
+            let timeline = CpuTimelineSemaphore { state: timeline.state.clone() };
+            move || timeline.wait(7, u64::MAX)
+        });
+        timeline.signal(7).unwrap();
+        waiter.join().unwrap().unwrap();
+        assert!(timeline.wait(8, 1_000).is_err());
+    }
+}
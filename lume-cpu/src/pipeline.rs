@@ -0,0 +1,371 @@
+use lume_core::device::{AttachmentLoadOp, ClearValue, QueueType, TextureCopyRegion, TextureFormat};
+use lume_core::{LumeError, LumeResult};
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::interp::{self, DispatchResources, Program, Slot};
+use crate::{CpuBindGroup, CpuBuffer, CpuDevice, CpuTexture, CpuTextureView};
+use crate::device::CpuBindingResource;
+use crate::texture::CpuTextureInner;
+
+pub struct CpuShaderModule {
+    pub module: Arc<naga::Module>,
+}
+
+impl lume_core::device::ShaderModule for CpuShaderModule {}
+
+pub struct CpuRenderPass {
+    pub color_format: TextureFormat,
+    pub depth_stencil_format: Option<TextureFormat>,
+    pub load_op: lume_core::device::AttachmentLoadOp,
+}
+
+impl lume_core::device::RenderPass for CpuRenderPass {}
+
+pub struct CpuPipelineLayout {
+    pub bind_group_count: usize,
+}
+
+impl lume_core::device::PipelineLayout for CpuPipelineLayout {}
+
+/// Graphics pipelines are accepted so frame code runs unchanged, but the CPU
+/// backend has no rasterizer.
+pub struct CpuGraphicsPipeline;
+
+impl lume_core::device::GraphicsPipeline for CpuGraphicsPipeline {}
+
+pub struct CpuComputePipeline {
+    pub program: Arc<Program>,
+}
+
+impl lume_core::device::ComputePipeline for CpuComputePipeline {}
+
+pub struct CpuFramebuffer {
+    pub attachments: Vec<CpuTextureView>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl lume_core::device::Framebuffer for CpuFramebuffer {}
+
+pub struct CpuCommandPool {
+    pub device: CpuDevice,
+}
+
+impl lume_core::device::CommandPool for CpuCommandPool {
+    type Device = CpuDevice;
+    type CommandBuffer = CpuCommandBuffer;
+
+    fn allocate_command_buffer(&self) -> LumeResult<Self::CommandBuffer> {
+        Ok(CpuCommandBuffer { commands: Vec::new() })
+    }
+}
+
+/// Recorded work, replayed in order when the command buffer is submitted.
+pub(crate) enum Command {
+    BindComputePipeline(Arc<Program>),
+    BindGroup(u32, CpuBindGroup),
+    SetPushConstants { offset: u32, data: Vec<u8> },
+    Dispatch([u32; 3]),
+    DispatchIndirect { buffer: CpuBuffer, offset: u64 },
+    CopyBufferToBuffer { source: CpuBuffer, destination: CpuBuffer, size: u64 },
+    CopyBufferToTexture { buffer: CpuBuffer, texture: CpuTexture, width: u32, height: u32 },
+    CopyTextureToBuffer { texture: CpuTexture, buffer: CpuBuffer, region: TextureCopyRegion },
+    /// Fills a view with a color, or with a depth value in the first component.
+    Clear { view: CpuTextureView, value: [f32; 4] },
+    Draw,
+}
+
+pub struct CpuCommandBuffer {
+    pub(crate) commands: Vec<Command>,
+}
+
+impl CpuCommandBuffer {
+    /// Executes the recorded commands against host memory.
+    pub(crate) fn execute(&self) -> LumeResult<()> {
+        let mut pipeline: Option<&Arc<Program>> = None;
+        let mut bind_groups: HashMap<u32, &CpuBindGroup> = HashMap::new();
+        let mut push_constants: Vec<u8> = Vec::new();
+
+        for command in &self.commands {
+            match command {
+                Command::BindComputePipeline(program) => pipeline = Some(program),
+                Command::BindGroup(index, group) => {
+                    bind_groups.insert(*index, group);
+                }
+                Command::SetPushConstants { offset, data } => {
+                    let end = *offset as usize + data.len();
+                    if push_constants.len() < end {
+                        push_constants.resize(end, 0);
+                    }
+                    push_constants[*offset as usize..end].copy_from_slice(data);
+                }
+                Command::Dispatch(groups) => {
+                    let program = pipeline.ok_or(LumeError::Generic("Dispatch without a bound compute pipeline"))?;
+                    dispatch(program, &bind_groups, &push_constants, *groups)?;
+                }
+                Command::DispatchIndirect { buffer, offset } => {
+                    let program = pipeline.ok_or(LumeError::Generic("Dispatch without a bound compute pipeline"))?;
+                    let mut args = [0u8; 12];
+                    lume_core::device::Buffer::read_data(buffer, *offset, &mut args)?;
+                    let groups = [0, 4, 8].map(|i| u32::from_le_bytes(args[i..i + 4].try_into().unwrap()));
+                    dispatch(program, &bind_groups, &push_constants, groups)?;
+                }
+                Command::CopyBufferToBuffer { source, destination, size } => {
+                    let mut data = vec![0; *size as usize];
+                    lume_core::device::Buffer::read_data(source, 0, &mut data)?;
+                    lume_core::device::Buffer::write_data(destination, 0, &data)?;
+                }
+                Command::CopyBufferToTexture { buffer, texture, width, height } => copy_buffer_to_texture(buffer, &texture.inner, *width, *height)?,
+                Command::CopyTextureToBuffer { texture, buffer, region } => copy_texture_to_buffer(&texture.inner, buffer, region)?,
+                Command::Clear { view, value } => clear(view, *value)?,
+                Command::Draw => log::warn!("CPU backend has no rasterizer; draw call skipped"),
+            }
+        }
+        Ok(())
+    }
+
+    fn clear_attachment(&mut self, view: &CpuTextureView, load_op: &AttachmentLoadOp, value: &ClearValue) {
+        if let AttachmentLoadOp::Clear = load_op {
+            let value = match *value {
+                ClearValue::Color(c) => c,
+                ClearValue::DepthStencil(d, _) => [d, 0.0, 0.0, 0.0],
+            };
+            self.commands.push(Command::Clear { view: view.clone(), value });
+        }
+    }
+}
+
+fn clear(view: &CpuTextureView, value: [f32; 4]) -> LumeResult<()> {
+    let texel = view.texture.format.texel_size() as usize;
+    let mut pattern = vec![0u8; texel];
+    interp::encode_color(view.format, value, &mut pattern)?;
+    let range = view.texture.mip_range(view.base_mip_level, view.mip_level_count);
+    for chunk in view.texture.data.lock().unwrap()[range].chunks_exact_mut(texel) {
+        chunk.copy_from_slice(&pattern);
+    }
+    Ok(())
+}
+
+fn copy_buffer_to_texture(buffer: &CpuBuffer, texture: &CpuTextureInner, width: u32, height: u32) -> LumeResult<()> {
+    if width > texture.width || height > texture.height {
+        return Err(LumeError::BackendError(format!(
+            "Copy of {}x{} texels exceeds {}x{} texture", width, height, texture.width, texture.height
+        )));
+    }
+    if width == 0 || height == 0 {
+        return Ok(());
+    }
+    let texel = texture.format.texel_size() as usize;
+    let row = width as usize * texel;
+    let pitch = texture.width as usize * texel;
+    let mut data = vec![0; row * height as usize];
+    lume_core::device::Buffer::read_data(buffer, 0, &mut data)?;
+    let mut target = texture.data.lock().unwrap();
+    for (y, src) in data.chunks_exact(row).enumerate() {
+        target[y * pitch..y * pitch + row].copy_from_slice(src);
+    }
+    Ok(())
+}
+
+fn copy_texture_to_buffer(texture: &CpuTextureInner, buffer: &CpuBuffer, region: &TextureCopyRegion) -> LumeResult<()> {
+    let exceeds = |offset: u32, size: u32, extent: u32| offset.checked_add(size).is_none_or(|end| end > extent);
+    if exceeds(region.x, region.width, texture.width) || exceeds(region.y, region.height, texture.height) {
+        return Err(LumeError::BackendError(format!(
+            "Copy of {}x{} texels at ({}, {}) exceeds {}x{} texture",
+            region.width, region.height, region.x, region.y, texture.width, texture.height
+        )));
+    }
+    let texel = texture.format.texel_size() as usize;
+    let row = region.width as usize * texel;
+    let pitch = if region.bytes_per_row == 0 { row } else { region.bytes_per_row as usize };
+    if pitch < row {
+        return Err(LumeError::Generic("Row pitch is smaller than a row of the copied region"));
+    }
+    let source = texture.data.lock().unwrap();
+    for y in 0..region.height as usize {
+        let start = ((region.y as usize + y) * texture.width as usize + region.x as usize) * texel;
+        let offset = region.buffer_offset + (y * pitch) as u64;
+        lume_core::device::Buffer::write_data(buffer, offset, &source[start..start + row])?;
+    }
+    Ok(())
+}
+
+fn dispatch(program: &Program, bind_groups: &HashMap<u32, &CpuBindGroup>, push_constants: &[u8], groups: [u32; 3]) -> LumeResult<()> {
+    let mut resources = DispatchResources { push_constants: push_constants.to_vec(), ..Default::default() };
+    let mut buffers: Vec<&CpuBuffer> = Vec::new();
+    let mut views: Vec<&CpuTextureView> = Vec::new();
+
+    // Resources bound more than once alias the same interpreter memory.
+    for (&group, bind_group) in bind_groups {
+        for (&binding, resource) in bind_group.entries.iter() {
+            let slot = match resource {
+                CpuBindingResource::Buffer(buffer) => {
+                    let index = buffers.iter().position(|b| Arc::ptr_eq(&b.inner, &buffer.inner)).unwrap_or_else(|| {
+                        buffers.push(buffer);
+                        resources.buffers.push(buffer.inner.data.lock().unwrap().clone());
+                        buffers.len() - 1
+                    });
+                    Slot::Buffer(index)
+                }
+                CpuBindingResource::TextureView(view) => {
+                    let same_levels = |v: &&CpuTextureView| {
+                        Arc::ptr_eq(&v.texture, &view.texture) && v.base_mip_level == view.base_mip_level && v.mip_level_count == view.mip_level_count
+                    };
+                    let index = views.iter().position(same_levels).unwrap_or_else(|| {
+                        views.push(view);
+                        let (width, height) = lume_core::device::mip_extent(view.texture.width, view.texture.height, view.base_mip_level);
+                        let range = view.texture.mip_range(view.base_mip_level, view.mip_level_count);
+                        resources.images.push(interp::ImageData {
+                            width,
+                            height,
+                            mip_level_count: view.mip_level_count,
+                            format: view.texture.format,
+                            data: view.texture.data.lock().unwrap()[range].to_vec(),
+                        });
+                        views.len() - 1
+                    });
+                    Slot::Image(index)
+                }
+                CpuBindingResource::Sampler => Slot::Sampler,
+            };
+            resources.bindings.insert((group, binding), slot);
+        }
+    }
+
+    let snapshots: Vec<Vec<u8>> = resources.images.iter().map(|image| image.data.clone()).collect();
+    let resources = program.dispatch(resources, groups)?;
+    for (buffer, data) in buffers.iter().zip(resources.buffers) {
+        *buffer.inner.data.lock().unwrap() = data;
+    }
+    // Views of overlapping levels, like all of an HZB next to the level being written, each
+    // have their own copy; only the levels a view changed are written back.
+    for ((view, image), snapshot) in views.iter().zip(resources.images).zip(snapshots) {
+        let start = view.texture.mip_range(view.base_mip_level, 0).start;
+        let mut data = view.texture.data.lock().unwrap();
+        for level in view.base_mip_level..view.base_mip_level + view.mip_level_count {
+            let range = view.texture.mip_range(level, 1);
+            let local = range.start - start..range.end - start;
+            if image.data[local.clone()] != snapshot[local.clone()] {
+                data[range].copy_from_slice(&image.data[local]);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl lume_core::device::CommandBuffer for CpuCommandBuffer {
+    type Device = CpuDevice;
+
+    fn reset(&mut self) -> LumeResult<()> {
+        self.commands.clear();
+        Ok(())
+    }
+
+    fn begin(&mut self) -> LumeResult<()> {
+        self.commands.clear();
+        Ok(())
+    }
+
+    fn end(&mut self) -> LumeResult<()> {
+        Ok(())
+    }
+
+    fn begin_render_pass(&mut self, render_pass: &CpuRenderPass, framebuffer: &CpuFramebuffer, clear_color: [f32; 4]) {
+        if render_pass.load_op == lume_core::device::AttachmentLoadOp::Load {
+            return;
+        }
+        for view in &framebuffer.attachments {
+            let value = if Some(view.format) == render_pass.depth_stencil_format {
+                [1.0, 0.0, 0.0, 0.0]
+            } else {
+                clear_color
+            };
+            self.commands.push(Command::Clear { view: view.clone(), value });
+        }
+    }
+
+    fn end_render_pass(&mut self) {}
+
+    fn begin_rendering(&mut self, descriptor: lume_core::device::RenderingDescriptor<Self::Device>) {
+        for attachment in descriptor.color_attachments {
+            self.clear_attachment(attachment.view, &attachment.load_op, &attachment.clear_value);
+        }
+        if let Some(attachment) = &descriptor.depth_attachment {
+            self.clear_attachment(attachment.view, &attachment.load_op, &attachment.clear_value);
+        }
+    }
+
+    fn end_rendering(&mut self) {}
+
+    fn bind_graphics_pipeline(&mut self, _pipeline: &CpuGraphicsPipeline) {}
+
+    fn bind_compute_pipeline(&mut self, pipeline: &CpuComputePipeline) {
+        self.commands.push(Command::BindComputePipeline(pipeline.program.clone()));
+    }
+
+    fn bind_vertex_buffer(&mut self, _buffer: &CpuBuffer) {}
+
+    fn bind_index_buffer(&mut self, _buffer: &CpuBuffer, _format: lume_core::device::IndexFormat) {}
+
+    fn bind_bind_group(&mut self, index: u32, bind_group: &CpuBindGroup) {
+        self.commands.push(Command::BindGroup(index, bind_group.clone()));
+    }
+
+    fn set_push_constants(&mut self, _stages: lume_core::device::ShaderStage, offset: u32, data: &[u8]) {
+        self.commands.push(Command::SetPushConstants { offset, data: data.to_vec() });
+    }
+
+    fn set_viewport(&mut self, _x: f32, _y: f32, _width: f32, _height: f32) {}
+
+    fn set_scissor(&mut self, _x: i32, _y: i32, _width: u32, _height: u32) {}
+
+    fn draw(&mut self, _vertex_count: u32, _instance_count: u32, _first_vertex: u32, _first_instance: u32) {
+        self.commands.push(Command::Draw);
+    }
+
+    fn draw_indexed(&mut self, _index_count: u32, _instance_count: u32, _first_index: u32, _base_vertex: i32, _first_instance: u32) {
+        self.commands.push(Command::Draw);
+    }
+
+    fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.commands.push(Command::Dispatch([x, y, z]));
+    }
+
+    fn draw_indirect(&mut self, _buffer: &CpuBuffer, _offset: u64, _draw_count: u32, _stride: u32) {
+        self.commands.push(Command::Draw);
+    }
+
+    fn draw_indirect_count(&mut self, _buffer: &CpuBuffer, _offset: u64, _count_buffer: &CpuBuffer, _count_offset: u64, _max_draw_count: u32, _stride: u32) {
+        self.commands.push(Command::Draw);
+    }
+
+    fn draw_indexed_indirect(&mut self, _buffer: &CpuBuffer, _offset: u64, _draw_count: u32, _stride: u32) {
+        self.commands.push(Command::Draw);
+    }
+
+    fn dispatch_indirect(&mut self, buffer: &CpuBuffer, offset: u64) {
+        self.commands.push(Command::DispatchIndirect { buffer: buffer.clone(), offset });
+    }
+
+    fn copy_buffer_to_buffer(&mut self, source: &CpuBuffer, destination: &CpuBuffer, size: u64) {
+        self.commands.push(Command::CopyBufferToBuffer { source: source.clone(), destination: destination.clone(), size });
+    }
+
+    fn copy_buffer_to_texture(&mut self, buffer: &CpuBuffer, texture: &CpuTexture, width: u32, height: u32) {
+        self.commands.push(Command::CopyBufferToTexture { buffer: buffer.clone(), texture: texture.clone(), width, height });
+    }
+
+    fn copy_texture_to_buffer(&mut self, texture: &CpuTexture, buffer: &CpuBuffer, region: TextureCopyRegion) {
+        self.commands.push(Command::CopyTextureToBuffer { texture: texture.clone(), buffer: buffer.clone(), region });
+    }
+
+    // Commands execute in recording order, so barriers have nothing to do.
+    fn texture_barrier(&mut self, _texture: &CpuTexture, _old_layout: lume_core::device::ImageLayout, _new_layout: lume_core::device::ImageLayout) {}
+
+    fn compute_barrier(&mut self) {}
+
+    // There is a single implicit queue, so ownership never changes hands.
+    fn buffer_ownership_barrier(&mut self, _buffer: &CpuBuffer, _src_queue: QueueType, _dst_queue: QueueType) {}
+
+    fn texture_ownership_barrier(&mut self, _texture: &CpuTexture, _src_queue: QueueType, _dst_queue: QueueType) {}
+}
@@ -0,0 +1,30 @@
+use lume_core::LumeResult;
+use crate::{CpuTexture, CpuTextureView};
+
+/// A ring of offscreen color targets that stands in for a window swapchain.
+pub struct CpuSwapchain {
+    pub textures: Vec<CpuTexture>,
+    pub views: Vec<CpuTextureView>,
+    pub current_image: u32,
+    /// Image passed to the last `present`, available for readback.
+    pub presented_image: Option<u32>,
+}
+
+impl lume_core::device::Swapchain for CpuSwapchain {
+    type TextureView = CpuTextureView;
+
+    fn present(&mut self, image_index: u32, _wait_semaphores: &[&impl lume_core::device::Semaphore]) -> LumeResult<()> {
+        self.presented_image = Some(image_index);
+        Ok(())
+    }
+
+    fn acquire_next_image(&mut self, _signal_semaphore: &impl lume_core::device::Semaphore) -> LumeResult<u32> {
+        let image_index = self.current_image;
+        self.current_image = (self.current_image + 1) % self.views.len() as u32;
+        Ok(image_index)
+    }
+
+    fn get_view(&self, index: u32) -> &Self::TextureView {
+        &self.views[index as usize]
+    }
+}
@@ -0,0 +1,48 @@
+use lume_core::device::{TextureFormat, TextureUsage};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+pub struct CpuTextureInner {
+    pub width: u32,
+    pub height: u32,
+    pub mip_level_count: u32,
+    pub format: TextureFormat,
+    pub usage: TextureUsage,
+    /// Every mip level one after another, each in tightly packed rows of texels in `format`.
+    pub data: Mutex<Vec<u8>>,
+}
+
+impl CpuTextureInner {
+    /// Byte range in `data` of `count` levels from `base` on.
+    pub fn mip_range(&self, base: u32, count: u32) -> Range<usize> {
+        let level_size = |level| {
+            let (width, height) = lume_core::device::mip_extent(self.width, self.height, level);
+            width as usize * height as usize * self.format.texel_size() as usize
+        };
+        let start = (0..base).map(level_size).sum::<usize>();
+        start..start + (base..base + count).map(level_size).sum::<usize>()
+    }
+}
+
+#[derive(Clone)]
+pub struct CpuTexture {
+    pub inner: Arc<CpuTextureInner>,
+}
+
+impl lume_core::device::Texture for CpuTexture {}
+
+#[derive(Clone)]
+pub struct CpuTextureView {
+    pub texture: Arc<CpuTextureInner>,
+    pub format: TextureFormat,
+    pub base_mip_level: u32,
+    pub mip_level_count: u32,
+}
+
+impl lume_core::device::TextureView for CpuTextureView {}
+
+pub struct CpuSampler {
+    pub descriptor: lume_core::device::SamplerDescriptor,
+}
+
+impl lume_core::device::Sampler for CpuSampler {}
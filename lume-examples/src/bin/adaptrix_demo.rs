@@ -11,7 +11,7 @@ use lume_core::{
 use lume_vulkan::{VulkanInstance, VulkanDevice};
 use lume_adaptrix::{AdaptrixFlatAsset, renderer::{AdaptrixMeshGPU, AdaptrixRenderer}};
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::BufReader;
 use glam::{Mat4, Vec3};
 
 struct App {
@@ -53,7 +53,7 @@ impl ApplicationHandler for App {
         let size = window.inner_size();
         let swapchain = device.create_swapchain(&surface, SwapchainDescriptor { width: size.width, height: size.height }).unwrap();
 
-        let command_pool = device.create_command_pool().unwrap();
+        let command_pool = device.create_command_pool(QueueType::Graphics).unwrap();
         let command_buffers = vec![command_pool.allocate_command_buffer().unwrap()];
 
         let file = File::open("test.lad").expect("test.lad not found!");
@@ -62,11 +62,11 @@ impl ApplicationHandler for App {
 
         let uniform_buffer = device.create_buffer(BufferDescriptor { size: 64, usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap();
 
-        let depth_texture = device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, format: TextureFormat::Depth32Float, usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT }).unwrap();
-        let depth_view = device.create_texture_view(&depth_texture, TextureViewDescriptor { format: None }).unwrap();
+        let depth_texture = device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, mip_level_count: 1, format: TextureFormat::Depth32Float, usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT }).unwrap();
+        let depth_view = device.create_texture_view(&depth_texture, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap();
 
-        let vis_texture = device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, format: TextureFormat::R32G32Uint, usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING }).unwrap();
-        let vis_view = device.create_texture_view(&vis_texture, TextureViewDescriptor { format: None }).unwrap();
+        let vis_texture = device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, mip_level_count: 1, format: TextureFormat::R32G32Uint, usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING }).unwrap();
+        let vis_view = device.create_texture_view(&vis_texture, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap();
 
         // Pass 1 Layout
         let vis_bg_layout = device.create_bind_group_layout(BindGroupLayoutDescriptor {
@@ -78,7 +78,7 @@ impl ApplicationHandler for App {
                 BindGroupLayoutEntry { binding: 4, visibility: ShaderStage::VERTEX, ty: BindingType::UniformBuffer },
             ],
         }).unwrap();
-        let vis_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&vis_bg_layout] }).unwrap();
+        let vis_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&vis_bg_layout], push_constant_ranges: &[] }).unwrap();
 
         // Pass 2 Layout
         let res_bg_layout = device.create_bind_group_layout(BindGroupLayoutDescriptor {
@@ -86,7 +86,7 @@ impl ApplicationHandler for App {
                 BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::FRAGMENT, ty: BindingType::SampledTexture },
             ],
         }).unwrap();
-        let res_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&res_bg_layout] }).unwrap();
+        let res_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&res_bg_layout], push_constant_ranges: &[] }).unwrap();
 
         let vis_pass = device.create_render_pass(RenderPassDescriptor { color_format: TextureFormat::R32G32Uint, depth_stencil_format: Some(TextureFormat::Depth32Float) }).unwrap();
         let vis_framebuffer = device.create_framebuffer(FramebufferDescriptor { render_pass: &vis_pass, attachments: &[&vis_view, &depth_view], width: size.width, height: size.height }).unwrap();
@@ -198,10 +198,9 @@ impl ApplicationHandler for App {
 }
 
 fn load_spv(path: &str) -> Vec<u32> {
-    let mut file = File::open(path).expect(&format!("MISSING SHADER: {}", path));
-    let mut data = Vec::new();
-    file.read_to_end(&mut data).unwrap();
-    data.chunks_exact(4).map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]])).collect()
+    let data = std::fs::read(path).unwrap_or_else(|e| panic!("MISSING SHADER: {}: {}", path, e));
+    // Not `ShaderSource::SpirV`: naga cannot parse the atomics in cluster_cull.comp.spv.
+    lume_core::shader::spirv_words(&data).unwrap_or_else(|e| panic!("{}: {}", path, e))
 }
 
 fn main() {
@@ -0,0 +1,51 @@
+use lume_core::{Instance, InstanceDescriptor, Backend, Device, device::{OffscreenSwapchainDescriptor, TextureFormat, Swapchain, CommandPool, CommandBuffer, RenderingDescriptor, RenderingAttachment, ImageLayout, AttachmentLoadOp, AttachmentStoreOp, ClearValue, QueueType}};
+use lume_vulkan::VulkanInstance;
+
+fn main() {
+    env_logger::init();
+
+    let instance_desc = InstanceDescriptor {
+        name: "Lume Headless Example",
+        backend: Backend::Vulkan,
+    };
+
+    let instance = VulkanInstance::new(instance_desc).expect("Failed to create Lume Instance");
+    let device = instance.request_device(None).expect("Failed to request device");
+
+    let image = render_thumbnail(&device, 256, 256);
+    image.save("headless.png").expect("Failed to save image");
+    println!("Saved headless.png ({}x{})", image.width(), image.height());
+}
+
+/// Renders a single frame without a window and reads it back.
+fn render_thumbnail<D: Device>(device: &D, width: u32, height: u32) -> image::RgbaImage {
+    let mut swapchain = device.create_offscreen_swapchain(OffscreenSwapchainDescriptor {
+        width,
+        height,
+        format: TextureFormat::Rgba8Unorm,
+        image_count: 2,
+    }).expect("Failed to create offscreen swapchain");
+
+    let command_pool = device.create_command_pool(QueueType::Graphics).expect("Failed to create command pool");
+    let mut cmd = command_pool.allocate_command_buffer().expect("Failed to allocate command buffer");
+
+    let token = device.begin_frame(&mut swapchain).expect("Failed to begin frame");
+    cmd.begin().expect("Failed to begin command buffer");
+    cmd.begin_rendering(RenderingDescriptor {
+        color_attachments: &[RenderingAttachment {
+            view: swapchain.get_view(token.image_index),
+            layout: ImageLayout::General,
+            load_op: AttachmentLoadOp::Clear,
+            store_op: AttachmentStoreOp::Store,
+            clear_value: ClearValue::Color([0.1, 0.2, 0.3, 1.0]),
+        }],
+        depth_attachment: None,
+        stencil_attachment: None,
+        view_mask: 0,
+    });
+    cmd.end_rendering();
+    cmd.end().expect("Failed to end command buffer");
+    device.end_frame(&mut swapchain, token, &[&cmd]).expect("Failed to end frame");
+
+    device.read_presented_image(&swapchain).expect("Failed to read back image")
+}
@@ -1,4 +1,4 @@
-use lume_core::{Instance, InstanceDescriptor, Backend, Device, device::{BufferDescriptor, BufferUsage, ShaderStage, BindingType, BindGroupLayoutDescriptor, BindGroupLayoutEntry, PipelineLayoutDescriptor, ComputePipelineDescriptor, CommandPool, CommandBuffer, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer}};
+use lume_core::{Instance, InstanceDescriptor, Backend, Device, shader::{compile_shader, ShaderSource}, device::{BufferDescriptor, BufferUsage, ShaderStage, BindingType, BindGroupLayoutDescriptor, BindGroupLayoutEntry, PipelineLayoutDescriptor, ComputePipelineDescriptor, CommandPool, CommandBuffer, BindGroupDescriptor, BindGroupEntry, BindingResource, Buffer, QueueType}};
 use lume_vulkan::{VulkanInstance};
 
 fn main() {
@@ -34,11 +34,8 @@ fn run_compute<D: Device>(device: &D) {
     buffer.write_data(0, data_bytes).expect("Failed to write data");
 
     // 3. Setup Pipeline
-    let shader_spv = include_bytes!("../../shaders/test.comp.spv");
-    let shader_code = unsafe {
-        std::slice::from_raw_parts(shader_spv.as_ptr() as *const u32, shader_spv.len() / 4)
-    };
-    let shader_module = device.create_shader_module(shader_code).expect("Failed to create shader module");
+    let shader_code = compile_shader(ShaderSource::SpirV(include_bytes!("../../shaders/test.comp.spv"))).expect("Invalid SPIR-V");
+    let shader_module = device.create_shader_module(&shader_code).expect("Failed to create shader module");
 
     let bind_group_layout = device.create_bind_group_layout(BindGroupLayoutDescriptor {
         entries: vec![
@@ -52,6 +49,7 @@ fn run_compute<D: Device>(device: &D) {
 
     let layout = device.create_pipeline_layout(PipelineLayoutDescriptor {
         bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
     }).expect("Failed to create layout");
 
     let pipeline = device.create_compute_pipeline(ComputePipelineDescriptor {
@@ -70,7 +68,7 @@ fn run_compute<D: Device>(device: &D) {
     }).expect("Failed to create bind group");
 
     // 4. Dispatch
-    let command_pool = device.create_command_pool().expect("Failed to create command pool");
+    let command_pool = device.create_command_pool(QueueType::Graphics).expect("Failed to create command pool");
     let mut cmd = command_pool.allocate_command_buffer().expect("Failed to allocate command buffer");
 
     cmd.begin().expect("Failed to begin cmd");
@@ -80,7 +78,7 @@ fn run_compute<D: Device>(device: &D) {
     cmd.compute_barrier();
     cmd.end().expect("Failed to end cmd");
 
-    device.submit(&[&cmd], &[], &[], None).expect("Failed to submit compute cmd");
+    device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).expect("Failed to submit compute cmd");
     device.wait_idle().expect("Wait idle failed");
 
     // 5. Read back
@@ -7,7 +7,7 @@ use std::sync::Arc;
 use std::time::SystemTime;
 use image::GenericImageView;
 use glam::{Mat4, Vec3};
-use lume_core::{Instance, InstanceDescriptor, Backend, Device, shader::{compile_shader, ShaderSource}, device::{SwapchainDescriptor, RenderPassDescriptor, TextureFormat, PipelineLayoutDescriptor, GraphicsPipelineDescriptor, PrimitiveState, PrimitiveTopology, CommandPool, CommandBuffer, FramebufferDescriptor, Swapchain, Buffer, BindGroupLayoutDescriptor, BindGroupLayoutEntry, ShaderStage, BindingType, BindGroupDescriptor, BindGroupEntry, BindingResource, TextureDescriptor, TextureUsage, SamplerDescriptor, FilterMode, AddressMode, TextureViewDescriptor, ImageLayout, DepthStencilState, CompareFunction}};
+use lume_core::{Instance, InstanceDescriptor, Backend, Device, shader::{compile_shader, ShaderSource}, device::{SwapchainDescriptor, RenderPassDescriptor, TextureFormat, PipelineLayoutDescriptor, GraphicsPipelineDescriptor, PrimitiveState, PrimitiveTopology, CommandPool, CommandBuffer, FramebufferDescriptor, Swapchain, Buffer, BindGroupLayoutDescriptor, BindGroupLayoutEntry, ShaderStage, BindingType, BindGroupDescriptor, BindGroupEntry, BindingResource, TextureDescriptor, TextureUsage, SamplerDescriptor, FilterMode, AddressMode, TextureViewDescriptor, ImageLayout, DepthStencilState, CompareFunction, QueueType, AttachmentLoadOp}};
 use lume_vulkan::VulkanInstance;
 
 struct App {
@@ -75,6 +75,7 @@ impl ApplicationHandler for App {
                 width,
                 height,
                 depth: 1,
+                mip_level_count: 1,
                 format: TextureFormat::Rgba8Unorm,
                 usage: TextureUsage::TEXTURE_BINDING | TextureUsage::COPY_DST,
             }).expect("Failed to create texture");
@@ -88,7 +89,7 @@ impl ApplicationHandler for App {
             staging_buffer.write_data(0, pixels).expect("Failed to write to staging buffer");
 
             // Upload texture
-            let command_pool = device.create_command_pool().expect("Failed to create command pool");
+            let command_pool = device.create_command_pool(QueueType::Graphics).expect("Failed to create command pool");
             let mut upload_cmd = command_pool.allocate_command_buffer().expect("Failed to allocate upload cmd");
             
             upload_cmd.begin().expect("Failed to begin upload cmd");
@@ -97,11 +98,13 @@ impl ApplicationHandler for App {
             upload_cmd.texture_barrier(&texture, ImageLayout::TransferDst, ImageLayout::ShaderReadOnly);
             upload_cmd.end().expect("Failed to end upload cmd");
 
-            device.submit(&[&upload_cmd], &[], &[], None).expect("Failed to submit texture upload");
+            device.submit(QueueType::Graphics, &[&upload_cmd], &[], &[], &[], &[], None).expect("Failed to submit texture upload");
             device.wait_idle().expect("Wait idle failed");
 
             let texture_view = device.create_texture_view(&texture, TextureViewDescriptor {
                 format: Some(TextureFormat::Rgba8Unorm),
+                base_mip_level: 0,
+                mip_level_count: None,
             }).expect("Failed to create texture view");
 
             let sampler = device.create_sampler(SamplerDescriptor {
@@ -116,12 +119,15 @@ impl ApplicationHandler for App {
                 width: size.width,
                 height: size.height,
                 depth: 1,
+                mip_level_count: 1,
                 format: TextureFormat::Depth32Float,
                 usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT,
             }).expect("Failed to create depth texture");
 
             let depth_view = device.create_texture_view(&depth_texture, TextureViewDescriptor {
                 format: Some(TextureFormat::Depth32Float),
+                base_mip_level: 0,
+                mip_level_count: None,
             }).expect("Failed to create depth view");
 
             // Load & Compile Shaders using Naga
@@ -150,6 +156,7 @@ impl ApplicationHandler for App {
             let render_pass = device.create_render_pass(RenderPassDescriptor {
                 color_format: TextureFormat::Bgra8UnormSrgb,
                 depth_stencil_format: Some(TextureFormat::Depth32Float),
+                load_op: AttachmentLoadOp::Clear,
             }).expect("Failed to create render pass");
 
             // Create Bind Group Layout
@@ -176,6 +183,7 @@ impl ApplicationHandler for App {
             log::info!("Creating Pipeline Layout...");
             let layout = device.create_pipeline_layout(PipelineLayoutDescriptor {
                 bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
             }).expect("Failed to create layout");
 
             log::info!("Creating Graphics Pipeline...");
@@ -295,7 +303,7 @@ impl ApplicationHandler for App {
             }).expect("Failed to create bind group");
 
             // Create Command Pool
-            let command_pool = device.create_command_pool().expect("Failed to create command pool");
+            let command_pool = device.create_command_pool(QueueType::Graphics).expect("Failed to create command pool");
 
             // Create Framebuffers and Command Buffers
             let mut framebuffers = Vec::new();
@@ -6,12 +6,18 @@ use winit::{
 };
 use lume_core::{Instance, InstanceDescriptor, Backend, Device, device::*};
 use lume_vulkan::VulkanInstance;
+use std::rc::Rc;
 use std::sync::Arc;
 use std::fs::File;
 use std::io::Read;
-use lume_adaptrix::{AdaptrixVertex, Cluster};
+use lume_adaptrix::{AdaptrixVertex, BvhNode, Cluster, MeshInstance, NodeQueue, WorkQueue};
 use bytemuck::{self, Zeroable};
-use glam::{Mat4, Vec4, Vec3};
+use glam::{Mat4, Quat, Vec4, Vec3};
+
+/// The mesh is drawn as a `FOREST_SIZE` x `FOREST_SIZE` grid of instances.
+const FOREST_SIZE: u32 = 32;
+/// Workgroups of the persistent BVH culling threads.
+const BVH_CULL_WORKGROUPS: u32 = 64;
 
 struct AdaptrixApp {
     window: Option<Arc<Window>>,
@@ -22,38 +28,74 @@ struct AdaptrixApp {
     clusters: Vec<Cluster>,
     vertices: Vec<AdaptrixVertex>,
     indices: Vec<u32>,
+    bvh: Vec<BvhNode>,
+    instances: Vec<MeshInstance>,
     cluster_buffer: Option<lume_vulkan::VulkanBuffer>,
     vertex_buffer: Option<lume_vulkan::VulkanBuffer>,
     index_buffer: Option<lume_vulkan::VulkanBuffer>,
-    visible_clusters_buffer: Option<lume_vulkan::VulkanBuffer>,
-    visible_count_buffer: Option<lume_vulkan::VulkanBuffer>,
     instance_buffer: Option<lume_vulkan::VulkanBuffer>,
     view_buffer: Option<lume_vulkan::VulkanBuffer>,
-    cull_pipeline: Option<lume_vulkan::VulkanComputePipeline>,
-    cull_layout: Option<lume_vulkan::VulkanPipelineLayout>,
-    cull_bind_group_0: Option<lume_vulkan::VulkanBindGroup>,
-    cull_bind_group_1: Option<lume_vulkan::VulkanBindGroup>,
+    /// The view the HZB was last built with, which the first occlusion pass tests against.
+    hzb_view_buffer: Option<lume_vulkan::VulkanBuffer>,
+    hzb_view_uniform: Option<ViewUniform>,
+    bvh_buffer: Option<lume_vulkan::VulkanBuffer>,
+    /// (instance, node) entries of the BVH traversal, with their `NodeQueue` header.
+    node_queue_buffer: Option<lume_vulkan::VulkanBuffer>,
+    node_entries_buffer: Option<lume_vulkan::VulkanBuffer>,
+    /// (instance, cluster) pairs of the visible BVH leaves, with their `WorkQueue` header.
+    work_queue_buffer: Option<lume_vulkan::VulkanBuffer>,
+    queue_buffer: Option<lume_vulkan::VulkanBuffer>,
+    /// (instance, cluster) pairs the first occlusion pass rejected, with their `WorkQueue` header.
+    occluded_queue_buffer: Option<lume_vulkan::VulkanBuffer>,
+    occluded_buffer: Option<lume_vulkan::VulkanBuffer>,
+    bvh_cull_pipeline: Option<lume_vulkan::VulkanComputePipeline>,
+    bvh_cull_layout: Option<lume_vulkan::VulkanPipelineLayout>,
+    bvh_cull_bind_group_0: Option<lume_vulkan::VulkanBindGroup>,
+    bvh_cull_bind_group_1: Option<lume_vulkan::VulkanBindGroup>,
+    /// The first and second occlusion culling passes, and the clusters each finds visible.
+    cull_passes: Vec<CullPass>,
+    visible_clusters_buffers: Vec<lume_vulkan::VulkanBuffer>,
     vis_pipeline: Option<lume_vulkan::VulkanGraphicsPipeline>,
     vis_layout: Option<lume_vulkan::VulkanPipelineLayout>,
-    vis_bind_group_0: Option<lume_vulkan::VulkanBindGroup>,
     vis_bind_group_1: Option<lume_vulkan::VulkanBindGroup>,
-    resolve_pipeline: Option<lume_vulkan::VulkanGraphicsPipeline>,
-    resolve_layout: Option<lume_vulkan::VulkanPipelineLayout>,
+    /// Reloads the resolve shaders when their files under `lume-adaptrix/src/shaders` change.
+    shaders: Option<lume_core::shader::ShaderReloader<'static, lume_vulkan::VulkanDevice>>,
+    resolve_pipeline: Option<lume_core::shader::GraphicsPipelineId>,
+    resolve_layout: Option<Rc<lume_vulkan::VulkanPipelineLayout>>,
     resolve_bind_group_0: Option<lume_vulkan::VulkanBindGroup>,
     resolve_bind_group_1: Option<lume_vulkan::VulkanBindGroup>,
     vis_buffer_texture: Option<lume_vulkan::VulkanTexture>,
     vis_buffer_view: Option<lume_vulkan::VulkanTextureView>,
     vis_depth_texture: Option<lume_vulkan::VulkanTexture>,
     vis_depth_view: Option<lume_vulkan::VulkanTextureView>,
+    /// Farthest VisBuffer depth at half resolution and below, one view per mip level.
+    hzb_texture: Option<lume_vulkan::VulkanTexture>,
+    hzb_views: Vec<lume_vulkan::VulkanTextureView>,
+    /// Every level of the HZB, for the occlusion tests.
+    hzb_view: Option<lume_vulkan::VulkanTextureView>,
+    /// Builds the first HZB level from the depth buffer, and every further level from the one before.
+    hzb_pipelines: Vec<(lume_vulkan::VulkanComputePipeline, lume_vulkan::VulkanPipelineLayout)>,
+    hzb_bind_groups: Vec<lume_vulkan::VulkanBindGroup>,
     vis_render_pass: Option<lume_vulkan::VulkanRenderPass>,
+    /// Draws the second pass's clusters over the first's.
+    vis_load_render_pass: Option<lume_vulkan::VulkanRenderPass>,
     vis_framebuffer: Option<lume_vulkan::VulkanFramebuffer>,
-    resolve_render_pass: Option<lume_vulkan::VulkanRenderPass>,
+    resolve_render_pass: Option<Rc<lume_vulkan::VulkanRenderPass>>,
     resolve_framebuffers: Vec<lume_vulkan::VulkanFramebuffer>,
     command_pool: Option<lume_vulkan::VulkanCommandPool>,
     command_buffer: Option<lume_vulkan::VulkanCommandBuffer>,
     start_time: std::time::Instant,
 }
 
+/// One pass of the two-pass occlusion culling, and the VisBuffer draw of the clusters it keeps.
+struct CullPass {
+    pipeline: (lume_vulkan::VulkanComputePipeline, lume_vulkan::VulkanPipelineLayout),
+    bind_groups: Vec<lume_vulkan::VulkanBindGroup>,
+    /// Indirect draw of the visible clusters, filled by the cull pass.
+    draw_args_buffer: lume_vulkan::VulkanBuffer,
+    vis_bind_group_0: lume_vulkan::VulkanBindGroup,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct ViewUniform {
@@ -70,23 +112,37 @@ impl AdaptrixApp {
         let mut file = File::open("test.lad").expect("Failed to open test.lad.");
         let mut magic = [0u8; 4]; file.read_exact(&mut magic).unwrap();
         let mut version = [0u8; 4]; file.read_exact(&mut version).unwrap();
-        let mut counts = [0u8; 12]; file.read_exact(&mut counts).unwrap();
+        let mut counts = [0u8; 16]; file.read_exact(&mut counts).unwrap();
         let cluster_count = u32::from_le_bytes(counts[0..4].try_into().unwrap());
         let vertex_count = u32::from_le_bytes(counts[4..8].try_into().unwrap());
         let index_count = u32::from_le_bytes(counts[8..12].try_into().unwrap());
+        let node_count = u32::from_le_bytes(counts[12..16].try_into().unwrap());
         let mut clusters = vec![Cluster::zeroed(); cluster_count as usize]; file.read_exact(bytemuck::cast_slice_mut(&mut clusters)).unwrap();
         let mut vertices = vec![AdaptrixVertex::zeroed(); vertex_count as usize]; file.read_exact(bytemuck::cast_slice_mut(&mut vertices)).unwrap();
         let mut indices = vec![0u32; index_count as usize]; file.read_exact(bytemuck::cast_slice_mut(&mut indices)).unwrap();
+        let mut bvh = vec![BvhNode::zeroed(); node_count as usize]; file.read_exact(bytemuck::cast_slice_mut(&mut bvh)).unwrap();
+        // 岩石森林：以原点为中心的网格，每个实例随机旋转
+        let bounding_sphere = lume_adaptrix::mesh_bounds(&clusters);
+        let spacing = bounding_sphere.w * 3.0;
+        let instances = (0..FOREST_SIZE * FOREST_SIZE).map(|i| {
+            let (x, z) = ((i % FOREST_SIZE) as f32 - (FOREST_SIZE / 2) as f32, (i / FOREST_SIZE) as f32 - (FOREST_SIZE / 2) as f32);
+            let world_from_local = Mat4::from_rotation_translation(Quat::from_rotation_y(i as f32 * 2.4), Vec3::new(x * spacing, 0.0, z * spacing));
+            MeshInstance { world_from_local, cluster_base: 0, cluster_count, bvh_root: 0, _padding: 0, bounding_sphere }
+        }).collect();
         Self {
             window: None, instance: None, surface: None, device: None, swapchain: None,
-            clusters, vertices, indices,
+            clusters, vertices, indices, bvh, instances,
             cluster_buffer: None, vertex_buffer: None, index_buffer: None,
-            visible_clusters_buffer: None, visible_count_buffer: None, instance_buffer: None, view_buffer: None,
-            cull_pipeline: None, cull_layout: None, cull_bind_group_0: None, cull_bind_group_1: None,
-            vis_pipeline: None, vis_layout: None, vis_bind_group_0: None, vis_bind_group_1: None,
-            resolve_pipeline: None, resolve_layout: None, resolve_bind_group_0: None, resolve_bind_group_1: None,
+            instance_buffer: None, view_buffer: None, hzb_view_buffer: None, hzb_view_uniform: None,
+            bvh_buffer: None, node_queue_buffer: None, node_entries_buffer: None, work_queue_buffer: None, queue_buffer: None,
+            occluded_queue_buffer: None, occluded_buffer: None,
+            bvh_cull_pipeline: None, bvh_cull_layout: None, bvh_cull_bind_group_0: None, bvh_cull_bind_group_1: None,
+            cull_passes: Vec::new(), visible_clusters_buffers: Vec::new(),
+            vis_pipeline: None, vis_layout: None, vis_bind_group_1: None,
+            shaders: None, resolve_pipeline: None, resolve_layout: None, resolve_bind_group_0: None, resolve_bind_group_1: None,
             vis_buffer_texture: None, vis_buffer_view: None, vis_depth_texture: None, vis_depth_view: None,
-            vis_render_pass: None, vis_framebuffer: None, resolve_render_pass: None, resolve_framebuffers: Vec::new(),
+            hzb_texture: None, hzb_views: Vec::new(), hzb_view: None, hzb_pipelines: Vec::new(), hzb_bind_groups: Vec::new(),
+            vis_render_pass: None, vis_load_render_pass: None, vis_framebuffer: None, resolve_render_pass: None, resolve_framebuffers: Vec::new(),
             command_pool: None, command_buffer: None, start_time: std::time::Instant::now(),
         }
     }
@@ -94,59 +150,123 @@ impl AdaptrixApp {
     fn setup_gpu_resources(&mut self) {
         let device = self.device.as_ref().unwrap();
         let size = self.window.as_ref().unwrap().inner_size();
-        self.cluster_buffer = Some(device.create_buffer(BufferDescriptor { size: (self.clusters.len() * 48) as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
+        self.cluster_buffer = Some(device.create_buffer(BufferDescriptor { size: (self.clusters.len() * std::mem::size_of::<Cluster>()) as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
         self.cluster_buffer.as_ref().unwrap().write_data(0, bytemuck::cast_slice(&self.clusters)).unwrap();
         self.vertex_buffer = Some(device.create_buffer(BufferDescriptor { size: (self.vertices.len() * 32) as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
         self.vertex_buffer.as_ref().unwrap().write_data(0, bytemuck::cast_slice(&self.vertices)).unwrap();
         self.index_buffer = Some(device.create_buffer(BufferDescriptor { size: (self.indices.len() * 4) as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
         self.index_buffer.as_ref().unwrap().write_data(0, bytemuck::cast_slice(&self.indices)).unwrap();
-        
-        // 关键修复：初始化 visible_clusters_buffer，默认为全可见
-        let initial_visible: Vec<u32> = (0..self.clusters.len() as u32).collect();
-        self.visible_clusters_buffer = Some(device.create_buffer(BufferDescriptor { size: (self.clusters.len() * 4) as u64, usage: BufferUsage::STORAGE, mapped_at_creation: true }).unwrap());
-        self.visible_clusters_buffer.as_ref().unwrap().write_data(0, bytemuck::cast_slice(&initial_visible)).unwrap();
-
-        self.visible_count_buffer = Some(device.create_buffer(BufferDescriptor { size: 4, usage: BufferUsage::STORAGE | BufferUsage::COPY_SRC | BufferUsage::COPY_DST | BufferUsage::INDIRECT, mapped_at_creation: true }).unwrap());
-        self.view_buffer = Some(device.create_buffer(BufferDescriptor { size: 144, usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
-        self.instance_buffer = Some(device.create_buffer(BufferDescriptor { size: 80, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
-        
-        self.vis_buffer_texture = Some(device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, format: TextureFormat::Rg32Uint, usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING }).unwrap());
-        self.vis_buffer_view = Some(device.create_texture_view(self.vis_buffer_texture.as_ref().unwrap(), TextureViewDescriptor { format: None }).unwrap());
-        self.vis_depth_texture = Some(device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, format: TextureFormat::Depth32Float, usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT }).unwrap());
-        self.vis_depth_view = Some(device.create_texture_view(self.vis_depth_texture.as_ref().unwrap(), TextureViewDescriptor { format: None }).unwrap());
-        let cull_module = device.create_shader_module(&lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Wgsl(include_str!("../../../lume-adaptrix/src/shaders/cull.wgsl"))).unwrap()).unwrap();
-        let vis_v_mod = device.create_shader_module(&lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Wgsl(include_str!("../../../lume-adaptrix/src/shaders/visbuffer.vert.wgsl"))).unwrap()).unwrap();
-        let vis_f_mod = device.create_shader_module(&lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Wgsl(include_str!("../../../lume-adaptrix/src/shaders/visbuffer.frag.wgsl"))).unwrap()).unwrap();
-        let res_v_mod = device.create_shader_module(&lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Wgsl(include_str!("../../../lume-adaptrix/src/shaders/resolve.vert.wgsl"))).unwrap()).unwrap();
-        let res_f_mod = device.create_shader_module(&lume_core::shader::compile_shader(lume_core::shader::ShaderSource::Wgsl(include_str!("../../../lume-adaptrix/src/shaders/resolve.frag.wgsl"))).unwrap()).unwrap();
-        let vis_rp = device.create_render_pass(RenderPassDescriptor { color_format: TextureFormat::Rg32Uint, depth_stencil_format: Some(TextureFormat::Depth32Float) }).unwrap();
+
+
+        // 每个实例的每个集群最多占一项
+        let pair_capacity = (self.clusters.len() * self.instances.len() * 8) as u64;
+        self.queue_buffer = Some(device.create_buffer(BufferDescriptor { size: pair_capacity, usage: BufferUsage::STORAGE, mapped_at_creation: false }).unwrap());
+        self.bvh_buffer = Some(device.create_buffer(BufferDescriptor { size: (self.bvh.len() * std::mem::size_of::<BvhNode>()) as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
+        self.bvh_buffer.as_ref().unwrap().write_data(0, bytemuck::cast_slice(&self.bvh)).unwrap();
+        self.node_queue_buffer = Some(device.create_buffer(BufferDescriptor { size: std::mem::size_of::<NodeQueue>() as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
+        // 节点队列只需填充一次：着色器取走条目后会将其复位
+        let node_entries = vec![0xffu8; self.bvh.len() * self.instances.len() * 8];
+        self.node_entries_buffer = Some(device.create_buffer(BufferDescriptor { size: node_entries.len() as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
+        self.node_entries_buffer.as_ref().unwrap().write_data(0, &node_entries).unwrap();
+        self.work_queue_buffer = Some(device.create_buffer(BufferDescriptor { size: std::mem::size_of::<WorkQueue>() as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST | BufferUsage::INDIRECT, mapped_at_creation: true }).unwrap());
+        self.occluded_queue_buffer = Some(device.create_buffer(BufferDescriptor { size: std::mem::size_of::<WorkQueue>() as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST | BufferUsage::INDIRECT, mapped_at_creation: true }).unwrap());
+        self.occluded_buffer = Some(device.create_buffer(BufferDescriptor { size: pair_capacity, usage: BufferUsage::STORAGE, mapped_at_creation: false }).unwrap());
+        self.view_buffer = Some(device.create_buffer(BufferDescriptor { size: std::mem::size_of::<ViewUniform>() as u64, usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
+        self.hzb_view_buffer = Some(device.create_buffer(BufferDescriptor { size: std::mem::size_of::<ViewUniform>() as u64, usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
+        self.instance_buffer = Some(device.create_buffer(BufferDescriptor { size: (self.instances.len() * std::mem::size_of::<MeshInstance>()) as u64, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST, mapped_at_creation: true }).unwrap());
+        self.instance_buffer.as_ref().unwrap().write_data(0, bytemuck::cast_slice(&self.instances)).unwrap();
+
+
+        self.vis_buffer_texture = Some(device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, mip_level_count: 1, format: TextureFormat::Rg32Uint, usage: TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING }).unwrap());
+        self.vis_buffer_view = Some(device.create_texture_view(self.vis_buffer_texture.as_ref().unwrap(), TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap());
+        self.vis_depth_texture = Some(device.create_texture(TextureDescriptor { width: size.width, height: size.height, depth: 1, mip_level_count: 1, format: TextureFormat::Depth32Float, usage: TextureUsage::DEPTH_STENCIL_ATTACHMENT | TextureUsage::TEXTURE_BINDING }).unwrap());
+        self.vis_depth_view = Some(device.create_texture_view(self.vis_depth_texture.as_ref().unwrap(), TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap());
+        let (hzb_width, hzb_height) = mip_extent(size.width, size.height, 1);
+        let hzb_texture = device.create_texture(TextureDescriptor { width: hzb_width, height: hzb_height, depth: 1, mip_level_count: mip_level_count(hzb_width, hzb_height), format: TextureFormat::R32Float, usage: TextureUsage::STORAGE_BINDING | TextureUsage::TEXTURE_BINDING }).unwrap();
+        self.hzb_views = (0..mip_level_count(hzb_width, hzb_height)).map(|level| device.create_texture_view(&hzb_texture, TextureViewDescriptor { format: None, base_mip_level: level, mip_level_count: Some(1) }).unwrap()).collect();
+        self.hzb_view = Some(device.create_texture_view(&hzb_texture, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap());
+        self.hzb_texture = Some(hzb_texture);
+        let resolver = lume_core::shader::MemoryResolver::new(lume_adaptrix::shaders::SOURCES);
+        let wgsl = |path| lume_core::shader::ShaderSource::WgslFile { path, resolver: &resolver, defines: Default::default() };
+        let hzb_from_depth = lume_core::shader::ShaderSource::WgslFile { path: "hzb.wgsl", resolver: &resolver, defines: [("HZB_FROM_DEPTH".to_string(), String::new())].into_iter().collect() };
+        for source in [hzb_from_depth, wgsl("hzb.wgsl")] {
+            let hzb = lume_core::shader::compile_shader_reflected(source).unwrap();
+            let layouts = device.create_pipeline_layout_from_reflection(&hzb.reflection).unwrap();
+            let pipeline = device.create_compute_pipeline(ComputePipelineDescriptor { shader: &device.create_shader_module(&hzb.spirv).unwrap(), layout: &layouts.pipeline_layout }).unwrap();
+            let sources: Vec<_> = if self.hzb_pipelines.is_empty() { vec![self.vis_depth_view.as_ref().unwrap()] } else { self.hzb_views[..self.hzb_views.len() - 1].iter().collect() };
+            for source in sources {
+                let destination = &self.hzb_views[self.hzb_bind_groups.len()];
+                self.hzb_bind_groups.push(device.create_bind_group(BindGroupDescriptor { layout: &layouts.bind_group_layouts[0], entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::TextureView(source) }, BindGroupEntry { binding: 1, resource: BindingResource::TextureView(destination) }] }).unwrap());
+            }
+            self.hzb_pipelines.push((pipeline, layouts.pipeline_layout));
+        }
+        let bvh_cull = lume_core::shader::compile_shader_reflected(wgsl("bvh_cull.wgsl")).unwrap();
+        let bvh_cull_module = device.create_shader_module(&bvh_cull.spirv).unwrap();
+        let vis_v = lume_core::shader::compile_shader_reflected(wgsl("visbuffer.vert.wgsl")).unwrap();
+        let vis_v_mod = device.create_shader_module(&vis_v.spirv).unwrap();
+        let vis_f = lume_core::shader::compile_shader_reflected(wgsl("visbuffer.frag.wgsl")).unwrap();
+        let vis_f_mod = device.create_shader_module(&vis_f.spirv).unwrap();
+        let res_v = lume_core::shader::compile_shader_reflected(wgsl("resolve.vert.wgsl")).unwrap();
+        // LUME_DEBUG_VIEW=CLUSTERS|TRIANGLES|SHADED selects the resolve debug view.
+        let res_f_permutations = lume_core::shader::ShaderPermutations::new("resolve.frag.wgsl", &resolver, vec![lume_core::shader::PermutationKey::enumerated("DEBUG_VIEW", &["CLUSTERS", "TRIANGLES", "SHADED"])]);
+        let debug_view = std::env::var("LUME_DEBUG_VIEW").ok();
+        let res_f_permutation = debug_view.as_deref().map_or_else(lume_core::shader::Permutation::new, |view| lume_core::shader::Permutation::new().with("DEBUG_VIEW", view));
+        let res_f = res_f_permutations.variant(&res_f_permutation).unwrap_or_else(|e| panic!("{}", e));
+        let vis_rp = device.create_render_pass(RenderPassDescriptor { color_format: TextureFormat::Rg32Uint, depth_stencil_format: Some(TextureFormat::Depth32Float), load_op: AttachmentLoadOp::Clear }).unwrap();
         self.vis_framebuffer = Some(device.create_framebuffer(FramebufferDescriptor { render_pass: &vis_rp, attachments: &[self.vis_buffer_view.as_ref().unwrap(), self.vis_depth_view.as_ref().unwrap()], width: size.width, height: size.height }).unwrap());
         self.vis_render_pass = Some(vis_rp);
-        let res_rp = device.create_render_pass(RenderPassDescriptor { color_format: TextureFormat::Bgra8UnormSrgb, depth_stencil_format: None }).unwrap();
+        self.vis_load_render_pass = Some(device.create_render_pass(RenderPassDescriptor { color_format: TextureFormat::Rg32Uint, depth_stencil_format: Some(TextureFormat::Depth32Float), load_op: AttachmentLoadOp::Load }).unwrap());
+        let res_rp = device.create_render_pass(RenderPassDescriptor { color_format: TextureFormat::Bgra8UnormSrgb, depth_stencil_format: None, load_op: AttachmentLoadOp::Clear }).unwrap();
         for i in 0..3 { self.resolve_framebuffers.push(device.create_framebuffer(FramebufferDescriptor { render_pass: &res_rp, attachments: &[self.swapchain.as_ref().unwrap().get_view(i as u32)], width: size.width, height: size.height }).unwrap()); }
-        self.resolve_render_pass = Some(res_rp);
-        let cull_bgl0 = device.create_bind_group_layout(BindGroupLayoutDescriptor { entries: vec![BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::COMPUTE, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 1, visibility: ShaderStage::COMPUTE, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 2, visibility: ShaderStage::COMPUTE, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 3, visibility: ShaderStage::COMPUTE, ty: BindingType::StorageBuffer }] }).unwrap();
-        let cull_bgl1 = device.create_bind_group_layout(BindGroupLayoutDescriptor { entries: vec![BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::COMPUTE, ty: BindingType::UniformBuffer }] }).unwrap();
-        let cull_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&cull_bgl0, &cull_bgl1] }).unwrap();
-        self.cull_pipeline = Some(device.create_compute_pipeline(ComputePipelineDescriptor { shader: &cull_module, layout: &cull_layout }).unwrap());
-        self.cull_bind_group_0 = Some(device.create_bind_group(BindGroupDescriptor { layout: &cull_bgl0, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.cluster_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(self.instance_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(self.visible_clusters_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 3, resource: BindingResource::Buffer(self.visible_count_buffer.as_ref().unwrap()) }] }).unwrap());
-        self.cull_bind_group_1 = Some(device.create_bind_group(BindGroupDescriptor { layout: &cull_bgl1, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.view_buffer.as_ref().unwrap()) }] }).unwrap());
-        self.cull_layout = Some(cull_layout);
-        let vis_bgl0 = device.create_bind_group_layout(BindGroupLayoutDescriptor { entries: vec![BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 1, visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 2, visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 3, visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT, ty: BindingType::StorageBuffer }] }).unwrap();
-        let vis_bgl1 = device.create_bind_group_layout(BindGroupLayoutDescriptor { entries: vec![BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::VERTEX | ShaderStage::FRAGMENT, ty: BindingType::UniformBuffer }] }).unwrap();
-        let vis_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&vis_bgl0, &vis_bgl1] }).unwrap();
+        self.resolve_render_pass = Some(Rc::new(res_rp));
+        let bvh_cull_layouts = device.create_pipeline_layout_from_reflection(&bvh_cull.reflection).unwrap();
+        let (bvh_cull_layout, bvh_cull_bgl0, bvh_cull_bgl1) = (bvh_cull_layouts.pipeline_layout, &bvh_cull_layouts.bind_group_layouts[0], &bvh_cull_layouts.bind_group_layouts[1]);
+        self.bvh_cull_pipeline = Some(device.create_compute_pipeline(ComputePipelineDescriptor { shader: &bvh_cull_module, layout: &bvh_cull_layout }).unwrap());
+        self.bvh_cull_bind_group_0 = Some(device.create_bind_group(BindGroupDescriptor { layout: bvh_cull_bgl0, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.bvh_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(self.instance_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(self.node_queue_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 3, resource: BindingResource::Buffer(self.node_entries_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 4, resource: BindingResource::Buffer(self.work_queue_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 5, resource: BindingResource::Buffer(self.queue_buffer.as_ref().unwrap()) }] }).unwrap());
+        self.bvh_cull_bind_group_1 = Some(device.create_bind_group(BindGroupDescriptor { layout: bvh_cull_bgl1, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.view_buffer.as_ref().unwrap()) }] }).unwrap());
+        self.bvh_cull_layout = Some(bvh_cull_layout);
+        let mut vis_reflection = vis_v.reflection.clone();
+        vis_reflection.merge(&vis_f.reflection).unwrap();
+        let vis_layouts = device.create_pipeline_layout_from_reflection(&vis_reflection).unwrap();
+        let (vis_layout, vis_bgl0, vis_bgl1) = (vis_layouts.pipeline_layout, &vis_layouts.bind_group_layouts[0], &vis_layouts.bind_group_layouts[1]);
         self.vis_pipeline = Some(device.create_graphics_pipeline(GraphicsPipelineDescriptor { vertex_shader: &vis_v_mod, fragment_shader: &vis_f_mod, render_pass: self.vis_render_pass.as_ref().unwrap(), layout: &vis_layout, primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList }, vertex_layout: None, depth_stencil: Some(DepthStencilState { format: TextureFormat::Depth32Float, depth_write_enabled: true, depth_compare: CompareFunction::LessEqual }) }).unwrap());
-        self.vis_bind_group_0 = Some(device.create_bind_group(BindGroupDescriptor { layout: &vis_bgl0, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.cluster_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(self.vertex_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(self.index_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 3, resource: BindingResource::Buffer(self.visible_clusters_buffer.as_ref().unwrap()) }] }).unwrap());
-        self.vis_bind_group_1 = Some(device.create_bind_group(BindGroupDescriptor { layout: &vis_bgl1, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.view_buffer.as_ref().unwrap()) }] }).unwrap());
+        // The first pass tests against last frame's HZB, from the view it was built with; the
+        // second re-tests what the first rejected against the HZB of what the first drew.
+        for (pass, hzb_view_buffer) in [("OCCLUSION_FIRST_PASS", &self.hzb_view_buffer), ("OCCLUSION_SECOND_PASS", &self.view_buffer)] {
+            let cull = lume_core::shader::compile_shader_reflected(lume_core::shader::ShaderSource::WgslFile { path: "cull.wgsl", resolver: &resolver, defines: [(pass.to_string(), String::new())].into_iter().collect() }).unwrap();
+            let layouts = device.create_pipeline_layout_from_reflection(&cull.reflection).unwrap();
+            let pipeline = device.create_compute_pipeline(ComputePipelineDescriptor { shader: &device.create_shader_module(&cull.spirv).unwrap(), layout: &layouts.pipeline_layout }).unwrap();
+            let visible_clusters_buffer = device.create_buffer(BufferDescriptor { size: pair_capacity, usage: BufferUsage::STORAGE, mapped_at_creation: false }).unwrap();
+            let draw_args_buffer = device.create_buffer(BufferDescriptor { size: 16, usage: BufferUsage::STORAGE | BufferUsage::COPY_DST | BufferUsage::INDIRECT, mapped_at_creation: true }).unwrap();
+            let bind_groups = vec![
+                device.create_bind_group(BindGroupDescriptor { layout: &layouts.bind_group_layouts[0], entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.cluster_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(self.instance_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(&visible_clusters_buffer) }, BindGroupEntry { binding: 3, resource: BindingResource::Buffer(&draw_args_buffer) }, BindGroupEntry { binding: 4, resource: BindingResource::Buffer(self.work_queue_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 5, resource: BindingResource::Buffer(self.queue_buffer.as_ref().unwrap()) }] }).unwrap(),
+                device.create_bind_group(BindGroupDescriptor { layout: &layouts.bind_group_layouts[1], entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.view_buffer.as_ref().unwrap()) }] }).unwrap(),
+                device.create_bind_group(BindGroupDescriptor { layout: &layouts.bind_group_layouts[2], entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::TextureView(self.hzb_view.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(hzb_view_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(self.occluded_queue_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 3, resource: BindingResource::Buffer(self.occluded_buffer.as_ref().unwrap()) }] }).unwrap(),
+            ];
+            let vis_bind_group_0 = device.create_bind_group(BindGroupDescriptor { layout: vis_bgl0, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.cluster_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(self.vertex_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(self.index_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 3, resource: BindingResource::Buffer(&visible_clusters_buffer) }, BindGroupEntry { binding: 4, resource: BindingResource::Buffer(self.instance_buffer.as_ref().unwrap()) }] }).unwrap();
+            self.cull_passes.push(CullPass { pipeline: (pipeline, layouts.pipeline_layout), bind_groups, draw_args_buffer, vis_bind_group_0 });
+            self.visible_clusters_buffers.push(visible_clusters_buffer);
+        }
+        self.vis_bind_group_1 = Some(device.create_bind_group(BindGroupDescriptor { layout: vis_bgl1, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.view_buffer.as_ref().unwrap()) }] }).unwrap());
         self.vis_layout = Some(vis_layout);
-        let res_bgl0 = device.create_bind_group_layout(BindGroupLayoutDescriptor { entries: vec![BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::FRAGMENT, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 1, visibility: ShaderStage::FRAGMENT, ty: BindingType::StorageBuffer }, BindGroupLayoutEntry { binding: 2, visibility: ShaderStage::FRAGMENT, ty: BindingType::StorageBuffer }] }).unwrap();
-        let res_bgl1 = device.create_bind_group_layout(BindGroupLayoutDescriptor { entries: vec![BindGroupLayoutEntry { binding: 0, visibility: ShaderStage::FRAGMENT, ty: BindingType::UniformBuffer }, BindGroupLayoutEntry { binding: 1, visibility: ShaderStage::FRAGMENT, ty: BindingType::SampledTexture }] }).unwrap();
-        let res_layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[&res_bgl0, &res_bgl1] }).unwrap();
-        self.resolve_pipeline = Some(device.create_graphics_pipeline(GraphicsPipelineDescriptor { vertex_shader: &res_v_mod, fragment_shader: &res_f_mod, render_pass: self.resolve_render_pass.as_ref().unwrap(), layout: &res_layout, primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList }, vertex_layout: None, depth_stencil: None }).unwrap());
-        self.resolve_bind_group_0 = Some(device.create_bind_group(BindGroupDescriptor { layout: &res_bgl0, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.cluster_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(self.vertex_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(self.index_buffer.as_ref().unwrap()) }] }).unwrap());
-        self.resolve_bind_group_1 = Some(device.create_bind_group(BindGroupDescriptor { layout: &res_bgl1, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.view_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::TextureView(self.vis_buffer_view.as_ref().unwrap()) }] }).unwrap());
+        let mut res_reflection = res_v.reflection.clone();
+        res_reflection.merge(&res_f.reflection).unwrap();
+        let res_layouts = device.create_pipeline_layout_from_reflection(&res_reflection).unwrap();
+        let (res_layout, res_bgl0, res_bgl1) = (res_layouts.pipeline_layout, &res_layouts.bind_group_layouts[0], &res_layouts.bind_group_layouts[1]);
+        let res_layout = Rc::new(res_layout);
+        let mut shaders = lume_core::shader::ShaderReloader::new();
+        let shader_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../lume-adaptrix/src/shaders");
+        let res_v_id = shaders.add_shader(device, shader_dir, "resolve.vert.wgsl", Default::default()).unwrap();
+        let res_f_id = shaders.add_shader(device, shader_dir, "resolve.frag.wgsl", res_f_permutations.defines(&res_f_permutation).unwrap()).unwrap();
+        let (render_pass, layout) = (self.resolve_render_pass.clone().unwrap(), res_layout.clone());
+        self.resolve_pipeline = Some(shaders.add_graphics_pipeline(device, res_v_id, res_f_id, move |device, vertex_shader, fragment_shader| {
+            device.create_graphics_pipeline(GraphicsPipelineDescriptor { vertex_shader, fragment_shader, render_pass: &render_pass, layout: &layout, primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList }, vertex_layout: None, depth_stencil: None })
+        }).unwrap());
+        self.shaders = Some(shaders);
+        self.resolve_bind_group_0 = Some(device.create_bind_group(BindGroupDescriptor { layout: res_bgl0, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.cluster_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::Buffer(self.vertex_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 2, resource: BindingResource::Buffer(self.index_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 3, resource: BindingResource::Buffer(self.instance_buffer.as_ref().unwrap()) }] }).unwrap());
+        self.resolve_bind_group_1 = Some(device.create_bind_group(BindGroupDescriptor { layout: res_bgl1, entries: vec![BindGroupEntry { binding: 0, resource: BindingResource::Buffer(self.view_buffer.as_ref().unwrap()) }, BindGroupEntry { binding: 1, resource: BindingResource::TextureView(self.vis_buffer_view.as_ref().unwrap()) }] }).unwrap());
         self.resolve_layout = Some(res_layout);
-        self.command_pool = Some(device.create_command_pool().unwrap());
+        self.command_pool = Some(device.create_command_pool(QueueType::Graphics).unwrap());
         self.command_buffer = Some(self.command_pool.as_ref().unwrap().allocate_command_buffer().unwrap());
     }
 }
@@ -170,6 +290,7 @@ impl ApplicationHandler for AdaptrixApp {
             WindowEvent::CloseRequested => event_loop.exit(),
             WindowEvent::RedrawRequested => {
                 if let (Some(device), Some(swapchain)) = (&self.device, self.swapchain.as_mut()) {
+                    self.shaders.as_mut().unwrap().poll(device);
                     let token = device.begin_frame(swapchain).expect("Failed to begin frame");
                     let elapsed = self.start_time.elapsed().as_secs_f32();
                     let cam_pos = Vec3::new(elapsed.cos() * 4.0, 1.5, elapsed.sin() * 4.0);
@@ -180,29 +301,71 @@ impl ApplicationHandler for AdaptrixApp {
                     proj_mat.col_mut(1).y *= -1.0; 
                     let view_proj = proj_mat * view_mat;
                     
-                    self.view_buffer.as_ref().unwrap().write_data(0, bytemuck::cast_slice(&[ViewUniform { view_proj, inv_view_proj: view_proj.inverse(), frustum: [Vec4::ZERO; 6], viewport_size: [1280.0, 720.0], error_threshold: 1.0, _padding: 0.0 }])).unwrap();
-                    self.visible_count_buffer.as_ref().unwrap().write_data(0, &[0u8; 4]).unwrap();
+                    let view = ViewUniform { view_proj, inv_view_proj: view_proj.inverse(), frustum: lume_adaptrix::frustum_planes(view_proj), viewport_size: [1280.0, 720.0], error_threshold: 1.0, _padding: 0.0 };
+                    // The first frame has no HZB yet; whatever the first pass rejects against it, the second draws.
+                    let hzb_view = self.hzb_view_uniform.replace(view).unwrap_or(view);
+                    self.view_buffer.as_ref().unwrap().write_data(0, bytemuck::bytes_of(&view)).unwrap();
+                    self.hzb_view_buffer.as_ref().unwrap().write_data(0, bytemuck::bytes_of(&hzb_view)).unwrap();
+                    self.node_queue_buffer.as_ref().unwrap().write_data(0, bytemuck::bytes_of(&NodeQueue::new(self.instances.len() as u32))).unwrap();
+                    self.work_queue_buffer.as_ref().unwrap().write_data(0, bytemuck::bytes_of(&WorkQueue::EMPTY)).unwrap();
+                    self.occluded_queue_buffer.as_ref().unwrap().write_data(0, bytemuck::bytes_of(&WorkQueue::EMPTY)).unwrap();
+                    for pass in &self.cull_passes {
+                        pass.draw_args_buffer.write_data(0, bytemuck::cast_slice(&[lume_adaptrix::MAX_CLUSTER_TRIANGLES * 3, 0, 0, 0])).unwrap();
+                    }
                     let cmd = self.command_buffer.as_mut().unwrap();
                     cmd.reset().unwrap(); cmd.begin().unwrap();
-                    
-                    // 暂时禁用 Compute Culling 的录制，以防它干扰可见性列表
-                    // cmd.bind_compute_pipeline(self.cull_pipeline.as_ref().unwrap());
-                    // ... 
-                    
-                    // Pass 1: VisBuffer (渲染所有集群)
-                    cmd.begin_render_pass(self.vis_render_pass.as_ref().unwrap(), self.vis_framebuffer.as_ref().unwrap(), [0.0, 0.0, 0.0, 0.0]);
-                    cmd.set_viewport(0.0, 0.0, 1280.0, 720.0); cmd.set_scissor(0, 0, 1280, 720);
-                    cmd.bind_graphics_pipeline(self.vis_pipeline.as_ref().unwrap());
-                    cmd.bind_bind_group(0, self.vis_bind_group_0.as_ref().unwrap());
-                    cmd.bind_bind_group(1, self.vis_bind_group_1.as_ref().unwrap());
-                    // 强制渲染所有集群
-                    cmd.draw(128 * 3, self.clusters.len() as u32, 0, 0); 
-                    cmd.end_render_pass();
+
+                    // HZB of the VisBuffer depth drawn so far
+                    let build_hzb = |cmd: &mut lume_vulkan::VulkanCommandBuffer| {
+                        for (level, bind_group) in self.hzb_bind_groups.iter().enumerate() {
+                            let texture = self.hzb_texture.as_ref().unwrap();
+                            let (width, height) = mip_extent(texture.width, texture.height, level as u32);
+                            cmd.bind_compute_pipeline(&self.hzb_pipelines[level.min(1)].0);
+                            cmd.bind_bind_group(0, bind_group);
+                            cmd.dispatch(width.div_ceil(8), height.div_ceil(8), 1);
+                            cmd.compute_barrier();
+                        }
+                    };
+
+                    // Culling: the instances' BVHs into (instance, cluster) pairs for the first pass
+                    cmd.bind_compute_pipeline(self.bvh_cull_pipeline.as_ref().unwrap());
+                    cmd.bind_bind_group(0, self.bvh_cull_bind_group_0.as_ref().unwrap());
+                    cmd.bind_bind_group(1, self.bvh_cull_bind_group_1.as_ref().unwrap());
+                    cmd.dispatch(BVH_CULL_WORKGROUPS, 1, 1);
+                    cmd.compute_barrier();
+
+                    // Pass 1: VisBuffer, drawn in two occlusion culling passes
+                    for (i, pass) in self.cull_passes.iter().enumerate() {
+                        let (queue, render_pass) = if i == 0 {
+                            (self.work_queue_buffer.as_ref().unwrap(), self.vis_render_pass.as_ref().unwrap())
+                        } else {
+                            // The second pass tests against the HZB of what the first drew
+                            build_hzb(cmd);
+                            (self.occluded_queue_buffer.as_ref().unwrap(), self.vis_load_render_pass.as_ref().unwrap())
+                        };
+                        cmd.bind_compute_pipeline(&pass.pipeline.0);
+                        for (index, bind_group) in pass.bind_groups.iter().enumerate() {
+                            cmd.bind_bind_group(index as u32, bind_group);
+                        }
+                        cmd.dispatch_indirect(queue, 0);
+                        cmd.compute_barrier();
+
+                        cmd.begin_render_pass(render_pass, self.vis_framebuffer.as_ref().unwrap(), [0.0, 0.0, 0.0, 0.0]);
+                        cmd.set_viewport(0.0, 0.0, 1280.0, 720.0); cmd.set_scissor(0, 0, 1280, 720);
+                        cmd.bind_graphics_pipeline(self.vis_pipeline.as_ref().unwrap());
+                        cmd.bind_bind_group(0, &pass.vis_bind_group_0);
+                        cmd.bind_bind_group(1, self.vis_bind_group_1.as_ref().unwrap());
+                        cmd.draw_indirect(&pass.draw_args_buffer, 0, 1, 16);
+                        cmd.end_render_pass();
+                    }
+
+                    // HZB of the whole frame, for the first pass of the next
+                    build_hzb(cmd);
 
                     // Pass 2: Resolve
                     cmd.begin_render_pass(self.resolve_render_pass.as_ref().unwrap(), &self.resolve_framebuffers[token.image_index as usize], [0.05, 0.05, 0.07, 1.0]);
                     cmd.set_viewport(0.0, 0.0, 1280.0, 720.0); cmd.set_scissor(0, 0, 1280, 720);
-                    cmd.bind_graphics_pipeline(self.resolve_pipeline.as_ref().unwrap());
+                    cmd.bind_graphics_pipeline(self.shaders.as_ref().unwrap().graphics_pipeline(self.resolve_pipeline.unwrap()));
                     cmd.bind_bind_group(0, self.resolve_bind_group_0.as_ref().unwrap());
                     cmd.bind_bind_group(1, self.resolve_bind_group_1.as_ref().unwrap());
                     cmd.draw(3, 1, 0, 0); 
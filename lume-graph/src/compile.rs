@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use lume_core::{Device, LumeError, LumeResult};
+use lume_core::device::{BufferUsage, ImageLayout, TextureUsage};
+use crate::pass::PassKind;
+use crate::resource::{BufferHandle, BufferInfo, BufferSource, TextureHandle, TextureInfo, TextureSource};
+use crate::RenderGraph;
+
+/// A synchronisation command recorded ahead of a pass.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Barrier {
+    Texture {
+        texture: TextureHandle,
+        old_layout: ImageLayout,
+        new_layout: ImageLayout,
+    },
+    /// Orders shader and transfer writes to buffers before later accesses, via `compute_barrier`.
+    Memory,
+}
+
+#[derive(Clone, Debug)]
+pub struct CompiledPass {
+    /// Index of the pass in declaration order.
+    pub index: usize,
+    pub name: String,
+    pub barriers: Vec<Barrier>,
+}
+
+/// The execution plan for a graph: surviving passes in order, the barriers before each,
+/// and the physical slot every transient resource is aliased onto.
+#[derive(Clone, Debug)]
+pub struct CompiledGraph {
+    pub passes: Vec<CompiledPass>,
+    pub culled: Vec<String>,
+    pub(crate) texture_slots: Vec<Option<usize>>,
+    pub(crate) texture_slot_descs: Vec<(TextureInfo, TextureUsage)>,
+    pub(crate) buffer_slots: Vec<Option<usize>>,
+    pub(crate) buffer_slot_descs: Vec<(BufferInfo, BufferUsage)>,
+}
+
+impl CompiledGraph {
+    /// Physical slot backing a transient texture, or `None` if it is imported or unused.
+    pub fn texture_slot(&self, texture: TextureHandle) -> Option<usize> {
+        self.texture_slots[texture.0]
+    }
+
+    pub fn buffer_slot(&self, buffer: BufferHandle) -> Option<usize> {
+        self.buffer_slots[buffer.0]
+    }
+
+    pub fn physical_texture_count(&self) -> usize {
+        self.texture_slot_descs.len()
+    }
+
+    pub fn physical_buffer_count(&self) -> usize {
+        self.buffer_slot_descs.len()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Physical {
+    Slot(usize),
+    Imported(usize),
+}
+
+#[derive(Clone, Copy, Default)]
+struct Hazard {
+    /// Written since the last barrier.
+    dirty: bool,
+    /// Read since the last barrier.
+    read: bool,
+}
+
+/// First and last position of a transient among the surviving passes, with the
+/// description its physical resource must match and the usages it needs.
+type Lifetime<I, U> = Option<(usize, usize, (I, U))>;
+
+/// Greedily assigns each transient a slot of the same description whose previous occupant
+/// is no longer live. Each slot is created with the usages of every transient placed in it.
+fn alias<I: Copy + PartialEq, U: Copy + std::ops::BitOr<Output = U>>(lifetimes: &[Lifetime<I, U>]) -> (Vec<Option<usize>>, Vec<(I, U)>) {
+    let mut order: Vec<usize> = (0..lifetimes.len()).filter(|&i| lifetimes[i].is_some()).collect();
+    order.sort_by_key(|&i| lifetimes[i].unwrap().0);
+
+    let mut assignment = vec![None; lifetimes.len()];
+    let mut slots: Vec<((I, U), usize)> = Vec::new();
+    for i in order {
+        let (first, last, (info, usage)) = lifetimes[i].unwrap();
+        let slot = match slots.iter().position(|&((slot_info, _), end)| slot_info == info && end < first) {
+            Some(slot) => slot,
+            None => {
+                slots.push(((info, usage), 0));
+                slots.len() - 1
+            }
+        };
+        let ((_, slot_usage), end) = &mut slots[slot];
+        *slot_usage = *slot_usage | usage;
+        *end = last;
+        assignment[i] = Some(slot);
+    }
+    (assignment, slots.into_iter().map(|(k, _)| k).collect())
+}
+
+pub(crate) fn compile<D: Device>(graph: &RenderGraph<'_, D>) -> LumeResult<CompiledGraph> {
+    let passes = &graph.passes;
+
+    // Walk backwards from the imported resources, keeping only passes whose writes are
+    // observed. A write that ignores previous contents ends the demand for earlier writers.
+    let mut needed_textures: Vec<bool> = graph.textures.iter().map(|t| !t.is_transient()).collect();
+    let mut needed_buffers: Vec<bool> = graph.buffers.iter().map(|b| !b.is_transient()).collect();
+    let mut kept = vec![false; passes.len()];
+    for (i, pass) in passes.iter().enumerate().rev() {
+        let observed = pass.textures.iter().any(|u| u.access.is_write() && needed_textures[u.texture.0])
+            || pass.buffers.iter().any(|u| u.writes && needed_buffers[u.buffer.0]);
+        if !observed {
+            continue;
+        }
+        kept[i] = true;
+        for u in &pass.textures {
+            if u.access.is_write() && !u.reads && graph.textures[u.texture.0].is_transient() {
+                needed_textures[u.texture.0] = false;
+            }
+        }
+        for u in &pass.buffers {
+            if u.writes && !u.reads && graph.buffers[u.buffer.0].is_transient() {
+                needed_buffers[u.buffer.0] = false;
+            }
+        }
+        for u in pass.textures.iter().filter(|u| u.reads) {
+            needed_textures[u.texture.0] = true;
+        }
+        for u in pass.buffers.iter().filter(|u| u.reads) {
+            needed_buffers[u.buffer.0] = true;
+        }
+    }
+
+    let order: Vec<usize> = (0..passes.len()).filter(|&i| kept[i]).collect();
+    let culled = passes.iter().zip(&kept).filter(|(_, k)| !**k).map(|(p, _)| p.name.clone()).collect();
+
+    // Validate and gather lifetimes and usage over the surviving passes.
+    let mut texture_life: Vec<Lifetime<TextureInfo, TextureUsage>> = vec![None; graph.textures.len()];
+    let mut buffer_life: Vec<Lifetime<BufferInfo, BufferUsage>> = vec![None; graph.buffers.len()];
+    let mut texture_written = vec![false; graph.textures.len()];
+    let mut buffer_written = vec![false; graph.buffers.len()];
+    for (position, &i) in order.iter().enumerate() {
+        let pass = &passes[i];
+        if pass.kind == PassKind::Compute && (!pass.color_attachments.is_empty() || pass.depth_attachment.is_some()) {
+            return Err(LumeError::BackendError(format!("Compute pass '{}' declares attachments", pass.name)));
+        }
+        for u in &pass.textures {
+            let resource = &graph.textures[u.texture.0];
+            if u.reads && resource.is_transient() && !texture_written[u.texture.0] {
+                return Err(LumeError::BackendError(format!(
+                    "Pass '{}' reads '{}' before any pass writes it", pass.name, resource.name
+                )));
+            }
+            texture_written[u.texture.0] |= u.access.is_write();
+            if let TextureSource::Transient(info) = resource.source {
+                let life = texture_life[u.texture.0].get_or_insert((position, position, (info, TextureUsage(0))));
+                life.1 = position;
+                life.2.1 = life.2.1 | u.access.usage();
+            }
+        }
+        for u in &pass.buffers {
+            let resource = &graph.buffers[u.buffer.0];
+            if u.reads && resource.is_transient() && !buffer_written[u.buffer.0] {
+                return Err(LumeError::BackendError(format!(
+                    "Pass '{}' reads '{}' before any pass writes it", pass.name, resource.name
+                )));
+            }
+            buffer_written[u.buffer.0] |= u.writes;
+            if let BufferSource::Transient(info) = resource.source {
+                let life = buffer_life[u.buffer.0].get_or_insert((position, position, (info, BufferUsage(0))));
+                life.1 = position;
+                life.2.1 = life.2.1 | u.usage;
+            }
+        }
+    }
+
+    let (texture_slots, texture_slot_descs) = alias(&texture_life);
+    let (buffer_slots, buffer_slot_descs) = alias(&buffer_life);
+
+    // Replay the surviving passes against the physical resources to place barriers.
+    let texture_physical = |h: TextureHandle| texture_slots[h.0].map_or(Physical::Imported(h.0), Physical::Slot);
+    let buffer_physical = |h: BufferHandle| buffer_slots[h.0].map_or(Physical::Imported(h.0), Physical::Slot);
+    let mut layouts: HashMap<Physical, ImageLayout> = HashMap::new();
+    let mut texture_hazards: HashMap<Physical, Hazard> = HashMap::new();
+    let mut buffer_hazards: HashMap<Physical, Hazard> = HashMap::new();
+
+    let mut compiled = Vec::with_capacity(order.len());
+    for &i in &order {
+        let pass = &passes[i];
+        let mut barriers = Vec::new();
+
+        for u in &pass.textures {
+            // View-only imports are transitioned by `begin_rendering` itself
+            if let TextureSource::Imported { texture: None, .. } = graph.textures[u.texture.0].source {
+                continue;
+            }
+            let physical = texture_physical(u.texture);
+            let initial = match graph.textures[u.texture.0].source {
+                TextureSource::Imported { layout, .. } => layout,
+                TextureSource::Transient(_) => ImageLayout::Undefined,
+            };
+            let old_layout = *layouts.entry(physical).or_insert(initial);
+            let new_layout = u.access.layout();
+            let hazard = texture_hazards.entry(physical).or_default();
+            let write = u.access.is_write();
+            if old_layout != new_layout || hazard.dirty || (write && hazard.read) {
+                barriers.push(Barrier::Texture { texture: u.texture, old_layout, new_layout });
+                *hazard = Hazard::default();
+            }
+            if write { hazard.dirty = true } else { hazard.read = true }
+            layouts.insert(physical, new_layout);
+        }
+
+        let needs_memory_barrier = pass.buffers.iter().any(|u| {
+            let hazard = buffer_hazards.get(&buffer_physical(u.buffer)).copied().unwrap_or_default();
+            hazard.dirty || (u.writes && hazard.read)
+        });
+        if needs_memory_barrier {
+            barriers.push(Barrier::Memory);
+            buffer_hazards.clear();
+        }
+        for u in &pass.buffers {
+            let hazard = buffer_hazards.entry(buffer_physical(u.buffer)).or_default();
+            hazard.dirty |= u.writes;
+            hazard.read |= u.reads;
+        }
+
+        compiled.push(CompiledPass { index: i, name: pass.name.clone(), barriers });
+    }
+
+    Ok(CompiledGraph {
+        passes: compiled,
+        culled,
+        texture_slots,
+        texture_slot_descs,
+        buffer_slots,
+        buffer_slot_descs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lume_core::device::{AttachmentLoadOp, ClearValue, TextureFormat};
+    use lume_core::device::{BufferDescriptor, TextureDescriptor, TextureViewDescriptor};
+    use lume_cpu::{CpuBuffer, CpuDevice, CpuTextureView};
+
+    const CLEAR: ClearValue = ClearValue::Color([0.0; 4]);
+
+    fn info() -> TextureInfo {
+        TextureInfo { width: 64, height: 64, format: TextureFormat::Rgba8Unorm }
+    }
+
+    fn buffer(device: &CpuDevice) -> CpuBuffer {
+        device.create_buffer(BufferDescriptor { size: 16, usage: BufferUsage::STORAGE, mapped_at_creation: true }).unwrap()
+    }
+
+    fn view(device: &CpuDevice) -> CpuTextureView {
+        let texture = device.create_texture(TextureDescriptor {
+            width: 64,
+            height: 64,
+            depth: 1,
+            mip_level_count: 1,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsage::RENDER_ATTACHMENT,
+        }).unwrap();
+        device.create_texture_view(&texture, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap()
+    }
+
+    fn names(compiled: &CompiledGraph) -> Vec<&str> {
+        compiled.passes.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn culls_passes_without_observed_writes() {
+        let output = buffer(&CpuDevice::new());
+        let mut graph = RenderGraph::<CpuDevice>::new();
+        let a = graph.create_buffer("a", BufferInfo { size: 16 });
+        let unused = graph.create_buffer("unused", BufferInfo { size: 16 });
+        let out = graph.import_buffer("out", &output);
+
+        graph.add_compute_pass("produce").write_buffer(a, BufferUsage::STORAGE).execute(|_| Ok(()));
+        graph.add_compute_pass("debug").read_buffer(a, BufferUsage::STORAGE).write_buffer(unused, BufferUsage::STORAGE).execute(|_| Ok(()));
+        graph.add_compute_pass("resolve").read_buffer(a, BufferUsage::STORAGE).write_buffer(out, BufferUsage::STORAGE).execute(|_| Ok(()));
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(names(&compiled), ["produce", "resolve"]);
+        assert_eq!(compiled.culled, ["debug"]);
+        assert_eq!(compiled.buffer_slot(unused), None);
+        assert_eq!(compiled.passes[1].barriers, [Barrier::Memory]);
+    }
+
+    #[test]
+    fn overwriting_pass_hides_earlier_writers() {
+        let view = view(&CpuDevice::new());
+        let mut graph = RenderGraph::<CpuDevice>::new();
+        let target = graph.create_texture("target", info());
+        let output = graph.create_texture("output", info());
+        let present = graph.import_texture_view("present", &view);
+
+        graph.add_raster_pass("stale").color_attachment(target, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("fresh").color_attachment(target, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("blit")
+            .read_texture(target)
+            .color_attachment(output, AttachmentLoadOp::Clear, CLEAR)
+            .execute(|_| Ok(()));
+        graph.add_raster_pass("present")
+            .read_texture(output)
+            .color_attachment(present, AttachmentLoadOp::Clear, CLEAR)
+            .execute(|_| Ok(()));
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(names(&compiled), ["fresh", "blit", "present"]);
+    }
+
+    #[test]
+    fn aliases_transients_with_disjoint_lifetimes() {
+        let view = view(&CpuDevice::new());
+        let mut graph = RenderGraph::<CpuDevice>::new();
+        let first = graph.create_texture("first", info());
+        let second = graph.create_texture("second", info());
+        let third = graph.create_texture("third", info());
+        let present = graph.import_texture_view("present", &view);
+
+        graph.add_raster_pass("a").color_attachment(first, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("b").read_texture(first).color_attachment(second, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("c").read_texture(second).color_attachment(third, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("d").read_texture(third).color_attachment(present, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(compiled.physical_texture_count(), 2);
+        assert_eq!(compiled.texture_slot(first), compiled.texture_slot(third));
+        assert_ne!(compiled.texture_slot(first), compiled.texture_slot(second));
+    }
+
+    #[test]
+    fn aliases_transients_with_different_usages() {
+        let output = buffer(&CpuDevice::new());
+        let mut graph = RenderGraph::<CpuDevice>::new();
+        let first = graph.create_texture("first", info());
+        let second = graph.create_texture("second", info());
+        let third = graph.create_texture("third", info());
+        let out = graph.import_buffer("out", &output);
+
+        // Only the third is also used as a storage image.
+        graph.add_raster_pass("a").color_attachment(first, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("b").read_texture(first).color_attachment(second, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("c").read_texture(second).color_attachment(third, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_compute_pass("d").storage_texture(third).write_buffer(out, BufferUsage::STORAGE).execute(|_| Ok(()));
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(compiled.physical_texture_count(), 2);
+        let slot = compiled.texture_slot(first).unwrap();
+        assert_eq!(compiled.texture_slot(third), Some(slot));
+        let usage = TextureUsage::RENDER_ATTACHMENT | TextureUsage::TEXTURE_BINDING | TextureUsage::STORAGE_BINDING;
+        assert_eq!(compiled.texture_slot_descs[slot], (info(), usage));
+    }
+
+    #[test]
+    fn transitions_follow_each_use() {
+        let view = view(&CpuDevice::new());
+        let mut graph = RenderGraph::<CpuDevice>::new();
+        let color = graph.create_texture("color", info());
+        let present = graph.import_texture_view("present", &view);
+
+        graph.add_raster_pass("draw").color_attachment(color, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+        graph.add_raster_pass("post").read_texture(color).color_attachment(present, AttachmentLoadOp::Clear, CLEAR).execute(|_| Ok(()));
+
+        let compiled = graph.compile().unwrap();
+        assert_eq!(compiled.passes[0].barriers, [Barrier::Texture {
+            texture: color,
+            old_layout: ImageLayout::Undefined,
+            new_layout: ImageLayout::ColorAttachment,
+        }]);
+        assert_eq!(compiled.passes[1].barriers, [Barrier::Texture {
+            texture: color,
+            old_layout: ImageLayout::ColorAttachment,
+            new_layout: ImageLayout::ShaderReadOnly,
+        }]);
+    }
+
+    #[test]
+    fn rejects_reads_of_unwritten_transients() {
+        let output = buffer(&CpuDevice::new());
+        let mut graph = RenderGraph::<CpuDevice>::new();
+        let a = graph.create_buffer("a", BufferInfo { size: 16 });
+        let out = graph.import_buffer("out", &output);
+        graph.add_compute_pass("resolve").read_buffer(a, BufferUsage::STORAGE).write_buffer(out, BufferUsage::STORAGE).execute(|_| Ok(()));
+        assert!(graph.compile().is_err());
+    }
+}
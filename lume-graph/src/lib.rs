@@ -0,0 +1,310 @@
+//! Render graph on top of `lume_core::Device`. Passes declare the named resources they
+//! read and write; compiling the graph culls passes whose results are never observed,
+//! aliases transient resources with disjoint lifetimes onto the same physical resource
+//! and places the barriers between passes. Passes run in declaration order, which is
+//! always a valid order since a pass can only consume what earlier passes produced.
+
+mod compile;
+mod pass;
+mod resource;
+
+pub use compile::{Barrier, CompiledGraph, CompiledPass};
+pub use pass::{Attachment, PassBuilder, PassContext, PassKind, TextureAccess};
+pub use resource::{BufferHandle, BufferInfo, TextureHandle, TextureInfo, TransientPool};
+
+use lume_core::{Device, LumeResult};
+use lume_core::device::{AttachmentStoreOp, CommandBuffer, ImageLayout, RenderingAttachment, RenderingDescriptor};
+use pass::PassNode;
+use resource::{BufferResource, BufferSource, TextureResource, TextureSource};
+
+pub struct RenderGraph<'a, D: Device> {
+    pub(crate) textures: Vec<TextureResource<'a, D>>,
+    pub(crate) buffers: Vec<BufferResource<'a, D>>,
+    pub(crate) passes: Vec<PassNode<'a, D>>,
+}
+
+impl<D: Device> Default for RenderGraph<'_, D> {
+    fn default() -> Self {
+        Self { textures: Vec::new(), buffers: Vec::new(), passes: Vec::new() }
+    }
+}
+
+impl<'a, D: Device> RenderGraph<'a, D> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a texture that only lives for the duration of the graph.
+    pub fn create_texture(&mut self, name: &str, info: TextureInfo) -> TextureHandle {
+        self.textures.push(TextureResource { name: name.to_string(), source: TextureSource::Transient(info) });
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    /// Declares a buffer that only lives for the duration of the graph.
+    pub fn create_buffer(&mut self, name: &str, info: BufferInfo) -> BufferHandle {
+        self.buffers.push(BufferResource { name: name.to_string(), source: BufferSource::Transient(info) });
+        BufferHandle(self.buffers.len() - 1)
+    }
+
+    /// Makes an external texture, currently in `layout`, available to passes.
+    /// Writes to imported resources are what keep passes from being culled.
+    pub fn import_texture(&mut self, name: &str, texture: &'a D::Texture, view: &'a D::TextureView, layout: ImageLayout) -> TextureHandle {
+        self.textures.push(TextureResource {
+            name: name.to_string(),
+            source: TextureSource::Imported { texture: Some(texture), view, layout },
+        });
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    /// Imports a view without its texture, such as a swapchain image, for use as an attachment.
+    pub fn import_texture_view(&mut self, name: &str, view: &'a D::TextureView) -> TextureHandle {
+        self.textures.push(TextureResource {
+            name: name.to_string(),
+            source: TextureSource::Imported { texture: None, view, layout: ImageLayout::Undefined },
+        });
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    pub fn import_buffer(&mut self, name: &str, buffer: &'a D::Buffer) -> BufferHandle {
+        self.buffers.push(BufferResource { name: name.to_string(), source: BufferSource::Imported(buffer) });
+        BufferHandle(self.buffers.len() - 1)
+    }
+
+    pub fn add_raster_pass(&mut self, name: &str) -> PassBuilder<'_, 'a, D> {
+        self.add_pass(name, PassKind::Raster)
+    }
+
+    pub fn add_compute_pass(&mut self, name: &str) -> PassBuilder<'_, 'a, D> {
+        self.add_pass(name, PassKind::Compute)
+    }
+
+    fn add_pass(&mut self, name: &str, kind: PassKind) -> PassBuilder<'_, 'a, D> {
+        PassBuilder {
+            graph: self,
+            node: PassNode {
+                name: name.to_string(),
+                kind,
+                textures: Vec::new(),
+                buffers: Vec::new(),
+                color_attachments: Vec::new(),
+                depth_attachment: None,
+                execute: None,
+            },
+        }
+    }
+
+    pub fn compile(&self) -> LumeResult<CompiledGraph> {
+        compile::compile(self)
+    }
+
+    /// Compiles the graph and records it into `cmd`, taking transient resources from `pool`.
+    pub fn execute(self, device: &D, cmd: &mut D::CommandBuffer, pool: &mut TransientPool<D>) -> LumeResult<CompiledGraph> {
+        let compiled = self.compile()?;
+        for name in &compiled.culled {
+            log::debug!("Render graph culled pass '{}'", name);
+        }
+
+        let texture_indices = pool.acquire_textures(device, &compiled.texture_slot_descs)?;
+        let buffer_indices = pool.acquire_buffers(device, &compiled.buffer_slot_descs)?;
+        let pool: &TransientPool<D> = pool;
+
+        let RenderGraph { textures, buffers, mut passes } = self;
+        let textures: Vec<_> = textures.iter().enumerate().map(|(i, t)| match t.source {
+            TextureSource::Transient(_) => compiled.texture_slots[i].map(|slot| {
+                let (texture, view) = pool.texture(texture_indices[slot]);
+                (Some(texture), view)
+            }),
+            TextureSource::Imported { texture, view, .. } => Some((texture, view)),
+        }).collect();
+        let buffers: Vec<_> = buffers.iter().enumerate().map(|(i, b)| match b.source {
+            BufferSource::Transient(_) => compiled.buffer_slots[i].map(|slot| pool.buffer(buffer_indices[slot])),
+            BufferSource::Imported(buffer) => Some(buffer),
+        }).collect();
+
+        for compiled_pass in &compiled.passes {
+            for barrier in &compiled_pass.barriers {
+                match *barrier {
+                    Barrier::Texture { texture, old_layout, new_layout } => {
+                        if let Some((Some(texture), _)) = textures[texture.0] {
+                            cmd.texture_barrier(texture, old_layout, new_layout);
+                        }
+                    }
+                    Barrier::Memory => cmd.compute_barrier(),
+                }
+            }
+
+            let pass = &mut passes[compiled_pass.index];
+            let execute = pass.execute.take().expect("Pass added without an execute callback");
+            let view = |attachment: &Attachment| textures[attachment.texture.0].expect("Attachment has no backing texture").1;
+
+            if pass.kind == PassKind::Raster {
+                let color_attachments: Vec<RenderingAttachment<D>> = pass.color_attachments.iter().map(|a| RenderingAttachment {
+                    view: view(a),
+                    layout: ImageLayout::ColorAttachment,
+                    load_op: a.load_op,
+                    store_op: AttachmentStoreOp::Store,
+                    clear_value: a.clear_value,
+                }).collect();
+                let depth_attachment = pass.depth_attachment.as_ref().map(|a| RenderingAttachment {
+                    view: view(a),
+                    layout: ImageLayout::DepthStencilAttachment,
+                    load_op: a.load_op,
+                    store_op: AttachmentStoreOp::Store,
+                    clear_value: a.clear_value,
+                });
+                cmd.begin_rendering(RenderingDescriptor {
+                    color_attachments: &color_attachments,
+                    depth_attachment,
+                    stencil_attachment: None,
+                    view_mask: 0,
+                });
+            }
+
+            let mut context = PassContext { device, cmd: &mut *cmd, textures: &textures, buffers: &buffers };
+            execute(&mut context)?;
+
+            if pass.kind == PassKind::Raster {
+                cmd.end_rendering();
+            }
+        }
+
+        Ok(compiled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lume_core::{Backend, Instance, InstanceDescriptor};
+    use lume_core::device::{Buffer, BufferDescriptor, BufferUsage, CommandPool, QueueType};
+    use lume_cpu::{CpuBuffer, CpuDevice, CpuInstance};
+
+    fn device() -> CpuDevice {
+        let instance = CpuInstance::new(InstanceDescriptor { name: "lume-graph test", backend: Backend::Cpu }).unwrap();
+        instance.request_device(None).unwrap()
+    }
+
+    fn buffer(device: &CpuDevice, data: &[u8]) -> CpuBuffer {
+        let buffer = device.create_buffer(BufferDescriptor {
+            size: data.len() as u64,
+            usage: BufferUsage::COPY_SRC | BufferUsage::COPY_DST,
+            mapped_at_creation: true,
+        }).unwrap();
+        buffer.write_data(0, data).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn executes_surviving_passes_through_a_transient() {
+        let device = device();
+        let source = buffer(&device, &[1, 2, 3, 4]);
+        let output = buffer(&device, &[0; 4]);
+        let mut pool = TransientPool::new();
+
+        let pool_size = {
+            let mut graph = RenderGraph::<CpuDevice>::new();
+            let staging = graph.create_buffer("staging", BufferInfo { size: 4 });
+            let scratch = graph.create_buffer("scratch", BufferInfo { size: 4 });
+            let source = graph.import_buffer("source", &source);
+            let output = graph.import_buffer("output", &output);
+
+            graph.add_compute_pass("upload")
+                .read_buffer(source, BufferUsage::COPY_SRC)
+                .write_buffer(staging, BufferUsage::COPY_DST)
+                .execute(move |ctx| {
+                    ctx.cmd.copy_buffer_to_buffer(ctx.buffer(source)?, ctx.buffer(staging)?, 4);
+                    Ok(())
+                });
+            graph.add_compute_pass("unused")
+                .read_buffer(staging, BufferUsage::COPY_SRC)
+                .write_buffer(scratch, BufferUsage::COPY_DST)
+                .execute(|_| panic!("culled pass was executed"));
+            graph.add_compute_pass("resolve")
+                .read_buffer(staging, BufferUsage::COPY_SRC)
+                .write_buffer(output, BufferUsage::COPY_DST)
+                .execute(move |ctx| {
+                    ctx.cmd.copy_buffer_to_buffer(ctx.buffer(staging)?, ctx.buffer(output)?, 4);
+                    // Only the culled pass declared it, so it was never allocated.
+                    assert!(ctx.buffer(scratch).is_err());
+                    Ok(())
+                });
+
+            let command_pool = device.create_command_pool(QueueType::Compute).unwrap();
+            let mut cmd = command_pool.allocate_command_buffer().unwrap();
+            cmd.begin().unwrap();
+            let compiled = graph.execute(&device, &mut cmd, &mut pool).unwrap();
+            cmd.end().unwrap();
+            device.submit(QueueType::Compute, &[&cmd], &[], &[], &[], &[], None).unwrap();
+            assert_eq!(compiled.culled, ["unused"]);
+            pool.buffer_count()
+        };
+
+        let mut result = [0u8; 4];
+        output.read_data(0, &mut result).unwrap();
+        assert_eq!(result, [1, 2, 3, 4]);
+        assert_eq!(pool_size, 1);
+    }
+
+    #[test]
+    fn records_cull_dispatch_before_draw_with_barrier() {
+        use lume_core::device::*;
+        use lume_core::recording::{Command, RecordingDevice};
+
+        let device = RecordingDevice::new();
+        let shader = device.create_shader_module(&[]).unwrap();
+        let layout = device.create_pipeline_layout(PipelineLayoutDescriptor { bind_group_layouts: &[], push_constant_ranges: &[] }).unwrap();
+        let render_pass = device.create_render_pass(RenderPassDescriptor { color_format: TextureFormat::Rgba8Unorm, depth_stencil_format: None, load_op: AttachmentLoadOp::Clear }).unwrap();
+        let cull = device.create_compute_pipeline(ComputePipelineDescriptor { shader: &shader, layout: &layout }).unwrap();
+        let draw = device.create_graphics_pipeline(GraphicsPipelineDescriptor {
+            vertex_shader: &shader,
+            fragment_shader: &shader,
+            render_pass: &render_pass,
+            layout: &layout,
+            primitive: PrimitiveState { topology: PrimitiveTopology::TriangleList },
+            vertex_layout: None,
+            depth_stencil: None,
+        }).unwrap();
+        let target = device.create_texture(TextureDescriptor {
+            width: 4,
+            height: 4,
+            depth: 1,
+            mip_level_count: 1,
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsage::RENDER_ATTACHMENT,
+        }).unwrap();
+        let target_view = device.create_texture_view(&target, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None }).unwrap();
+        let mut pool = TransientPool::new();
+
+        let mut graph = RenderGraph::<RecordingDevice>::new();
+        let args = graph.create_buffer("draw args", BufferInfo { size: 16 });
+        let color = graph.import_texture("target", &target, &target_view, ImageLayout::Undefined);
+        graph.add_compute_pass("cull")
+            .write_buffer(args, BufferUsage::STORAGE)
+            .execute(|ctx| {
+                ctx.cmd.bind_compute_pipeline(&cull);
+                ctx.cmd.dispatch(1, 1, 1);
+                Ok(())
+            });
+        graph.add_raster_pass("visbuffer")
+            .read_buffer(args, BufferUsage::INDIRECT)
+            .color_attachment(color, AttachmentLoadOp::Clear, ClearValue::Color([0.0; 4]))
+            .execute(|ctx| {
+                ctx.cmd.bind_graphics_pipeline(&draw);
+                ctx.cmd.draw_indirect(ctx.buffer(args)?, 0, 1, 16);
+                Ok(())
+            });
+
+        let command_pool = device.create_command_pool(QueueType::Graphics).unwrap();
+        let mut cmd = command_pool.allocate_command_buffer().unwrap();
+        graph.execute(&device, &mut cmd, &mut pool).unwrap();
+        device.submit(QueueType::Graphics, &[&cmd], &[], &[], &[], &[], None).unwrap();
+
+        let log = device.submitted();
+        let dispatch = log.dispatch_with(&cull);
+        let draw_call = log.draw_with(&draw);
+        log.assert_barrier_between(dispatch, draw_call);
+        let begin = log.expect("begin_rendering", |c| matches!(c.command, Command::BeginRendering { .. }));
+        log.assert_before(dispatch, begin);
+        log.assert_before(begin, draw_call);
+    }
+}
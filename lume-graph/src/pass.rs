@@ -0,0 +1,157 @@
+use lume_core::{Device, LumeError, LumeResult};
+use lume_core::device::{AttachmentLoadOp, BufferUsage, ClearValue, ImageLayout, TextureUsage};
+use crate::resource::{BufferHandle, TextureHandle};
+use crate::RenderGraph;
+
+/// How a pass uses a texture.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextureAccess {
+    Sampled,
+    Storage,
+    ColorAttachment,
+    DepthAttachment,
+}
+
+impl TextureAccess {
+    pub fn layout(self) -> ImageLayout {
+        match self {
+            TextureAccess::Sampled => ImageLayout::ShaderReadOnly,
+            TextureAccess::Storage => ImageLayout::General,
+            TextureAccess::ColorAttachment => ImageLayout::ColorAttachment,
+            TextureAccess::DepthAttachment => ImageLayout::DepthStencilAttachment,
+        }
+    }
+
+    pub fn is_write(self) -> bool {
+        self != TextureAccess::Sampled
+    }
+
+    pub(crate) fn usage(self) -> TextureUsage {
+        match self {
+            TextureAccess::Sampled => TextureUsage::TEXTURE_BINDING,
+            TextureAccess::Storage => TextureUsage::STORAGE_BINDING,
+            TextureAccess::ColorAttachment => TextureUsage::RENDER_ATTACHMENT,
+            TextureAccess::DepthAttachment => TextureUsage::DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PassKind {
+    /// Recorded between `begin_rendering` and `end_rendering` on its attachments.
+    Raster,
+    Compute,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Attachment {
+    pub texture: TextureHandle,
+    pub load_op: AttachmentLoadOp,
+    pub clear_value: ClearValue,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct TextureUse {
+    pub texture: TextureHandle,
+    pub access: TextureAccess,
+    /// Whether the pass depends on the previous contents.
+    pub reads: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct BufferUse {
+    pub buffer: BufferHandle,
+    pub usage: BufferUsage,
+    pub reads: bool,
+    pub writes: bool,
+}
+
+type ExecuteFn<'a, D> = Box<dyn FnOnce(&mut PassContext<'_, D>) -> LumeResult<()> + 'a>;
+
+pub(crate) struct PassNode<'a, D: Device> {
+    pub name: String,
+    pub kind: PassKind,
+    pub textures: Vec<TextureUse>,
+    pub buffers: Vec<BufferUse>,
+    pub color_attachments: Vec<Attachment>,
+    pub depth_attachment: Option<Attachment>,
+    pub execute: Option<ExecuteFn<'a, D>>,
+}
+
+/// Declares the resources a pass uses. The pass joins the graph once `execute` is called.
+pub struct PassBuilder<'g, 'a, D: Device> {
+    pub(crate) graph: &'g mut RenderGraph<'a, D>,
+    pub(crate) node: PassNode<'a, D>,
+}
+
+impl<'a, D: Device> PassBuilder<'_, 'a, D> {
+    pub fn read_texture(mut self, texture: TextureHandle) -> Self {
+        self.node.textures.push(TextureUse { texture, access: TextureAccess::Sampled, reads: true });
+        self
+    }
+
+    /// Reads and writes a texture bound as a storage image.
+    pub fn storage_texture(mut self, texture: TextureHandle) -> Self {
+        self.node.textures.push(TextureUse { texture, access: TextureAccess::Storage, reads: true });
+        self
+    }
+
+    pub fn color_attachment(mut self, texture: TextureHandle, load_op: AttachmentLoadOp, clear_value: ClearValue) -> Self {
+        let reads = load_op == AttachmentLoadOp::Load;
+        self.node.textures.push(TextureUse { texture, access: TextureAccess::ColorAttachment, reads });
+        self.node.color_attachments.push(Attachment { texture, load_op, clear_value });
+        self
+    }
+
+    pub fn depth_attachment(mut self, texture: TextureHandle, load_op: AttachmentLoadOp, clear_value: ClearValue) -> Self {
+        let reads = load_op == AttachmentLoadOp::Load;
+        self.node.textures.push(TextureUse { texture, access: TextureAccess::DepthAttachment, reads });
+        self.node.depth_attachment = Some(Attachment { texture, load_op, clear_value });
+        self
+    }
+
+    /// Reads a buffer bound with `usage`, e.g. `BufferUsage::INDIRECT` for draw arguments.
+    pub fn read_buffer(mut self, buffer: BufferHandle, usage: BufferUsage) -> Self {
+        self.node.buffers.push(BufferUse { buffer, usage, reads: true, writes: false });
+        self
+    }
+
+    /// Overwrites a buffer; declare a read as well if the pass depends on its contents.
+    pub fn write_buffer(mut self, buffer: BufferHandle, usage: BufferUsage) -> Self {
+        self.node.buffers.push(BufferUse { buffer, usage, reads: false, writes: true });
+        self
+    }
+
+    /// Adds the pass to the graph with the callback that records its commands.
+    pub fn execute(mut self, execute: impl FnOnce(&mut PassContext<'_, D>) -> LumeResult<()> + 'a) {
+        self.node.execute = Some(Box::new(execute));
+        self.graph.passes.push(self.node);
+    }
+}
+
+/// A texture and its view as seen by passes; imported views have no texture.
+pub(crate) type PhysicalTexture<'p, D> = (Option<&'p <D as Device>::Texture>, &'p <D as Device>::TextureView);
+
+/// Everything a pass callback needs to record its commands.
+pub struct PassContext<'p, D: Device> {
+    pub device: &'p D,
+    pub cmd: &'p mut D::CommandBuffer,
+    pub(crate) textures: &'p [Option<PhysicalTexture<'p, D>>],
+    pub(crate) buffers: &'p [Option<&'p D::Buffer>],
+}
+
+impl<'p, D: Device> PassContext<'p, D> {
+    pub fn buffer(&self, handle: BufferHandle) -> LumeResult<&'p D::Buffer> {
+        self.buffers[handle.0].ok_or(LumeError::Generic("Buffer is not used by any pass"))
+    }
+
+    pub fn texture(&self, handle: TextureHandle) -> LumeResult<&'p D::Texture> {
+        let (texture, _) = self.textures[handle.0].ok_or(LumeError::Generic("Texture is not used by any pass"))?;
+        texture.ok_or(LumeError::Generic("Texture was imported as a view only"))
+    }
+
+    pub fn texture_view(&self, handle: TextureHandle) -> LumeResult<&'p D::TextureView> {
+        let (_, view) = self.textures[handle.0].ok_or(LumeError::Generic("Texture is not used by any pass"))?;
+        Ok(view)
+    }
+}
@@ -0,0 +1,169 @@
+use lume_core::{Device, LumeResult};
+use lume_core::device::{BufferDescriptor, BufferUsage, ImageLayout, TextureDescriptor, TextureFormat, TextureUsage, TextureViewDescriptor};
+
+/// A texture declared in a graph. Only valid for the graph that created it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TextureHandle(pub(crate) usize);
+
+/// A buffer declared in a graph. Only valid for the graph that created it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BufferHandle(pub(crate) usize);
+
+/// Shape of a transient texture; its usage is inferred from the passes that access it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextureInfo {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+}
+
+/// Size of a transient buffer; its usage is inferred from the passes that access it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BufferInfo {
+    pub size: u64,
+}
+
+pub(crate) enum TextureSource<'a, D: Device> {
+    Transient(TextureInfo),
+    /// A texture owned by the caller. Without the texture itself (e.g. a swapchain image)
+    /// the graph cannot insert barriers for it and relies on `begin_rendering`.
+    Imported {
+        texture: Option<&'a D::Texture>,
+        view: &'a D::TextureView,
+        layout: ImageLayout,
+    },
+}
+
+pub(crate) struct TextureResource<'a, D: Device> {
+    pub name: String,
+    pub source: TextureSource<'a, D>,
+}
+
+pub(crate) enum BufferSource<'a, D: Device> {
+    Transient(BufferInfo),
+    Imported(&'a D::Buffer),
+}
+
+pub(crate) struct BufferResource<'a, D: Device> {
+    pub name: String,
+    pub source: BufferSource<'a, D>,
+}
+
+impl<D: Device> TextureResource<'_, D> {
+    pub fn is_transient(&self) -> bool {
+        matches!(self.source, TextureSource::Transient(_))
+    }
+}
+
+impl<D: Device> BufferResource<'_, D> {
+    pub fn is_transient(&self) -> bool {
+        matches!(self.source, BufferSource::Transient(_))
+    }
+}
+
+struct PooledTexture<D: Device> {
+    info: TextureInfo,
+    usage: TextureUsage,
+    texture: D::Texture,
+    view: D::TextureView,
+}
+
+struct PooledBuffer<D: Device> {
+    info: BufferInfo,
+    usage: BufferUsage,
+    buffer: D::Buffer,
+}
+
+/// Physical resources backing transient textures and buffers.
+///
+/// The pool outlives a single graph so its resources stay alive until the GPU has
+/// finished with them and can be reused by the next frame's graph.
+pub struct TransientPool<D: Device> {
+    textures: Vec<PooledTexture<D>>,
+    buffers: Vec<PooledBuffer<D>>,
+}
+
+impl<D: Device> Default for TransientPool<D> {
+    fn default() -> Self {
+        Self { textures: Vec::new(), buffers: Vec::new() }
+    }
+}
+
+impl<D: Device> TransientPool<D> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Finds or creates one pooled texture per slot, returning their indices.
+    pub(crate) fn acquire_textures(&mut self, device: &D, slots: &[(TextureInfo, TextureUsage)]) -> LumeResult<Vec<usize>> {
+        let mut taken = vec![false; self.textures.len()];
+        let mut indices = Vec::with_capacity(slots.len());
+        for &(info, usage) in slots {
+            let found = self.textures.iter().enumerate()
+                .position(|(i, t)| !taken[i] && t.info == info && t.usage == usage);
+            let index = match found {
+                Some(index) => index,
+                None => {
+                    let texture = device.create_texture(TextureDescriptor {
+                        width: info.width,
+                        height: info.height,
+                        depth: 1,
+                        mip_level_count: 1,
+                        format: info.format,
+                        usage,
+                    })?;
+                    let view = device.create_texture_view(&texture, TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None })?;
+                    self.textures.push(PooledTexture { info, usage, texture, view });
+                    taken.push(false);
+                    self.textures.len() - 1
+                }
+            };
+            taken[index] = true;
+            indices.push(index);
+        }
+        Ok(indices)
+    }
+
+    /// Finds or creates one pooled buffer per slot, returning their indices.
+    pub(crate) fn acquire_buffers(&mut self, device: &D, slots: &[(BufferInfo, BufferUsage)]) -> LumeResult<Vec<usize>> {
+        let mut taken = vec![false; self.buffers.len()];
+        let mut indices = Vec::with_capacity(slots.len());
+        for &(info, usage) in slots {
+            let found = self.buffers.iter().enumerate()
+                .position(|(i, b)| !taken[i] && b.info == info && b.usage == usage);
+            let index = match found {
+                Some(index) => index,
+                None => {
+                    let buffer = device.create_buffer(BufferDescriptor {
+                        size: info.size,
+                        usage,
+                        mapped_at_creation: false,
+                    })?;
+                    self.buffers.push(PooledBuffer { info, usage, buffer });
+                    taken.push(false);
+                    self.buffers.len() - 1
+                }
+            };
+            taken[index] = true;
+            indices.push(index);
+        }
+        Ok(indices)
+    }
+
+    pub(crate) fn texture(&self, index: usize) -> (&D::Texture, &D::TextureView) {
+        let pooled = &self.textures[index];
+        (&pooled.texture, &pooled.view)
+    }
+
+    pub(crate) fn buffer(&self, index: usize) -> &D::Buffer {
+        &self.buffers[index].buffer
+    }
+}
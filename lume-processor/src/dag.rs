@@ -0,0 +1,266 @@
+//! The cluster LOD DAG from Docs/Adaptrix_Algorithm_Guide.md. Each level merges neighbouring
+//! clusters into groups, simplifies every group to half its triangles with the group border
+//! locked, and splits the result into the clusters of the next level, until one remains.
+
+use glam::Vec4;
+use lume_adaptrix::{merge_spheres, AdaptrixVertex, MAX_CLUSTER_TRIANGLES};
+use meshopt::{build_meshlets, compute_meshlet_bounds, simplify, simplify_scale, SimplifyOptions, VertexDataAdapter};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+pub const MAX_VERTICES: usize = 128;
+pub const MAX_TRIANGLES: usize = MAX_CLUSTER_TRIANGLES as usize;
+const GROUP_SIZE: usize = 4;
+/// Groups keeping more of their triangles than this are not simplified; their clusters are
+/// grouped again at the next level.
+const MAX_KEPT_TRIANGLES: f32 = 0.85;
+const MAX_LEVELS: u32 = 32;
+
+pub struct DagCluster {
+    /// Indices into the mesh's vertices.
+    pub vertices: Vec<u32>,
+    /// Indices into `vertices`, three per triangle.
+    pub triangles: Vec<u8>,
+    pub level: u32,
+    /// Shared by all clusters simplified from the same group; the tight bounds at level 0.
+    pub lod_sphere: Vec4,
+    /// World-space simplification error, accumulated over all levels below.
+    pub error: f32,
+    /// `lod_sphere` and `error` of the clusters this one was simplified into.
+    pub parent_sphere: Vec4,
+    /// `f32::MAX` for roots.
+    pub parent_error: f32,
+}
+
+impl DagCluster {
+    fn indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.triangles.iter().map(|&t| self.vertices[t as usize])
+    }
+}
+
+/// Every level of the DAG, finest first.
+pub fn build_dag(vertices: &[AdaptrixVertex], indices: &[u32]) -> Vec<DagCluster> {
+    let adapter = VertexDataAdapter::new(bytemuck::cast_slice(vertices), std::mem::size_of::<AdaptrixVertex>(), 0).unwrap();
+    let scale = simplify_scale(&adapter);
+    let welded = weld_positions(vertices);
+
+    let mut clusters = split(indices, &adapter, None, 0.0, 0);
+    let mut level: Vec<usize> = (0..clusters.len()).collect();
+    let mut depth = 0;
+    while level.len() > 1 && depth < MAX_LEVELS {
+        depth += 1;
+        let mut next = Vec::new();
+        let mut simplified_any = false;
+        for group in group_clusters(&clusters, &level, &welded) {
+            let merged: Vec<u32> = group.iter().flat_map(|&c| clusters[c].indices()).collect();
+            let mut relative_error = 0.0;
+            let simplified = simplify(&merged, &adapter, merged.len() / 6 * 3, 1.0, SimplifyOptions::LockBorder, Some(&mut relative_error));
+            if simplified.len() as f32 > merged.len() as f32 * MAX_KEPT_TRIANGLES {
+                // Most of the group is its locked border. Grouped with other neighbours at the
+                // next level, that border is inside the group and can be simplified.
+                next.extend(group);
+                continue;
+            }
+            simplified_any = true;
+
+            // Bounding the children's spheres and errors keeps both monotonic up the DAG.
+            let sphere = group.iter().map(|&c| clusters[c].lod_sphere).reduce(merge_spheres).unwrap();
+            let error = group.iter().map(|&c| clusters[c].error).fold(0.0, f32::max) + relative_error * scale;
+            for &c in &group {
+                clusters[c].parent_sphere = sphere;
+                clusters[c].parent_error = error;
+            }
+            let first = clusters.len();
+            clusters.extend(split(&simplified, &adapter, Some(sphere), error, depth));
+            next.extend(first..clusters.len());
+        }
+        // Grouping the same clusters again would stall the same way; they stay roots.
+        if !simplified_any {
+            break;
+        }
+        level = next;
+    }
+    clusters
+}
+
+fn split(indices: &[u32], adapter: &VertexDataAdapter, lod_sphere: Option<Vec4>, error: f32, level: u32) -> Vec<DagCluster> {
+    let meshlets = build_meshlets(indices, adapter, MAX_VERTICES, MAX_TRIANGLES, 0.0);
+    meshlets.iter().map(|m| {
+        let lod_sphere = lod_sphere.unwrap_or_else(|| {
+            let bounds = compute_meshlet_bounds(m, adapter);
+            Vec4::new(bounds.center[0], bounds.center[1], bounds.center[2], bounds.radius)
+        });
+        DagCluster {
+            vertices: m.vertices.to_vec(),
+            triangles: m.triangles.to_vec(),
+            level,
+            lod_sphere,
+            error,
+            parent_sphere: lod_sphere,
+            parent_error: f32::MAX,
+        }
+    }).collect()
+}
+
+/// Maps every vertex to the first one at the same position, so clusters split along UV or
+/// normal seams are still neighbours.
+fn weld_positions(vertices: &[AdaptrixVertex]) -> Vec<u32> {
+    let mut first = HashMap::new();
+    vertices.iter().enumerate()
+        .map(|(i, v)| *first.entry(v.position.map(f32::to_bits)).or_insert(i as u32))
+        .collect()
+}
+
+/// Partitions `level` into groups of up to `GROUP_SIZE` clusters, growing each group by the
+/// cluster sharing the most edges with it, or the nearest one if none does.
+fn group_clusters(clusters: &[DagCluster], level: &[usize], welded: &[u32]) -> Vec<Vec<usize>> {
+    let mut edges: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+    for (i, &c) in level.iter().enumerate() {
+        let indices: Vec<u32> = clusters[c].indices().map(|v| welded[v as usize]).collect();
+        for triangle in indices.chunks_exact(3) {
+            for k in 0..3 {
+                let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+                let owners = edges.entry((a.min(b), a.max(b))).or_default();
+                if owners.last() != Some(&i) {
+                    owners.push(i);
+                }
+            }
+        }
+    }
+    let mut shared_edges: Vec<HashMap<usize, u32>> = vec![HashMap::new(); level.len()];
+    for owners in edges.values() {
+        for &a in owners {
+            for &b in owners.iter().filter(|&&b| b != a) {
+                *shared_edges[a].entry(b).or_default() += 1;
+            }
+        }
+    }
+
+    let center = |i: usize| clusters[level[i]].lod_sphere.truncate();
+    let mut grouped = vec![false; level.len()];
+    let mut groups = Vec::new();
+    for seed in 0..level.len() {
+        if grouped[seed] {
+            continue;
+        }
+        grouped[seed] = true;
+        let mut group = vec![seed];
+        while group.len() < GROUP_SIZE {
+            let mut shared: HashMap<usize, u32> = HashMap::new();
+            for &member in &group {
+                for (&neighbour, &count) in shared_edges[member].iter().filter(|&(&n, _)| !grouped[n]) {
+                    *shared.entry(neighbour).or_default() += count;
+                }
+            }
+            let next = shared.into_iter().max_by_key(|&(n, count)| (count, Reverse(n))).map(|(n, _)| n).or_else(|| {
+                (0..level.len()).filter(|&n| !grouped[n])
+                    .min_by(|&a, &b| center(a).distance(center(seed)).total_cmp(&center(b).distance(center(seed))))
+            });
+            let Some(next) = next else { break };
+            grouped[next] = true;
+            group.push(next);
+        }
+        groups.push(group);
+    }
+
+    // A cluster left on its own has nothing to simplify but its locked border, so it joins
+    // the group it shares the most edges with instead.
+    if groups.len() > 1 && groups.last().unwrap().len() == 1 {
+        let single = groups.pop().unwrap()[0];
+        let shared_with = |group: &Vec<usize>| group.iter().filter_map(|g| shared_edges[single].get(g)).sum::<u32>();
+        let nearest = |group: &Vec<usize>| group.iter().map(|&g| center(g).distance(center(single))).fold(f32::MAX, f32::min);
+        let target = groups.iter_mut()
+            .max_by(|a, b| shared_with(a).cmp(&shared_with(b)).then(nearest(b).total_cmp(&nearest(a))))
+            .unwrap();
+        target.push(single);
+    }
+    groups.into_iter().map(|group| group.into_iter().map(|i| level[i]).collect()).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A closed UV sphere with shared poles, split along the seam at longitude 0 like a
+    /// textured mesh would be.
+    pub(crate) fn uv_sphere(rings: u32, segments: u32) -> (Vec<AdaptrixVertex>, Vec<u32>) {
+        let vertex = |position: [f32; 3], uv: [f32; 2]| AdaptrixVertex { position, normal: position, uv };
+        let mut vertices = vec![vertex([0.0, 1.0, 0.0], [0.5, 0.0])];
+        for ring in 1..rings {
+            for segment in 0..=segments {
+                let (u, v) = (segment as f32 / segments as f32, ring as f32 / rings as f32);
+                let (theta, phi) = (u * std::f32::consts::TAU, v * std::f32::consts::PI);
+                vertices.push(vertex([phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin()], [u, v]));
+            }
+        }
+        vertices.push(vertex([0.0, -1.0, 0.0], [0.5, 1.0]));
+
+        let south = vertices.len() as u32 - 1;
+        let ring_start = |ring: u32| 1 + (ring - 1) * (segments + 1);
+        let mut indices = Vec::new();
+        for segment in 0..segments {
+            indices.extend([0, ring_start(1) + segment + 1, ring_start(1) + segment]);
+            indices.extend([south, ring_start(rings - 1) + segment, ring_start(rings - 1) + segment + 1]);
+        }
+        for ring in 1..rings - 1 {
+            for segment in 0..segments {
+                let (a, b) = (ring_start(ring) + segment, ring_start(ring + 1) + segment);
+                indices.extend([a, a + 1, b, a + 1, b + 1, b]);
+            }
+        }
+        (vertices, indices)
+    }
+
+    #[test]
+    fn simplifies_to_a_root_with_monotonic_errors_and_spheres() {
+        let (vertices, indices) = uv_sphere(64, 128);
+        let clusters = build_dag(&vertices, &indices);
+
+        let triangles = |level: u32| clusters.iter().filter(|c| c.level == level).map(|c| c.triangles.len() / 3).sum::<usize>();
+        assert_eq!(triangles(0), indices.len() / 3);
+        let top = clusters.iter().map(|c| c.level).max().unwrap();
+        assert!(top >= 4, "only {} levels", top + 1);
+        assert!(triangles(top) <= MAX_TRIANGLES, "{} triangles at the top", triangles(top));
+
+        let roots: Vec<_> = clusters.iter().filter(|c| c.parent_error == f32::MAX).collect();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].level, top);
+        for cluster in &clusters {
+            assert!(cluster.vertices.len() <= MAX_VERTICES && cluster.triangles.len() / 3 <= MAX_TRIANGLES);
+            if cluster.level == 0 {
+                assert_eq!(cluster.error, 0.0);
+            }
+            if cluster.parent_error == f32::MAX {
+                continue;
+            }
+            assert!(cluster.error <= cluster.parent_error);
+            let (child, parent) = (cluster.lod_sphere, cluster.parent_sphere);
+            assert!(child.truncate().distance(parent.truncate()) + child.w <= parent.w * 1.0001, "{} is outside {}", child, parent);
+        }
+        // Siblings in a group share their parent's sphere, so they switch LOD together.
+        for cluster in clusters.iter().filter(|c| c.level > 0) {
+            assert!(clusters.iter().filter(|c| c.level == cluster.level - 1).any(|c| c.parent_sphere == cluster.lod_sphere && c.parent_error == cluster.error));
+        }
+    }
+
+    #[test]
+    fn regroups_clusters_of_groups_that_do_not_simplify() {
+        // Bumps finer than the triangles on the north cap fold over when simplified, so some
+        // of its groups stall well below the top.
+        let (mut vertices, indices) = uv_sphere(64, 128);
+        for vertex in vertices.iter_mut().filter(|v| v.position[1] > 0.7) {
+            let [x, y, z] = vertex.position.map(|p| p * 80.0);
+            let bump = 1.0 + 0.1 * x.sin() * (z * 1.3).cos() * (y * 0.7).sin();
+            vertex.position = vertex.position.map(|p| p * bump);
+        }
+        let clusters = build_dag(&vertices, &indices);
+
+        let roots: Vec<_> = clusters.iter().filter(|c| c.parent_error == f32::MAX).collect();
+        let top = clusters.iter().map(|c| c.level).max().unwrap();
+        assert!(roots.iter().all(|c| c.level == top), "roots at levels {:?}", roots.iter().map(|c| c.level).collect::<Vec<_>>());
+        for cluster in clusters.iter().filter(|c| c.parent_error != f32::MAX) {
+            assert!(cluster.error <= cluster.parent_error);
+            assert!(clusters.iter().any(|c| c.level > cluster.level && c.lod_sphere == cluster.parent_sphere && c.error == cluster.parent_error));
+        }
+    }
+}
@@ -1,6 +1,9 @@
+mod dag;
+
 use anyhow::{Context, Result};
+use dag::build_dag;
+use lume_adaptrix::bvh::build_bvh;
 use lume_adaptrix::{AdaptrixMesh, AdaptrixVertex, Cluster};
-use meshopt::{build_meshlets, compute_meshlet_bounds, VertexDataAdapter};
 use std::env;
 
 fn main() -> Result<()> {
@@ -77,44 +80,50 @@ fn load_obj(path: &str) -> Result<RawMesh> {
 }
 
 fn process_mesh(raw: RawMesh) -> Result<AdaptrixMesh> {
-    let vertex_stride = std::mem::size_of::<AdaptrixVertex>();
-    let vertex_data = bytemuck::cast_slice(&raw.vertices);
-    let adapter = VertexDataAdapter::new(vertex_data, vertex_stride, 0).unwrap();
-
-    let meshlets = build_meshlets(&raw.indices, &adapter, 128, 256, 0.0);
+    let dag = build_dag(&raw.vertices, &raw.indices);
+
+    let lod_clusters: Vec<Cluster> = dag.iter().map(|c| Cluster {
+        bounding_sphere: c.lod_sphere,
+        error_metric: c.error,
+        parent_error: c.parent_error,
+        parent_sphere: c.parent_sphere,
+        ..bytemuck::Zeroable::zeroed()
+    }).collect();
+    let levels: Vec<u32> = dag.iter().map(|c| c.level).collect();
+    // The BVH's leaves need the clusters of each group next to each other.
+    let (bvh, order) = build_bvh(&lod_clusters, &levels);
 
     let mut clusters = Vec::new();
     let mut all_vertices = Vec::new();
     let mut all_indices = Vec::new();
 
-    for m in meshlets.iter() {
-        let bounds = compute_meshlet_bounds(m, &adapter);
-        
+    for (c, lod) in order.iter().map(|&i| (&dag[i], &lod_clusters[i])) {
         let cluster = Cluster {
             vertex_offset: all_vertices.len() as u32,
             triangle_offset: all_indices.len() as u32,
-            vertex_count: m.vertices.len() as u32,
-            triangle_count: (m.triangles.len() / 3) as u32,
-            bounding_sphere: [bounds.center[0], bounds.center[1], bounds.center[2], bounds.radius].into(),
-            error_metric: 0.0,
-            parent_error: 1e10,
-            _padding: [0.0; 2],
+            vertex_count: c.vertices.len() as u32,
+            triangle_count: (c.triangles.len() / 3) as u32,
+            ..*lod
         };
 
         clusters.push(cluster);
 
-        for &v_idx in m.vertices {
+        for &v_idx in &c.vertices {
             all_vertices.push(raw.vertices[v_idx as usize]);
         }
-        for &t_idx in m.triangles {
+        for &t_idx in &c.triangles {
             all_indices.push(t_idx as u32);
         }
     }
 
+    let level_count = levels.iter().map(|l| l + 1).max().unwrap_or(0);
+    println!("Built {} clusters in {} LOD levels, {} BVH nodes.", clusters.len(), level_count, bvh.len());
+
     Ok(AdaptrixMesh {
         clusters,
         vertices: all_vertices,
         indices: all_indices,
+        bvh,
     })
 }
 
@@ -125,14 +134,41 @@ fn save_adaptrix_mesh(mesh: &AdaptrixMesh, path: &str) -> Result<()> {
     let mut file = File::create(path)?;
     
     file.write_all(b"LAD ")?;
-    file.write_all(&1u32.to_le_bytes())?;
+    file.write_all(&3u32.to_le_bytes())?;
     file.write_all(&(mesh.clusters.len() as u32).to_le_bytes())?;
     file.write_all(&(mesh.vertices.len() as u32).to_le_bytes())?;
     file.write_all(&(mesh.indices.len() as u32).to_le_bytes())?;
+    file.write_all(&(mesh.bvh.len() as u32).to_le_bytes())?;
 
     file.write_all(bytemuck::cast_slice(&mesh.clusters))?;
     file.write_all(bytemuck::cast_slice(&mesh.vertices))?;
     file.write_all(bytemuck::cast_slice(&mesh.indices))?;
+    file.write_all(bytemuck::cast_slice(&mesh.bvh))?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_each_bvh_leaf_as_one_dag_group() {
+        let (vertices, indices) = dag::tests::uv_sphere(32, 64);
+        let mesh = process_mesh(RawMesh { vertices, indices }).unwrap();
+
+        let mut covered = vec![0; mesh.clusters.len()];
+        for leaf in mesh.bvh.iter().filter(|n| n.leaf != 0) {
+            let group = &mesh.clusters[leaf.first as usize..(leaf.first + leaf.count) as usize];
+            assert!(group.iter().all(|c| c.parent_sphere == leaf.lod_sphere && c.parent_error == leaf.max_parent_error));
+            for i in leaf.first..leaf.first + leaf.count {
+                covered[i as usize] += 1;
+            }
+        }
+        assert!(covered.iter().all(|&n| n == 1), "{:?}", covered);
+        // Clusters keep their own geometry when reordered.
+        let triangles: u32 = mesh.clusters.iter().map(|c| c.triangle_count * 3).sum();
+        assert_eq!(triangles as usize, mesh.indices.len());
+        assert!(mesh.clusters.windows(2).all(|w| w[0].triangle_offset + w[0].triangle_count * 3 == w[1].triangle_offset));
+    }
+}
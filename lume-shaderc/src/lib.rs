@@ -0,0 +1,202 @@
+//! Offline shader compilation, used by the `lume-shaderc` binary and by build scripts that
+//! keep committed SPIR-V in sync with its sources.
+
+use anyhow::{bail, Context, Result};
+use lume_core::device::ShaderStage;
+use lume_core::shader::{compile_entry_point, compile_shader_reflected, CompiledShader, FileResolver, ShaderReflection, ShaderSource};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Default, Debug)]
+pub struct CompileOptions {
+    /// Substituted by the WGSL preprocessor, or passed to the GLSL frontend.
+    pub defines: Vec<(String, String)>,
+    /// Where WGSL `#include`s not found next to the including file are looked up; defaults to
+    /// the directory of the input.
+    pub include_dir: Option<PathBuf>,
+    /// GLSL stage; defaults to the one named by the extension (`.vert`, `.frag`, `.comp`).
+    pub stage: Option<naga::ShaderStage>,
+    /// Compile only this entry point, renamed to `main`.
+    pub entry_point: Option<String>,
+}
+
+/// Compiles a `.wgsl` file or a GLSL `.vert`/`.frag`/`.comp` file (optionally followed by `.glsl`).
+pub fn compile_file(path: &Path, options: &CompileOptions) -> Result<CompiledShader> {
+    let path = std::path::absolute(path).with_context(|| format!("Invalid path {}", path.display()))?;
+    let defines = options.defines.iter().cloned().collect();
+    let resolver;
+    let glsl;
+    let source = if path.extension().is_some_and(|e| e == "wgsl") {
+        let root = options.include_dir.clone().or_else(|| path.parent().map(Path::to_path_buf)).unwrap_or_default();
+        resolver = FileResolver::new(root);
+        ShaderSource::WgslFile { path: path.to_str().context("Non UTF-8 path")?, resolver: &resolver, defines }
+    } else {
+        let stage = match options.stage {
+            Some(stage) => stage,
+            None => glsl_stage(&path).with_context(|| format!("Cannot tell the shader stage of {}; pass --stage", path.display()))?,
+        };
+        glsl = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        ShaderSource::Glsl { source: &glsl, stage, defines }
+    };
+
+    let compiled = match &options.entry_point {
+        Some(name) => compile_entry_point(source, name),
+        None => compile_shader_reflected(source),
+    };
+    compiled.map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+fn glsl_stage(path: &Path) -> Option<naga::ShaderStage> {
+    let name = path.file_name()?.to_str()?;
+    let name = name.strip_suffix(".glsl").unwrap_or(name);
+    match name.rsplit('.').next()? {
+        "vert" => Some(naga::ShaderStage::Vertex),
+        "frag" => Some(naga::ShaderStage::Fragment),
+        "comp" => Some(naga::ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+pub fn parse_stage(name: &str) -> Result<naga::ShaderStage> {
+    match name {
+        "vert" | "vertex" => Ok(naga::ShaderStage::Vertex),
+        "frag" | "fragment" => Ok(naga::ShaderStage::Fragment),
+        "comp" | "compute" => Ok(naga::ShaderStage::Compute),
+        _ => bail!("Unknown shader stage '{}'", name),
+    }
+}
+
+pub fn spirv_bytes(spirv: &[u32]) -> Vec<u8> {
+    spirv.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+/// Whether `path` exists and holds exactly `contents`.
+pub fn is_up_to_date(path: &Path, contents: &[u8]) -> bool {
+    std::fs::read(path).is_ok_and(|existing| existing == contents)
+}
+
+/// Writes `contents` unless the file already holds them, so build systems see no change.
+pub fn write_if_changed(path: &Path, contents: &[u8]) -> Result<bool> {
+    if is_up_to_date(path, contents) {
+        return Ok(false);
+    }
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// The reflection as pretty-printed JSON, with stages as lists of names.
+pub fn reflection_json(reflection: &ShaderReflection) -> String {
+    let mut json = String::from("{\n  \"entry_points\": [");
+    for (i, ep) in reflection.entry_points.iter().enumerate() {
+        let inputs: Vec<String> = ep.vertex_inputs.iter().map(|input| format!(
+            "{{ \"location\": {}, \"name\": {}, \"format\": {} }}",
+            input.location,
+            optional_string(input.name.as_deref()),
+            optional_string(input.format.map(|f| format!("{:?}", f)).as_deref()),
+        )).collect();
+        let _ = write!(
+            json,
+            "{}\n    {{ \"name\": {}, \"stage\": {}, \"workgroup_size\": {:?}, \"vertex_inputs\": [{}] }}",
+            if i == 0 { "" } else { "," },
+            string(&ep.name),
+            stages(ep.stage),
+            ep.workgroup_size,
+            inputs.join(", "),
+        );
+    }
+    json.push_str(if reflection.entry_points.is_empty() { "],\n  \"bindings\": [" } else { "\n  ],\n  \"bindings\": [" });
+    for (i, binding) in reflection.bindings.iter().enumerate() {
+        let _ = write!(
+            json,
+            "{}\n    {{ \"group\": {}, \"binding\": {}, \"name\": {}, \"type\": \"{:?}\", \"visibility\": {} }}",
+            if i == 0 { "" } else { "," },
+            binding.group,
+            binding.binding,
+            optional_string(binding.name.as_deref()),
+            binding.ty,
+            stages(binding.visibility),
+        );
+    }
+    json.push_str(if reflection.bindings.is_empty() { "],\n  \"push_constant_ranges\": [" } else { "\n  ],\n  \"push_constant_ranges\": [" });
+    for (i, range) in reflection.push_constant_ranges.iter().enumerate() {
+        let _ = write!(
+            json,
+            "{}\n    {{ \"stages\": {}, \"offset\": {}, \"size\": {} }}",
+            if i == 0 { "" } else { "," },
+            stages(range.stages),
+            range.offset,
+            range.size,
+        );
+    }
+    json.push_str(if reflection.push_constant_ranges.is_empty() { "]\n}\n" } else { "\n  ]\n}\n" });
+    json
+}
+
+fn stages(stages: ShaderStage) -> String {
+    let names: Vec<&str> = [(ShaderStage::VERTEX, "\"vertex\""), (ShaderStage::FRAGMENT, "\"fragment\""), (ShaderStage::COMPUTE, "\"compute\"")]
+        .into_iter()
+        .filter(|(stage, _)| stages.0 & stage.0 != 0)
+        .map(|(_, name)| name)
+        .collect();
+    format!("[{}]", names.join(", "))
+}
+
+fn optional_string(s: Option<&str>) -> String {
+    s.map_or_else(|| "null".to_string(), string)
+}
+
+fn string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiles_wgsl_with_includes_defines_and_reflection() {
+        let dir = std::env::temp_dir().join(format!("lume-shaderc-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("include")).unwrap();
+        std::fs::write(dir.join("include/data.wgsl"), "@group(0) @binding(0) var<storage, read_write> data: array<u32>;").unwrap();
+        let shader = dir.join("double.wgsl");
+        std::fs::write(&shader, "#include \"data.wgsl\"\n@compute @workgroup_size(SIZE) fn main(@builtin(global_invocation_id) id: vec3<u32>) { data[id.x] *= 2u; }").unwrap();
+
+        let options = CompileOptions { defines: vec![("SIZE".to_string(), "16".to_string())], include_dir: Some(dir.join("include")), ..Default::default() };
+        let compiled = compile_file(&shader, &options).unwrap();
+        let json = reflection_json(&compiled.reflection);
+        assert!(json.contains("\"name\": \"main\", \"stage\": [\"compute\"], \"workgroup_size\": [16, 1, 1]"), "{}", json);
+        assert!(json.contains("{ \"group\": 0, \"binding\": 0, \"name\": \"data\", \"type\": \"StorageBuffer\", \"visibility\": [\"compute\"] }"), "{}", json);
+        assert!(json.contains("\"push_constant_ranges\": []"), "{}", json);
+
+        let output = dir.join("double.spv");
+        let bytes = spirv_bytes(&compiled.spirv);
+        assert!(!is_up_to_date(&output, &bytes));
+        assert!(write_if_changed(&output, &bytes).unwrap());
+        assert!(!write_if_changed(&output, &bytes).unwrap());
+        assert!(is_up_to_date(&output, &bytes));
+
+        assert!(compile_file(&shader, &CompileOptions::default()).is_err(), "SIZE is undefined and data.wgsl is not next to the shader");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn detects_glsl_stages_from_extensions() {
+        assert_eq!(glsl_stage(Path::new("shaders/visbuffer.vert")), Some(naga::ShaderStage::Vertex));
+        assert_eq!(glsl_stage(Path::new("cull.comp.glsl")), Some(naga::ShaderStage::Compute));
+        assert_eq!(glsl_stage(Path::new("shader.glsl")), None);
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{bail, Context, Result};
+use lume_shaderc::{compile_file, is_up_to_date, parse_stage, reflection_json, spirv_bytes, write_if_changed, CompileOptions};
+use std::env;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: lume-shaderc [options] <input>
+
+Compiles a WGSL (.wgsl) or GLSL (.vert, .frag, .comp) shader to SPIR-V.
+
+Options:
+  -o <file>           Output SPIR-V file [default: <input>.spv]
+  -D <name>[=value]   Define a preprocessor symbol
+  -I <dir>            Directory to look up WGSL includes in
+  --stage <stage>     GLSL stage: vert, frag or comp [default: from the extension]
+  --entry <name>      Compile only this entry point
+  --reflect <file>    Write the reflected bindings and entry points as JSON
+  --check             Only check that the outputs are up to date; exits with 1 if not";
+
+struct Args {
+    input: PathBuf,
+    output: Option<PathBuf>,
+    reflect: Option<PathBuf>,
+    check: bool,
+    options: CompileOptions,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut args = env::args().skip(1);
+    let mut input = None;
+    let mut parsed = Args { input: PathBuf::new(), output: None, reflect: None, check: false, options: CompileOptions::default() };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().with_context(|| format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "-o" => parsed.output = Some(value()?.into()),
+            "-D" => {
+                let define = value()?;
+                let (name, value) = define.split_once('=').unwrap_or((&define, ""));
+                parsed.options.defines.push((name.to_string(), value.to_string()));
+            }
+            "-I" => parsed.options.include_dir = Some(value()?.into()),
+            "--stage" => parsed.options.stage = Some(parse_stage(&value()?)?),
+            "--entry" => parsed.options.entry_point = Some(value()?),
+            "--reflect" => parsed.reflect = Some(value()?.into()),
+            "--check" => parsed.check = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') => bail!("Unknown option {}\n\n{}", arg, USAGE),
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+            _ => bail!("Unexpected argument {}\n\n{}", arg, USAGE),
+        }
+    }
+    parsed.input = input.with_context(|| USAGE.to_string())?;
+    Ok(parsed)
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let args = parse_args()?;
+    let compiled = compile_file(&args.input, &args.options)?;
+
+    let spirv_path = args.output.unwrap_or_else(|| {
+        let mut path = args.input.clone().into_os_string();
+        path.push(".spv");
+        path.into()
+    });
+    let mut outputs = vec![(spirv_path, spirv_bytes(&compiled.spirv))];
+    if let Some(path) = args.reflect {
+        outputs.push((path, reflection_json(&compiled.reflection).into_bytes()));
+    }
+
+    if args.check {
+        let stale: Vec<_> = outputs.iter().filter(|(path, contents)| !is_up_to_date(path, contents)).collect();
+        for (path, _) in &stale {
+            eprintln!("{} is out of date with {}", path.display(), args.input.display());
+        }
+        if !stale.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    for (path, contents) in &outputs {
+        if write_if_changed(path, contents)? {
+            println!("Wrote {}", path.display());
+        }
+    }
+    Ok(())
+}
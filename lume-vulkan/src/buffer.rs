@@ -7,6 +7,7 @@ pub struct VulkanBuffer {
     pub buffer: vk::Buffer,
     pub allocation: Allocation,
     pub size: u64,
+    pub access: Arc<Mutex<crate::tracking::ResourceState>>,
     pub allocator: Arc<Mutex<Allocator>>,
     pub device: crate::VulkanDevice,
 }
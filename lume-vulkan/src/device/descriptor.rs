@@ -1,11 +1,13 @@
 use ash::vk;
 use std::collections::HashMap;
-use lume_core::{LumeError, LumeResult, device::{BindingType, BindGroupDescriptor, BindGroupLayoutDescriptor}};
+use lume_core::{LumeError, LumeResult, device::{BindingType, BindGroupDescriptor, BindGroupLayoutDescriptor, ShaderStage}};
 use crate::VulkanDevice;
+use crate::tracking::{Access, BoundResource, TrackedResource};
 
 pub struct VulkanBindGroupLayout {
     pub layout: vk::DescriptorSetLayout,
     pub entries: HashMap<u32, BindingType>,
+    pub visibility: HashMap<u32, ShaderStage>,
     pub device: ash::Device,
 }
 
@@ -21,6 +23,8 @@ impl lume_core::device::BindGroupLayout for VulkanBindGroupLayout {}
 
 pub struct VulkanBindGroup {
     pub set: vk::DescriptorSet,
+    /// Buffers and textures in the set, so binding it can synchronise them.
+    pub(crate) resources: Vec<BoundResource>,
 }
 
 impl lume_core::device::BindGroup for VulkanBindGroup {}
@@ -29,6 +33,7 @@ impl VulkanDevice {
     pub fn create_bind_group_layout_impl(&self, descriptor: BindGroupLayoutDescriptor) -> LumeResult<VulkanBindGroupLayout> {
         let mut entries = Vec::new();
         let mut type_map = HashMap::new();
+        let mut visibility = HashMap::new();
 
         for entry in descriptor.entries {
             let vk_type = match entry.ty {
@@ -39,19 +44,15 @@ impl VulkanDevice {
                 BindingType::Sampler => vk::DescriptorType::SAMPLER,
             };
 
-            let mut stage_flags = vk::ShaderStageFlags::empty();
-            if entry.visibility.0 & lume_core::device::ShaderStage::VERTEX.0 != 0 { stage_flags |= vk::ShaderStageFlags::VERTEX; }
-            if entry.visibility.0 & lume_core::device::ShaderStage::FRAGMENT.0 != 0 { stage_flags |= vk::ShaderStageFlags::FRAGMENT; }
-            if entry.visibility.0 & lume_core::device::ShaderStage::COMPUTE.0 != 0 { stage_flags |= vk::ShaderStageFlags::COMPUTE; }
-
             entries.push(vk::DescriptorSetLayoutBinding {
                 binding: entry.binding,
                 descriptor_type: vk_type,
                 descriptor_count: 1,
-                stage_flags,
+                stage_flags: map_shader_stages(entry.visibility),
                 ..Default::default()
             });
             type_map.insert(entry.binding, entry.ty);
+            visibility.insert(entry.binding, entry.visibility);
         }
 
         let create_info = vk::DescriptorSetLayoutCreateInfo {
@@ -68,6 +69,7 @@ impl VulkanDevice {
         Ok(VulkanBindGroupLayout {
             layout,
             entries: type_map,
+            visibility,
             device: self.inner.device.clone(),
         })
     }
@@ -89,8 +91,13 @@ impl VulkanDevice {
         
         let mut final_buffer_infos = Vec::new();
         let mut final_image_infos = Vec::new();
+        let mut resources = Vec::new();
         
         for entry in &descriptor.entries {
+            let usage = match (descriptor.layout.entries.get(&entry.binding), descriptor.layout.visibility.get(&entry.binding)) {
+                (Some(&ty), Some(&visibility)) => Access::for_binding(ty, visibility),
+                _ => Access::default(),
+            };
             match entry.resource {
                 lume_core::device::BindingResource::Buffer(buf) => {
                     final_buffer_infos.push(vk::DescriptorBufferInfo {
@@ -98,6 +105,7 @@ impl VulkanDevice {
                         offset: 0,
                         range: buf.size,
                     });
+                    resources.push(BoundResource { resource: TrackedResource::buffer(buf), usage, layout: None });
                 }
                 lume_core::device::BindingResource::TextureView(view) => {
                     let layout = if let Some(BindingType::StorageTexture) = descriptor.layout.entries.get(&entry.binding) {
@@ -110,6 +118,7 @@ impl VulkanDevice {
                         image_layout: layout,
                         ..Default::default()
                     });
+                    resources.extend(TrackedResource::view(view).map(|resource| BoundResource { resource, usage, layout: Some(layout) }));
                 }
                 lume_core::device::BindingResource::Sampler(sampler) => {
                     final_image_infos.push(vk::DescriptorImageInfo {
@@ -177,6 +186,14 @@ impl VulkanDevice {
             self.inner.device.update_descriptor_sets(&writes, &[]);
         }
 
-        Ok(VulkanBindGroup { set })
+        Ok(VulkanBindGroup { set, resources })
     }
 }
+
+pub fn map_shader_stages(stages: lume_core::device::ShaderStage) -> vk::ShaderStageFlags {
+    let mut stage_flags = vk::ShaderStageFlags::empty();
+    if stages.0 & lume_core::device::ShaderStage::VERTEX.0 != 0 { stage_flags |= vk::ShaderStageFlags::VERTEX; }
+    if stages.0 & lume_core::device::ShaderStage::FRAGMENT.0 != 0 { stage_flags |= vk::ShaderStageFlags::FRAGMENT; }
+    if stages.0 & lume_core::device::ShaderStage::COMPUTE.0 != 0 { stage_flags |= vk::ShaderStageFlags::COMPUTE; }
+    stage_flags
+}
@@ -10,6 +10,10 @@ pub struct VulkanDeviceInner {
     pub graphics_queue_index: u32,
     pub present_queue: vk::Queue, 
     pub graphics_queue: vk::Queue,
+    pub compute_queue_index: u32,
+    pub compute_queue: vk::Queue,
+    pub transfer_queue_index: u32,
+    pub transfer_queue: vk::Queue,
     pub physical_device: vk::PhysicalDevice,
     pub device: ash::Device,
     pub instance: ash::Instance,
@@ -26,6 +30,36 @@ pub struct VulkanFrameSyncManager {
     pub render_finished: Vec<vk::Semaphore>,
 }
 
+/// Queues retrieved at device creation. Compute and transfer alias the graphics
+/// queue when the GPU has no dedicated family for them.
+pub struct VulkanQueues {
+    pub graphics: vk::Queue,
+    pub graphics_family: u32,
+    pub present: vk::Queue,
+    pub compute: vk::Queue,
+    pub compute_family: u32,
+    pub transfer: vk::Queue,
+    pub transfer_family: u32,
+}
+
+/// Queue family of each `QueueType`, which may share one family.
+#[derive(Clone, Copy, Debug)]
+pub struct QueueFamilies {
+    pub graphics: u32,
+    pub compute: u32,
+    pub transfer: u32,
+}
+
+impl QueueFamilies {
+    pub fn get(&self, queue: lume_core::device::QueueType) -> u32 {
+        match queue {
+            lume_core::device::QueueType::Graphics => self.graphics,
+            lume_core::device::QueueType::Compute => self.compute,
+            lume_core::device::QueueType::Transfer => self.transfer,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct VulkanDevice {
     pub inner: Arc<VulkanDeviceInner>,
@@ -42,9 +76,7 @@ impl VulkanDevice {
     pub fn new(
         instance: ash::Instance,
         device: ash::Device,
-        graphics_queue: vk::Queue,
-        present_queue: vk::Queue,
-        graphics_queue_index: u32,
+        queues: VulkanQueues,
         allocator: Option<Arc<Mutex<Allocator>>>,
         physical_device: vk::PhysicalDevice,
     ) -> Self {
@@ -125,9 +157,13 @@ impl VulkanDevice {
                 instance,
                 device,
                 physical_device,
-                graphics_queue,
-                present_queue,
-                graphics_queue_index,
+                graphics_queue: queues.graphics,
+                present_queue: queues.present,
+                graphics_queue_index: queues.graphics_family,
+                compute_queue: queues.compute,
+                compute_queue_index: queues.compute_family,
+                transfer_queue: queues.transfer,
+                transfer_queue_index: queues.transfer_family,
                 descriptor_pool,
                 bindless_descriptor_set,
                 bindless_layout,
@@ -144,6 +180,24 @@ impl VulkanDevice {
     }
 }
 
+impl VulkanDeviceInner {
+    pub fn queue(&self, queue: lume_core::device::QueueType) -> vk::Queue {
+        match queue {
+            lume_core::device::QueueType::Graphics => self.graphics_queue,
+            lume_core::device::QueueType::Compute => self.compute_queue,
+            lume_core::device::QueueType::Transfer => self.transfer_queue,
+        }
+    }
+
+    pub fn queue_families(&self) -> QueueFamilies {
+        QueueFamilies {
+            graphics: self.graphics_queue_index,
+            compute: self.compute_queue_index,
+            transfer: self.transfer_queue_index,
+        }
+    }
+}
+
 impl Drop for VulkanDeviceInner {
     fn drop(&mut self) {
         unsafe {
@@ -169,3 +223,4 @@ mod descriptor;
 mod queue;
 
 pub use descriptor::{VulkanBindGroup, VulkanBindGroupLayout};
+pub(crate) use descriptor::map_shader_stages;
@@ -31,15 +31,23 @@ impl VulkanDevice {
             TextureFormat::Rgba8UnormSrgb => vk::Format::R8G8B8A8_SRGB,
             TextureFormat::Rgba8Unorm => vk::Format::R8G8B8A8_UNORM,
             TextureFormat::Rg32Uint => vk::Format::R32G32_UINT,
+            TextureFormat::R32Float => vk::Format::R32_SFLOAT,
             TextureFormat::Depth32Float => return Err(LumeError::Generic("Cannot use Depth32Float as color format")),
         };
+        let load_op = match descriptor.load_op {
+            AttachmentLoadOp::Load => vk::AttachmentLoadOp::LOAD,
+            AttachmentLoadOp::Clear => vk::AttachmentLoadOp::CLEAR,
+            AttachmentLoadOp::DontCare => vk::AttachmentLoadOp::DONT_CARE,
+        };
+        // Loaded attachments are moved into the layout the subpass uses by `begin_render_pass`
+        let initial_layout = |layout| if load_op == vk::AttachmentLoadOp::LOAD { layout } else { vk::ImageLayout::UNDEFINED };
 
         attachments.push(vk::AttachmentDescription {
             format: color_format,
             samples: vk::SampleCountFlags::TYPE_1,
-            load_op: vk::AttachmentLoadOp::CLEAR,
+            load_op,
             store_op: vk::AttachmentStoreOp::STORE,
-            initial_layout: vk::ImageLayout::UNDEFINED,
+            initial_layout: initial_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL),
             final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
             ..Default::default()
         });
@@ -59,9 +67,10 @@ impl VulkanDevice {
             attachments.push(vk::AttachmentDescription {
                 format: depth_format,
                 samples: vk::SampleCountFlags::TYPE_1,
-                load_op: vk::AttachmentLoadOp::CLEAR,
-                store_op: vk::AttachmentStoreOp::DONT_CARE,
-                initial_layout: vk::ImageLayout::UNDEFINED,
+                load_op,
+                // Kept for later passes that load it or read it, like an HZB build
+                store_op: vk::AttachmentStoreOp::STORE,
+                initial_layout: initial_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL),
                 final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
                 ..Default::default()
             });
@@ -108,16 +117,25 @@ impl VulkanDevice {
 
         Ok(crate::VulkanRenderPass {
             render_pass,
+            initial_layouts: attachments.iter().map(|a| a.initial_layout).collect(),
+            final_layouts: attachments.iter().map(|a| a.final_layout).collect(),
             device: self.inner.device.clone(),
         })
     }
 
     pub fn create_pipeline_layout_impl(&self, descriptor: PipelineLayoutDescriptor<Self>) -> LumeResult<crate::VulkanPipelineLayout> {
         let set_layouts: Vec<vk::DescriptorSetLayout> = descriptor.bind_group_layouts.iter().map(|l| l.layout).collect();
+        let push_constant_ranges: Vec<vk::PushConstantRange> = descriptor.push_constant_ranges.iter().map(|r| vk::PushConstantRange {
+            stage_flags: super::descriptor::map_shader_stages(r.stages),
+            offset: r.offset,
+            size: r.size,
+        }).collect();
         
         let create_info = vk::PipelineLayoutCreateInfo {
             set_layout_count: set_layouts.len() as u32,
             p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: push_constant_ranges.len() as u32,
+            p_push_constant_ranges: push_constant_ranges.as_ptr(),
             ..Default::default()
         };
 
@@ -319,6 +337,14 @@ impl VulkanDevice {
             framebuffer,
             width: descriptor.width,
             height: descriptor.height,
+            attachments: descriptor.attachments.iter().flat_map(|a| {
+                let usage = if a.aspect_mask.contains(vk::ImageAspectFlags::DEPTH) {
+                    crate::tracking::Access::DEPTH_ATTACHMENT_WRITE
+                } else {
+                    crate::tracking::Access::COLOR_ATTACHMENT_WRITE
+                };
+                crate::tracking::TrackedResource::view(a).map(move |attachment| (attachment, usage))
+            }).collect(),
             device: self.inner.device.clone(),
         })
     }
@@ -12,6 +12,7 @@ impl lume_core::Device for VulkanDevice {
     type GraphicsPipeline = crate::VulkanGraphicsPipeline;
     type ComputePipeline = crate::VulkanComputePipeline;
     type Semaphore = crate::VulkanSemaphore;
+    type TimelineSemaphore = crate::VulkanTimelineSemaphore;
     type Framebuffer = crate::VulkanFramebuffer;
     type TextureView = crate::VulkanTextureView;
     type Texture = crate::VulkanTexture;
@@ -40,6 +41,23 @@ impl lume_core::Device for VulkanDevice {
         })
     }
 
+    fn create_timeline_semaphore(&self, initial_value: u64) -> LumeResult<Self::TimelineSemaphore> {
+        let mut type_info = vk::SemaphoreTypeCreateInfo {
+            semaphore_type: vk::SemaphoreType::TIMELINE,
+            initial_value,
+            ..Default::default()
+        };
+        let create_info = vk::SemaphoreCreateInfo::default().push_next(&mut type_info);
+        let semaphore = unsafe {
+            self.inner.device.create_semaphore(&create_info, None)
+                .map_err(|e| LumeError::ResourceCreationFailed(format!("Failed to create timeline semaphore: {}", e)))?
+        };
+        Ok(crate::VulkanTimelineSemaphore {
+            semaphore,
+            device: self.inner.device.clone(),
+        })
+    }
+
     fn create_fence(&self, signaled: bool) -> LumeResult<Self::Fence> {
         let flags = if signaled { vk::FenceCreateFlags::SIGNALED } else { vk::FenceCreateFlags::empty() };
         let create_info = vk::FenceCreateInfo {
@@ -72,9 +90,9 @@ impl lume_core::Device for VulkanDevice {
         }
     }
 
-    fn create_command_pool(&self) -> LumeResult<Self::CommandPool> {
+    fn create_command_pool(&self, queue: lume_core::device::QueueType) -> LumeResult<Self::CommandPool> {
         let create_info = vk::CommandPoolCreateInfo {
-            queue_family_index: self.inner.graphics_queue_index,
+            queue_family_index: self.inner.queue_families().get(queue),
             flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
             ..Default::default()
         };
@@ -86,38 +104,61 @@ impl lume_core::Device for VulkanDevice {
 
         Ok(crate::VulkanCommandPool {
             pool,
+            queue_family_index: self.inner.queue_families().get(queue),
+            queue_families: self.inner.queue_families(),
             device: self.inner.device.clone(),
         })
     }
 
     fn submit(
         &self,
+        queue: lume_core::device::QueueType,
         command_buffers: &[&Self::CommandBuffer],
         wait_semaphores: &[&Self::Semaphore],
         signal_semaphores: &[&Self::Semaphore],
+        timeline_waits: &[(&Self::TimelineSemaphore, u64)],
+        timeline_signals: &[(&Self::TimelineSemaphore, u64)],
         fence: Option<&Self::Fence>,
     ) -> LumeResult<()> {
-        let vk_command_buffers: Vec<vk::CommandBuffer> = command_buffers.iter().map(|cb| cb.buffer).collect();
-        let vk_wait_semaphores: Vec<vk::Semaphore> = wait_semaphores.iter().map(|s| s.semaphore).collect();
-        let vk_signal_semaphores: Vec<vk::Semaphore> = signal_semaphores.iter().map(|s| s.semaphore).collect();
-
-        let wait_stages = vec![vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT; vk_wait_semaphores.len()];
+        // Each buffer is ordered after the ones submitted before it, in submission order.
+        let mut vk_command_buffers = Vec::new();
+        for cb in command_buffers {
+            vk_command_buffers.extend(cb.resolve()?.into_iter().map(|command_buffer| vk::CommandBufferSubmitInfo {
+                command_buffer,
+                ..Default::default()
+            }));
+        }
 
-        let submit_info = vk::SubmitInfo {
-            wait_semaphore_count: vk_wait_semaphores.len() as u32,
-            p_wait_semaphores: vk_wait_semaphores.as_ptr(),
-            p_wait_dst_stage_mask: wait_stages.as_ptr(),
-            command_buffer_count: vk_command_buffers.len() as u32,
-            p_command_buffers: vk_command_buffers.as_ptr(),
-            signal_semaphore_count: vk_signal_semaphores.len() as u32,
-            p_signal_semaphores: vk_signal_semaphores.as_ptr(),
+        // Binary and timeline semaphores share one list; the value is ignored for binary ones.
+        let semaphore_info = |semaphore: vk::Semaphore, value: u64, stage_mask: vk::PipelineStageFlags2| vk::SemaphoreSubmitInfo {
+            semaphore,
+            value,
+            stage_mask,
+            ..Default::default()
+        };
+        let vk_wait_semaphores: Vec<vk::SemaphoreSubmitInfo> = wait_semaphores.iter()
+            .map(|s| semaphore_info(s.semaphore, 0, vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT))
+            .chain(timeline_waits.iter().map(|(s, value)| semaphore_info(s.semaphore, *value, vk::PipelineStageFlags2::ALL_COMMANDS)))
+            .collect();
+        let vk_signal_semaphores: Vec<vk::SemaphoreSubmitInfo> = signal_semaphores.iter()
+            .map(|s| semaphore_info(s.semaphore, 0, vk::PipelineStageFlags2::ALL_COMMANDS))
+            .chain(timeline_signals.iter().map(|(s, value)| semaphore_info(s.semaphore, *value, vk::PipelineStageFlags2::ALL_COMMANDS)))
+            .collect();
+
+        let submit_info = vk::SubmitInfo2 {
+            wait_semaphore_info_count: vk_wait_semaphores.len() as u32,
+            p_wait_semaphore_infos: vk_wait_semaphores.as_ptr(),
+            command_buffer_info_count: vk_command_buffers.len() as u32,
+            p_command_buffer_infos: vk_command_buffers.as_ptr(),
+            signal_semaphore_info_count: vk_signal_semaphores.len() as u32,
+            p_signal_semaphore_infos: vk_signal_semaphores.as_ptr(),
             ..Default::default()
         };
 
         let vk_fence = fence.map(|f| f.fence).unwrap_or(vk::Fence::null());
 
         unsafe {
-            self.inner.device.queue_submit(self.inner.graphics_queue, &[submit_info], vk_fence)
+            self.inner.device.queue_submit2(self.inner.queue(queue), &[submit_info], vk_fence)
                 .map_err(|e| LumeError::SubmissionFailed(format!("Failed to submit command buffers: {}", e)))
         }
     }
@@ -174,6 +215,10 @@ impl lume_core::Device for VulkanDevice {
         self.create_swapchain_impl(surface, descriptor)
     }
 
+    fn create_offscreen_swapchain(&self, descriptor: lume_core::device::OffscreenSwapchainDescriptor) -> LumeResult<Self::Swapchain> {
+        self.create_offscreen_swapchain_impl(descriptor)
+    }
+
     fn begin_frame(&self, swapchain: &mut Self::Swapchain) -> LumeResult<lume_core::device::FrameToken> {
         let sync = self.inner.frame_sync.lock().unwrap();
         let frame_index = sync.current_frame;
@@ -200,18 +245,23 @@ impl lume_core::Device for VulkanDevice {
         let frame_index = token.frame_index;
 
         // 1. Submit command buffers
-        let vk_command_buffers: Vec<vk::CommandBuffer> = command_buffers.iter().map(|cb| cb.buffer).collect();
+        let mut vk_command_buffers = Vec::new();
+        for cb in command_buffers {
+            vk_command_buffers.extend(cb.resolve()?);
+        }
         let wait_semaphores = [sync.image_available[frame_index]];
         let signal_semaphores = [sync.render_finished[frame_index]];
         let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        // Offscreen images are never acquired, so there is nothing to wait on or hand to a presentation engine
+        let semaphore_count = if swapchain.is_offscreen() { 0 } else { 1 };
 
         let submit_info = vk::SubmitInfo {
-            wait_semaphore_count: 1,
+            wait_semaphore_count: semaphore_count,
             p_wait_semaphores: wait_semaphores.as_ptr(),
             p_wait_dst_stage_mask: wait_stages.as_ptr(),
             command_buffer_count: vk_command_buffers.len() as u32,
             p_command_buffers: vk_command_buffers.as_ptr(),
-            signal_semaphore_count: 1,
+            signal_semaphore_count: semaphore_count,
             p_signal_semaphores: signal_semaphores.as_ptr(),
             ..Default::default()
         };
@@ -222,13 +272,35 @@ impl lume_core::Device for VulkanDevice {
         }
 
         // 2. Present
-        swapchain.present_raw(token.image_index, &signal_semaphores)?;
+        swapchain.present_raw(token.image_index, &signal_semaphores[..semaphore_count as usize])?;
 
         // 3. Move to next frame slot
         sync.current_frame = (sync.current_frame + 1) % sync.frames_in_flight;
 
         Ok(())
     }
-}
 
-    
\ No newline at end of file
+    fn read_texture(&self, texture: &Self::Texture) -> LumeResult<Vec<u8>> {
+        let format = super::resource::unmap_texture_format(texture.format)
+            .ok_or(LumeError::Generic("Unsupported texture format for readback"))?;
+        let aspect_mask = if super::resource::is_depth_format(texture.format) {
+            vk::ImageAspectFlags::DEPTH
+        } else {
+            vk::ImageAspectFlags::COLOR
+        };
+        let extent = vk::Extent2D { width: texture.width, height: texture.height };
+        self.read_image_impl(texture.image, extent, format.texel_size(), aspect_mask, &texture.mips[0])
+    }
+
+    fn read_presented_image(&self, swapchain: &Self::Swapchain) -> LumeResult<image::RgbaImage> {
+        if !swapchain.is_offscreen() {
+            return Err(LumeError::Generic("Only offscreen swapchains can be read back"));
+        }
+        let index = swapchain.presented_image.ok_or(LumeError::Generic("No image has been presented yet"))? as usize;
+        let format = super::resource::unmap_texture_format(swapchain.format)
+            .ok_or(LumeError::Generic("Unsupported offscreen swapchain format"))?;
+        let texture = &swapchain.offscreen_textures[index];
+        let data = self.read_image_impl(texture.image, swapchain.extent, format.texel_size(), vk::ImageAspectFlags::COLOR, &texture.mips[0])?;
+        lume_core::device::texels_to_rgba_image(format, swapchain.extent.width, swapchain.extent.height, data)
+    }
+}
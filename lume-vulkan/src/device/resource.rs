@@ -3,10 +3,17 @@ use gpu_allocator::vulkan::*;
 use gpu_allocator::MemoryLocation;
 use lume_core::{LumeError, LumeResult};
 use crate::VulkanDevice;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 impl VulkanDevice {
     pub fn create_buffer_impl(&self, descriptor: lume_core::device::BufferDescriptor) -> LumeResult<crate::VulkanBuffer> {
+        let location = if descriptor.mapped_at_creation { MemoryLocation::CpuToGpu } else { MemoryLocation::GpuOnly };
+        self.create_buffer_in(descriptor, location)
+    }
+
+    /// Creates a buffer in `location`, for internal buffers the descriptor cannot describe,
+    /// like readback staging.
+    pub(crate) fn create_buffer_in(&self, descriptor: lume_core::device::BufferDescriptor, location: MemoryLocation) -> LumeResult<crate::VulkanBuffer> {
         let mut usage = vk::BufferUsageFlags::empty();
         let u = descriptor.usage;
         if u.0 & lume_core::device::BufferUsage::VERTEX.0 != 0 { usage |= vk::BufferUsageFlags::VERTEX_BUFFER; }
@@ -15,6 +22,7 @@ impl VulkanDevice {
         if u.0 & lume_core::device::BufferUsage::STORAGE.0 != 0 { usage |= vk::BufferUsageFlags::STORAGE_BUFFER; }
         if u.0 & lume_core::device::BufferUsage::COPY_SRC.0 != 0 { usage |= vk::BufferUsageFlags::TRANSFER_SRC; }
         if u.0 & lume_core::device::BufferUsage::COPY_DST.0 != 0 { usage |= vk::BufferUsageFlags::TRANSFER_DST; }
+        if u.0 & lume_core::device::BufferUsage::INDIRECT.0 != 0 { usage |= vk::BufferUsageFlags::INDIRECT_BUFFER; }
 
         let create_info = vk::BufferCreateInfo {
             size: descriptor.size,
@@ -29,7 +37,6 @@ impl VulkanDevice {
         };
 
         let requirements = unsafe { self.inner.device.get_buffer_memory_requirements(buffer) };
-        let location = if descriptor.mapped_at_creation { MemoryLocation::CpuToGpu } else { MemoryLocation::GpuOnly };
 
         let allocator = self.inner.allocator.as_ref().ok_or_else(|| LumeError::BackendError("Allocator not initialized".to_string()))?;
         let allocation = allocator.lock().unwrap().allocate(&AllocationCreateDesc {
@@ -49,6 +56,7 @@ impl VulkanDevice {
             buffer,
             allocation,
             size: descriptor.size,
+            access: Arc::default(),
             allocator: allocator.clone(),
             device: self.clone(),
         })
@@ -69,7 +77,7 @@ impl VulkanDevice {
             image_type: vk::ImageType::TYPE_2D,
             format,
             extent: vk::Extent3D { width: descriptor.width, height: descriptor.height, depth: 1 },
-            mip_levels: 1,
+            mip_levels: descriptor.mip_level_count.max(1),
             array_layers: 1,
             samples: vk::SampleCountFlags::TYPE_1,
             tiling: vk::ImageTiling::OPTIMAL,
@@ -105,9 +113,10 @@ impl VulkanDevice {
             format,
             width: descriptor.width,
             height: descriptor.height,
+            mip_level_count: descriptor.mip_level_count.max(1),
             allocator: allocator.clone(),
             device: self.inner.device.clone(),
-            current_layout: Arc::new(Mutex::new(vk::ImageLayout::UNDEFINED)),
+            mips: (0..descriptor.mip_level_count.max(1)).map(|_| crate::tracking::MipState::default()).collect(),
         })
     }
 
@@ -140,6 +149,17 @@ impl VulkanDevice {
     pub fn create_texture_view_impl(&self, texture: &crate::VulkanTexture, descriptor: lume_core::device::TextureViewDescriptor) -> LumeResult<crate::VulkanTextureView> {
         let format = descriptor.format.map(map_texture_format).unwrap_or(texture.format);
         let aspect_mask = if is_depth_format(format) { vk::ImageAspectFlags::DEPTH } else { vk::ImageAspectFlags::COLOR };
+        if descriptor.base_mip_level >= texture.mip_level_count {
+            return Err(LumeError::ResourceCreationFailed(format!("Mip level {} of a texture with {} levels", descriptor.base_mip_level, texture.mip_level_count)));
+        }
+        let level_count = descriptor.mip_level_count.unwrap_or(texture.mip_level_count - descriptor.base_mip_level);
+        let levels = descriptor.base_mip_level..descriptor.base_mip_level + level_count;
+        if level_count == 0 || levels.end > texture.mip_level_count {
+            return Err(LumeError::ResourceCreationFailed(format!(
+                "Mip levels {}..{} of a texture with {} levels", levels.start, levels.end, texture.mip_level_count
+            )));
+        }
+        let (width, height) = lume_core::device::mip_extent(texture.width, texture.height, descriptor.base_mip_level);
 
         let create_info = vk::ImageViewCreateInfo {
             image: texture.image,
@@ -147,8 +167,8 @@ impl VulkanDevice {
             format,
             subresource_range: vk::ImageSubresourceRange {
                 aspect_mask,
-                base_mip_level: 0,
-                level_count: 1,
+                base_mip_level: descriptor.base_mip_level,
+                level_count,
                 base_array_layer: 0,
                 layer_count: 1,
             },
@@ -163,8 +183,10 @@ impl VulkanDevice {
         Ok(crate::VulkanTextureView {
             view,
             image: texture.image,
-            extent: vk::Extent2D { width: texture.width, height: texture.height },
-            current_layout: texture.current_layout.clone(),
+            extent: vk::Extent2D { width, height },
+            aspect_mask,
+            base_mip_level: descriptor.base_mip_level,
+            mips: texture.mips[levels.start as usize..levels.end as usize].to_vec(),
             device: self.inner.device.clone(),
         })
     }
@@ -225,7 +247,9 @@ impl VulkanDevice {
                 view, 
                 image,
                 extent,
-                current_layout: Arc::new(Mutex::new(vk::ImageLayout::UNDEFINED)),
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                mips: vec![crate::tracking::MipState::default()],
                 device: self.inner.device.clone() 
             });
         }
@@ -241,8 +265,169 @@ impl VulkanDevice {
             current_frame: 0,
             device: self.inner.device.clone(),
             present_queue: self.inner.present_queue,
+            offscreen_textures: Vec::new(),
+            presented_image: None,
+        })
+    }
+
+    pub fn create_offscreen_swapchain_impl(&self, descriptor: lume_core::device::OffscreenSwapchainDescriptor) -> LumeResult<crate::VulkanSwapchain> {
+        let extent = vk::Extent2D { width: descriptor.width, height: descriptor.height };
+        let mut offscreen_textures = Vec::new();
+        let mut image_views = Vec::new();
+        for _ in 0..descriptor.image_count {
+            let texture = self.create_texture_impl(lume_core::device::TextureDescriptor {
+                width: descriptor.width,
+                height: descriptor.height,
+                depth: 1,
+                mip_level_count: 1,
+                format: descriptor.format,
+                usage: lume_core::device::TextureUsage::RENDER_ATTACHMENT
+                    | lume_core::device::TextureUsage::TEXTURE_BINDING
+                    | lume_core::device::TextureUsage::COPY_SRC,
+            })?;
+            image_views.push(self.create_texture_view_impl(&texture, lume_core::device::TextureViewDescriptor { format: None, base_mip_level: 0, mip_level_count: None })?);
+            offscreen_textures.push(texture);
+        }
+
+        Ok(crate::VulkanSwapchain {
+            swapchain_loader: ash::khr::swapchain::Device::new(&self.inner.instance, &self.inner.device),
+            swapchain: vk::SwapchainKHR::null(),
+            images: offscreen_textures.iter().map(|t| t.image).collect(),
+            image_views,
+            extent,
+            format: map_texture_format(descriptor.format),
+            image_available_semaphores: Vec::new(),
+            current_frame: 0,
+            device: self.inner.device.clone(),
+            present_queue: self.inner.present_queue,
+            offscreen_textures,
+            presented_image: None,
         })
     }
+
+    /// Copies the first mip level of an image into host memory, blocking until the GPU is done.
+    /// The image is returned to its tracked layout afterwards, with its access history cleared
+    /// since the readback waited for everything before it.
+    pub(crate) fn read_image_impl(
+        &self,
+        image: vk::Image,
+        extent: vk::Extent2D,
+        texel_size: u32,
+        aspect_mask: vk::ImageAspectFlags,
+        mip: &crate::tracking::MipState,
+    ) -> LumeResult<Vec<u8>> {
+        let size = extent.width as u64 * extent.height as u64 * texel_size as u64;
+        let staging = self.create_buffer_in(lume_core::device::BufferDescriptor {
+            size,
+            usage: lume_core::device::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        }, MemoryLocation::GpuToCpu)?;
+
+        let device = &self.inner.device;
+        let mut layout = mip.layout.lock().unwrap();
+        let old_layout = *layout;
+        let range = vk::ImageSubresourceRange {
+            aspect_mask,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let to_transfer = vk::ImageMemoryBarrier2 {
+            src_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+            src_access_mask: vk::AccessFlags2::MEMORY_WRITE,
+            dst_stage_mask: vk::PipelineStageFlags2::COPY,
+            dst_access_mask: vk::AccessFlags2::TRANSFER_READ,
+            old_layout,
+            new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            image,
+            subresource_range: range,
+            ..Default::default()
+        };
+        // An undefined image has no layout worth restoring, so it stays in TRANSFER_SRC_OPTIMAL
+        let restore_layout = if old_layout == vk::ImageLayout::UNDEFINED { vk::ImageLayout::TRANSFER_SRC_OPTIMAL } else { old_layout };
+        let from_transfer = vk::ImageMemoryBarrier2 {
+            src_stage_mask: vk::PipelineStageFlags2::COPY,
+            src_access_mask: vk::AccessFlags2::TRANSFER_READ,
+            dst_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+            dst_access_mask: vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+            old_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            new_layout: restore_layout,
+            image,
+            subresource_range: range,
+            ..Default::default()
+        };
+        let region = vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+            image_extent: vk::Extent3D { width: extent.width, height: extent.height, depth: 1 },
+        };
+
+        unsafe {
+            let pool = device.create_command_pool(&vk::CommandPoolCreateInfo {
+                flags: vk::CommandPoolCreateFlags::TRANSIENT,
+                queue_family_index: self.inner.graphics_queue_index,
+                ..Default::default()
+            }, None).map_err(|e| LumeError::ResourceCreationFailed(format!("Failed to create readback command pool: {}", e)))?;
+            let fence = device.create_fence(&vk::FenceCreateInfo::default(), None)
+                .map_err(|e| LumeError::ResourceCreationFailed(format!("Failed to create readback fence: {}", e)));
+
+            let result = fence.and_then(|fence| {
+                let cmd = device.allocate_command_buffers(&vk::CommandBufferAllocateInfo {
+                    command_pool: pool,
+                    level: vk::CommandBufferLevel::PRIMARY,
+                    command_buffer_count: 1,
+                    ..Default::default()
+                }).map_err(|e| LumeError::ResourceCreationFailed(format!("Failed to allocate readback command buffer: {}", e)))?[0];
+
+                device.begin_command_buffer(cmd, &vk::CommandBufferBeginInfo {
+                    flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                    ..Default::default()
+                }).map_err(|e| LumeError::BackendError(format!("Failed to begin readback command buffer: {}", e)))?;
+                device.cmd_pipeline_barrier2(cmd, &vk::DependencyInfo {
+                    image_memory_barrier_count: 1,
+                    p_image_memory_barriers: &to_transfer,
+                    ..Default::default()
+                });
+                device.cmd_copy_image_to_buffer(cmd, image, vk::ImageLayout::TRANSFER_SRC_OPTIMAL, staging.buffer, &[region]);
+                device.cmd_pipeline_barrier2(cmd, &vk::DependencyInfo {
+                    image_memory_barrier_count: 1,
+                    p_image_memory_barriers: &from_transfer,
+                    ..Default::default()
+                });
+                device.end_command_buffer(cmd)
+                    .map_err(|e| LumeError::BackendError(format!("Failed to end readback command buffer: {}", e)))?;
+
+                let submit_info = vk::SubmitInfo {
+                    command_buffer_count: 1,
+                    p_command_buffers: &cmd,
+                    ..Default::default()
+                };
+                let submitted = device.queue_submit(self.inner.graphics_queue, &[submit_info], fence)
+                    .map_err(|e| LumeError::SubmissionFailed(format!("Failed to submit readback: {}", e)))
+                    .and_then(|_| device.wait_for_fences(&[fence], true, u64::MAX)
+                        .map_err(|e| LumeError::BackendError(format!("Failed to wait for readback: {}", e))));
+                device.destroy_fence(fence, None);
+                submitted
+            });
+            device.destroy_command_pool(pool, None);
+            result?;
+        }
+        *layout = restore_layout;
+        mip.access.lock().unwrap().reset();
+
+        let mut data = vec![0u8; size as usize];
+        lume_core::device::Buffer::read_data(&staging, 0, &mut data)?;
+        Ok(data)
+    }
 }
 
 pub fn map_texture_format(format: lume_core::device::TextureFormat) -> vk::Format {
@@ -251,10 +436,23 @@ pub fn map_texture_format(format: lume_core::device::TextureFormat) -> vk::Forma
         lume_core::device::TextureFormat::Rgba8UnormSrgb => vk::Format::R8G8B8A8_SRGB,
         lume_core::device::TextureFormat::Rgba8Unorm => vk::Format::R8G8B8A8_UNORM,
         lume_core::device::TextureFormat::Rg32Uint => vk::Format::R32G32_UINT,
+        lume_core::device::TextureFormat::R32Float => vk::Format::R32_SFLOAT,
         lume_core::device::TextureFormat::Depth32Float => vk::Format::D32_SFLOAT,
     }
 }
 
+pub fn unmap_texture_format(format: vk::Format) -> Option<lume_core::device::TextureFormat> {
+    match format {
+        vk::Format::B8G8R8A8_SRGB => Some(lume_core::device::TextureFormat::Bgra8UnormSrgb),
+        vk::Format::R8G8B8A8_SRGB => Some(lume_core::device::TextureFormat::Rgba8UnormSrgb),
+        vk::Format::R8G8B8A8_UNORM => Some(lume_core::device::TextureFormat::Rgba8Unorm),
+        vk::Format::R32G32_UINT => Some(lume_core::device::TextureFormat::Rg32Uint),
+        vk::Format::R32_SFLOAT => Some(lume_core::device::TextureFormat::R32Float),
+        vk::Format::D32_SFLOAT => Some(lume_core::device::TextureFormat::Depth32Float),
+        _ => None,
+    }
+}
+
 pub fn is_depth_format(format: vk::Format) -> bool {
     matches!(
         format,
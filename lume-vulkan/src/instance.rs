@@ -8,6 +8,7 @@ use gpu_allocator::AllocationSizes;
 use std::sync::{Arc, Mutex};
 
 pub struct VulkanInstance {
+    has_surface: bool,
     _debug_messenger: Option<vk::DebugUtilsMessengerEXT>,
     _debug_utils_loader: Option<ash::ext::debug_utils::Instance>,
     instance: ash::Instance,
@@ -78,17 +79,37 @@ impl Instance for VulkanInstance {
         };
 
         let layer_names: [*const i8; 0] = [];
-        let extension_names = [
-            ash::ext::debug_utils::NAME.as_ptr(),
-            ash::khr::surface::NAME.as_ptr(),
+        let available_extensions = unsafe {
+            entry.enumerate_instance_extension_properties(None)
+                .map_err(|e| lume_core::LumeError::InstanceCreationFailed(format!("Failed to enumerate instance extensions: {}", e)))?
+        };
+        let is_available = |wanted: &CStr| available_extensions.iter().any(|ext| {
+            let name = unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) };
+            name == wanted
+        });
+
+        let surface_extensions = [
+            ash::khr::surface::NAME,
             #[cfg(target_os = "windows")]
-            ash::khr::win32_surface::NAME.as_ptr(),
+            ash::khr::win32_surface::NAME,
             #[cfg(target_os = "linux")]
-            ash::khr::xlib_surface::NAME.as_ptr(),
+            ash::khr::xlib_surface::NAME,
+        ];
+        let mut extension_names = vec![
+            ash::ext::debug_utils::NAME.as_ptr(),
             #[cfg(target_os = "macos")]
             ash::khr::portability_enumeration::NAME.as_ptr(),
         ];
 
+        // Headless machines may have no window system; surfaces are then unavailable but
+        // offscreen rendering still works
+        let has_surface = surface_extensions.iter().all(|&name| is_available(name));
+        if has_surface {
+            extension_names.extend(surface_extensions.iter().map(|name| name.as_ptr()));
+        } else {
+            warn!("Window system extensions NOT supported; only offscreen rendering is available.");
+        }
+
         let create_info = vk::InstanceCreateInfo {
             p_application_info: &app_info,
             enabled_extension_count: extension_names.len() as u32,
@@ -109,6 +130,7 @@ impl Instance for VulkanInstance {
         info!("Vulkan Instance created successfully");
 
         Ok(VulkanInstance {
+            has_surface,
             _entry: entry,
             instance,
             _debug_utils_loader: Some(debug_utils),
@@ -121,6 +143,9 @@ impl Instance for VulkanInstance {
         display_handle: impl raw_window_handle::HasDisplayHandle,
         window_handle: impl raw_window_handle::HasWindowHandle,
     ) -> lume_core::LumeResult<Self::Surface> {
+        if !self.has_surface {
+            return Err(lume_core::LumeError::SurfaceCreationFailed("Window system extensions are not supported by this Vulkan instance".to_string()));
+        }
         let surface = unsafe {
             let display = display_handle.display_handle().map_err(|e| lume_core::LumeError::SurfaceCreationFailed(e.to_string()))?;
             let window = window_handle.window_handle().map_err(|e| lume_core::LumeError::SurfaceCreationFailed(e.to_string()))?;
@@ -202,13 +227,29 @@ impl Instance for VulkanInstance {
         let selected_device_name = unsafe { std::ffi::CStr::from_ptr(props.device_name.as_ptr()) }.to_string_lossy();
         info!("Selected GPU: {}", selected_device_name);
 
+        // Prefer families that do not share hardware with graphics for async compute and transfers
+        let families = unsafe { self.instance.get_physical_device_queue_family_properties(pdevice) };
+        let find_family = |required: vk::QueueFlags, excluded: vk::QueueFlags| {
+            families.iter().enumerate()
+                .find(|(_, f)| f.queue_flags.contains(required) && !f.queue_flags.intersects(excluded))
+                .map(|(idx, _)| idx as u32)
+        };
+        let compute_family = find_family(vk::QueueFlags::COMPUTE, vk::QueueFlags::GRAPHICS).unwrap_or(queue_family_index);
+        let transfer_family = find_family(vk::QueueFlags::TRANSFER, vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+            .unwrap_or(compute_family);
+        info!("Queue families: graphics {}, compute {}, transfer {}", queue_family_index, compute_family, transfer_family);
+
+        let mut unique_families = vec![queue_family_index, compute_family, transfer_family];
+        unique_families.sort();
+        unique_families.dedup();
+
         let priorities = [1.0];
-        let queue_info = vk::DeviceQueueCreateInfo {
+        let queue_infos: Vec<vk::DeviceQueueCreateInfo> = unique_families.iter().map(|&queue_family_index| vk::DeviceQueueCreateInfo {
             queue_family_index,
             queue_count: 1,
             p_queue_priorities: priorities.as_ptr(),
             ..Default::default()
-        };
+        }).collect();
 
         let available_extensions = unsafe {
             self.instance.enumerate_device_extension_properties(pdevice)
@@ -220,12 +261,23 @@ impl Instance for VulkanInstance {
             name == ash::ext::mesh_shader::NAME
         });
 
+        let has_swapchain = available_extensions.iter().any(|ext| {
+            let name = unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) };
+            name == ash::khr::swapchain::NAME
+        });
+
         let mut device_extension_names = vec![
-            ash::khr::swapchain::NAME.as_ptr(),
             #[cfg(target_os = "macos")]
             ash::vk::KhrPortabilitySubsetFn::name().as_ptr(),
         ];
 
+        // Headless devices may lack presentation support; offscreen swapchains do not need it
+        if has_swapchain {
+            device_extension_names.push(ash::khr::swapchain::NAME.as_ptr());
+        } else if surface.is_some() {
+            return Err(lume_core::LumeError::DeviceCreationFailed("Swapchain extension is not supported by this GPU".to_string()));
+        }
+
         if has_mesh_shader {
             info!("Mesh Shader extension supported and enabled.");
             device_extension_names.push(ash::ext::mesh_shader::NAME.as_ptr());
@@ -251,14 +303,16 @@ impl Instance for VulkanInstance {
             runtime_descriptor_array: vk::TRUE,
             descriptor_binding_variable_descriptor_count: vk::TRUE,
             descriptor_binding_partially_bound: vk::TRUE,
+            draw_indirect_count: vk::TRUE,
+            timeline_semaphore: vk::TRUE,
             ..Default::default()
         };
 
         let features = vk::PhysicalDeviceFeatures::default();
         let create_info = vk::DeviceCreateInfo {
             p_next: &features12 as *const _ as *const std::ffi::c_void,
-            p_queue_create_infos: &queue_info,
-            queue_create_info_count: 1,
+            p_queue_create_infos: queue_infos.as_ptr(),
+            queue_create_info_count: queue_infos.len() as u32,
             pp_enabled_extension_names: device_extension_names.as_ptr(),
             enabled_extension_count: device_extension_names.len() as u32,
             p_enabled_features: &features,
@@ -279,6 +333,15 @@ impl Instance for VulkanInstance {
         };
 
         let graphics_queue = unsafe { device.get_device_queue(queue_family_index, 0) };
+        let queues = crate::device::VulkanQueues {
+            graphics: graphics_queue,
+            graphics_family: queue_family_index,
+            present: graphics_queue,
+            compute: unsafe { device.get_device_queue(compute_family, 0) },
+            compute_family,
+            transfer: unsafe { device.get_device_queue(transfer_family, 0) },
+            transfer_family,
+        };
 
         let allocator = Allocator::new(&AllocatorCreateDesc {
             instance: self.instance.clone(),
@@ -294,9 +357,7 @@ impl Instance for VulkanInstance {
         Ok(VulkanDevice::new(
             self.instance.clone(),
             device,
-            graphics_queue,
-            graphics_queue,
-            queue_family_index,
+            queues,
             Some(Arc::new(Mutex::new(allocator))),
             pdevice,
         ))
@@ -5,10 +5,11 @@ mod swapchain;
 mod pipeline;
 mod buffer;
 mod texture;
+mod tracking;
 
 pub use instance::VulkanInstance;
 pub use surface::VulkanSurface;
-pub use device::VulkanDevice;
+pub use device::{VulkanDevice, QueueFamilies};
 pub use swapchain::VulkanSwapchain;
 pub use texture::{VulkanTexture, VulkanTextureView, VulkanSampler};
 pub use pipeline::*;
@@ -31,6 +32,53 @@ impl Drop for VulkanSemaphore {
     }
 }
 
+pub struct VulkanTimelineSemaphore {
+    pub semaphore: ash::vk::Semaphore,
+    pub device: ash::Device,
+}
+
+impl lume_core::device::TimelineSemaphore for VulkanTimelineSemaphore {
+    fn current_value(&self) -> lume_core::LumeResult<u64> {
+        unsafe {
+            self.device.get_semaphore_counter_value(self.semaphore)
+                .map_err(|e| lume_core::LumeError::BackendError(format!("Failed to query timeline semaphore: {}", e)))
+        }
+    }
+
+    fn signal(&self, value: u64) -> lume_core::LumeResult<()> {
+        let signal_info = ash::vk::SemaphoreSignalInfo {
+            semaphore: self.semaphore,
+            value,
+            ..Default::default()
+        };
+        unsafe {
+            self.device.signal_semaphore(&signal_info)
+                .map_err(|e| lume_core::LumeError::BackendError(format!("Failed to signal timeline semaphore: {}", e)))
+        }
+    }
+
+    fn wait(&self, value: u64, timeout: u64) -> lume_core::LumeResult<()> {
+        let wait_info = ash::vk::SemaphoreWaitInfo {
+            semaphore_count: 1,
+            p_semaphores: &self.semaphore,
+            p_values: &value,
+            ..Default::default()
+        };
+        unsafe {
+            self.device.wait_semaphores(&wait_info, timeout)
+                .map_err(|e| lume_core::LumeError::BackendError(format!("Wait for timeline semaphore failed: {}", e)))
+        }
+    }
+}
+
+impl Drop for VulkanTimelineSemaphore {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_semaphore(self.semaphore, None);
+        }
+    }
+}
+
 pub struct VulkanFence {
     pub fence: ash::vk::Fence,
     pub device: ash::Device,
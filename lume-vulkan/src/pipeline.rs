@@ -1,5 +1,7 @@
 use ash::vk;
 use lume_core::{LumeError, LumeResult};
+use crate::device::QueueFamilies;
+use crate::tracking::{Access, Barrier, BoundGroups, LayoutTransition, LocalUse, TrackedResource};
 
 pub struct VulkanShaderModule {
     pub module: vk::ShaderModule,
@@ -16,6 +18,9 @@ impl Drop for VulkanShaderModule {
 
 pub struct VulkanRenderPass {
     pub render_pass: vk::RenderPass,
+    /// Layout each attachment is expected in, `UNDEFINED` where its contents are discarded.
+    pub initial_layouts: Vec<vk::ImageLayout>,
+    pub final_layouts: Vec<vk::ImageLayout>,
     pub device: ash::Device,
 }
 
@@ -71,6 +76,8 @@ impl Drop for VulkanComputePipeline {
 
 pub struct VulkanCommandPool {
     pub pool: vk::CommandPool,
+    pub queue_family_index: u32,
+    pub queue_families: QueueFamilies,
     pub device: ash::Device,
 }
 
@@ -90,7 +97,7 @@ impl lume_core::device::CommandPool for VulkanCommandPool {
         let allocate_info = vk::CommandBufferAllocateInfo {
             command_pool: self.pool,
             level: vk::CommandBufferLevel::PRIMARY,
-            command_buffer_count: 1,
+            command_buffer_count: 2,
             ..Default::default()
         };
 
@@ -101,60 +108,227 @@ impl lume_core::device::CommandPool for VulkanCommandPool {
 
         Ok(VulkanCommandBuffer {
             buffer: command_buffers[0],
+            preamble: command_buffers[1],
             device: self.device.clone(),
             current_pipeline_layout: vk::PipelineLayout::null(),
+            queue_family_index: self.queue_family_index,
+            queue_families: self.queue_families,
+            in_render_pass: false,
+            tracked: Vec::new(),
+            bind_point: vk::PipelineBindPoint::GRAPHICS,
+            graphics_groups: BoundGroups::default(),
+            compute_groups: BoundGroups::default(),
         })
     }
 }
 
 pub struct VulkanCommandBuffer {
     pub buffer: vk::CommandBuffer,
+    /// Recorded at submit with the barriers that order `buffer` after earlier submissions.
+    preamble: vk::CommandBuffer,
     pub device: ash::Device,
     pub current_pipeline_layout: vk::PipelineLayout,
+    /// Family of the queue this buffer is recorded for.
+    pub queue_family_index: u32,
+    /// Family index of each `QueueType`, for ownership transfers.
+    pub queue_families: QueueFamilies,
+    in_render_pass: bool,
+    /// What this recording does to each resource it uses, resolved against earlier
+    /// submissions at submit and flushed ahead of each render pass.
+    tracked: Vec<LocalUse>,
+    /// Bind point of the last pipeline bound, which bind groups are bound to.
+    bind_point: vk::PipelineBindPoint,
+    /// Resources of the bind groups currently bound at each bind point, tracked again at
+    /// every draw and dispatch.
+    graphics_groups: BoundGroups,
+    compute_groups: BoundGroups,
 }
 
 impl VulkanCommandBuffer {
-    fn internal_barrier(&self, view: &crate::VulkanTextureView, target_layout: vk::ImageLayout) {
-        let mut current_layout = view.current_layout.lock().unwrap();
-        if *current_layout == target_layout {
-            return;
+    /// Moves every mip level of an attachment to `target_layout` for a render pass writing it.
+    fn track_attachment(&mut self, view: &crate::VulkanTextureView, target_layout: vk::ImageLayout) {
+        let usage = if target_layout == vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL {
+            Access::DEPTH_ATTACHMENT_WRITE
+        } else {
+            Access::COLOR_ATTACHMENT_WRITE
+        };
+        for attachment in TrackedResource::view(view) {
+            self.track(&attachment, usage, Some(target_layout));
         }
+    }
 
-        let image_barrier = vk::ImageMemoryBarrier2 {
-            src_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
-            src_access_mask: vk::AccessFlags2::MEMORY_WRITE | vk::AccessFlags2::MEMORY_READ,
-            dst_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
-            dst_access_mask: vk::AccessFlags2::MEMORY_WRITE | vk::AccessFlags2::MEMORY_READ,
-            old_layout: *current_layout,
-            new_layout: target_layout,
-            image: view.image,
-            subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                base_mip_level: 0,
-                level_count: 1,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            ..Default::default()
+    /// This recording's use of `resource`.
+    fn local(&mut self, resource: &TrackedResource) -> &mut LocalUse {
+        let index = match self.tracked.iter().position(|t| t.resource.is(resource)) {
+            Some(index) => index,
+            None => {
+                self.tracked.push(LocalUse::new(resource.clone()));
+                self.tracked.len() - 1
+            }
         };
+        &mut self.tracked[index]
+    }
 
-        // Correct aspect mask based on layout/usage if needed, but for now we assume color or let it pass
-        let mut barrier_cloned = image_barrier;
-        if target_layout == vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL {
-            barrier_cloned.subresource_range.aspect_mask = vk::ImageAspectFlags::DEPTH;
+    /// Records `usage` of `resource`, emitting the barrier and layout transition it needs
+    /// within this recording; the first use is left for `resolve` to order at submit.
+    ///
+    /// # Panics
+    ///
+    /// Inside a render pass no barrier can be recorded, so a use that needs one panics. The
+    /// groups bound when the pass begins are synchronised by `begin_graphics`; anything else
+    /// the pass uses must be made ready before it begins.
+    fn track(&mut self, resource: &TrackedResource, usage: Access, target_layout: Option<vk::ImageLayout>) {
+        if usage.access.is_empty() {
+            return;
+        }
+        let in_render_pass = self.in_render_pass;
+        let local = self.local(resource);
+        if in_render_pass {
+            if local.needs_barrier(usage, target_layout) {
+                panic!("Resource needs a barrier inside a render pass; bind its group or use it before the pass begins");
+            }
+            local.access(usage, target_layout);
+            return;
         }
 
-        let dependency_info = vk::DependencyInfo {
-            image_memory_barrier_count: 1,
-            p_image_memory_barriers: &barrier_cloned,
+        if let Some((barrier, transition)) = local.access(usage, target_layout) {
+            self.emit_barriers(&[(resource.clone(), barrier, transition)]);
+        }
+    }
+
+    /// The groups bound at `bind_point`.
+    fn groups(&mut self, bind_point: vk::PipelineBindPoint) -> &mut BoundGroups {
+        if bind_point == vk::PipelineBindPoint::COMPUTE { &mut self.compute_groups } else { &mut self.graphics_groups }
+    }
+
+    /// Tracks the resources of the bound groups again for the draw or dispatch about to be
+    /// recorded. Draws inside one render pass cannot be separated by barriers, so only the
+    /// first use in a pass is tracked, by the bind or the flush in `begin_graphics`.
+    fn track_bound(&mut self, bind_point: vk::PipelineBindPoint) {
+        let resources = self.groups(bind_point).next_use();
+        if self.in_render_pass {
+            return;
+        }
+        for bound in resources {
+            self.track(&bound.resource, bound.usage, bound.layout);
+        }
+    }
+
+    /// Tracks the bound groups again, emitting their barriers and layout transitions, ahead
+    /// of a pipeline bind or a render pass that will use them.
+    fn track_groups(&mut self, bind_point: vk::PipelineBindPoint) {
+        for bound in self.groups(bind_point).resources() {
+            self.track(&bound.resource, bound.usage, bound.layout);
+        }
+    }
+
+    /// Synchronises the bound groups for the render pass about to begin, and makes pending
+    /// writes visible to the graphics pipeline.
+    fn begin_graphics(&mut self) {
+        self.track_groups(vk::PipelineBindPoint::GRAPHICS);
+        let mut barriers = Vec::new();
+        for local in &mut self.tracked {
+            let usage = match local.resource {
+                TrackedResource::Buffer { .. } => Access::GRAPHICS_BUFFER_READ,
+                TrackedResource::Image { .. } => Access::GRAPHICS_IMAGE_READ,
+            };
+            if let Some((barrier, layouts)) = local.make_visible(usage) {
+                barriers.push((local.resource.clone(), barrier, layouts));
+            }
+        }
+        self.emit_barriers(&barriers);
+    }
+
+    fn emit_barriers(&self, barriers: &[(TrackedResource, Barrier, Option<LayoutTransition>)]) {
+        record_barriers(&self.device, self.buffer, barriers);
+    }
+
+    /// Orders this recording after the buffers submitted before it, leaving the resources it
+    /// uses in the state it ends with. Returns the command buffers to submit in its place.
+    pub(crate) fn resolve(&self) -> LumeResult<Vec<vk::CommandBuffer>> {
+        let barriers: Vec<_> = self.tracked.iter()
+            .filter_map(|local| local.resolve(self.queue_family_index).map(|(barrier, layouts)| (local.resource.clone(), barrier, layouts)))
+            .collect();
+        if barriers.is_empty() {
+            return Ok(vec![self.buffer]);
+        }
+
+        let begin_info = vk::CommandBufferBeginInfo {
+            flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
             ..Default::default()
         };
-
         unsafe {
-            self.device.cmd_pipeline_barrier2(self.buffer, &dependency_info);
+            self.device.begin_command_buffer(self.preamble, &begin_info)
+                .map_err(|e| LumeError::BackendError(format!("Failed to begin barrier command buffer: {}", e)))?;
+            record_barriers(&self.device, self.preamble, &barriers);
+            self.device.end_command_buffer(self.preamble)
+                .map_err(|e| LumeError::BackendError(format!("Failed to end barrier command buffer: {}", e)))?;
+        }
+        Ok(vec![self.preamble, self.buffer])
+    }
+}
+
+/// Records `barriers` into `command_buffer` as a single pipeline barrier.
+fn record_barriers(device: &ash::Device, command_buffer: vk::CommandBuffer, barriers: &[(TrackedResource, Barrier, Option<LayoutTransition>)]) {
+    if barriers.is_empty() {
+        return;
+    }
+
+    let mut buffer_barriers = Vec::new();
+    let mut image_barriers = Vec::new();
+    for (resource, barrier, transition) in barriers {
+        let src_stage_mask = if barrier.src.stages.is_empty() { vk::PipelineStageFlags2::NONE } else { barrier.src.stages };
+        match resource {
+            TrackedResource::Buffer { buffer, .. } => buffer_barriers.push(vk::BufferMemoryBarrier2 {
+                src_stage_mask,
+                src_access_mask: barrier.src.access,
+                dst_stage_mask: barrier.dst.stages,
+                dst_access_mask: barrier.dst.access,
+                src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                buffer: *buffer,
+                offset: 0,
+                size: vk::WHOLE_SIZE,
+                ..Default::default()
+            }),
+            TrackedResource::Image { image, aspect_mask, mip_level, layout, .. } => {
+                let (old_layout, new_layout) = transition.unwrap_or_else(|| {
+                    let current = *layout.lock().unwrap();
+                    (current, current)
+                });
+                image_barriers.push(vk::ImageMemoryBarrier2 {
+                    src_stage_mask,
+                    src_access_mask: barrier.src.access,
+                    dst_stage_mask: barrier.dst.stages,
+                    dst_access_mask: barrier.dst.access,
+                    old_layout,
+                    new_layout,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    image: *image,
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: *aspect_mask,
+                        base_mip_level: *mip_level,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    },
+                    ..Default::default()
+                });
+            }
         }
+    }
 
-        *current_layout = target_layout;
+    let dependency_info = vk::DependencyInfo {
+        buffer_memory_barrier_count: buffer_barriers.len() as u32,
+        p_buffer_memory_barriers: buffer_barriers.as_ptr(),
+        image_memory_barrier_count: image_barriers.len() as u32,
+        p_image_memory_barriers: image_barriers.as_ptr(),
+        ..Default::default()
+    };
+
+    unsafe {
+        device.cmd_pipeline_barrier2(command_buffer, &dependency_info);
     }
 }
 
@@ -169,6 +343,11 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
     }
 
     fn begin(&mut self) -> LumeResult<()> {
+        self.tracked.clear();
+        self.graphics_groups.clear();
+        self.compute_groups.clear();
+        self.bind_point = vk::PipelineBindPoint::GRAPHICS;
+        self.in_render_pass = false;
         let begin_info = vk::CommandBufferBeginInfo {
             flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
             ..Default::default()
@@ -188,6 +367,14 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
     }
 
     fn begin_render_pass(&mut self, render_pass: &crate::VulkanRenderPass, framebuffer: &crate::VulkanFramebuffer, clear_color: [f32; 4]) {
+        for ((attachment, usage), &layout) in framebuffer.attachments.iter().zip(&render_pass.initial_layouts) {
+            if layout != vk::ImageLayout::UNDEFINED {
+                self.track(attachment, *usage, Some(layout));
+            }
+        }
+        self.begin_graphics();
+        self.in_render_pass = true;
+
         let clear_values = [
             vk::ClearValue {
                 color: vk::ClearColorValue {
@@ -220,27 +407,77 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
         unsafe {
             self.device.cmd_begin_render_pass(self.buffer, &render_pass_begin_info, vk::SubpassContents::INLINE);
         }
+
+        // The render pass transitions its attachments, so keep the tracked layouts in sync
+        for ((attachment, usage), &final_layout) in framebuffer.attachments.iter().zip(&render_pass.final_layouts) {
+            self.local(attachment).written_by_pass(*usage, final_layout);
+        }
     }
 
     fn draw(&mut self, vertex_count: u32, instance_count: u32, first_vertex: u32, first_instance: u32) {
+        self.track_bound(vk::PipelineBindPoint::GRAPHICS);
         unsafe {
             self.device.cmd_draw(self.buffer, vertex_count, instance_count, first_vertex, first_instance);
         }
     }
 
+    fn draw_indexed(&mut self, index_count: u32, instance_count: u32, first_index: u32, base_vertex: i32, first_instance: u32) {
+        self.track_bound(vk::PipelineBindPoint::GRAPHICS);
+        unsafe {
+            self.device.cmd_draw_indexed(self.buffer, index_count, instance_count, first_index, base_vertex, first_instance);
+        }
+    }
+
     fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.track_bound(vk::PipelineBindPoint::COMPUTE);
         unsafe {
             self.device.cmd_dispatch(self.buffer, x, y, z);
         }
     }
 
+    fn draw_indirect(&mut self, buffer: &crate::VulkanBuffer, offset: u64, draw_count: u32, stride: u32) {
+        self.track_bound(vk::PipelineBindPoint::GRAPHICS);
+        self.track(&TrackedResource::buffer(buffer), Access::INDIRECT_READ, None);
+        unsafe {
+            self.device.cmd_draw_indirect(self.buffer, buffer.buffer, offset, draw_count, stride);
+        }
+    }
+
+    fn draw_indirect_count(&mut self, buffer: &crate::VulkanBuffer, offset: u64, count_buffer: &crate::VulkanBuffer, count_offset: u64, max_draw_count: u32, stride: u32) {
+        self.track_bound(vk::PipelineBindPoint::GRAPHICS);
+        self.track(&TrackedResource::buffer(buffer), Access::INDIRECT_READ, None);
+        self.track(&TrackedResource::buffer(count_buffer), Access::INDIRECT_READ, None);
+        unsafe {
+            self.device.cmd_draw_indirect_count(self.buffer, buffer.buffer, offset, count_buffer.buffer, count_offset, max_draw_count, stride);
+        }
+    }
+
+    fn draw_indexed_indirect(&mut self, buffer: &crate::VulkanBuffer, offset: u64, draw_count: u32, stride: u32) {
+        self.track_bound(vk::PipelineBindPoint::GRAPHICS);
+        self.track(&TrackedResource::buffer(buffer), Access::INDIRECT_READ, None);
+        unsafe {
+            self.device.cmd_draw_indexed_indirect(self.buffer, buffer.buffer, offset, draw_count, stride);
+        }
+    }
+
+    fn dispatch_indirect(&mut self, buffer: &crate::VulkanBuffer, offset: u64) {
+        self.track_bound(vk::PipelineBindPoint::COMPUTE);
+        self.track(&TrackedResource::buffer(buffer), Access::INDIRECT_READ, None);
+        unsafe {
+            self.device.cmd_dispatch_indirect(self.buffer, buffer.buffer, offset);
+        }
+    }
+
     fn end_render_pass(&mut self) {
+        self.in_render_pass = false;
         unsafe {
             self.device.cmd_end_render_pass(self.buffer);
         }
     }
 
     fn begin_rendering(&mut self, descriptor: lume_core::device::RenderingDescriptor<Self::Device>) {
+        self.begin_graphics();
+
         // 1. Automatic Transitions
         for at in descriptor.color_attachments {
             let target_layout = match at.layout {
@@ -248,12 +485,13 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
                 lume_core::device::ImageLayout::ShaderReadOnly => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
                 _ => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
             };
-            self.internal_barrier(at.view, target_layout);
+            self.track_attachment(at.view, target_layout);
         }
 
         if let Some(ref at) = descriptor.depth_attachment {
-            self.internal_barrier(at.view, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+            self.track_attachment(at.view, vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
         }
+        self.in_render_pass = true;
 
         // 2. Build Rendering Info (Existing logic improved)
         let color_attachments: Vec<vk::RenderingAttachmentInfo> = descriptor.color_attachments.iter().map(|at| {
@@ -339,12 +577,18 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
     }
 
     fn end_rendering(&mut self) {
+        self.in_render_pass = false;
         unsafe {
             self.device.cmd_end_rendering(self.buffer);
         }
     }
 
     fn bind_graphics_pipeline(&mut self, pipeline: &<Self::Device as lume_core::Device>::GraphicsPipeline) {
+        // Inside a render pass the groups were synchronised when it began
+        if !self.in_render_pass {
+            self.track_groups(vk::PipelineBindPoint::GRAPHICS);
+        }
+        self.bind_point = vk::PipelineBindPoint::GRAPHICS;
         unsafe {
             self.device.cmd_bind_pipeline(self.buffer, vk::PipelineBindPoint::GRAPHICS, pipeline.pipeline);
             self.current_pipeline_layout = pipeline.layout;
@@ -352,6 +596,8 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
     }
 
     fn bind_compute_pipeline(&mut self, pipeline: &crate::VulkanComputePipeline) {
+        self.track_groups(vk::PipelineBindPoint::COMPUTE);
+        self.bind_point = vk::PipelineBindPoint::COMPUTE;
         unsafe {
             self.device.cmd_bind_pipeline(self.buffer, vk::PipelineBindPoint::COMPUTE, pipeline.pipeline);
             self.current_pipeline_layout = pipeline.layout;
@@ -359,37 +605,46 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
     }
 
     fn bind_vertex_buffer(&mut self, buffer: &crate::VulkanBuffer) {
+        self.track(&TrackedResource::buffer(buffer), Access::VERTEX_READ, None);
         unsafe {
             self.device.cmd_bind_vertex_buffers(self.buffer, 0, &[buffer.buffer], &[0]);
         }
     }
 
-    fn bind_bind_group(&mut self, index: u32, bind_group: &crate::VulkanBindGroup) {
-        // Try to detect if we are in graphics or compute.
-        // For simplicity, we can bind to BOTH or use a flag. 
-        // WebGPU-like and Vulkan allow binding to different bind points.
-        // Given our abstraction, we might need a way to know the current bind point.
-        // For now, let's bind to GRAPHICS if a render pass is active, else COMPUTE?
-        // Actually, let's just bind to both if we don't know, or use the last bound pipeline's type.
-        // Better: Bind to GRAPHICS for now, and implement separate if needed.
-        // WAIT: cmd_bind_descriptor_sets REQUIRES a pipeline_bind_point.
-        // Let's use a simple heuristic: if we have a current_pipeline_layout and we bound a compute pipeline last, use COMPUTE.
-        // We'll need to track the bind point in VulkanCommandBuffer.
+    fn bind_index_buffer(&mut self, buffer: &crate::VulkanBuffer, format: lume_core::device::IndexFormat) {
+        let index_type = match format {
+            lume_core::device::IndexFormat::Uint16 => vk::IndexType::UINT16,
+            lume_core::device::IndexFormat::Uint32 => vk::IndexType::UINT32,
+        };
+        self.track(&TrackedResource::buffer(buffer), Access::INDEX_READ, None);
         unsafe {
-            self.device.cmd_bind_descriptor_sets(
+            self.device.cmd_bind_index_buffer(self.buffer, buffer.buffer, 0, index_type);
+        }
+    }
+
+    fn set_push_constants(&mut self, stages: lume_core::device::ShaderStage, offset: u32, data: &[u8]) {
+        unsafe {
+            self.device.cmd_push_constants(
                 self.buffer,
-                vk::PipelineBindPoint::GRAPHICS, // Default to GRAPHICS
                 self.current_pipeline_layout,
-                index,
-                &[bind_group.set],
-                &[],
+                crate::device::map_shader_stages(stages),
+                offset,
+                data,
             );
-            
-            // Also bind to COMPUTE just in case, if layout is compatible.
-            // In Vulkan, layouts are often the same.
+        }
+    }
+
+    fn bind_bind_group(&mut self, index: u32, bind_group: &crate::VulkanBindGroup) {
+        for bound in &bind_group.resources {
+            self.track(&bound.resource, bound.usage, bound.layout);
+        }
+        let bind_point = self.bind_point;
+        self.groups(bind_point).bind(index, &bind_group.resources);
+
+        unsafe {
             self.device.cmd_bind_descriptor_sets(
                 self.buffer,
-                vk::PipelineBindPoint::COMPUTE,
+                bind_point,
                 self.current_pipeline_layout,
                 index,
                 &[bind_group.set],
@@ -425,6 +680,8 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
             dst_offset: 0,
             size,
         };
+        self.track(&TrackedResource::buffer(source), Access::TRANSFER_READ, None);
+        self.track(&TrackedResource::buffer(destination), Access::TRANSFER_WRITE, None);
         unsafe {
             self.device.cmd_copy_buffer(self.buffer, source.buffer, destination.buffer, &[region]);
         }
@@ -444,6 +701,8 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
             image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
             image_extent: vk::Extent3D { width, height, depth: 1 },
         };
+        self.track(&TrackedResource::buffer(source), Access::TRANSFER_READ, None);
+        self.track(&TrackedResource::texture(destination), Access::TRANSFER_WRITE, Some(vk::ImageLayout::TRANSFER_DST_OPTIMAL));
 
         unsafe {
             self.device.cmd_copy_buffer_to_image(
@@ -456,6 +715,41 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
         }
     }
 
+    fn copy_texture_to_buffer(&mut self, source: &crate::VulkanTexture, destination: &crate::VulkanBuffer, region: lume_core::device::TextureCopyRegion) {
+        let aspect_mask = if crate::device::resource::is_depth_format(source.format) {
+            vk::ImageAspectFlags::DEPTH
+        } else {
+            vk::ImageAspectFlags::COLOR
+        };
+        self.track(&TrackedResource::texture(source), Access::TRANSFER_READ, Some(vk::ImageLayout::TRANSFER_SRC_OPTIMAL));
+        self.track(&TrackedResource::buffer(destination), Access::TRANSFER_WRITE, None);
+
+        let texel_size = crate::device::resource::unmap_texture_format(source.format).map_or(4, |f| f.texel_size());
+        let copy = vk::BufferImageCopy {
+            buffer_offset: region.buffer_offset,
+            buffer_row_length: region.bytes_per_row / texel_size,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_offset: vk::Offset3D { x: region.x as i32, y: region.y as i32, z: 0 },
+            image_extent: vk::Extent3D { width: region.width, height: region.height, depth: 1 },
+        };
+
+        unsafe {
+            self.device.cmd_copy_image_to_buffer(
+                self.buffer,
+                source.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                destination.buffer,
+                &[copy],
+            );
+        }
+    }
+
     fn texture_barrier(&mut self, texture: &crate::VulkanTexture, old_layout: lume_core::device::ImageLayout, new_layout: lume_core::device::ImageLayout) {
         let barrier = vk::ImageMemoryBarrier {
             old_layout: map_layout(old_layout),
@@ -464,20 +758,29 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
             dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
             image: texture.image,
             subresource_range: vk::ImageSubresourceRange {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
+                aspect_mask: if crate::device::resource::is_depth_format(texture.format) {
+                    vk::ImageAspectFlags::DEPTH
+                } else {
+                    vk::ImageAspectFlags::COLOR
+                },
                 base_mip_level: 0,
-                level_count: 1,
+                level_count: vk::REMAINING_MIP_LEVELS,
                 base_array_layer: 0,
                 layer_count: 1,
             },
             src_access_mask: match old_layout {
                 lume_core::device::ImageLayout::Undefined => vk::AccessFlags::empty(),
                 lume_core::device::ImageLayout::TransferDst => vk::AccessFlags::TRANSFER_WRITE,
+                lume_core::device::ImageLayout::ColorAttachment => vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                lume_core::device::ImageLayout::DepthStencilAttachment => vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                lume_core::device::ImageLayout::General => vk::AccessFlags::SHADER_WRITE,
                 _ => vk::AccessFlags::MEMORY_READ, // Simplified
             },
             dst_access_mask: match new_layout {
                 lume_core::device::ImageLayout::TransferDst => vk::AccessFlags::TRANSFER_WRITE,
                 lume_core::device::ImageLayout::ShaderReadOnly => vk::AccessFlags::SHADER_READ,
+                lume_core::device::ImageLayout::ColorAttachment => vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                lume_core::device::ImageLayout::DepthStencilAttachment => vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
                 _ => vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
             },
             ..Default::default()
@@ -486,12 +789,20 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
         let src_stage = match old_layout {
             lume_core::device::ImageLayout::Undefined => vk::PipelineStageFlags::TOP_OF_PIPE,
             lume_core::device::ImageLayout::TransferDst => vk::PipelineStageFlags::TRANSFER,
+            lume_core::device::ImageLayout::ColorAttachment => vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            lume_core::device::ImageLayout::DepthStencilAttachment => vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
             _ => vk::PipelineStageFlags::ALL_COMMANDS,
         };
 
         let dst_stage = match new_layout {
             lume_core::device::ImageLayout::TransferDst => vk::PipelineStageFlags::TRANSFER,
-            lume_core::device::ImageLayout::ShaderReadOnly => vk::PipelineStageFlags::FRAGMENT_SHADER,
+            lume_core::device::ImageLayout::ShaderReadOnly => {
+                vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER
+            }
+            lume_core::device::ImageLayout::ColorAttachment => vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            lume_core::device::ImageLayout::DepthStencilAttachment => {
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS
+            }
             _ => vk::PipelineStageFlags::ALL_COMMANDS,
         };
 
@@ -506,20 +817,28 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
                 &[barrier],
             );
         }
+
+        // An explicit transition supersedes whatever was tracked for the texture
+        let expected = (old_layout != lume_core::device::ImageLayout::Undefined).then(|| map_layout(old_layout));
+        for mip in TrackedResource::mips(texture) {
+            self.local(&mip).reset(expected, map_layout(new_layout));
+        }
     }
 
     fn compute_barrier(&mut self) {
+        let shaders = vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER;
         let barrier = vk::MemoryBarrier {
-            src_access_mask: vk::AccessFlags::SHADER_WRITE,
-            dst_access_mask: vk::AccessFlags::SHADER_READ | vk::AccessFlags::UNIFORM_READ,
+            src_access_mask: vk::AccessFlags::SHADER_WRITE | vk::AccessFlags::TRANSFER_WRITE,
+            dst_access_mask: vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE | vk::AccessFlags::UNIFORM_READ | vk::AccessFlags::INDIRECT_COMMAND_READ
+                | vk::AccessFlags::TRANSFER_READ | vk::AccessFlags::TRANSFER_WRITE,
             ..Default::default()
         };
 
         unsafe {
             self.device.cmd_pipeline_barrier(
                 self.buffer,
-                vk::PipelineStageFlags::COMPUTE_SHADER,
-                vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                shaders | vk::PipelineStageFlags::TRANSFER,
+                shaders | vk::PipelineStageFlags::DRAW_INDIRECT | vk::PipelineStageFlags::TRANSFER,
                 vk::DependencyFlags::empty(),
                 &[barrier],
                 &[],
@@ -527,6 +846,87 @@ impl lume_core::device::CommandBuffer for VulkanCommandBuffer {
             );
         }
     }
+
+    fn buffer_ownership_barrier(&mut self, buffer: &crate::VulkanBuffer, src_queue: lume_core::device::QueueType, dst_queue: lume_core::device::QueueType) {
+        let (src_family, dst_family) = (self.queue_families.get(src_queue), self.queue_families.get(dst_queue));
+        if src_family == dst_family {
+            return;
+        }
+
+        let barrier = vk::BufferMemoryBarrier2 {
+            src_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+            src_access_mask: vk::AccessFlags2::MEMORY_WRITE,
+            dst_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+            dst_access_mask: vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+            src_queue_family_index: src_family,
+            dst_queue_family_index: dst_family,
+            buffer: buffer.buffer,
+            offset: 0,
+            size: vk::WHOLE_SIZE,
+            ..Default::default()
+        };
+
+        let dependency_info = vk::DependencyInfo {
+            buffer_memory_barrier_count: 1,
+            p_buffer_memory_barriers: &barrier,
+            ..Default::default()
+        };
+
+        unsafe {
+            self.device.cmd_pipeline_barrier2(self.buffer, &dependency_info);
+        }
+        self.local(&TrackedResource::buffer(buffer)).transfer_to_queue(dst_family);
+    }
+
+    fn texture_ownership_barrier(&mut self, texture: &crate::VulkanTexture, src_queue: lume_core::device::QueueType, dst_queue: lume_core::device::QueueType) {
+        let (src_family, dst_family) = (self.queue_families.get(src_queue), self.queue_families.get(dst_queue));
+        if src_family == dst_family {
+            return;
+        }
+
+        let aspect_mask = if crate::device::resource::is_depth_format(texture.format) {
+            vk::ImageAspectFlags::DEPTH
+        } else {
+            vk::ImageAspectFlags::COLOR
+        };
+        // Levels may sit in different layouts, so each gets its own barrier
+        let mips: Vec<TrackedResource> = TrackedResource::mips(texture).collect();
+        let barriers: Vec<vk::ImageMemoryBarrier2> = mips.iter().zip(0..).map(|(mip, mip_level)| {
+            let layout = self.local(mip).layout();
+            vk::ImageMemoryBarrier2 {
+                src_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                src_access_mask: vk::AccessFlags2::MEMORY_WRITE,
+                dst_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                dst_access_mask: vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+                old_layout: layout,
+                new_layout: layout,
+                src_queue_family_index: src_family,
+                dst_queue_family_index: dst_family,
+                image: texture.image,
+                subresource_range: vk::ImageSubresourceRange {
+                    aspect_mask,
+                    base_mip_level: mip_level,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                },
+                ..Default::default()
+            }
+        }).collect();
+
+        let dependency_info = vk::DependencyInfo {
+            image_memory_barrier_count: barriers.len() as u32,
+            p_image_memory_barriers: barriers.as_ptr(),
+            ..Default::default()
+        };
+
+        unsafe {
+            self.device.cmd_pipeline_barrier2(self.buffer, &dependency_info);
+        }
+        for mip in &mips {
+            self.local(mip).transfer_to_queue(dst_family);
+        }
+    }
 }
 
 fn map_layout(layout: lume_core::device::ImageLayout) -> vk::ImageLayout {
@@ -536,6 +936,9 @@ fn map_layout(layout: lume_core::device::ImageLayout) -> vk::ImageLayout {
         lume_core::device::ImageLayout::TransferSrc => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
         lume_core::device::ImageLayout::TransferDst => vk::ImageLayout::TRANSFER_DST_OPTIMAL,
         lume_core::device::ImageLayout::ShaderReadOnly => vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        lume_core::device::ImageLayout::ColorAttachment => vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        lume_core::device::ImageLayout::DepthStencilAttachment => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        lume_core::device::ImageLayout::Present => vk::ImageLayout::PRESENT_SRC_KHR,
     }
 }
 
@@ -544,6 +947,8 @@ pub struct VulkanFramebuffer {
     pub framebuffer: vk::Framebuffer,
     pub width: u32,
     pub height: u32,
+    /// Each attachment and how the pass writes it.
+    pub attachments: Vec<(TrackedResource, Access)>,
     pub device: ash::Device,
 }
 
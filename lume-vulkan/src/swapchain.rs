@@ -1,6 +1,6 @@
 use ash::vk;
 use log::{info};
-use crate::VulkanTextureView;
+use crate::{VulkanTexture, VulkanTextureView};
 
 pub struct VulkanSwapchain {
     pub swapchain_loader: ash::khr::swapchain::Device,
@@ -16,6 +16,10 @@ pub struct VulkanSwapchain {
     
     pub device: ash::Device,
     pub present_queue: vk::Queue,
+
+    // Offscreen swapchains own their images and have no `vk::SwapchainKHR`
+    pub offscreen_textures: Vec<VulkanTexture>,
+    pub presented_image: Option<u32>,
 }
 
 // View moved to texture.rs
@@ -25,7 +29,10 @@ impl Drop for VulkanSwapchain {
         unsafe {
             info!("Destroying Swapchain");
             self.image_views.clear(); // This will trigger drop on each VulkanTextureView
-            self.swapchain_loader.destroy_swapchain(self.swapchain, None);
+            self.offscreen_textures.clear();
+            if !self.is_offscreen() {
+                self.swapchain_loader.destroy_swapchain(self.swapchain, None);
+            }
             for &sem in &self.image_available_semaphores {
                 self.device.destroy_semaphore(sem, None);
             }
@@ -37,6 +44,10 @@ impl lume_core::device::Swapchain for VulkanSwapchain {
     type TextureView = VulkanTextureView;
 
     fn acquire_next_image(&mut self, signal_semaphore: &impl lume_core::device::Semaphore) -> lume_core::LumeResult<u32> {
+        if self.is_offscreen() {
+            return self.acquire_next_image_raw(vk::Semaphore::null());
+        }
+
         let vk_semaphore = unsafe {
             let s = &*(signal_semaphore as *const dyn lume_core::device::Semaphore as *const crate::VulkanSemaphore);
             s.semaphore
@@ -56,6 +67,11 @@ impl lume_core::device::Swapchain for VulkanSwapchain {
     }
 
     fn present(&mut self, image_index: u32, wait_semaphores: &[&impl lume_core::device::Semaphore]) -> lume_core::LumeResult<()> {
+        self.presented_image = Some(image_index);
+        if self.is_offscreen() {
+            return Ok(());
+        }
+
         let swapchains = [self.swapchain];
         let image_indices = [image_index];
         
@@ -87,7 +103,16 @@ impl lume_core::device::Swapchain for VulkanSwapchain {
 }
 
 impl VulkanSwapchain {
+    pub fn is_offscreen(&self) -> bool {
+        self.swapchain == vk::SwapchainKHR::null()
+    }
+
     pub fn acquire_next_image_raw(&mut self, signal_semaphore: vk::Semaphore) -> lume_core::LumeResult<u32> {
+        if self.is_offscreen() {
+            let index = self.presented_image.map_or(0, |i| (i + 1) % self.images.len() as u32);
+            return Ok(index);
+        }
+
         unsafe {
             let (index, _is_suboptimal) = self.swapchain_loader
                 .acquire_next_image(
@@ -102,6 +127,11 @@ impl VulkanSwapchain {
     }
 
     pub fn present_raw(&mut self, image_index: u32, wait_semaphores: &[vk::Semaphore]) -> lume_core::LumeResult<()> {
+        self.presented_image = Some(image_index);
+        if self.is_offscreen() {
+            return Ok(());
+        }
+
         let swapchains = [self.swapchain];
         let image_indices = [image_index];
         
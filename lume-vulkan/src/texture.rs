@@ -8,9 +8,11 @@ pub struct VulkanTexture {
     pub format: vk::Format,
     pub width: u32,
     pub height: u32,
+    pub mip_level_count: u32,
     pub allocator: Arc<Mutex<Allocator>>,
     pub device: ash::Device,
-    pub current_layout: Arc<Mutex<vk::ImageLayout>>,
+    /// One per mip level, so views of different levels are transitioned and synchronised apart.
+    pub mips: Vec<crate::tracking::MipState>,
 }
 
 impl Drop for VulkanTexture {
@@ -29,7 +31,10 @@ pub struct VulkanTextureView {
     pub view: vk::ImageView,
     pub image: vk::Image,
     pub extent: vk::Extent2D,
-    pub current_layout: Arc<Mutex<vk::ImageLayout>>,
+    pub aspect_mask: vk::ImageAspectFlags,
+    pub base_mip_level: u32,
+    /// State of the texture's levels the view covers, from `base_mip_level` on.
+    pub mips: Vec<crate::tracking::MipState>,
     pub device: ash::Device,
 }
 
@@ -0,0 +1,673 @@
+use ash::vk;
+use std::sync::{Arc, Mutex};
+
+/// Old and new layout of an image barrier.
+pub type LayoutTransition = (vk::ImageLayout, vk::ImageLayout);
+
+/// A set of pipeline stages together with the memory accesses they perform.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Access {
+    pub stages: vk::PipelineStageFlags2,
+    pub access: vk::AccessFlags2,
+}
+
+const WRITE_ACCESS: vk::AccessFlags2 = vk::AccessFlags2::from_raw(
+    vk::AccessFlags2::SHADER_WRITE.as_raw()
+        | vk::AccessFlags2::SHADER_STORAGE_WRITE.as_raw()
+        | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE.as_raw()
+        | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw()
+        | vk::AccessFlags2::TRANSFER_WRITE.as_raw()
+        | vk::AccessFlags2::HOST_WRITE.as_raw()
+        | vk::AccessFlags2::MEMORY_WRITE.as_raw(),
+);
+
+impl Access {
+    pub const fn new(stages: vk::PipelineStageFlags2, access: vk::AccessFlags2) -> Self {
+        Self { stages, access }
+    }
+
+    pub fn is_write(&self) -> bool {
+        self.access.intersects(WRITE_ACCESS)
+    }
+
+    /// The same access with its writes removed.
+    pub fn reads(&self) -> Self {
+        Self { stages: self.stages, access: self.access & !WRITE_ACCESS }
+    }
+
+    /// Every read the graphics pipeline can make of a buffer.
+    pub const GRAPHICS_BUFFER_READ: Self = Self::new(
+        vk::PipelineStageFlags2::from_raw(
+            vk::PipelineStageFlags2::DRAW_INDIRECT.as_raw()
+                | vk::PipelineStageFlags2::INDEX_INPUT.as_raw()
+                | vk::PipelineStageFlags2::VERTEX_ATTRIBUTE_INPUT.as_raw()
+                | vk::PipelineStageFlags2::VERTEX_SHADER.as_raw()
+                | vk::PipelineStageFlags2::FRAGMENT_SHADER.as_raw(),
+        ),
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::INDIRECT_COMMAND_READ.as_raw()
+                | vk::AccessFlags2::INDEX_READ.as_raw()
+                | vk::AccessFlags2::VERTEX_ATTRIBUTE_READ.as_raw()
+                | vk::AccessFlags2::UNIFORM_READ.as_raw()
+                | vk::AccessFlags2::SHADER_STORAGE_READ.as_raw(),
+        ),
+    );
+
+    /// Every read the graphics pipeline can make of a texture outside its attachments.
+    pub const GRAPHICS_IMAGE_READ: Self = Self::new(
+        vk::PipelineStageFlags2::from_raw(
+            vk::PipelineStageFlags2::VERTEX_SHADER.as_raw() | vk::PipelineStageFlags2::FRAGMENT_SHADER.as_raw(),
+        ),
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::SHADER_SAMPLED_READ.as_raw() | vk::AccessFlags2::SHADER_STORAGE_READ.as_raw(),
+        ),
+    );
+
+    pub const TRANSFER_READ: Self = Self::new(vk::PipelineStageFlags2::COPY, vk::AccessFlags2::TRANSFER_READ);
+    pub const TRANSFER_WRITE: Self = Self::new(vk::PipelineStageFlags2::COPY, vk::AccessFlags2::TRANSFER_WRITE);
+    pub const INDIRECT_READ: Self = Self::new(vk::PipelineStageFlags2::DRAW_INDIRECT, vk::AccessFlags2::INDIRECT_COMMAND_READ);
+    pub const VERTEX_READ: Self = Self::new(vk::PipelineStageFlags2::VERTEX_ATTRIBUTE_INPUT, vk::AccessFlags2::VERTEX_ATTRIBUTE_READ);
+    pub const INDEX_READ: Self = Self::new(vk::PipelineStageFlags2::INDEX_INPUT, vk::AccessFlags2::INDEX_READ);
+    pub const COLOR_ATTACHMENT_WRITE: Self = Self::new(
+        vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::COLOR_ATTACHMENT_READ.as_raw() | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE.as_raw(),
+        ),
+    );
+    pub const DEPTH_ATTACHMENT_WRITE: Self = Self::new(
+        vk::PipelineStageFlags2::from_raw(
+            vk::PipelineStageFlags2::EARLY_FRAGMENT_TESTS.as_raw() | vk::PipelineStageFlags2::LATE_FRAGMENT_TESTS.as_raw(),
+        ),
+        vk::AccessFlags2::from_raw(
+            vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ.as_raw()
+                | vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw(),
+        ),
+    );
+
+    /// How shaders in `visibility` may access a resource bound as `ty`.
+    pub fn for_binding(ty: lume_core::device::BindingType, visibility: lume_core::device::ShaderStage) -> Self {
+        use lume_core::device::{BindingType, ShaderStage};
+
+        let mut stages = vk::PipelineStageFlags2::NONE;
+        if visibility.0 & ShaderStage::VERTEX.0 != 0 { stages |= vk::PipelineStageFlags2::VERTEX_SHADER; }
+        if visibility.0 & ShaderStage::FRAGMENT.0 != 0 { stages |= vk::PipelineStageFlags2::FRAGMENT_SHADER; }
+        if visibility.0 & ShaderStage::COMPUTE.0 != 0 { stages |= vk::PipelineStageFlags2::COMPUTE_SHADER; }
+
+        // Storage bindings carry no read-only flag, so they are assumed to be written.
+        let access = match ty {
+            BindingType::UniformBuffer => vk::AccessFlags2::UNIFORM_READ,
+            BindingType::SampledTexture => vk::AccessFlags2::SHADER_SAMPLED_READ,
+            BindingType::StorageBuffer | BindingType::StorageTexture => {
+                vk::AccessFlags2::SHADER_STORAGE_READ | vk::AccessFlags2::SHADER_STORAGE_WRITE
+            }
+            BindingType::Sampler => vk::AccessFlags2::NONE,
+        };
+        Self { stages, access }
+    }
+}
+
+/// The source and destination halves of a pipeline barrier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Barrier {
+    pub src: Access,
+    pub dst: Access,
+}
+
+/// Access history of one buffer or texture: its last write, the accesses that have already
+/// been made to wait on it, and the reads issued since.
+#[derive(Clone, Debug, Default)]
+pub struct ResourceState {
+    last_write: Option<Access>,
+    visible: Access,
+    reads: vk::PipelineStageFlags2,
+    queue_family: Option<u32>,
+}
+
+impl ResourceState {
+    /// Records `next` and returns the barrier that must precede it, if any.
+    pub fn access(&mut self, next: Access) -> Option<Barrier> {
+        if next.is_write() {
+            // Write-after-write needs the old write made available; write-after-read
+            // only needs the reads to finish.
+            let src = Access {
+                stages: self.last_write.map_or(vk::PipelineStageFlags2::NONE, |w| w.stages) | self.reads,
+                access: self.last_write.map_or(vk::AccessFlags2::NONE, |w| w.access),
+            };
+            self.last_write = Some(next);
+            self.visible = Access::default();
+            self.reads = vk::PipelineStageFlags2::NONE;
+            return (!src.stages.is_empty()).then_some(Barrier { src, dst: next });
+        }
+
+        self.reads |= next.stages;
+        let write = self.last_write?;
+        if self.is_visible_to(next) {
+            return None;
+        }
+        self.visible.stages |= next.stages;
+        self.visible.access |= next.access;
+        Some(Barrier { src: write, dst: next })
+    }
+
+    /// True when the last write has already been made visible to `next`, or there was none.
+    pub fn is_visible_to(&self, next: Access) -> bool {
+        self.last_write.is_none() || (self.visible.stages.contains(next.stages) && self.visible.access.contains(next.access))
+    }
+
+    /// Records a layout transition ahead of `next`, returning the barrier that performs it.
+    pub fn transition(&mut self, next: Access) -> Barrier {
+        let src = Access {
+            stages: self.last_write.map_or(vk::PipelineStageFlags2::NONE, |w| w.stages) | self.reads,
+            access: self.last_write.map_or(vk::AccessFlags2::NONE, |w| w.access),
+        };
+        // The transition itself acts as a write that only `next` has waited on.
+        self.last_write = Some(if next.is_write() { next } else { Access::new(next.stages, vk::AccessFlags2::NONE) });
+        self.visible = if next.is_write() { Access::default() } else { next };
+        self.reads = vk::PipelineStageFlags2::NONE;
+        Barrier { src, dst: next }
+    }
+
+    pub fn has_pending_write(&self) -> bool {
+        self.last_write.is_some()
+    }
+
+    /// Forgets all history, for use after a full barrier.
+    pub fn reset(&mut self) {
+        let queue_family = self.queue_family;
+        *self = Self { queue_family, ..Self::default() };
+    }
+
+    /// Notes the queue family using the resource, returning the previous one when it changed
+    /// without an ownership transfer.
+    pub fn use_on_queue(&mut self, family: u32) -> Option<u32> {
+        let previous = self.queue_family.replace(family);
+        previous.filter(|&p| p != family)
+    }
+
+    /// Hands the resource to another queue family, as an ownership barrier does.
+    pub fn transfer_to_queue(&mut self, family: u32) {
+        self.reset();
+        self.queue_family = Some(family);
+    }
+}
+
+/// Layout and access history of one mip level of an image as of the last submission, shared
+/// by every handle to it.
+#[derive(Clone, Default)]
+pub struct MipState {
+    pub layout: Arc<Mutex<vk::ImageLayout>>,
+    pub access: Arc<Mutex<ResourceState>>,
+}
+
+/// A buffer or one mip level of an image, together with the state submissions have left it in.
+#[derive(Clone)]
+pub enum TrackedResource {
+    Buffer {
+        buffer: vk::Buffer,
+        state: Arc<Mutex<ResourceState>>,
+    },
+    Image {
+        image: vk::Image,
+        aspect_mask: vk::ImageAspectFlags,
+        mip_level: u32,
+        layout: Arc<Mutex<vk::ImageLayout>>,
+        state: Arc<Mutex<ResourceState>>,
+    },
+}
+
+impl TrackedResource {
+    pub fn buffer(buffer: &crate::VulkanBuffer) -> Self {
+        Self::Buffer { buffer: buffer.buffer, state: buffer.access.clone() }
+    }
+
+    /// The first mip level of `texture`, the one copies read and write.
+    pub fn texture(texture: &crate::VulkanTexture) -> Self {
+        let aspect_mask = if crate::device::resource::is_depth_format(texture.format) {
+            vk::ImageAspectFlags::DEPTH
+        } else {
+            vk::ImageAspectFlags::COLOR
+        };
+        Self::Image {
+            image: texture.image,
+            aspect_mask,
+            mip_level: 0,
+            layout: texture.mips[0].layout.clone(),
+            state: texture.mips[0].access.clone(),
+        }
+    }
+
+    /// Every mip level of `texture`.
+    pub fn mips(texture: &crate::VulkanTexture) -> impl Iterator<Item = Self> + '_ {
+        let Self::Image { aspect_mask, .. } = Self::texture(texture) else { unreachable!() };
+        texture.mips.iter().zip(0..).map(move |(mip, mip_level)| Self::Image {
+            image: texture.image,
+            aspect_mask,
+            mip_level,
+            layout: mip.layout.clone(),
+            state: mip.access.clone(),
+        })
+    }
+
+    /// Every mip level `view` covers.
+    pub fn view(view: &crate::VulkanTextureView) -> impl Iterator<Item = Self> + '_ {
+        view.mips.iter().zip(view.base_mip_level..).map(|(mip, mip_level)| Self::Image {
+            image: view.image,
+            aspect_mask: view.aspect_mask,
+            mip_level,
+            layout: mip.layout.clone(),
+            state: mip.access.clone(),
+        })
+    }
+
+    pub fn state(&self) -> &Arc<Mutex<ResourceState>> {
+        match self {
+            Self::Buffer { state, .. } | Self::Image { state, .. } => state,
+        }
+    }
+
+    /// The layout submissions have left the image in.
+    pub fn layout(&self) -> Option<vk::ImageLayout> {
+        match self {
+            Self::Buffer { .. } => None,
+            Self::Image { layout, .. } => Some(*layout.lock().unwrap()),
+        }
+    }
+
+    /// Whether both refer to the same buffer or mip level.
+    pub fn is(&self, other: &Self) -> bool {
+        Arc::ptr_eq(self.state(), other.state())
+    }
+}
+
+/// What one command buffer does to a resource. Its first accesses are synchronised at submit,
+/// against whatever earlier submissions left the resource in; the rest are ordered by barriers
+/// recorded into the buffer itself.
+pub struct LocalUse {
+    pub resource: TrackedResource,
+    /// The accesses made before the buffer first writes the resource, and the layout they
+    /// expect the image in.
+    first: Option<(Access, Option<vk::ImageLayout>)>,
+    state: ResourceState,
+    layout: Option<vk::ImageLayout>,
+    /// Whether the buffer writes, transitions or resets the resource, so that its history
+    /// replaces the submitted one instead of adding reads to it.
+    replaces: bool,
+}
+
+impl LocalUse {
+    pub fn new(resource: TrackedResource) -> Self {
+        Self { resource, first: None, state: ResourceState::default(), layout: None, replaces: false }
+    }
+
+    /// The layout the image is in at this point of the buffer, as far as it is known.
+    pub fn layout(&self) -> vk::ImageLayout {
+        match (&self.resource, self.layout) {
+            (_, Some(layout)) => layout,
+            (resource, None) => resource.layout().unwrap_or(vk::ImageLayout::UNDEFINED),
+        }
+    }
+
+    fn transition_to(&self, target: Option<vk::ImageLayout>) -> Option<LayoutTransition> {
+        match (self.layout, target) {
+            (Some(current), Some(target)) if current != target => Some((current, target)),
+            _ => None,
+        }
+    }
+
+    /// Records `usage` of the resource, moving images to `target_layout`, and returns the
+    /// barrier and layout transition that must precede it in the buffer. Images always get a
+    /// layout pair, which is the same layout twice when no transition is needed.
+    pub fn access(&mut self, usage: Access, target_layout: Option<vk::ImageLayout>) -> Option<(Barrier, Option<LayoutTransition>)> {
+        let transition = self.transition_to(target_layout);
+        match &mut self.first {
+            None => {
+                self.first = Some((usage, target_layout));
+                self.state.access(usage);
+                self.layout = target_layout;
+                self.replaces = usage.is_write();
+                return None;
+            }
+            // Reads ahead of the buffer's first write all wait on the submitted history
+            Some((first, _)) if !self.replaces && transition.is_none() && !usage.is_write() => {
+                first.stages |= usage.stages;
+                first.access |= usage.access;
+                self.state.access(usage);
+                return None;
+            }
+            Some(_) => {}
+        }
+
+        let barrier = match transition {
+            Some(_) => Some(self.state.transition(usage)),
+            None => self.state.access(usage),
+        };
+        self.replaces |= usage.is_write() || transition.is_some();
+        let layouts = if target_layout.is_some() {
+            self.layout = target_layout;
+            transition.or(target_layout.map(|layout| (layout, layout)))
+        } else {
+            self.layout.map(|layout| (layout, layout))
+        };
+        barrier.map(|barrier| (barrier, layouts))
+    }
+
+    /// True when `usage` needs a layout transition, or reads something an earlier write in the
+    /// buffer has not been made visible to.
+    pub fn needs_barrier(&self, usage: Access, target_layout: Option<vk::ImageLayout>) -> bool {
+        let reads = usage.reads();
+        self.transition_to(target_layout).is_some() || (!reads.access.is_empty() && !self.state.is_visible_to(reads))
+    }
+
+    /// Makes the buffer's last write of the resource visible to `usage`, returning the barrier
+    /// that does so.
+    pub fn make_visible(&mut self, usage: Access) -> Option<(Barrier, Option<LayoutTransition>)> {
+        if !self.state.has_pending_write() || self.state.is_visible_to(usage) {
+            return None;
+        }
+        let barrier = self.state.access(usage)?;
+        Some((barrier, self.layout.map(|layout| (layout, layout))))
+    }
+
+    /// Notes a barrier recorded outside the tracker, which expects the image in `from` (any
+    /// layout when `None`) and leaves it in `to` with its history cleared.
+    pub fn reset(&mut self, from: Option<vk::ImageLayout>, to: vk::ImageLayout) {
+        if self.first.is_none() {
+            self.first = Some((Access::default(), from));
+        }
+        self.state.reset();
+        self.layout = Some(to);
+        self.replaces = true;
+    }
+
+    /// Notes that a render pass wrote the attachment with `usage` and left it in `layout`.
+    pub fn written_by_pass(&mut self, usage: Access, layout: vk::ImageLayout) {
+        self.state.reset();
+        self.state.access(usage);
+        self.layout = Some(layout);
+        self.replaces = true;
+    }
+
+    /// Notes an ownership transfer of the resource to another queue family.
+    pub fn transfer_to_queue(&mut self, family: u32) {
+        if self.first.is_none() {
+            self.first = Some((Access::default(), None));
+        }
+        self.state.transfer_to_queue(family);
+        self.replaces = true;
+    }
+
+    /// Orders the buffer's first accesses after what earlier submissions did to the resource,
+    /// returning the barrier and layout transition to record ahead of the buffer, and then
+    /// leaves the resource in the state the buffer ends with. Buffers must be resolved in the
+    /// order they are submitted.
+    pub fn resolve(&self, queue_family: u32) -> Option<(Barrier, Option<LayoutTransition>)> {
+        let mut submitted = self.resource.state().lock().unwrap();
+        let barrier = self.first.and_then(|(usage, target_layout)| {
+            if let Some(previous) = submitted.use_on_queue(queue_family) {
+                log::warn!(
+                    "Resource used on queue family {} after family {} without an ownership transfer",
+                    queue_family, previous
+                );
+            }
+            let current = self.resource.layout();
+            let transition = match (current, target_layout) {
+                (Some(current), Some(target)) if current != target => Some((current, target)),
+                _ => None,
+            };
+            let barrier = match transition {
+                Some(_) => Some(submitted.transition(usage)),
+                None => submitted.access(usage),
+            };
+            barrier.map(|barrier| (barrier, transition.or(current.map(|layout| (layout, layout)))))
+        });
+
+        if self.replaces {
+            let queue_family = self.state.queue_family.or(submitted.queue_family);
+            *submitted = ResourceState { queue_family, ..self.state.clone() };
+        }
+        if let (TrackedResource::Image { layout, .. }, Some(local)) = (&self.resource, self.layout) {
+            *layout.lock().unwrap() = local;
+        }
+        barrier
+    }
+}
+
+/// A resource referenced by a bind group, with the access and layout its shaders expect.
+#[derive(Clone)]
+pub struct BoundResource {
+    pub resource: TrackedResource,
+    pub usage: Access,
+    pub layout: Option<vk::ImageLayout>,
+}
+
+/// The resources of the bind groups bound to a command buffer, by set index. Binding tracks
+/// a group for its first draw or dispatch; every later one that still uses it has to wait on
+/// what the previous one wrote, so it is tracked again.
+#[derive(Default)]
+pub struct BoundGroups {
+    groups: Vec<Option<(Vec<BoundResource>, bool)>>,
+}
+
+impl BoundGroups {
+    pub fn bind(&mut self, index: u32, resources: &[BoundResource]) {
+        let index = index as usize;
+        if self.groups.len() <= index {
+            self.groups.resize(index + 1, None);
+        }
+        self.groups[index] = Some((resources.to_vec(), false));
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+    }
+
+    /// Every resource of the bound groups.
+    pub fn resources(&self) -> Vec<BoundResource> {
+        self.groups.iter().flatten().flat_map(|(group, _)| group.iter().cloned()).collect()
+    }
+
+    /// The resources a draw or dispatch must track again before it is recorded.
+    pub fn next_use(&mut self) -> Vec<BoundResource> {
+        let mut resources = Vec::new();
+        for (group, used) in self.groups.iter_mut().flatten() {
+            if std::mem::replace(used, true) {
+                resources.extend(group.iter().cloned());
+            }
+        }
+        resources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPUTE_WRITE: Access = Access::new(vk::PipelineStageFlags2::COMPUTE_SHADER, vk::AccessFlags2::SHADER_STORAGE_WRITE);
+    const COMPUTE_READ: Access = Access::new(vk::PipelineStageFlags2::COMPUTE_SHADER, vk::AccessFlags2::SHADER_STORAGE_READ);
+    const VERTEX_READ: Access = Access::new(vk::PipelineStageFlags2::VERTEX_SHADER, vk::AccessFlags2::SHADER_STORAGE_READ);
+
+    #[test]
+    fn read_after_write_waits_once() {
+        let mut state = ResourceState::default();
+        assert_eq!(state.access(COMPUTE_WRITE), None);
+        assert_eq!(state.access(VERTEX_READ), Some(Barrier { src: COMPUTE_WRITE, dst: VERTEX_READ }));
+        assert_eq!(state.access(VERTEX_READ), None);
+        assert_eq!(state.access(COMPUTE_READ), Some(Barrier { src: COMPUTE_WRITE, dst: COMPUTE_READ }));
+    }
+
+    #[test]
+    fn write_after_read_waits_for_readers_only() {
+        let mut state = ResourceState::default();
+        state.access(COMPUTE_WRITE);
+        state.access(VERTEX_READ);
+        let barrier = state.access(COMPUTE_WRITE).unwrap();
+        assert_eq!(barrier.src.stages, vk::PipelineStageFlags2::COMPUTE_SHADER | vk::PipelineStageFlags2::VERTEX_SHADER);
+        assert_eq!(barrier.src.access, vk::AccessFlags2::SHADER_STORAGE_WRITE);
+    }
+
+    #[test]
+    fn reads_without_a_write_need_no_barrier() {
+        let mut state = ResourceState::default();
+        assert_eq!(state.access(VERTEX_READ), None);
+        assert_eq!(state.access(COMPUTE_READ), None);
+        let barrier = state.access(COMPUTE_WRITE).unwrap();
+        assert_eq!(barrier.src.access, vk::AccessFlags2::NONE);
+    }
+
+    #[test]
+    fn transition_orders_later_reads_after_it() {
+        let mut state = ResourceState::default();
+        state.access(COMPUTE_WRITE);
+        let barrier = state.transition(VERTEX_READ);
+        assert_eq!(barrier.src, COMPUTE_WRITE);
+        assert_eq!(state.access(VERTEX_READ), None);
+        let barrier = state.access(COMPUTE_READ).unwrap();
+        assert_eq!(barrier.src.stages, vk::PipelineStageFlags2::VERTEX_SHADER);
+    }
+
+    #[test]
+    fn dispatches_on_one_bind_group_wait_on_each_other() {
+        use lume_core::device::{BindingType, ShaderStage};
+
+        let storage = BoundResource {
+            resource: TrackedResource::Buffer { buffer: vk::Buffer::null(), state: Default::default() },
+            usage: Access::for_binding(BindingType::StorageBuffer, ShaderStage::COMPUTE),
+            layout: None,
+        };
+        let mut local = LocalUse::new(storage.resource.clone());
+        let mut track = |resources: &[BoundResource]| -> Vec<Barrier> {
+            resources.iter().filter_map(|b| local.access(b.usage, b.layout)).map(|(barrier, _)| barrier).collect()
+        };
+        let mut bound = BoundGroups::default();
+        assert_eq!(track(std::slice::from_ref(&storage)), vec![]);
+        bound.bind(0, &[storage]);
+
+        // The first dispatch writes the buffer, covered by the bind; the second reads it back.
+        assert!(bound.next_use().is_empty());
+        let barriers = track(&bound.next_use());
+        assert_eq!(barriers.len(), 1);
+        assert_eq!(barriers[0].src.stages, vk::PipelineStageFlags2::COMPUTE_SHADER);
+        assert!(barriers[0].src.access.contains(vk::AccessFlags2::SHADER_STORAGE_WRITE));
+        assert!(barriers[0].dst.access.contains(vk::AccessFlags2::SHADER_STORAGE_READ));
+
+        // Rebinding the slot replaces the group.
+        bound.bind(0, &[]);
+        bound.next_use();
+        assert!(bound.next_use().is_empty());
+    }
+
+    #[test]
+    fn mip_levels_are_transitioned_apart() {
+        // A downsample chain: each level is written, then read to build the next one.
+        let mips: Vec<MipState> = (0..3).map(|_| MipState::default()).collect();
+        let mut levels: Vec<LocalUse> = mips.iter().zip(0..).map(|(mip, mip_level)| LocalUse::new(TrackedResource::Image {
+            image: vk::Image::null(),
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level,
+            layout: mip.layout.clone(),
+            state: mip.access.clone(),
+        })).collect();
+        let general = Some(vk::ImageLayout::GENERAL);
+        let sampled = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+
+        assert_eq!(levels[0].access(COMPUTE_WRITE, general), None);
+        for mip_level in 1..3 {
+            let (barrier, layouts) = levels[mip_level - 1].access(COMPUTE_READ, general).unwrap();
+            assert_eq!((barrier.src, layouts), (COMPUTE_WRITE, Some((vk::ImageLayout::GENERAL, vk::ImageLayout::GENERAL))));
+            // The level being written has not been touched by the reads of the one before it.
+            assert_eq!(levels[mip_level].access(COMPUTE_WRITE, general), None);
+        }
+
+        // Sampling the whole chain moves every level on its own.
+        for level in &mut levels {
+            let (_, layouts) = level.access(VERTEX_READ, Some(sampled)).unwrap();
+            assert_eq!(layouts, Some((vk::ImageLayout::GENERAL, sampled)));
+        }
+
+        // At submit each level leaves its undefined contents for its first write.
+        for (level, mip) in levels.iter().zip(&mips) {
+            let (_, layouts) = level.resolve(0).unwrap();
+            assert_eq!(layouts, Some((vk::ImageLayout::UNDEFINED, vk::ImageLayout::GENERAL)));
+            assert_eq!(*mip.layout.lock().unwrap(), sampled);
+        }
+    }
+
+    #[test]
+    fn command_buffers_are_ordered_by_submission() {
+        let buffer = || TrackedResource::Buffer { buffer: vk::Buffer::null(), state: Default::default() };
+
+        // Recorded in one order, submitted in the other.
+        let resource = buffer();
+        let mut reader = LocalUse::new(resource.clone());
+        assert_eq!(reader.access(VERTEX_READ, None), None);
+        let mut writer = LocalUse::new(resource);
+        assert_eq!(writer.access(COMPUTE_WRITE, None), None);
+        assert_eq!(writer.resolve(0), None);
+        assert_eq!(reader.resolve(0), Some((Barrier { src: COMPUTE_WRITE, dst: VERTEX_READ }, None)));
+
+        // Submitted in recording order, the write waits on the read instead.
+        let resource = buffer();
+        let mut reader = LocalUse::new(resource.clone());
+        reader.access(VERTEX_READ, None);
+        let mut writer = LocalUse::new(resource);
+        writer.access(COMPUTE_WRITE, None);
+        assert_eq!(reader.resolve(0), None);
+        let (barrier, _) = writer.resolve(0).unwrap();
+        assert_eq!(barrier.src, Access::new(vk::PipelineStageFlags2::VERTEX_SHADER, vk::AccessFlags2::NONE));
+        assert_eq!(barrier.dst, COMPUTE_WRITE);
+    }
+
+    #[test]
+    fn reads_ahead_of_the_first_write_wait_at_submit_together() {
+        let resource = TrackedResource::Buffer { buffer: vk::Buffer::null(), state: Default::default() };
+        let mut producer = LocalUse::new(resource.clone());
+        producer.access(COMPUTE_WRITE, None);
+        producer.resolve(0);
+
+        let mut local = LocalUse::new(resource);
+        assert_eq!(local.access(VERTEX_READ, None), None);
+        assert_eq!(local.access(COMPUTE_READ, None), None);
+        // A write later in the buffer waits on both reads within it.
+        let (barrier, _) = local.access(COMPUTE_WRITE, None).unwrap();
+        assert_eq!(barrier.src.stages, vk::PipelineStageFlags2::VERTEX_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER);
+
+        let (barrier, _) = local.resolve(0).unwrap();
+        assert_eq!(barrier.src, COMPUTE_WRITE);
+        assert_eq!(barrier.dst, Access::new(
+            vk::PipelineStageFlags2::VERTEX_SHADER | vk::PipelineStageFlags2::COMPUTE_SHADER,
+            vk::AccessFlags2::SHADER_STORAGE_READ,
+        ));
+    }
+
+    #[test]
+    fn render_pass_uses_needing_a_barrier_are_caught() {
+        let mip = MipState::default();
+        let mut local = LocalUse::new(TrackedResource::Image {
+            image: vk::Image::null(),
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            layout: mip.layout.clone(),
+            state: mip.access.clone(),
+        });
+        let general = Some(vk::ImageLayout::GENERAL);
+
+        // The first use is ordered at submit, so it never needs one.
+        assert!(!local.needs_barrier(COMPUTE_WRITE, general));
+        local.access(COMPUTE_WRITE, general);
+        assert!(local.needs_barrier(VERTEX_READ, general));
+
+        // Once the flush ahead of the pass made the write visible, only a layout change does.
+        assert!(local.make_visible(Access::GRAPHICS_IMAGE_READ).is_some());
+        assert!(!local.needs_barrier(VERTEX_READ, general));
+        assert!(local.needs_barrier(VERTEX_READ, Some(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)));
+    }
+
+    #[test]
+    fn queue_changes_are_reported_until_transferred() {
+        let mut state = ResourceState::default();
+        assert_eq!(state.use_on_queue(0), None);
+        assert_eq!(state.use_on_queue(2), Some(0));
+        state.transfer_to_queue(1);
+        assert_eq!(state.use_on_queue(1), None);
+    }
+}